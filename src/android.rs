@@ -0,0 +1,583 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use roxmltree::{Document, Node};
+
+use crate::{
+    configs::DocumentFormat,
+    errors::PoLlmError,
+    files::{FileGuard, write_atomically},
+    linguist::{escape_xml, indent_before},
+    plurals::{cldr_forms, plural_forms_or_default},
+    translations::{self, FormatAdapter, TranslationUnit, WriteOptions, document_as},
+    xliff::{inner_range, start_tag_end},
+};
+
+/// Elements holding translatable resources.
+const RESOURCE_ELEMENTS: &[&str] = &["string", "plurals", "string-array"];
+
+/// An Android `res/values/strings.xml` resource file.
+#[derive(Debug, Clone)]
+pub struct AndroidStrings {
+    text: String,
+}
+
+/// A `<string>` or a `<plurals>` resource. Values are the raw markup
+/// between the tags, so `<xliff:g>` spans and `\'` escapes are kept.
+enum Resource<'a, 'input> {
+    Single {
+        name: String,
+        value: String,
+        node: Node<'a, 'input>,
+    },
+    Plural {
+        name: String,
+        /// `(quantity, value)` pairs in document order.
+        items: Vec<(String, String)>,
+        node: Node<'a, 'input>,
+    },
+}
+
+impl Resource<'_, '_> {
+    fn name(&self) -> &str {
+        match self {
+            Resource::Single { name, .. } | Resource::Plural { name, .. } => name,
+        }
+    }
+
+    fn is_filled(&self) -> bool {
+        match self {
+            Resource::Single { value, .. } => !value.trim().is_empty(),
+            Resource::Plural { items, .. } => items
+                .iter()
+                .any(|(quantity, value)| quantity == "other" && !value.trim().is_empty()),
+        }
+    }
+}
+
+impl AndroidStrings {
+    pub fn read(path: &Path) -> Result<Self, PoLlmError> {
+        let text = fs::read_to_string(path).map_err(|e| {
+            PoLlmError::io(format!("Failed to read Android strings file {:?}", path), e)
+        })?;
+        Self::parse(text)
+            .map_err(|e| e.context(format!("Failed to parse Android strings file {:?}", path)))
+    }
+
+    pub fn parse(text: String) -> Result<Self, PoLlmError> {
+        let document = Document::parse(&text).map_err(|e| PoLlmError::Parse(e.to_string()))?;
+        let root = document.root_element();
+        if !root.has_tag_name("resources") {
+            return Err(PoLlmError::Parse(format!(
+                "root element is <{}>, not <resources>",
+                root.tag_name().name()
+            )));
+        }
+        Ok(Self { text })
+    }
+
+    /// The same file without its resources and the comments between them,
+    /// as the start of a translation.
+    pub fn preamble(&self) -> Result<Self, PoLlmError> {
+        let document = Document::parse(&self.text).map_err(|e| PoLlmError::Parse(e.to_string()))?;
+        let mut text = self.text.clone();
+        let resources: Vec<Range<usize>> = document
+            .root_element()
+            .children()
+            .filter(|n| {
+                n.is_comment()
+                    || (n.is_element() && RESOURCE_ELEMENTS.contains(&n.tag_name().name()))
+            })
+            .map(|n| n.range())
+            .collect();
+        for range in resources.into_iter().rev() {
+            // Take the line break and indentation before the element along.
+            let start = text[..range.start].trim_end().len();
+            text.replace_range(start..range.end, "");
+        }
+        Ok(Self { text })
+    }
+
+    fn filled_names(&self) -> HashSet<String> {
+        let Ok(document) = Document::parse(&self.text) else {
+            return HashSet::new();
+        };
+        resources(&self.text, &document)
+            .iter()
+            .filter(|resource| resource.is_filled())
+            .map(|resource| resource.name().to_string())
+            .collect()
+    }
+}
+
+/// Translatable resources in document order: `<string>`s and `<plurals>`
+/// not marked `translatable="false"`. String arrays are left out.
+fn resources<'a, 'input>(text: &str, document: &'a Document<'input>) -> Vec<Resource<'a, 'input>> {
+    document
+        .root_element()
+        .children()
+        .filter(|n| n.is_element() && n.attribute("translatable") != Some("false"))
+        .filter_map(|node| {
+            let name = node.attribute("name")?.to_string();
+            match node.tag_name().name() {
+                "string" => Some(Resource::Single {
+                    name,
+                    value: text[inner_range(text, node)].to_string(),
+                    node,
+                }),
+                "plurals" => Some(Resource::Plural {
+                    name,
+                    items: node
+                        .children()
+                        .filter(|n| n.has_tag_name("item"))
+                        .filter_map(|item| {
+                            Some((
+                                item.attribute("quantity")?.to_string(),
+                                text[inner_range(text, item)].to_string(),
+                            ))
+                        })
+                        .collect(),
+                    node,
+                }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// The resource qualifier Android uses for a language tag: `fr` stays `fr`,
+/// `zh-CN` and `zh_CN` become `zh-rCN`, and tags with a script or a numeric
+/// region, such as `zh-Hans` or `es-419`, use the `b+zh+Hans` form.
+pub fn android_qualifier(lang: &str) -> String {
+    let parts: Vec<&str> = lang.split(['-', '_']).filter(|p| !p.is_empty()).collect();
+    match parts.as_slice() {
+        [language] => language.to_lowercase(),
+        [language, region]
+            if region.len() == 2 && region.chars().all(|c| c.is_ascii_alphabetic()) =>
+        {
+            format!("{}-r{}", language.to_lowercase(), region.to_uppercase())
+        }
+        _ => format!("b+{}", parts.join("+")),
+    }
+}
+
+/// Resources go to a `values-<qualifier>` directory next to the `values`
+/// directory holding the input, under the same file name.
+fn values_path(input_path: &Path, target_lang: &str) -> Result<PathBuf, PoLlmError> {
+    let file_name = input_path.file_name().ok_or_else(|| {
+        PoLlmError::io(
+            format!("Invalid filename {:?}", input_path),
+            io::ErrorKind::InvalidInput.into(),
+        )
+    })?;
+    let parent = input_path.parent().unwrap_or(Path::new(""));
+    let base = match parent.file_name() {
+        Some(dir) if dir.to_string_lossy().starts_with("values") => {
+            parent.parent().unwrap_or(Path::new(""))
+        }
+        _ => parent,
+    };
+    Ok(base
+        .join(format!("values-{}", android_qualifier(target_lang)))
+        .join(file_name))
+}
+
+/// Outputs start with the input's `<resources>` element and no resources;
+/// a resource counts as translated once it is present with a value (for
+/// plurals, an `other` item).
+pub struct AndroidAdapter;
+
+impl FormatAdapter for AndroidAdapter {
+    fn format(&self) -> DocumentFormat {
+        DocumentFormat::Android
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["xml"]
+    }
+
+    fn output_path(
+        &self,
+        input_path: &Path,
+        target_lang: &str,
+    ) -> Result<Option<PathBuf>, PoLlmError> {
+        Ok(Some(values_path(input_path, target_lang)?))
+    }
+
+    fn create_output(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+        _target_lang: &str,
+    ) -> Result<(), PoLlmError> {
+        let preamble = AndroidStrings::read(input_path)?.preamble()?;
+        write_atomically(output_path, |writer| {
+            writer.write_all(preamble.text.as_bytes())
+        })
+        .map_err(|e| PoLlmError::io("Failed to write Android strings file", e))
+    }
+
+    fn load_template(&self, path: &Path) -> Result<translations::Document, PoLlmError> {
+        Ok(Box::new(AndroidStrings::read(path)?))
+    }
+
+    fn load_existing(
+        &self,
+        output_path: &Path,
+        template: &translations::Document,
+    ) -> Result<translations::Document, PoLlmError> {
+        if output_path.exists() {
+            return Ok(Box::new(AndroidStrings::read(output_path)?));
+        }
+        println!("         Output doesn't exist, starting from an empty resource file");
+        let template: &AndroidStrings = document_as(template);
+        Ok(Box::new(template.preamble()?))
+    }
+
+    fn extract_units(
+        &self,
+        existing: &translations::Document,
+        template: &translations::Document,
+        skip_translated: bool,
+    ) -> Vec<TranslationUnit> {
+        let document: &AndroidStrings = document_as(existing);
+        let template: &AndroidStrings = document_as(template);
+        let translated = if skip_translated {
+            document.filled_names()
+        } else {
+            HashSet::new()
+        };
+        let Ok(parsed) = Document::parse(&template.text) else {
+            return Vec::new();
+        };
+
+        resources(&template.text, &parsed)
+            .into_iter()
+            .filter(|resource| !translated.contains(resource.name()))
+            .filter_map(|resource| {
+                let formatted = match &resource {
+                    Resource::Single { node, .. } | Resource::Plural { node, .. } => {
+                        node.attribute("formatted") != Some("false")
+                    }
+                };
+                let (name, msg_id, msg_id_plural) = match resource {
+                    Resource::Single { name, value, .. } => (name, value, None),
+                    Resource::Plural { name, items, .. } => {
+                        let item = |quantity: &str| {
+                            items.iter().find(|(q, _)| q == quantity).map(|(_, v)| v)
+                        };
+                        let singular = item("one").or(items.first().map(|(_, v)| v))?.clone();
+                        let plural = item("other")?.clone();
+                        (name, singular, Some(plural))
+                    }
+                };
+                if msg_id.trim().is_empty() {
+                    return None;
+                }
+                let is_plural = msg_id_plural.is_some();
+
+                Some(TranslationUnit {
+                    msg_id,
+                    msg_id_plural,
+                    msg_str: (!is_plural).then(String::new),
+                    msg_str_plural: is_plural.then(Vec::new),
+                    context: Some(name),
+                    comments: None,
+                    rationale: None,
+                    failure: None,
+                    warnings: Vec::new(),
+                    findings: Vec::new(),
+                    attribution: None,
+                    // Android passes every string with arguments through
+                    // String.format unless it is marked formatted="false".
+                    format_flags: vec![
+                        if formatted { "c-format" } else { "no-c-format" }.to_string(),
+                    ],
+                    references: String::new(),
+                    fuzzy: false,
+                    previous: None,
+                })
+            })
+            .collect()
+    }
+
+    fn write_translations(
+        &self,
+        translations: Vec<TranslationUnit>,
+        target_lang: &str,
+        output: &FileGuard,
+        options: &WriteOptions,
+    ) -> Result<(), PoLlmError> {
+        let output_path = output.path();
+        let document = AndroidStrings::read(output_path)?;
+        let targets = targets(translations, target_lang);
+
+        let text = write_values(&document.text, &targets).map_err(PoLlmError::Parse)?;
+
+        write_atomically(output_path, |writer| writer.write_all(text.as_bytes()))
+            .map_err(|e| PoLlmError::io("Failed to write Android strings file", e))?;
+
+        if options.verify_roundtrip {
+            let reparsed = AndroidStrings::read(output_path).map_err(|e| {
+                PoLlmError::Validation(format!("Round-trip check failed, cannot re-parse: {}", e))
+            })?;
+            let parsed = Document::parse(&reparsed.text).map_err(|e| {
+                PoLlmError::Validation(format!("Round-trip check failed, cannot re-parse: {}", e))
+            })?;
+            let values: HashMap<String, Value> = resources(&reparsed.text, &parsed)
+                .into_iter()
+                .map(|resource| match resource {
+                    Resource::Single { name, value, .. } => (name, Value::Single(value)),
+                    Resource::Plural { name, items, .. } => (name, Value::Plural(items)),
+                })
+                .collect();
+            let differing = targets
+                .iter()
+                .filter(|(name, value)| values.get(name) != Some(value))
+                .count();
+            if differing > 0 {
+                return Err(PoLlmError::Validation(format!(
+                    "Round-trip check failed: {} value(s) read back differently",
+                    differing
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The markup to write for a resource.
+#[derive(Debug, PartialEq)]
+enum Value {
+    Single(String),
+    Plural(Vec<(String, String)>),
+}
+
+/// The values to write for the translated units, by resource name.
+fn targets(translations: Vec<TranslationUnit>, target_lang: &str) -> Vec<(String, Value)> {
+    let categories = plural_forms_or_default(target_lang).categories;
+    let mut targets = Vec::new();
+    for unit in translations
+        .into_iter()
+        .filter(|unit| unit.failure.is_none())
+    {
+        let Some(name) = unit.context else {
+            continue;
+        };
+        match (unit.msg_id_plural, unit.msg_str_plural, unit.msg_str) {
+            (Some(_), Some(forms), _) if forms.iter().any(|form| !form.is_empty()) => {
+                let items = cldr_forms(categories, &forms)
+                    .into_iter()
+                    .map(|(category, form)| (category.to_string(), escape_android(form)))
+                    .collect();
+                targets.push((name, Value::Plural(items)));
+            }
+            (None, _, Some(text)) if !text.is_empty() => {
+                targets.push((name, Value::Single(escape_android(&text))));
+            }
+            _ => {}
+        }
+    }
+    targets
+}
+
+/// Escapes what Android's resource compiler would otherwise reject or
+/// strip in the text between tags: bare apostrophes and double quotes, a
+/// leading `@` or `?`, and ampersands that do not start an entity.
+fn escape_android(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut in_tag = false;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            '\\' if !in_tag => {
+                escaped.push(c);
+                if let Some((_, next)) = chars.next() {
+                    escaped.push(next);
+                }
+                continue;
+            }
+            '\'' | '"' if !in_tag => escaped.push('\\'),
+            '@' | '?' if i == 0 => escaped.push('\\'),
+            '&' if !starts_entity(&text[i..]) => {
+                escaped.push_str("&amp;");
+                continue;
+            }
+            _ => {}
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn starts_entity(text: &str) -> bool {
+    let Some(end) = text.find(';') else {
+        return false;
+    };
+    let name = &text[1..end];
+    !name.is_empty()
+        && (name.chars().all(|c| c.is_ascii_alphanumeric())
+            || name
+                .strip_prefix('#')
+                .is_some_and(|n| n.chars().all(|c| c.is_ascii_hexdigit() || c == 'x')))
+}
+
+/// Replaces each named resource's value, appending the ones the file does
+/// not have yet before `</resources>` in the file's indentation.
+fn write_values(text: &str, targets: &[(String, Value)]) -> Result<String, String> {
+    let document = Document::parse(text).map_err(|e| e.to_string())?;
+    let root = document.root_element();
+    let existing: HashMap<String, Node> = root
+        .children()
+        .filter(|n| n.is_element() && RESOURCE_ELEMENTS.contains(&n.tag_name().name()))
+        .filter_map(|n| Some((n.attribute("name")?.to_string(), n)))
+        .collect();
+
+    let indent = root
+        .children()
+        .find(|n| n.is_element())
+        .map_or("    ", |n| indent_before(text, n.range().start));
+    let close_start = text[..root.range().end]
+        .rfind("</")
+        .ok_or("<resources> has no closing tag")?;
+    let mut appended = String::new();
+    if !text[..close_start].ends_with('\n') {
+        appended.push('\n');
+    }
+
+    let element = |name: &str, value: &Value| {
+        let name = escape_xml(name).replace('"', "&quot;");
+        match value {
+            Value::Single(text) => format!("<string name=\"{}\">{}</string>", name, text),
+            Value::Plural(items) => {
+                let mut element = format!("<plurals name=\"{}\">", name);
+                for (quantity, text) in items {
+                    element.push_str(&format!(
+                        "\n{0}{0}<item quantity=\"{1}\">{2}</item>",
+                        indent, quantity, text
+                    ));
+                }
+                element.push_str(&format!("\n{}</plurals>", indent));
+                element
+            }
+        }
+    };
+
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+    let mut added = HashSet::new();
+    for (name, value) in targets {
+        match (existing.get(name), value) {
+            (Some(node), Value::Single(markup))
+                if node.has_tag_name("string")
+                    && !text[..start_tag_end(text, node.range().start)].ends_with("/>") =>
+            {
+                edits.push((inner_range(text, *node), markup.clone()));
+            }
+            (Some(node), _) => edits.push((node.range(), element(name, value))),
+            (None, _) if added.insert(name) => {
+                appended.push_str(&format!("{}{}\n", indent, element(name, value)));
+            }
+            (None, _) => {}
+        }
+    }
+    if !added.is_empty() {
+        edits.push((close_start..close_start, appended));
+    }
+
+    // Apply from the end so earlier offsets stay valid.
+    edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+    let mut result = text.to_string();
+    for (range, replacement) in edits {
+        result.replace_range(range, &replacement);
+    }
+
+    Document::parse(&result).map_err(|e| format!("Rewritten document is not valid XML: {}", e))?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const TEMPLATE: &str = "<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<resources>
+    <string name=\"open\">Open</string>
+    <plurals name=\"files\">
+        <item quantity=\"one\">%d file</item>
+        <item quantity=\"other\">%d files</item>
+    </plurals>
+</resources>
+";
+
+    fn unit(value: serde_json::Value) -> TranslationUnit {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn translated(target_lang: &str) -> String {
+        let template = AndroidStrings::parse(TEMPLATE.to_string()).unwrap();
+        let units = vec![
+            unit(json!({"msg_id": "Open", "msg_str": "Öffnen", "context": "open"})),
+            unit(json!({
+                "msg_id": "%d file",
+                "msg_id_plural": "%d files",
+                "msg_str_plural": ["%d файл", "%d файла", "%d файлов"],
+                "context": "files",
+            })),
+        ];
+        write_values(
+            &template.preamble().unwrap().text,
+            &targets(units, target_lang),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn plurals_without_other_get_it() {
+        assert_eq!(
+            translated("ru"),
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<resources>
+    <string name=\"open\">Öffnen</string>
+    <plurals name=\"files\">
+        <item quantity=\"one\">%d файл</item>
+        <item quantity=\"few\">%d файла</item>
+        <item quantity=\"many\">%d файлов</item>
+        <item quantity=\"other\">%d файлов</item>
+    </plurals>
+</resources>
+"
+        );
+    }
+
+    #[test]
+    fn written_plurals_count_as_translated() {
+        let adapter = AndroidAdapter;
+        let template: translations::Document =
+            Box::new(AndroidStrings::parse(TEMPLATE.to_string()).unwrap());
+        let existing: translations::Document =
+            Box::new(AndroidStrings::parse(translated("ru")).unwrap());
+        assert!(adapter.extract_units(&existing, &template, true).is_empty());
+        assert_eq!(adapter.extract_units(&existing, &template, false).len(), 2);
+    }
+
+    #[test]
+    fn escapes_what_aapt_rejects() {
+        assert_eq!(escape_android("L'app \"x\""), "L\\'app \\\"x\\\"");
+        assert_eq!(escape_android("@home"), "\\@home");
+        assert_eq!(escape_android("A & B &amp; C"), "A &amp; B &amp; C");
+        assert_eq!(
+            escape_android("<xliff:g id=\"n\">%d</xliff:g> it's"),
+            "<xliff:g id=\"n\">%d</xliff:g> it\\'s"
+        );
+    }
+}
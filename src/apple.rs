@@ -0,0 +1,806 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use encoding_rs::{UTF_16BE, UTF_16LE};
+use roxmltree::{Document, Node, ParsingOptions};
+
+use crate::{
+    configs::DocumentFormat,
+    errors::PoLlmError,
+    files::{FileGuard, write_atomically},
+    plurals::{cldr_forms, plural_forms_or_default},
+    translations::{
+        self, FormatAdapter, TranslationUnit, WriteOptions, document_as, existing_or_template,
+    },
+};
+
+/// Comment Xcode writes for strings that have none; useless as context.
+const NO_COMMENT: &str = "No comment provided by engineer.";
+const FORMAT_KEY: &str = "NSStringLocalizedFormatKey";
+const PLURAL_CATEGORIES: &[&str] = &["zero", "one", "two", "few", "many", "other"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TextEncoding {
+    Utf8 { bom: bool },
+    Utf16Le,
+    Utf16Be,
+}
+
+/// A `.strings` file: `"key" = "value";` entries with optional comments.
+#[derive(Debug, Clone)]
+pub struct StringsFile {
+    text: String,
+    encoding: TextEncoding,
+    entries: Vec<StringsEntry>,
+}
+
+#[derive(Debug, Clone)]
+struct StringsEntry {
+    key: String,
+    value: String,
+    comment: Option<String>,
+    /// Byte range of the value literal, quotes included.
+    value_range: Range<usize>,
+}
+
+impl StringsFile {
+    pub fn read(path: &Path) -> Result<Self, PoLlmError> {
+        let bytes = fs::read(path)
+            .map_err(|e| PoLlmError::io(format!("Failed to read strings file {:?}", path), e))?;
+        let (text, encoding) = decode_strings(&bytes).map_err(|e| {
+            PoLlmError::Parse(format!("Failed to decode strings file {:?}: {}", path, e))
+        })?;
+        Self::parse(text, encoding).map_err(|e| {
+            PoLlmError::Parse(format!("Failed to parse strings file {:?}: {}", path, e))
+        })
+    }
+
+    fn parse(text: String, encoding: TextEncoding) -> Result<Self, String> {
+        let entries = StringsParser {
+            text: &text,
+            pos: 0,
+        }
+        .entries()?;
+        Ok(Self {
+            text,
+            encoding,
+            entries,
+        })
+    }
+
+    fn values(&self) -> HashMap<&str, &str> {
+        self.entries
+            .iter()
+            .map(|entry| (entry.key.as_str(), entry.value.as_str()))
+            .collect()
+    }
+
+    fn encode(&self, text: &str) -> Vec<u8> {
+        match self.encoding {
+            TextEncoding::Utf8 { bom } => {
+                let mut bytes = if bom {
+                    vec![0xEF, 0xBB, 0xBF]
+                } else {
+                    Vec::new()
+                };
+                bytes.extend_from_slice(text.as_bytes());
+                bytes
+            }
+            TextEncoding::Utf16Le => [0xFF, 0xFE]
+                .into_iter()
+                .chain(text.encode_utf16().flat_map(u16::to_le_bytes))
+                .collect(),
+            TextEncoding::Utf16Be => [0xFE, 0xFF]
+                .into_iter()
+                .chain(text.encode_utf16().flat_map(u16::to_be_bytes))
+                .collect(),
+        }
+    }
+}
+
+fn decode_strings(bytes: &[u8]) -> Result<(String, TextEncoding), String> {
+    let (encoding, body) = match bytes {
+        [0xEF, 0xBB, 0xBF, rest @ ..] => (TextEncoding::Utf8 { bom: true }, rest),
+        [0xFF, 0xFE, rest @ ..] => (TextEncoding::Utf16Le, rest),
+        [0xFE, 0xFF, rest @ ..] => (TextEncoding::Utf16Be, rest),
+        // Without a BOM, ASCII text in UTF-16 shows up as alternating zero bytes.
+        [first, 0, ..] if *first != 0 => (TextEncoding::Utf16Le, bytes),
+        [0, second, ..] if *second != 0 => (TextEncoding::Utf16Be, bytes),
+        _ => (TextEncoding::Utf8 { bom: false }, bytes),
+    };
+
+    let text = match encoding {
+        TextEncoding::Utf8 { .. } => std::str::from_utf8(body)
+            .map(str::to_string)
+            .map_err(|e| e.to_string())?,
+        TextEncoding::Utf16Le => UTF_16LE
+            .decode_without_bom_handling_and_without_replacement(body)
+            .ok_or("invalid UTF-16LE")?
+            .into_owned(),
+        TextEncoding::Utf16Be => UTF_16BE
+            .decode_without_bom_handling_and_without_replacement(body)
+            .ok_or("invalid UTF-16BE")?
+            .into_owned(),
+    };
+    Ok((text, encoding))
+}
+
+struct StringsParser<'t> {
+    text: &'t str,
+    pos: usize,
+}
+
+impl StringsParser<'_> {
+    fn entries(mut self) -> Result<Vec<StringsEntry>, String> {
+        let mut entries = Vec::new();
+        let mut comment = None;
+
+        loop {
+            self.skip_whitespace();
+            let rest = &self.text[self.pos..];
+            if rest.is_empty() {
+                return Ok(entries);
+            }
+
+            if let Some(body) = rest.strip_prefix("/*") {
+                let end = body
+                    .find("*/")
+                    .ok_or_else(|| self.error("unterminated comment"))?;
+                comment = Some(body[..end].trim().to_string());
+                self.pos += 2 + end + 2;
+                continue;
+            }
+            if let Some(body) = rest.strip_prefix("//") {
+                let end = body.find('\n').unwrap_or(body.len());
+                comment = Some(body[..end].trim().to_string());
+                self.pos += 2 + end;
+                continue;
+            }
+
+            let (key, _) = self.literal()?;
+            self.expect('=')?;
+            let (value, value_range) = self.literal()?;
+            self.expect(';')?;
+
+            entries.push(StringsEntry {
+                key,
+                value,
+                comment: comment.take().filter(|c| !c.is_empty() && c != NO_COMMENT),
+                value_range,
+            });
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.text[self.pos..].starts_with(expected) {
+            self.pos += expected.len_utf8();
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", expected)))
+        }
+    }
+
+    /// A quoted string or a bare word, returned unescaped with its byte range.
+    fn literal(&mut self) -> Result<(String, Range<usize>), String> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let rest = &self.text[start..];
+
+        let Some(body) = rest.strip_prefix('"') else {
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || "_.-$:/".contains(c)))
+                .unwrap_or(rest.len());
+            if len == 0 {
+                return Err(self.error("expected a string"));
+            }
+            self.pos += len;
+            return Ok((rest[..len].to_string(), start..self.pos));
+        };
+
+        let mut value = String::new();
+        let mut chars = body.char_indices();
+        while let Some((offset, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos = start + 1 + offset + 1;
+                    return Ok((value, start..self.pos));
+                }
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some('r') => value.push('\r'),
+                    Some('0') => value.push('\0'),
+                    Some('u' | 'U') => {
+                        let hex: String = chars.by_ref().take(4).map(|(_, c)| c).collect();
+                        let c = u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| self.error("invalid \\U escape"))?;
+                        value.push(c);
+                    }
+                    Some(c) => value.push(c),
+                    None => break,
+                },
+                c => value.push(c),
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    fn error(&self, message: &str) -> String {
+        let line = self.text[..self.pos].matches('\n').count() + 1;
+        format!("line {}: {}", line, message)
+    }
+}
+
+fn escape_strings(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn translated_targets(translations: Vec<TranslationUnit>) -> Vec<TranslationUnit> {
+    translations
+        .into_iter()
+        .filter(|unit| unit.failure.is_none() && unit.context.is_some())
+        .filter(|unit| {
+            unit.translation_pairs()
+                .iter()
+                .any(|(_, translation)| !translation.is_empty())
+        })
+        .collect()
+}
+
+/// Outputs start as a copy of their input, so an entry counts as translated
+/// once its value no longer matches the source.
+pub struct StringsAdapter;
+
+/// Resources go to a `{lang}.lproj` directory next to the one holding the
+/// input, under the same file name.
+fn lproj_path(input_path: &Path, target_lang: &str) -> Result<PathBuf, PoLlmError> {
+    let file_name = input_path.file_name().ok_or_else(|| {
+        PoLlmError::io(
+            format!("Invalid filename {:?}", input_path),
+            io::ErrorKind::InvalidInput.into(),
+        )
+    })?;
+    let parent = input_path.parent().unwrap_or(Path::new(""));
+    let base = match parent.extension() {
+        Some(ext) if ext == "lproj" => parent.parent().unwrap_or(Path::new("")),
+        _ => parent,
+    };
+    Ok(base.join(format!("{}.lproj", target_lang)).join(file_name))
+}
+
+impl FormatAdapter for StringsAdapter {
+    fn format(&self) -> DocumentFormat {
+        DocumentFormat::Strings
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["strings"]
+    }
+
+    fn output_path(
+        &self,
+        input_path: &Path,
+        target_lang: &str,
+    ) -> Result<Option<PathBuf>, PoLlmError> {
+        Ok(Some(lproj_path(input_path, target_lang)?))
+    }
+
+    fn load_template(&self, path: &Path) -> Result<translations::Document, PoLlmError> {
+        Ok(Box::new(StringsFile::read(path)?))
+    }
+
+    fn load_existing(
+        &self,
+        output_path: &Path,
+        template: &translations::Document,
+    ) -> Result<translations::Document, PoLlmError> {
+        existing_or_template(output_path, template, StringsFile::read)
+    }
+
+    fn extract_units(
+        &self,
+        existing: &translations::Document,
+        template: &translations::Document,
+        skip_translated: bool,
+    ) -> Vec<TranslationUnit> {
+        let document: &StringsFile = document_as(existing);
+        let template: &StringsFile = document_as(template);
+        let existing = document.values();
+        let mut seen = HashSet::new();
+
+        template
+            .entries
+            .iter()
+            .filter(|entry| !entry.value.trim().is_empty())
+            .filter(|entry| {
+                !skip_translated
+                    || existing
+                        .get(entry.key.as_str())
+                        .is_none_or(|value| value.is_empty() || *value == entry.value)
+            })
+            .filter(|entry| seen.insert(entry.key.clone()))
+            .map(|entry| TranslationUnit {
+                msg_id: entry.value.clone(),
+                msg_id_plural: None,
+                msg_str: Some(String::new()),
+                msg_str_plural: None,
+                context: Some(entry.key.clone()),
+                comments: entry.comment.clone(),
+                rationale: None,
+                failure: None,
+                warnings: Vec::new(),
+                findings: Vec::new(),
+                attribution: None,
+                format_flags: Vec::new(),
+                references: String::new(),
+                fuzzy: false,
+                previous: None,
+            })
+            .collect()
+    }
+
+    fn write_translations(
+        &self,
+        translations: Vec<TranslationUnit>,
+        _target_lang: &str,
+        output: &FileGuard,
+        options: &WriteOptions,
+    ) -> Result<(), PoLlmError> {
+        let output_path = output.path();
+        let document = StringsFile::read(output_path)?;
+
+        let mut targets: HashMap<String, TranslationUnit> = translated_targets(translations)
+            .into_iter()
+            .filter_map(|unit| Some((unit.context.clone()?, unit)))
+            .collect();
+        let expected: HashMap<String, String> = targets
+            .iter()
+            .map(|(key, unit)| (key.clone(), unit.msg_str.clone().unwrap_or_default()))
+            .collect();
+
+        let mut text = document.text.clone();
+        for entry in document.entries.iter().rev() {
+            if let Some(unit) = targets.remove(&entry.key) {
+                let value = unit.msg_str.unwrap_or_default();
+                text.replace_range(
+                    entry.value_range.clone(),
+                    &format!("\"{}\"", escape_strings(&value)),
+                );
+            }
+        }
+
+        // Keys added to the input since the output was copied from it.
+        let mut appended: Vec<_> = targets.into_values().collect();
+        appended.sort_by(|a, b| a.context.cmp(&b.context));
+        for unit in appended {
+            if !text.is_empty() && !text.ends_with('\n') {
+                text.push('\n');
+            }
+            text.push('\n');
+            if let Some(comment) = &unit.comments {
+                text.push_str(&format!("/* {} */\n", comment.replace("*/", "* /")));
+            }
+            text.push_str(&format!(
+                "\"{}\" = \"{}\";\n",
+                escape_strings(unit.context.as_deref().unwrap_or_default()),
+                escape_strings(unit.msg_str.as_deref().unwrap_or_default())
+            ));
+        }
+
+        let rewritten = StringsFile::parse(text, document.encoding).map_err(|e| {
+            PoLlmError::Validation(format!("Rewritten strings file does not parse: {}", e))
+        })?;
+        let bytes = document.encode(&rewritten.text);
+        write_atomically(output_path, |writer| writer.write_all(&bytes))
+            .map_err(|e| PoLlmError::io("Failed to write strings file", e))?;
+
+        if options.verify_roundtrip {
+            let reparsed = StringsFile::read(output_path).map_err(|e| {
+                PoLlmError::Validation(format!("Round-trip check failed, cannot re-parse: {}", e))
+            })?;
+            let values = reparsed.values();
+            let differing = expected
+                .iter()
+                .filter(|(key, value)| values.get(key.as_str()) != Some(&value.as_str()))
+                .count();
+            if differing > 0 {
+                return Err(PoLlmError::Validation(format!(
+                    "Round-trip check failed: {} value(s) read back differently",
+                    differing
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A `.stringsdict` plist holding plural variants.
+#[derive(Debug, Clone)]
+pub struct StringsdictFile {
+    text: String,
+}
+
+/// A translatable piece of a `.stringsdict` entry: its format string, or one
+/// of the plural variables it references.
+struct StringsdictItem<'a, 'input> {
+    context: String,
+    kind: ItemKind<'a, 'input>,
+}
+
+enum ItemKind<'a, 'input> {
+    Format {
+        text: String,
+        node: Node<'a, 'input>,
+    },
+    Plural {
+        /// `(category, text)` pairs in document order.
+        forms: Vec<(String, String)>,
+        /// `<key>`/`<string>` node pairs holding the forms.
+        nodes: Vec<(Node<'a, 'input>, Node<'a, 'input>)>,
+    },
+}
+
+impl StringsdictFile {
+    pub fn read(path: &Path) -> Result<Self, PoLlmError> {
+        let text = fs::read_to_string(path).map_err(|e| {
+            PoLlmError::io(format!("Failed to read stringsdict file {:?}", path), e)
+        })?;
+        parse_plist(&text).map_err(|e| {
+            PoLlmError::Parse(format!(
+                "Failed to parse stringsdict file {:?}: {}",
+                path, e
+            ))
+        })?;
+        Ok(Self { text })
+    }
+
+    fn texts(&self) -> HashMap<String, Vec<String>> {
+        let Ok(document) = parse_plist(&self.text) else {
+            return HashMap::new();
+        };
+        stringsdict_items(&document)
+            .into_iter()
+            .map(|item| {
+                let texts = match item.kind {
+                    ItemKind::Format { text, .. } => vec![text],
+                    ItemKind::Plural { forms, .. } => forms.into_iter().map(|(_, t)| t).collect(),
+                };
+                (item.context, texts)
+            })
+            .collect()
+    }
+}
+
+/// Plists always carry a DOCTYPE, which roxmltree rejects by default.
+fn parse_plist(text: &str) -> Result<Document<'_>, roxmltree::Error> {
+    Document::parse_with_options(
+        text,
+        ParsingOptions {
+            allow_dtd: true,
+            ..ParsingOptions::default()
+        },
+    )
+}
+
+fn dict_pairs<'a, 'input>(dict: Node<'a, 'input>) -> Vec<(Node<'a, 'input>, Node<'a, 'input>)> {
+    let elements: Vec<_> = dict.children().filter(Node::is_element).collect();
+    elements
+        .chunks_exact(2)
+        .filter(|pair| pair[0].has_tag_name("key"))
+        .map(|pair| (pair[0], pair[1]))
+        .collect()
+}
+
+fn element_text(node: Node) -> String {
+    node.children().filter_map(|n| n.text()).collect()
+}
+
+fn stringsdict_items<'a, 'input>(
+    document: &'a Document<'input>,
+) -> Vec<StringsdictItem<'a, 'input>> {
+    let Some(root) = document
+        .root_element()
+        .children()
+        .find(|n| n.has_tag_name("dict"))
+    else {
+        return Vec::new();
+    };
+
+    let mut items = Vec::new();
+    for (key, entry) in dict_pairs(root) {
+        if !entry.has_tag_name("dict") {
+            continue;
+        }
+        let key = element_text(key);
+
+        for (name, value) in dict_pairs(entry) {
+            let name = element_text(name);
+            if name == FORMAT_KEY && value.has_tag_name("string") {
+                items.push(StringsdictItem {
+                    context: key.clone(),
+                    kind: ItemKind::Format {
+                        text: element_text(value),
+                        node: value,
+                    },
+                });
+                continue;
+            }
+            if !value.has_tag_name("dict") {
+                continue;
+            }
+
+            let nodes: Vec<_> = dict_pairs(value)
+                .into_iter()
+                .filter(|(category, form)| {
+                    form.has_tag_name("string")
+                        && PLURAL_CATEGORIES.contains(&element_text(*category).as_str())
+                })
+                .collect();
+            if nodes.is_empty() {
+                continue;
+            }
+            items.push(StringsdictItem {
+                context: format!("{}/{}", key, name),
+                kind: ItemKind::Plural {
+                    forms: nodes
+                        .iter()
+                        .map(|(category, form)| (element_text(*category), element_text(*form)))
+                        .collect(),
+                    nodes,
+                },
+            });
+        }
+    }
+    items
+}
+
+/// Whether a format string has text besides its `%#@variable@` references.
+fn has_own_text(format: &str) -> bool {
+    let mut rest = format;
+    while let Some(start) = rest.find("%#@") {
+        if rest[..start].chars().any(char::is_alphabetic) {
+            return true;
+        }
+        match rest[start + 3..].find('@') {
+            Some(end) => rest = &rest[start + 3 + end + 1..],
+            None => return rest[start..].chars().any(char::is_alphabetic),
+        }
+    }
+    rest.chars().any(char::is_alphabetic)
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Outputs start as a copy of their input, so an entry counts as translated
+/// once its text no longer matches the source.
+pub struct StringsdictAdapter;
+
+impl FormatAdapter for StringsdictAdapter {
+    fn format(&self) -> DocumentFormat {
+        DocumentFormat::Stringsdict
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["stringsdict"]
+    }
+
+    fn output_path(
+        &self,
+        input_path: &Path,
+        target_lang: &str,
+    ) -> Result<Option<PathBuf>, PoLlmError> {
+        Ok(Some(lproj_path(input_path, target_lang)?))
+    }
+
+    fn load_template(&self, path: &Path) -> Result<translations::Document, PoLlmError> {
+        Ok(Box::new(StringsdictFile::read(path)?))
+    }
+
+    fn load_existing(
+        &self,
+        output_path: &Path,
+        template: &translations::Document,
+    ) -> Result<translations::Document, PoLlmError> {
+        existing_or_template(output_path, template, StringsdictFile::read)
+    }
+
+    fn extract_units(
+        &self,
+        existing: &translations::Document,
+        template: &translations::Document,
+        skip_translated: bool,
+    ) -> Vec<TranslationUnit> {
+        let document: &StringsdictFile = document_as(existing);
+        let template: &StringsdictFile = document_as(template);
+        let Ok(parsed) = parse_plist(&template.text) else {
+            return Vec::new();
+        };
+        let existing = document.texts();
+        let source = template.texts();
+
+        stringsdict_items(&parsed)
+            .into_iter()
+            .filter(|item| {
+                !skip_translated
+                    || existing
+                        .get(&item.context)
+                        .is_none_or(|texts| Some(texts) == source.get(&item.context))
+            })
+            .filter_map(|item| {
+                let (msg_id, msg_id_plural) = match item.kind {
+                    ItemKind::Format { text, .. } if has_own_text(&text) => (text, None),
+                    ItemKind::Format { .. } => return None,
+                    ItemKind::Plural { forms, .. } => {
+                        let form = |category: &str| {
+                            forms.iter().find(|(c, _)| c == category).map(|(_, t)| t)
+                        };
+                        let singular = form("one").or(forms.first().map(|(_, t)| t))?;
+                        let plural = form("other").or(forms.last().map(|(_, t)| t))?;
+                        (singular.clone(), Some(plural.clone()))
+                    }
+                };
+                let is_plural = msg_id_plural.is_some();
+
+                Some(TranslationUnit {
+                    msg_id,
+                    msg_id_plural,
+                    msg_str: (!is_plural).then(String::new),
+                    msg_str_plural: is_plural.then(Vec::new),
+                    context: Some(item.context),
+                    comments: None,
+                    rationale: None,
+                    failure: None,
+                    warnings: Vec::new(),
+                    findings: Vec::new(),
+                    attribution: None,
+                    format_flags: Vec::new(),
+                    references: String::new(),
+                    fuzzy: false,
+                    previous: None,
+                })
+            })
+            .collect()
+    }
+
+    fn write_translations(
+        &self,
+        translations: Vec<TranslationUnit>,
+        target_lang: &str,
+        output: &FileGuard,
+        options: &WriteOptions,
+    ) -> Result<(), PoLlmError> {
+        let output_path = output.path();
+        let document = StringsdictFile::read(output_path)?;
+        let parsed = parse_plist(&document.text).map_err(|e| PoLlmError::Parse(e.to_string()))?;
+        let categories = plural_forms_or_default(target_lang).categories;
+
+        let targets: HashMap<String, TranslationUnit> = translated_targets(translations)
+            .into_iter()
+            .filter_map(|unit| Some((unit.context.clone()?, unit)))
+            .collect();
+
+        let text = document.text.as_str();
+        let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+        for item in stringsdict_items(&parsed) {
+            let Some(unit) = targets.get(&item.context) else {
+                continue;
+            };
+
+            match item.kind {
+                ItemKind::Format { node, .. } => {
+                    let range = node.range();
+                    edits.push((
+                        range,
+                        format!(
+                            "<string>{}</string>",
+                            escape_xml(unit.msg_str.as_deref().unwrap_or_default())
+                        ),
+                    ));
+                }
+                ItemKind::Plural { nodes, .. } => {
+                    let Some(forms) = &unit.msg_str_plural else {
+                        continue;
+                    };
+                    let pairs = cldr_forms(categories, forms);
+
+                    let indent = nodes[0]
+                        .0
+                        .prev_sibling()
+                        .and_then(|n| n.text())
+                        .filter(|t| t.trim().is_empty())
+                        .unwrap_or("");
+                    let replacement = pairs
+                        .iter()
+                        .enumerate()
+                        .map(|(i, (category, form))| {
+                            format!(
+                                "{}<key>{}</key>{}<string>{}</string>",
+                                if i == 0 { "" } else { indent },
+                                category,
+                                indent,
+                                escape_xml(form)
+                            )
+                        })
+                        .collect::<String>();
+
+                    for (i, (key, form)) in nodes.iter().enumerate() {
+                        // Take the whitespace before each later pair along with it.
+                        let start = match i {
+                            0 => key.range().start,
+                            _ => key
+                                .prev_sibling()
+                                .filter(|n| n.is_text())
+                                .map_or(key.range().start, |n| n.range().start),
+                        };
+                        let replacement = if i == 0 {
+                            replacement.clone()
+                        } else {
+                            String::new()
+                        };
+                        edits.push((start..form.range().end, replacement));
+                    }
+                }
+            }
+        }
+
+        edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+        let mut rewritten = text.to_string();
+        for (range, replacement) in edits {
+            rewritten.replace_range(range, &replacement);
+        }
+        parse_plist(&rewritten).map_err(|e| {
+            PoLlmError::Validation(format!("Rewritten stringsdict is not valid XML: {}", e))
+        })?;
+
+        write_atomically(output_path, |writer| writer.write_all(rewritten.as_bytes()))
+            .map_err(|e| PoLlmError::io("Failed to write stringsdict file", e))?;
+
+        if options.verify_roundtrip {
+            let reparsed = StringsdictFile::read(output_path).map_err(|e| {
+                PoLlmError::Validation(format!("Round-trip check failed, cannot re-parse: {}", e))
+            })?;
+            let texts = reparsed.texts();
+            let missing = targets
+                .keys()
+                .filter(|context| !texts.contains_key(*context))
+                .count();
+            if missing > 0 {
+                return Err(PoLlmError::Validation(format!(
+                    "Round-trip check failed: {} message(s) missing after writing",
+                    missing
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
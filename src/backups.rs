@@ -0,0 +1,112 @@
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+pub struct BackupManager {
+    suffix: String,
+    keep: usize,
+    timestamp: String,
+    seen: Mutex<HashSet<PathBuf>>,
+    created: Mutex<Vec<PathBuf>>,
+}
+
+impl BackupManager {
+    pub fn new(suffix: &str, keep: usize) -> Self {
+        Self {
+            suffix: suffix.to_string(),
+            keep,
+            timestamp: chrono::Local::now().format("%Y%m%d%H%M%S").to_string(),
+            seen: Mutex::new(HashSet::new()),
+            created: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn backup_once(&self, path: &Path) -> io::Result<Option<PathBuf>> {
+        if !self.seen.lock().unwrap().insert(path.to_path_buf()) {
+            return Ok(None);
+        }
+
+        match fs::metadata(path) {
+            Ok(meta) if meta.len() > 0 => {}
+            _ => return Ok(None),
+        }
+
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let backup_path = self.backup_path(path);
+
+        fs::copy(path, &backup_path)?;
+        self.created.lock().unwrap().push(backup_path.clone());
+        self.prune(path, &file_name)?;
+
+        Ok(Some(backup_path))
+    }
+
+    /// The backup this run made of `path`, if any.
+    pub fn backup_of(&self, path: &Path) -> Option<PathBuf> {
+        let backup_path = self.backup_path(path);
+        self.created
+            .lock()
+            .unwrap()
+            .contains(&backup_path)
+            .then_some(backup_path)
+    }
+
+    fn backup_path(&self, path: &Path) -> PathBuf {
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        path.with_file_name(format!(
+            "{}{}",
+            file_name,
+            self.suffix.replace("{timestamp}", &self.timestamp)
+        ))
+    }
+
+    pub fn created(&self) -> Vec<PathBuf> {
+        self.created.lock().unwrap().clone()
+    }
+
+    fn prune(&self, path: &Path, file_name: &str) -> io::Result<()> {
+        if self.keep == 0 || !self.suffix.contains("{timestamp}") {
+            return Ok(());
+        }
+
+        let (prefix, postfix) = self.suffix.split_once("{timestamp}").unwrap();
+        let prefix = format!("{}{}", file_name, prefix);
+        let dir = match path.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p,
+            _ => Path::new("."),
+        };
+
+        let mut backups: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(Result::ok)
+            .map(|e| e.path())
+            .filter(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .and_then(|n| n.strip_prefix(prefix.as_str()))
+                    .and_then(|n| n.strip_suffix(postfix))
+                    .is_some_and(|ts| !ts.is_empty() && ts.chars().all(|c| c.is_ascii_digit()))
+            })
+            .collect();
+
+        if backups.len() <= self.keep {
+            return Ok(());
+        }
+
+        backups.sort();
+        let excess = backups.len() - self.keep;
+        for old in backups.into_iter().take(excess) {
+            fs::remove_file(old)?;
+        }
+
+        Ok(())
+    }
+}
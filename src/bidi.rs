@@ -0,0 +1,201 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::configs::BidiMode;
+
+const LRM: char = '\u{200e}';
+const FSI: char = '\u{2068}';
+const PDI: char = '\u{2069}';
+
+/// Explicit directional formatting characters: embeddings and overrides
+/// closed by PDF, isolates closed by PDI.
+const EMBEDDINGS: &[char] = &['\u{202a}', '\u{202b}', '\u{202d}', '\u{202e}'];
+const PDF: char = '\u{202c}';
+const ISOLATES: &[char] = &['\u{2066}', '\u{2067}', FSI];
+
+/// Every character that changes the direction of what follows, the marks
+/// included.
+const DIRECTIONAL: &[char] = &[
+    '\u{200e}', '\u{200f}', '\u{061c}', '\u{202a}', '\u{202b}', '\u{202c}', '\u{202d}', '\u{202e}',
+    '\u{2066}', '\u{2067}', '\u{2068}', '\u{2069}',
+];
+
+/// The languages written right to left, by primary subtag, unless
+/// `rtl_languages` says otherwise.
+pub const RTL_LANGUAGES: &[&str] = &["ar", "fa", "ur", "ps", "ckb", "he", "yi", "dv", "sd", "ug"];
+
+/// Whether a language is one of `rtl_languages`, given as full tags such as
+/// `ar-EG` or as primary subtags.
+pub fn is_rtl(lang: &str, rtl_languages: &[String]) -> bool {
+    let lang = lang.trim().to_lowercase().replace('_', "-");
+    let primary = lang.split('-').next().unwrap_or_default();
+    rtl_languages.iter().any(|rtl| {
+        let rtl = rtl.trim().to_lowercase().replace('_', "-");
+        rtl == lang || rtl == primary
+    })
+}
+
+static RTL_LETTER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[\p{Arabic}\p{Hebrew}\p{Syriac}\p{Thaana}\p{Nko}]").unwrap());
+
+/// Latin words, numbers, URLs and placeholders, with the spaces between them: the runs
+/// an RTL paragraph may lay out in the wrong order.
+static LTR_RUN: LazyLock<Regex> = LazyLock::new(|| {
+    let atom = r"(?:\b(?:https?|ftp)://[^\s<>]+|\{[^{}]*\}|%(?:\d+\$)?[-+#0-9.]*[a-zA-Z@]|\$\w+|[\p{Latin}\d][\p{Latin}\p{M}\d._\-/+#@&']*)";
+    Regex::new(&format!(r"{0}(?:[ \t]+{0})*", atom)).unwrap()
+});
+
+/// Gives an RTL translation the directional characters of `mode`, whatever
+/// the model put there: the marks and isolates its source lacks are
+/// removed, then its Latin runs, placeholders, URLs and numbers such as
+/// `1.5` are wrapped in FSI/PDI isolates, or between LRMs with
+/// [`BidiMode::Mark`], so that they keep their order whatever surrounds
+/// them. Translations without RTL letters, or whose source carries
+/// directional characters of its own, keep those and get no more.
+pub fn normalize_bidi(source: &str, translation: &str, mode: BidiMode) -> String {
+    let (open, close) = match mode {
+        BidiMode::Isolate => (FSI, PDI),
+        BidiMode::Mark => (LRM, LRM),
+        BidiMode::Off | BidiMode::Check => return translation.to_string(),
+    };
+    let text: String = translation
+        .chars()
+        .filter(|c| !DIRECTIONAL.contains(c) || source.contains(*c))
+        .collect();
+    if !RTL_LETTER.is_match(&text) || text.contains(DIRECTIONAL) {
+        return text;
+    }
+
+    let mut isolated = String::with_capacity(text.len());
+    let mut last = 0;
+    for run in LTR_RUN.find_iter(&text) {
+        let trimmed = run
+            .as_str()
+            .trim_end_matches(['.', '-', '/', '\'', '&', '+', '#', '@', '_']);
+        // Digits alone already keep their order.
+        if trimmed
+            .chars()
+            .all(|c| c.is_ascii_digit() || c.is_whitespace())
+        {
+            continue;
+        }
+        isolated.push_str(&text[last..run.start()]);
+        isolated.push(open);
+        isolated.push_str(trimmed);
+        isolated.push(close);
+        last = run.start() + trimmed.len();
+    }
+    isolated.push_str(&text[last..]);
+    isolated
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    Embedding,
+    Isolate,
+}
+
+/// Embeddings and overrides must be closed by PDF and isolates by PDI, in
+/// the order they were opened, before the end of their line.
+pub fn check_directional_balance(text: &str) -> Result<(), String> {
+    for line in text.split('\n') {
+        let mut open = Vec::new();
+        for c in line.chars() {
+            if EMBEDDINGS.contains(&c) {
+                open.push(Scope::Embedding);
+            } else if ISOLATES.contains(&c) {
+                open.push(Scope::Isolate);
+            } else if c == PDF {
+                if open.pop() != Some(Scope::Embedding) {
+                    return Err(
+                        "directional formatting: PDF (U+202C) without an open embedding"
+                            .to_string(),
+                    );
+                }
+            } else if c == PDI && open.pop() != Some(Scope::Isolate) {
+                return Err(
+                    "directional formatting: PDI (U+2069) without an open isolate".to_string(),
+                );
+            }
+        }
+        if !open.is_empty() {
+            return Err(format!(
+                "directional formatting: {} embedding(s) or isolate(s) left open",
+                open.len()
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latin_runs_and_placeholders_are_isolated() {
+        assert_eq!(
+            normalize_bidi(
+                "Open %s in Google Chrome",
+                "افتح %s في Google Chrome",
+                BidiMode::Isolate
+            ),
+            "افتح \u{2068}%s\u{2069} في \u{2068}Google Chrome\u{2069}"
+        );
+        assert_eq!(
+            normalize_bidi("Open {file}.", "פתח את {file}.", BidiMode::Mark),
+            "פתח את \u{200e}{file}\u{200e}."
+        );
+        // Digits alone keep their order, a dotted number does not.
+        assert_eq!(
+            normalize_bidi("42 files", "42 ملفا", BidiMode::Isolate),
+            "42 ملفا"
+        );
+        assert_eq!(
+            normalize_bidi("Version 1.5", "الإصدار 1.5", BidiMode::Isolate),
+            "الإصدار \u{2068}1.5\u{2069}"
+        );
+    }
+
+    #[test]
+    fn only_rtl_translations_without_their_own_marks_change() {
+        for mode in [BidiMode::Off, BidiMode::Check] {
+            assert_eq!(normalize_bidi("Open %s", "افتح %s", mode), "افتح %s");
+        }
+        assert_eq!(
+            normalize_bidi("Open %s", "Ouvrir %s", BidiMode::Isolate),
+            "Ouvrir %s"
+        );
+        // Marks the model added are dropped before isolating; the source's own are kept.
+        assert_eq!(
+            normalize_bidi("Open %s", "افتح \u{200f}%s", BidiMode::Isolate),
+            "افتح \u{2068}%s\u{2069}"
+        );
+        assert_eq!(
+            normalize_bidi("\u{200f}Open %s", "\u{200f}افتح %s", BidiMode::Isolate),
+            "\u{200f}افتح %s"
+        );
+    }
+
+    #[test]
+    fn directional_formatting_must_balance() {
+        assert_eq!(
+            check_directional_balance("افتح \u{2068}%s\u{2069} \u{202b}x\u{202c}"),
+            Ok(())
+        );
+        assert!(check_directional_balance("افتح \u{2068}%s").is_err());
+        assert!(check_directional_balance("افتح %s\u{2069}").is_err());
+        assert!(check_directional_balance("\u{202b}\u{2068}x\u{202c}\u{2069}").is_err());
+        assert!(check_directional_balance("\u{2068}x\n\u{2069}").is_err());
+    }
+
+    #[test]
+    fn rtl_languages_match_by_tag_or_primary_subtag() {
+        let languages: Vec<String> = RTL_LANGUAGES.iter().map(|l| l.to_string()).collect();
+        assert!(is_rtl("ar_EG", &languages));
+        assert!(is_rtl("HE", &languages));
+        assert!(!is_rtl("fr", &languages));
+        assert!(!is_rtl("ar", &["ar-EG".to_string()]));
+    }
+}
@@ -0,0 +1,125 @@
+use std::{fs, path::Path};
+
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use polib::{catalog::Catalog, metadata::CatalogMetadata, po_file};
+
+use crate::errors::PoLlmError;
+
+const HEADER_SCAN_LIMIT: usize = 8192;
+
+pub fn read_catalog(path: &Path) -> Result<Catalog, PoLlmError> {
+    let bytes =
+        fs::read(path).map_err(|e| PoLlmError::io(format!("Failed to read {:?}", path), e))?;
+    let (text, warning) = decode(&bytes);
+
+    if let Some(warning) = warning {
+        eprintln!("         ⚠️  {}: {}", path.display(), warning);
+    }
+
+    po_file::parse_from_reader(text.as_bytes()).map_err(|e| PoLlmError::Parse(e.to_string()))
+}
+
+pub fn resolve_charset(label: &str) -> Result<&'static Encoding, String> {
+    match Encoding::for_label(label.trim().as_bytes()) {
+        Some(encoding) if encoding.output_encoding() == encoding => Ok(encoding),
+        Some(_) => Err(format!(
+            "charset \"{}\" cannot be used for PO output; use an ASCII-compatible charset such as UTF-8",
+            label
+        )),
+        None => Err(format!("unknown charset \"{}\"", label)),
+    }
+}
+
+pub fn set_charset(metadata: &mut CatalogMetadata, label: &str) {
+    let media_type = metadata
+        .content_type
+        .split(';')
+        .next()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .unwrap_or("text/plain");
+
+    metadata.content_type = format!("{}; charset={}", media_type, label);
+}
+
+pub fn encode(text: &str, label: &str) -> Result<Vec<u8>, String> {
+    let encoding = resolve_charset(label)?;
+    if encoding == UTF_8 {
+        return Ok(text.as_bytes().to_vec());
+    }
+
+    let (bytes, _, had_errors) = encoding.encode(text);
+    if had_errors {
+        return Err(format!(
+            "some translations cannot be represented in charset {}",
+            label
+        ));
+    }
+    Ok(bytes.into_owned())
+}
+
+/// The text of a PO file in the charset it declares, with a warning when
+/// it could not be decoded as such.
+pub fn decode(bytes: &[u8]) -> (String, Option<String>) {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(bytes) {
+        let (text, had_errors) = encoding.decode_without_bom_handling(&bytes[bom_length..]);
+        let warning = had_errors.then(|| {
+            format!(
+                "contains bytes that are not valid {}; they were replaced",
+                encoding.name()
+            )
+        });
+        return (text.into_owned(), warning);
+    }
+
+    let declared = declared_charset(bytes).filter(|label| !label.eq_ignore_ascii_case("CHARSET"));
+    let (encoding, warning) = match &declared {
+        Some(label) => match Encoding::for_label(label.as_bytes()) {
+            Some(encoding) => (encoding, None),
+            None => (
+                UTF_8,
+                Some(format!("declares unknown charset \"{}\"", label)),
+            ),
+        },
+        None => (UTF_8, None),
+    };
+
+    let (text, had_errors) = encoding.decode_without_bom_handling(bytes);
+    if !had_errors {
+        return (text.into_owned(), warning);
+    }
+
+    match declared {
+        Some(label) if encoding != UTF_8 => {
+            let warning = format!(
+                "declares charset \"{}\" but contains invalid bytes; they were replaced",
+                label
+            );
+            (text.into_owned(), Some(warning))
+        }
+        _ => {
+            let problem = match declared {
+                Some(label) => format!("declares charset \"{}\" but is not valid UTF-8", label),
+                None => "declares no charset and is not valid UTF-8".to_string(),
+            };
+            let (text, _) = WINDOWS_1252.decode_without_bom_handling(bytes);
+            let warning = format!("{}; reading it as ISO-8859-1", problem);
+            (text.into_owned(), Some(warning))
+        }
+    }
+}
+
+fn declared_charset(bytes: &[u8]) -> Option<String> {
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(HEADER_SCAN_LIMIT)]);
+
+    head.lines()
+        .take_while(|line| !line.trim().is_empty())
+        .find_map(|line| {
+            let start = line.to_ascii_lowercase().find("charset=")? + "charset=".len();
+            let label: String = line[start..]
+                .chars()
+                .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':' | '.'))
+                .collect();
+            (!label.is_empty()).then_some(label)
+        })
+}
@@ -0,0 +1,58 @@
+use std::{collections::BTreeMap, fs, io::Write, path::Path};
+
+use serde_json::Value;
+
+use crate::{files::write_atomically, translations::TranslationUnit};
+
+/// Joins msgctxt and msgid in keys, as compiled gettext catalogs do.
+const CONTEXT_SEPARATOR: char = '\u{4}';
+
+type Combined = BTreeMap<String, BTreeMap<String, Value>>;
+
+/// Merges each language's translations into a single JSON file of the form
+/// `{ "<lang>": { "<msgid>": "<msgstr>" | ["<form>", ...] } }`, keeping the
+/// entries already in it. Returns the number of entries written.
+pub fn write_combined(
+    path: &Path,
+    languages: &[(&str, &[TranslationUnit])],
+) -> Result<usize, String> {
+    let mut combined: Combined = if fs::metadata(path).is_ok_and(|m| m.len() > 0) {
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("existing file is not a combined translation file: {}", e))?
+    } else {
+        Combined::new()
+    };
+
+    let mut written = 0;
+    for (lang, units) in languages {
+        let entries = combined.entry(lang.to_string()).or_default();
+        for unit in units.iter().filter(|unit| unit.failure.is_none()) {
+            let value = match (&unit.msg_str_plural, &unit.msg_str) {
+                (Some(forms), _) if unit.is_plural() && forms.iter().any(|f| !f.is_empty()) => {
+                    Value::from(forms.clone())
+                }
+                (_, Some(text)) if !unit.is_plural() && !text.is_empty() => {
+                    Value::from(text.clone())
+                }
+                _ => continue,
+            };
+            entries.insert(combined_key(unit), value);
+            written += 1;
+        }
+    }
+
+    let mut text = serde_json::to_string_pretty(&combined).map_err(|e| e.to_string())?;
+    text.push('\n');
+    write_atomically(path, |writer| writer.write_all(text.as_bytes()))
+        .map_err(|e| e.to_string())?;
+
+    Ok(written)
+}
+
+pub fn combined_key(unit: &TranslationUnit) -> String {
+    match &unit.context {
+        Some(context) => format!("{}{}{}", context, CONTEXT_SEPARATOR, unit.msg_id),
+        None => unit.msg_id.clone(),
+    }
+}
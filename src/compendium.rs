@@ -0,0 +1,70 @@
+use std::path::{Path, PathBuf};
+
+use polib::catalog::Catalog;
+
+use crate::{charsets::read_catalog, translations::TranslationUnit};
+
+/// A gettext compendium: a PO file per language whose translations are
+/// shared across projects and applied before asking the LLM.
+pub struct Compendium {
+    catalog: Option<Catalog>,
+}
+
+/// The compendium file of a language, from a pattern with `{lang}`.
+pub fn compendium_path(pattern: &str, lang: &str) -> PathBuf {
+    PathBuf::from(pattern.replace("{lang}", lang))
+}
+
+impl Compendium {
+    /// Loads the compendium; a missing file is an empty compendium.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self { catalog: None });
+        }
+        let catalog = read_catalog(path)
+            .map_err(|e| format!("Failed to parse compendium {:?}: {}", path, e))?;
+        Ok(Self {
+            catalog: Some(catalog),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.catalog.as_ref().map_or(0, |catalog| catalog.count())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The unit with the compendium's translation of the same msgctxt,
+    /// msgid and msgid_plural, if it has a translated, non-fuzzy one.
+    pub fn translate(&self, unit: &TranslationUnit) -> Option<TranslationUnit> {
+        let msg = self.catalog.as_ref()?.find_message(
+            unit.context.as_deref(),
+            &unit.msg_id,
+            unit.msg_id_plural.as_deref(),
+        )?;
+        if !msg.is_translated() || msg.is_fuzzy() {
+            return None;
+        }
+
+        let mut unit = unit.clone();
+        if unit.is_plural() {
+            unit.msg_str_plural = msg.msgstr_plural().ok().cloned();
+        } else {
+            unit.msg_str = msg.msgstr().ok().map(String::from);
+        }
+        Some(unit)
+    }
+
+    /// Whether the compendium translates the unit's message differently.
+    pub fn conflicts_with(&self, unit: &TranslationUnit) -> bool {
+        self.translate(unit).is_some_and(|known| {
+            if unit.is_plural() {
+                known.msg_str_plural != unit.msg_str_plural
+            } else {
+                known.msg_str != unit.msg_str
+            }
+        })
+    }
+}
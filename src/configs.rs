@@ -1,28 +1,698 @@
 use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Component, Path, PathBuf},
+};
+
+use glob::{MatchOptions, Pattern};
+use regex::Regex;
+use reqwest::header::{HeaderName, HeaderValue};
+
+use crate::{
+    bidi::RTL_LANGUAGES, charsets::resolve_charset, formats::format_by_extension, usage::Pricing,
+    validations::RULES,
+};
 
 #[derive(Deserialize, Debug)]
 pub struct AppConfig {
     pub llm: LlmConfig,
     pub translation: TranslationConfig,
     pub project: ProjectConfig,
+    #[serde(default)]
+    pub output: OutputConfig,
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    #[serde(default)]
+    pub deepl: DeepLConfig,
+    #[serde(default)]
+    pub validation: ValidationConfig,
+}
+
+impl AppConfig {
+    pub fn from_toml(content: &str, profile: Option<&str>) -> Result<Self, String> {
+        let mut table: toml::Table = toml::from_str(content).map_err(|e| e.to_string())?;
+        let profiles = table.remove("profiles");
+
+        if let Some(name) = profile {
+            let available: Vec<&String> = match &profiles {
+                Some(toml::Value::Table(profiles)) => profiles.keys().collect(),
+                _ => Vec::new(),
+            };
+            let overrides = match profiles.as_ref().and_then(|p| p.get(name)) {
+                Some(toml::Value::Table(overrides)) => overrides,
+                Some(_) => return Err(format!("[profiles.{}] must be a table", name)),
+                None if available.is_empty() => {
+                    return Err(format!(
+                        "Profile \"{}\" not found: the config has no [profiles] section",
+                        name
+                    ));
+                }
+                None => {
+                    return Err(format!(
+                        "Profile \"{}\" not found (available: {})",
+                        name,
+                        available
+                            .iter()
+                            .map(|s| s.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ));
+                }
+            };
+            merge_tables(&mut table, overrides);
+        }
+
+        let mut config: AppConfig = table
+            .try_into()
+            .map_err(|e: toml::de::Error| e.to_string())?;
+        config.translation.resolve_inputs()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let translation = &self.translation;
+
+        for group in &translation.inputs {
+            let output_pattern = group.output_pattern();
+            if output_pattern.is_empty() {
+                return Err(format!(
+                    "input \"{}\" has no output_pattern; set one on it or at the top of [translation]",
+                    group.pattern
+                ));
+            }
+            if output_pattern.contains("{lang}") {
+                continue;
+            }
+            if !translation.single_language_output {
+                return Err(format!(
+                    "output_pattern \"{}\" does not contain {{lang}}, so every language would overwrite the same file.\n  Use a pattern such as \"{{name}}_{{lang}}.po\", or set single_language_output = true if you only translate into one language.",
+                    output_pattern
+                ));
+            }
+            if translation.target_languages.len() > 1 {
+                return Err(format!(
+                    "single_language_output = true requires exactly one target language, but {} are configured ({}).\n  Add {{lang}} to output_pattern \"{}\" instead.",
+                    translation.target_languages.len(),
+                    translation.target_languages.join(", "),
+                    output_pattern
+                ));
+            }
+        }
+
+        if let Some(pattern) = &translation.combined_output
+            && pattern.contains("{lang}")
+        {
+            return Err(format!(
+                "combined_output \"{}\" must not contain {{lang}}; it collects every language into one file",
+                pattern
+            ));
+        }
+
+        if let Some(charset) = &translation.output_charset {
+            resolve_charset(charset).map_err(|e| format!("output_charset: {}", e))?;
+        }
+
+        if let Some(ratio) = &translation.max_expansion_ratio
+            && ratio
+                .values()
+                .into_iter()
+                .any(|ratio| ratio.is_nan() || ratio <= 0.0)
+        {
+            return Err("max_expansion_ratio must be greater than 0".to_string());
+        }
+
+        if let Err(e) = Regex::new(&translation.passthrough_pattern) {
+            return Err(format!(
+                "passthrough_pattern \"{}\" is not a valid regex: {}",
+                translation.passthrough_pattern, e
+            ));
+        }
+
+        match self.llm.backend {
+            Backend::OpenAi if self.llm.api_base.trim().is_empty() => {
+                return Err("[llm] api_base is required".to_string());
+            }
+            Backend::Command
+                if self
+                    .llm
+                    .command
+                    .as_deref()
+                    .is_none_or(|command| command.trim().is_empty()) =>
+            {
+                return Err("backend = \"command\" requires [llm] command".to_string());
+            }
+            Backend::DeepL => {
+                if self.deepl.api_key.trim().is_empty() {
+                    return Err("backend = \"deepl\" requires [deepl] api_key".to_string());
+                }
+                if let Some(formality) = &self.deepl.formality
+                    && !DEEPL_FORMALITIES.contains(&formality.as_str())
+                {
+                    return Err(format!(
+                        "[deepl] formality must be one of {}, got \"{}\"",
+                        DEEPL_FORMALITIES.join(", "),
+                        formality
+                    ));
+                }
+            }
+            _ => {}
+        }
+        for (name, value) in &self.llm.headers {
+            HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| format!("invalid [llm] header name \"{}\": {}", name, e))?;
+            HeaderValue::from_str(value)
+                .map_err(|e| format!("invalid value of [llm] header \"{}\": {}", name, e))?;
+        }
+        for name in self.validation.rules.keys() {
+            if !RULES.contains(&name.as_str())
+                && !self.validation.custom.iter().any(|rule| &rule.name == name)
+            {
+                return Err(format!(
+                    "[validation.rules] has no rule \"{}\" (built-in rules: {})",
+                    name,
+                    RULES.join(", ")
+                ));
+            }
+        }
+        for (i, custom) in self.validation.custom.iter().enumerate() {
+            if RULES.contains(&custom.name.as_str()) {
+                return Err(format!(
+                    "custom rule \"{}\" is named after a built-in rule",
+                    custom.name
+                ));
+            }
+            if self.validation.custom[..i]
+                .iter()
+                .any(|other| other.name == custom.name)
+            {
+                return Err(format!("custom rule \"{}\" is defined twice", custom.name));
+            }
+        }
+        if self.llm.multi_language {
+            if self.llm.backend != Backend::OpenAi {
+                return Err("multi_language requires the \"openai\" backend".to_string());
+            }
+            if let ModelSetting::PerLanguage(_) = self.llm.model {
+                return Err("multi_language requires a single model for every language".to_string());
+            }
+        }
+
+        match &translation.compendium_pattern {
+            Some(pattern)
+                if !pattern.contains("{lang}") && translation.target_languages.len() > 1 =>
+            {
+                return Err(format!(
+                    "compendium_pattern \"{}\" must contain {{lang}}; each language needs its own compendium",
+                    pattern
+                ));
+            }
+            None if translation.update_compendium => {
+                return Err("update_compendium requires compendium_pattern".to_string());
+            }
+            _ => {}
+        }
+
+        if !(0.0..=1.0).contains(&translation.translation_memory_threshold) {
+            return Err(format!(
+                "translation_memory_threshold must be between 0.0 and 1.0, got {}",
+                translation.translation_memory_threshold
+            ));
+        }
+
+        for (name, value) in [
+            ("frequency_penalty", self.llm.frequency_penalty),
+            ("presence_penalty", self.llm.presence_penalty),
+        ] {
+            if let Some(value) = value
+                && !(-2.0..=2.0).contains(&value)
+            {
+                return Err(format!(
+                    "{} must be between -2.0 and 2.0, got {}",
+                    name, value
+                ));
+            }
+        }
+
+        if let Some(stop) = &self.llm.stop
+            && stop.len() > 4
+        {
+            return Err(format!(
+                "stop accepts at most 4 sequences, got {}",
+                stop.len()
+            ));
+        }
+
+        if self.llm.max_spend_usd.is_some() && self.llm.pricing().is_none() {
+            return Err(
+                "max_spend_usd requires input_price_per_million and output_price_per_million"
+                    .to_string(),
+            );
+        }
+
+        let langs_without_model: Vec<&str> = translation
+            .target_languages
+            .iter()
+            .filter(|lang| self.llm.model.for_language(lang).is_none())
+            .map(String::as_str)
+            .collect();
+        if !langs_without_model.is_empty() {
+            return Err(format!(
+                "No model configured for language(s): {}. Add them to [llm.model] or set a \"default\" entry.",
+                langs_without_model.join(", ")
+            ));
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Deserialize, Debug)]
 pub struct LlmConfig {
+    #[serde(default)]
+    pub backend: Backend,
+    /// Run for every batch when `backend = "command"`.
+    pub command: Option<String>,
+    #[serde(default)]
     pub api_base: String,
+    #[serde(default)]
     pub api_key: String,
-    pub model: String,
+    pub model: ModelSetting,
     pub custom_prompt: Option<String>,
     #[serde(default = "default_system_prompt")]
     pub system_prompt: String,
+    #[serde(default)]
+    pub schema_dialect: SchemaDialect,
+    pub stop: Option<Vec<String>>,
+    pub frequency_penalty: Option<f32>,
+    pub presence_penalty: Option<f32>,
+    #[serde(default)]
+    pub stream: bool,
+    /// Translate messages several languages need in one request covering
+    /// all of them.
+    #[serde(default)]
+    pub multi_language: bool,
+    pub input_price_per_million: Option<f64>,
+    pub output_price_per_million: Option<f64>,
+    pub max_spend_usd: Option<f64>,
+    pub max_total_retries: Option<u64>,
+    /// Sent with every request of the openai backend, such as `User-Agent`
+    /// or a gateway's routing header.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+impl LlmConfig {
+    pub fn pricing(&self) -> Option<Pricing> {
+        Some(Pricing {
+            input_per_million: self.input_price_per_million?,
+            output_per_million: self.output_price_per_million?,
+        })
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum ModelSetting {
+    Single(String),
+    PerLanguage(BTreeMap<String, String>),
+}
+
+impl ModelSetting {
+    pub fn for_language(&self, lang: &str) -> Option<&str> {
+        match self {
+            ModelSetting::Single(model) => Some(model),
+            ModelSetting::PerLanguage(models) => models
+                .get(lang)
+                .or_else(|| models.get("default"))
+                .map(String::as_str),
+        }
+    }
+}
+
+impl fmt::Display for ModelSetting {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelSetting::Single(model) => write!(f, "{}", model),
+            ModelSetting::PerLanguage(models) => {
+                let entries: Vec<String> = models
+                    .iter()
+                    .map(|(lang, model)| format!("{} → {}", lang, model))
+                    .collect();
+                write!(f, "{}", entries.join(", "))
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    #[default]
+    #[serde(rename = "openai")]
+    OpenAi,
+    Command,
+    #[serde(rename = "deepl")]
+    DeepL,
+}
+
+const DEEPL_FORMALITIES: &[&str] = &["default", "more", "less", "prefer_more", "prefer_less"];
+
+/// Settings of the DeepL backend, used when `backend = "deepl"`.
+#[derive(Deserialize, Debug, Default)]
+pub struct DeepLConfig {
+    #[serde(default)]
+    pub api_key: String,
+    /// Defaults to the free API for keys ending in `:fx`, and to the pro API
+    /// otherwise.
+    pub api_base: Option<String>,
+    pub formality: Option<String>,
+    /// Glossary IDs by target language.
+    #[serde(default)]
+    pub glossaries: BTreeMap<String, String>,
+}
+
+impl DeepLConfig {
+    pub fn api_base(&self) -> &str {
+        match &self.api_base {
+            Some(api_base) => api_base,
+            None if self.api_key.ends_with(":fx") => "https://api-free.deepl.com",
+            None => "https://api.deepl.com",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SchemaDialect {
+    #[default]
+    #[serde(rename = "openai")]
+    OpenAi,
+    Inline,
+    Minimal,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EscapeMode {
+    Off,
+    #[default]
+    Warn,
+    Fail,
+    Repair,
+}
+
+/// Whether a translation must keep the embedded newlines and blank-line
+/// separated paragraphs of its source.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ParagraphMode {
+    Off,
+    #[default]
+    Warn,
+    Fail,
+}
+
+/// Whether a translation must end like its source: with the same ellipsis,
+/// period, colon, question or exclamation mark, or none.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PunctuationMode {
+    #[default]
+    Off,
+    Warn,
+    Fail,
+    /// Replace the translation's terminator with the source's, in the
+    /// target language's form.
+    Fix,
+}
+
+/// What becomes of control, zero-width and BOM characters a translation has
+/// and its source does not.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SanitizeMode {
+    #[default]
+    Fail,
+    /// Remove them, leaving newlines and tabs to fail the entry.
+    Strip,
+}
+
+/// How Latin runs and placeholders in right-to-left translations are kept in
+/// order, and whether directional formatting characters are checked.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BidiMode {
+    #[default]
+    Off,
+    /// Only fail translations with unbalanced embeddings or isolates.
+    Check,
+    /// Wrap them in FSI/PDI isolates, and check.
+    Isolate,
+    /// Put them between LRMs, and check.
+    Mark,
+}
+
+/// The Unicode normalization form translations are brought to.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NormalizationForm {
+    #[default]
+    None,
+    Nfc,
+    Nfkc,
+}
+
+/// What becomes of a translation over its length limit.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LengthMode {
+    #[default]
+    Warn,
+    Fail,
+    Fuzzy,
+    /// Ask the model once more for a shorter translation, failing the entry
+    /// if that one is too long as well.
+    Reprompt,
+}
+
+/// What is written for a message over `skip_above_tokens`.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OversizedMode {
+    /// The source text, as for `passthrough_pattern`.
+    #[default]
+    Copy,
+    Empty,
+}
+
+/// A ratio for every language, or per language with an optional
+/// `"default"` entry.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum RatioSetting {
+    Single(f64),
+    PerLanguage(BTreeMap<String, f64>),
+}
+
+impl RatioSetting {
+    pub fn for_language(&self, lang: &str) -> Option<f64> {
+        match self {
+            RatioSetting::Single(ratio) => Some(*ratio),
+            RatioSetting::PerLanguage(ratios) => {
+                ratios.get(lang).or_else(|| ratios.get("default")).copied()
+            }
+        }
+    }
+
+    fn values(&self) -> Vec<f64> {
+        match self {
+            RatioSetting::Single(ratio) => vec![*ratio],
+            RatioSetting::PerLanguage(ratios) => ratios.values().copied().collect(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentFormat {
+    #[default]
+    Gettext,
+    Xliff,
+    Strings,
+    Stringsdict,
+    Json,
+    Yaml,
+    Ts,
+    Resx,
+    Android,
+}
+
+impl DocumentFormat {
+    /// The format implied by the file extension; anything unknown is PO.
+    pub fn of(path: &Path) -> Self {
+        format_by_extension(path).unwrap_or_default()
+    }
 }
 
 #[derive(Deserialize, Debug)]
 pub struct TranslationConfig {
+    #[serde(default = "default_source_language")]
+    pub source_language: String,
     pub target_languages: Vec<String>,
-    pub input_pattern: String,
+    /// Folded into `inputs` as its only group when loading the config.
+    #[serde(default)]
+    pub input_pattern: Option<String>,
+    /// The default for inputs without their own `output_pattern`.
+    #[serde(default)]
     pub output_pattern: String,
+    #[serde(default)]
+    pub inputs: Vec<InputGroup>,
+    pub combined_output: Option<String>,
+    pub format: Option<DocumentFormat>,
+    #[serde(default)]
+    pub format_patterns: FormatPatterns,
     pub batch_size: usize,
+    #[serde(default)]
+    pub bisect_failed_batches: bool,
+    #[serde(default)]
+    pub translate_po_inputs: bool,
+    #[serde(default)]
+    pub merge_inputs: bool,
+    #[serde(default)]
+    pub translate_comments: bool,
+    /// Only messages whose comments carry this tag are translated.
+    pub require_tag: Option<String>,
+    #[serde(default)]
+    pub attribution: String,
+    #[serde(default)]
+    pub preserve_escapes: EscapeMode,
+    #[serde(default)]
+    pub store_rationale: bool,
+    #[serde(default)]
+    pub glob_case_insensitive: bool,
+    #[serde(default = "default_true")]
+    pub glob_include_hidden: bool,
+    #[serde(default)]
+    pub markdown_aware: bool,
+    #[serde(default)]
+    pub single_language_output: bool,
+    #[serde(default)]
+    pub output_charset: Option<String>,
+    #[serde(default = "default_passthrough_pattern")]
+    pub passthrough_pattern: String,
+    #[serde(default)]
+    pub straighten_quotes: bool,
+    pub term_memory_path: Option<String>,
+    #[serde(default)]
+    pub translation_memory: Vec<String>,
+    pub compendium_pattern: Option<String>,
+    #[serde(default)]
+    pub update_compendium: bool,
+    #[serde(default = "default_translation_memory_threshold")]
+    pub translation_memory_threshold: f64,
+    pub overrides_file: Option<String>,
+    #[serde(default)]
+    pub accelerator_markers: Vec<char>,
+    #[serde(default)]
+    pub accelerator_autofix: bool,
+    #[serde(default)]
+    pub preserve_delimiters: Vec<char>,
+    #[serde(default)]
+    pub terminal_punctuation: PunctuationMode,
+    #[serde(default)]
+    pub paragraph_structure: ParagraphMode,
+    /// Languages whose translations may break lines and paragraphs
+    /// differently from the source.
+    #[serde(default)]
+    pub paragraph_structure_exempt: Vec<String>,
+    #[serde(default)]
+    pub stray_characters: SanitizeMode,
+    #[serde(default)]
+    pub normalize_unicode: NormalizationForm,
+    #[serde(default)]
+    pub bidi_isolation: BidiMode,
+    #[serde(default = "default_rtl_languages")]
+    pub rtl_languages: Vec<String>,
+    #[serde(default)]
+    pub check_placeholders: bool,
+    #[serde(default)]
+    pub placeholder_patterns: PlaceholderPatterns,
+    #[serde(default)]
+    pub write_failed_as_empty: bool,
+    #[serde(default)]
+    pub flag_untranslated_echo: bool,
+    /// Languages whose translations may equal their source, such as German
+    /// for cognates.
+    #[serde(default)]
+    pub allow_same_as_source: Vec<String>,
+    #[serde(default)]
+    pub same_as_source_pattern: SourcePattern,
+    #[serde(default = "default_true")]
+    pub detect_wrong_language: bool,
+    /// Per language, URL prefixes of the source and the localized prefix a
+    /// translation may use instead.
+    #[serde(default)]
+    pub localized_urls: BTreeMap<String, BTreeMap<String, String>>,
+    /// Longest a translation may be relative to its source, in grapheme
+    /// clusters.
+    #[serde(default)]
+    pub max_expansion_ratio: Option<RatioSetting>,
+    /// Longest a translation may be, in grapheme clusters; a
+    /// `po-llm:max-length=N` comment on the entry takes precedence.
+    #[serde(default)]
+    pub max_chars: Option<usize>,
+    #[serde(default)]
+    pub on_too_long: LengthMode,
+    /// Messages estimated at more tokens than this are never sent.
+    #[serde(default)]
+    pub skip_above_tokens: Option<usize>,
+    #[serde(default)]
+    pub on_oversized: OversizedMode,
+}
+
+impl TranslationConfig {
+    /// The format of the first `format_patterns` entry matching the path,
+    /// else the input group's `format`, else the configured `format`, else
+    /// the one implied by the extension.
+    pub fn format_of(&self, group: &InputGroup, path: &Path) -> DocumentFormat {
+        self.format_patterns
+            .format_of(path, self.glob_case_insensitive)
+            .or(group.format)
+            .or(self.format)
+            .unwrap_or_else(|| DocumentFormat::of(path))
+    }
+
+    /// Turns the flat `input_pattern` into the single input group, and fills
+    /// in the groups' default `output_pattern`.
+    fn resolve_inputs(&mut self) -> Result<(), String> {
+        match self.input_pattern.take() {
+            Some(_) if !self.inputs.is_empty() => {
+                return Err(
+                    "input_pattern and [[translation.inputs]] cannot be combined; move input_pattern into inputs"
+                        .to_string(),
+                );
+            }
+            Some(pattern) => self.inputs.push(InputGroup {
+                pattern,
+                format: None,
+                output_pattern: None,
+            }),
+            None if self.inputs.is_empty() => {
+                return Err(
+                    "missing input_pattern: set it or list [[translation.inputs]]".to_string(),
+                );
+            }
+            None => {}
+        }
+
+        for group in &mut self.inputs {
+            if group.output_pattern.is_none() {
+                group.output_pattern = Some(self.output_pattern.clone());
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -30,6 +700,335 @@ pub struct ProjectConfig {
     pub context: String,
     pub base_path: String,
     pub skip_translated: bool,
+    #[serde(default)]
+    pub use_header_context: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct OutputConfig {
+    #[serde(default)]
+    pub backup: bool,
+    #[serde(default = "default_backup_suffix")]
+    pub backup_suffix: String,
+    #[serde(default = "default_backup_keep")]
+    pub backup_keep: usize,
+    #[serde(alias = "wrap_width")]
+    pub wrap: Option<WrapMode>,
+    #[serde(default)]
+    pub compile_mo: bool,
+    #[serde(default = "default_true")]
+    pub check_po: bool,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct HooksConfig {
+    pub post_file: Option<String>,
+}
+
+/// Regexes describing what a placeholder looks like in this project, e.g.
+/// `:name` or `$name`, combined into one so overlapping patterns match once.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(try_from = "Vec<String>")]
+pub struct PlaceholderPatterns(Option<Regex>);
+
+impl PlaceholderPatterns {
+    pub fn regex(&self) -> Option<&Regex> {
+        self.0.as_ref()
+    }
+}
+
+impl TryFrom<Vec<String>> for PlaceholderPatterns {
+    type Error = String;
+
+    fn try_from(patterns: Vec<String>) -> Result<Self, Self::Error> {
+        for pattern in &patterns {
+            Regex::new(pattern).map_err(|e| {
+                format!(
+                    "placeholder pattern \"{}\" is not a valid regex: {}",
+                    pattern, e
+                )
+            })?;
+        }
+        if patterns.is_empty() {
+            return Ok(Self(None));
+        }
+
+        let combined = patterns
+            .iter()
+            .map(|pattern| format!("(?:{})", pattern))
+            .collect::<Vec<_>>()
+            .join("|");
+        Regex::new(&combined)
+            .map(|regex| Self(Some(regex)))
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// A regex a whole source text must match, such as `OK|Email`.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(try_from = "String")]
+pub struct SourcePattern(Option<Regex>);
+
+impl SourcePattern {
+    pub fn is_match(&self, source: &str) -> bool {
+        self.0.as_ref().is_some_and(|regex| regex.is_match(source))
+    }
+}
+
+impl TryFrom<String> for SourcePattern {
+    type Error = String;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        Regex::new(&format!("^(?:{})$", pattern))
+            .map(|regex| Self(Some(regex)))
+            .map_err(|e| format!("\"{}\" is not a valid regex: {}", pattern, e))
+    }
+}
+
+/// What becomes of a translation breaking a validation rule.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleSeverity {
+    /// Not checked.
+    Off,
+    /// Written anyway, and listed for review.
+    Warn,
+    /// Not written; the message stays untranslated.
+    Fail,
+    /// Written and marked fuzzy.
+    Fuzzy,
+}
+
+/// The `[validation]` section: the built-in rules turned on or off, or
+/// given another severity, by name, and rules of the project's own.
+#[derive(Deserialize, Debug, Default)]
+pub struct ValidationConfig {
+    #[serde(default)]
+    pub rules: BTreeMap<String, RuleSeverity>,
+    #[serde(default)]
+    pub custom: Vec<CustomRule>,
+    /// Correct the findings of rules with a mechanical fix, such as a
+    /// missing ellipsis, instead of failing or flagging the translation.
+    #[serde(default)]
+    pub autofix: bool,
+}
+
+impl ValidationConfig {
+    /// The severity of a rule, or `None` when it is off. `default` is what
+    /// the rest of the configuration makes of it, `None` for not checked.
+    pub fn severity(&self, rule: &str, default: Option<RuleSeverity>) -> Option<RuleSeverity> {
+        match self.rules.get(rule).copied().or(default) {
+            Some(RuleSeverity::Off) | None => None,
+            severity => severity,
+        }
+    }
+}
+
+/// A regex check of translations. With `must_match_source`, what the
+/// pattern matches in the source must be found in the translation as
+/// well, as for product names; without it, translations must not match at
+/// all, as for banned terms.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(try_from = "RawCustomRule")]
+pub struct CustomRule {
+    pub name: String,
+    pub pattern: Regex,
+    pub must_match_source: bool,
+    pub message: Option<String>,
+    pub severity: RuleSeverity,
+}
+
+#[derive(Deserialize)]
+struct RawCustomRule {
+    name: String,
+    pattern: String,
+    #[serde(default)]
+    must_match_source: bool,
+    message: Option<String>,
+    #[serde(default = "default_custom_severity")]
+    severity: RuleSeverity,
+}
+
+fn default_custom_severity() -> RuleSeverity {
+    RuleSeverity::Fail
+}
+
+impl TryFrom<RawCustomRule> for CustomRule {
+    type Error = String;
+
+    fn try_from(raw: RawCustomRule) -> Result<Self, Self::Error> {
+        if raw.name.trim().is_empty() {
+            return Err("custom rules need a name".to_string());
+        }
+        let pattern = Regex::new(&raw.pattern).map_err(|e| {
+            format!(
+                "pattern of custom rule \"{}\" is not a valid regex: {}",
+                raw.name, e
+            )
+        })?;
+        Ok(Self {
+            name: raw.name,
+            pattern,
+            must_match_source: raw.must_match_source,
+            message: raw.message,
+            severity: raw.severity,
+        })
+    }
+}
+
+/// A set of input files translated alike: found by `pattern` (relative to
+/// `base_path`), read with `format` and written to `output_pattern`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct InputGroup {
+    pub pattern: String,
+    pub format: Option<DocumentFormat>,
+    pub output_pattern: Option<String>,
+}
+
+impl InputGroup {
+    pub fn output_pattern(&self) -> &str {
+        self.output_pattern.as_deref().unwrap_or_default()
+    }
+}
+
+/// Formats chosen per input path, for extensions that are ambiguous or
+/// misleading. Patterns match the end of the input path, so
+/// `"i18n/*.json"` matches `po-files/app/i18n/en.json`; the first match wins.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(try_from = "Vec<RawFormatPattern>")]
+pub struct FormatPatterns(Vec<(Pattern, DocumentFormat)>);
+
+#[derive(Deserialize)]
+struct RawFormatPattern {
+    pattern: String,
+    format: DocumentFormat,
+}
+
+impl FormatPatterns {
+    pub fn format_of(&self, path: &Path, case_insensitive: bool) -> Option<DocumentFormat> {
+        let options = MatchOptions {
+            case_sensitive: !case_insensitive,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+        let components: Vec<Component> = path.components().collect();
+        self.0
+            .iter()
+            .find(|(pattern, _)| {
+                (0..components.len()).any(|start| {
+                    let suffix: PathBuf = components[start..].iter().collect();
+                    pattern.matches_path_with(&suffix, options)
+                })
+            })
+            .map(|(_, format)| *format)
+    }
+}
+
+impl TryFrom<Vec<RawFormatPattern>> for FormatPatterns {
+    type Error = String;
+
+    fn try_from(raw: Vec<RawFormatPattern>) -> Result<Self, Self::Error> {
+        raw.into_iter()
+            .map(|entry| {
+                Pattern::new(&entry.pattern)
+                    .map(|pattern| (pattern, entry.format))
+                    .map_err(|e| {
+                        format!(
+                            "format pattern \"{}\" is not a valid glob: {}",
+                            entry.pattern, e
+                        )
+                    })
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "RawWrapMode")]
+pub enum WrapMode {
+    Gettext,
+    None,
+    Width(usize),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawWrapMode {
+    Width(usize),
+    Name(String),
+}
+
+impl TryFrom<RawWrapMode> for WrapMode {
+    type Error = String;
+
+    fn try_from(raw: RawWrapMode) -> Result<Self, Self::Error> {
+        match raw {
+            RawWrapMode::Width(width) => Ok(WrapMode::Width(width)),
+            RawWrapMode::Name(name) => match name.as_str() {
+                "gettext" => Ok(WrapMode::Gettext),
+                "none" => Ok(WrapMode::None),
+                other => Err(format!(
+                    "invalid wrap mode \"{}\", expected \"gettext\", \"none\" or a column width",
+                    other
+                )),
+            },
+        }
+    }
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            backup: false,
+            backup_suffix: default_backup_suffix(),
+            backup_keep: default_backup_keep(),
+            wrap: None,
+            compile_mo: false,
+            check_po: true,
+        }
+    }
+}
+
+fn merge_tables(base: &mut toml::Table, overrides: &toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overrides)) => {
+                merge_tables(base, overrides)
+            }
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+fn default_backup_suffix() -> String {
+    ".bak-{timestamp}".to_string()
+}
+
+fn default_backup_keep() -> usize {
+    5
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_rtl_languages() -> Vec<String> {
+    RTL_LANGUAGES.iter().map(|lang| lang.to_string()).collect()
+}
+
+fn default_passthrough_pattern() -> String {
+    r"^[^\p{L}]*$".to_string()
+}
+
+fn default_translation_memory_threshold() -> f64 {
+    0.7
+}
+
+fn default_source_language() -> String {
+    "en".to_string()
 }
 
 fn default_system_prompt() -> String {
@@ -52,3 +1051,19 @@ Strict Requirements:
 Output: Return a JSON array of objects with keys: "index", "msg_str", "msg_str_plural"."#
         .to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_accepts_names_widths_and_its_alias() {
+        let wrap = |toml: &str| toml::from_str::<OutputConfig>(toml).map(|output| output.wrap);
+        assert_eq!(wrap("").unwrap(), None);
+        assert_eq!(wrap("wrap = \"gettext\"").unwrap(), Some(WrapMode::Gettext));
+        assert_eq!(wrap("wrap = \"none\"").unwrap(), Some(WrapMode::None));
+        assert_eq!(wrap("wrap = 100").unwrap(), Some(WrapMode::Width(100)));
+        assert_eq!(wrap("wrap_width = 0").unwrap(), Some(WrapMode::Width(0)));
+        assert!(wrap("wrap = \"narrow\"").is_err());
+    }
+}
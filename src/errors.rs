@@ -0,0 +1,102 @@
+use std::{fmt, io};
+
+/// Errors of the library API, by what failed: reading a file, the LLM
+/// backend, its response, a check of the written output, or the file system.
+#[derive(Debug, thiserror::Error)]
+pub enum PoLlmError {
+    /// A file that is not valid in its format.
+    #[error("{0}")]
+    Parse(String),
+    /// A request the LLM backend failed or refused; `status` is the HTTP
+    /// status when the API answered with one.
+    #[error("{message}")]
+    Api {
+        status: Option<u16>,
+        message: String,
+    },
+    /// A response that does not follow the requested schema.
+    #[error("{0}")]
+    Schema(String),
+    /// Output that was written but failed a check.
+    #[error("{0}")]
+    Validation(String),
+    #[error("{message}: {source}")]
+    Io {
+        message: String,
+        #[source]
+        source: io::Error,
+    },
+}
+
+impl PoLlmError {
+    pub fn io(message: impl Into<String>, source: io::Error) -> Self {
+        Self::Io {
+            message: message.into(),
+            source,
+        }
+    }
+
+    /// The same error with `context`, such as the file it is about, put
+    /// before its message.
+    pub fn context(self, context: impl fmt::Display) -> Self {
+        let with = |message: String| format!("{}: {}", context, message);
+        match self {
+            Self::Parse(message) => Self::Parse(with(message)),
+            Self::Api { status, message } => Self::Api {
+                status,
+                message: with(message),
+            },
+            Self::Schema(message) => Self::Schema(with(message)),
+            Self::Validation(message) => Self::Validation(with(message)),
+            Self::Io { message, source } => Self::Io {
+                message: with(message),
+                source,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::PoLlmError;
+    use crate::{charsets::read_catalog, xliff::XliffDocument};
+
+    #[test]
+    fn missing_files_are_io_errors() {
+        let path = Path::new("/nonexistent/po-llm/fr.xlf");
+        match XliffDocument::read(path) {
+            Err(PoLlmError::Io { source, .. }) => {
+                assert_eq!(source.kind(), std::io::ErrorKind::NotFound)
+            }
+            other => panic!("expected an Io error, got {:?}", other.err()),
+        }
+        assert!(matches!(
+            read_catalog(Path::new("/nonexistent/po-llm/fr.po")),
+            Err(PoLlmError::Io { .. })
+        ));
+    }
+
+    #[test]
+    fn invalid_files_are_parse_errors() {
+        let error = XliffDocument::parse("<xliff".to_string()).err().unwrap();
+        assert!(matches!(error, PoLlmError::Parse(_)));
+    }
+
+    #[test]
+    fn context_keeps_the_variant() {
+        let error = PoLlmError::Api {
+            status: Some(429),
+            message: "rate limited".to_string(),
+        }
+        .context("batch 3");
+        match error {
+            PoLlmError::Api { status, message } => {
+                assert_eq!(status, Some(429));
+                assert_eq!(message, "batch 3: rate limited");
+            }
+            other => panic!("expected an Api error, got {:?}", other),
+        }
+    }
+}
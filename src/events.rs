@@ -0,0 +1,111 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::Path,
+    sync::Mutex,
+};
+
+use serde::Serialize;
+
+/// What a run reports to tools driving it, one JSON object per line with
+/// its kind in `event`.
+#[derive(Serialize, Debug)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    RunStarted {
+        files: usize,
+        languages: &'a [String],
+    },
+    FileStarted {
+        file: &'a Path,
+    },
+    LanguageStarted {
+        file: &'a Path,
+        lang: &'a str,
+        output: &'a Path,
+    },
+    BatchFinished {
+        file: &'a Path,
+        lang: &'a str,
+        batch: usize,
+        total_batches: usize,
+        translated: usize,
+        failed: usize,
+    },
+    LanguageFinished {
+        file: &'a Path,
+        lang: &'a str,
+        translated: usize,
+        failed: usize,
+    },
+    LanguageFailed {
+        file: &'a Path,
+        lang: &'a str,
+        error: String,
+    },
+    FileFinished {
+        file: &'a Path,
+        translated: usize,
+        failed: usize,
+    },
+    FileFailed {
+        file: &'a Path,
+        error: String,
+    },
+    RunFinished {
+        files: usize,
+        files_failed: usize,
+        translated: usize,
+        failed: usize,
+        duration_secs: f64,
+    },
+}
+
+/// Newline-delimited JSON events, written as they happen.
+pub struct EventLog {
+    writer: Mutex<File>,
+}
+
+impl EventLog {
+    /// Appends to a file, created if missing.
+    pub fn create(path: &Path) -> Result<Self, String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Failed to open events file {:?}: {}", path, e))?;
+        Ok(Self {
+            writer: Mutex::new(file),
+        })
+    }
+
+    /// Writes to file descriptor 3, which the caller opens, such as with
+    /// `3>events.ndjson` or a pipe. It is reopened through `/dev/fd` rather
+    /// than taken over, so a descriptor the caller left closed is an error,
+    /// not whatever the process opened there itself.
+    pub fn fd3() -> Result<Self, String> {
+        let file = OpenOptions::new()
+            .append(true)
+            .open("/dev/fd/3")
+            .map_err(|e| {
+                format!(
+                    "--json-logs writes to file descriptor 3, which is not open for writing ({}); redirect it, such as with 3>events.ndjson, or use --events-file",
+                    e
+                )
+            })?;
+        Ok(Self {
+            writer: Mutex::new(file),
+        })
+    }
+
+    /// Failing to write an event never fails the run; a reader gone away
+    /// only loses the events.
+    pub fn emit(&self, event: &Event<'_>) {
+        let Ok(mut line) = serde_json::to_vec(event) else {
+            return;
+        };
+        line.push(b'\n');
+        let mut writer = self.writer.lock().unwrap();
+        let _ = writer.write_all(&line).and_then(|()| writer.flush());
+    }
+}
@@ -0,0 +1,225 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputKind {
+    Template,
+    Catalog,
+}
+
+impl InputKind {
+    pub fn of(path: &Path) -> Self {
+        match path.extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("po") => InputKind::Catalog,
+            _ => InputKind::Template,
+        }
+    }
+}
+
+impl fmt::Display for InputKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputKind::Template => write!(f, "template"),
+            InputKind::Catalog => write!(f, "catalog"),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct FileLocks {
+    locks: Mutex<HashMap<PathBuf, Arc<AsyncMutex<()>>>>,
+}
+
+pub struct FileGuard {
+    path: PathBuf,
+    _guard: OwnedMutexGuard<()>,
+}
+
+impl FileLocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn lock(&self, path: &Path) -> FileGuard {
+        let lock = self
+            .locks
+            .lock()
+            .unwrap()
+            .entry(path.to_path_buf())
+            .or_default()
+            .clone();
+
+        FileGuard {
+            path: path.to_path_buf(),
+            _guard: lock.lock_owned().await,
+        }
+    }
+}
+
+impl FileGuard {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+pub fn write_atomically<F>(path: &Path, write: F) -> io::Result<()>
+where
+    F: FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+{
+    let temp_path = temp_path_for(path);
+
+    let result = write_and_sync(&temp_path, write).and_then(|_| replace_file(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+fn write_and_sync<F>(temp_path: &Path, write: F) -> io::Result<()>
+where
+    F: FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+{
+    let mut writer = BufWriter::new(File::create(temp_path)?);
+    write(&mut writer)?;
+    writer.flush()?;
+    writer.get_ref().sync_all()
+}
+
+fn replace_file(temp_path: &Path, path: &Path) -> io::Result<()> {
+    fs::rename(temp_path, path)?;
+    sync_parent_dir(path);
+    Ok(())
+}
+
+fn temp_path_for(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let counter = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+
+    path.with_file_name(format!(
+        ".{}.po-llm-{}-{}.tmp",
+        file_name,
+        process::id(),
+        counter
+    ))
+}
+
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) {
+    if let Some(parent) = path.parent() {
+        let parent = if parent.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            parent
+        };
+        if let Ok(dir) = File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) {}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("po-llm-{}-{}", name, process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn entries(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn interrupted_write_keeps_the_old_file() {
+        let dir = scratch_dir("interrupted");
+        let path = dir.join("fr.po");
+        fs::write(&path, "old").unwrap();
+
+        let result = write_atomically(&path, |writer| {
+            writer.write_all(b"half of the new")?;
+            Err(io::Error::other("interrupted"))
+        });
+
+        assert_eq!(result.unwrap_err().to_string(), "interrupted");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+        assert_eq!(entries(&dir), ["fr.po"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn finished_write_replaces_the_file() {
+        let dir = scratch_dir("finished");
+        let path = dir.join("fr.po");
+        fs::write(&path, "old").unwrap();
+
+        write_atomically(&path, |writer| writer.write_all(b"new")).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(entries(&dir), ["fr.po"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn writers_of_one_file_take_turns() {
+        let dir = scratch_dir("concurrent");
+        let path = dir.join("fr.po");
+        fs::write(&path, "").unwrap();
+        let locks = Arc::new(FileLocks::new());
+
+        let writers = (0..8).map(|i| {
+            let locks = locks.clone();
+            let path = path.clone();
+            tokio::spawn(async move {
+                let guard = locks.lock(&path).await;
+                let text = fs::read_to_string(guard.path()).unwrap();
+                tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+                write_atomically(guard.path(), |writer| {
+                    writer.write_all(format!("{}{}\n", text, i).as_bytes())
+                })
+                .unwrap();
+            })
+        });
+        for writer in writers.collect::<Vec<_>>() {
+            writer.await.unwrap();
+        }
+
+        let mut lines: Vec<u32> = fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| line.parse().unwrap())
+            .collect();
+        lines.sort();
+        assert_eq!(lines, (0..8).collect::<Vec<_>>());
+        assert_eq!(entries(&dir), ["fr.po"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,44 @@
+use std::path::Path;
+
+use crate::{
+    android::AndroidAdapter,
+    apple::{StringsAdapter, StringsdictAdapter},
+    configs::DocumentFormat,
+    json_catalog::JsonCatalogAdapter,
+    linguist::LinguistAdapter,
+    resx::ResxAdapter,
+    translations::{FormatAdapter, GettextAdapter},
+    xliff::XliffAdapter,
+    yaml::YamlAdapter,
+};
+
+/// Every supported format. A new one implements `FormatAdapter` and is
+/// listed here; the pipeline only ever talks to the trait.
+pub static ADAPTERS: &[&dyn FormatAdapter] = &[
+    &GettextAdapter,
+    &XliffAdapter,
+    &StringsAdapter,
+    &StringsdictAdapter,
+    &JsonCatalogAdapter,
+    &YamlAdapter,
+    &LinguistAdapter,
+    &ResxAdapter,
+    &AndroidAdapter,
+];
+
+pub fn adapter_for(format: DocumentFormat) -> &'static dyn FormatAdapter {
+    ADAPTERS
+        .iter()
+        .copied()
+        .find(|adapter| adapter.format() == format)
+        .expect("every format has an adapter")
+}
+
+/// The format whose adapter claims the file's extension, if any.
+pub fn format_by_extension(path: &Path) -> Option<DocumentFormat> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    ADAPTERS
+        .iter()
+        .find(|adapter| adapter.extensions().contains(&extension.as_str()))
+        .map(|adapter| adapter.format())
+}
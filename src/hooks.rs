@@ -0,0 +1,75 @@
+use std::path::Path;
+
+use tokio::process::Command;
+
+pub struct HookOutput {
+    pub command: String,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+pub async fn run_post_file(template: &str, path: &Path, lang: &str) -> Result<HookOutput, String> {
+    let command = template
+        .replace("{path}", &shell_quote(&path.to_string_lossy()))
+        .replace("{lang}", &shell_quote(lang));
+
+    let output = shell(&command)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run hook `{}`: {}", command, e))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout)
+        .trim_end()
+        .to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr)
+        .trim_end()
+        .to_string();
+
+    if !output.status.success() {
+        let status = match output.status.code() {
+            Some(code) => format!("exit code {}", code),
+            None => "a signal".to_string(),
+        };
+        let details = if stderr.is_empty() { &stdout } else { &stderr };
+        return Err(format!(
+            "Hook `{}` failed with {}{}",
+            command,
+            status,
+            if details.is_empty() {
+                String::new()
+            } else {
+                format!(": {}", details)
+            }
+        ));
+    }
+
+    Ok(HookOutput {
+        command,
+        stdout,
+        stderr,
+    })
+}
+
+#[cfg(unix)]
+pub(crate) fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(windows)]
+pub(crate) fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
+
+#[cfg(unix)]
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(windows)]
+fn shell_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
@@ -0,0 +1,269 @@
+use std::fmt;
+
+/// An argument of an ICU message, such as `{name}`, `{n, number}` or
+/// `{count, plural, ...}`, with the selectors of its branches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Argument {
+    pub name: String,
+    /// `plural`, `select`, `number`...; empty for a plain `{name}`.
+    pub kind: String,
+    pub selectors: Vec<String>,
+}
+
+impl Argument {
+    /// Whether the argument picks a branch: `plural`, `selectordinal` or
+    /// `select`.
+    pub fn has_branches(&self) -> bool {
+        matches!(self.kind.as_str(), "plural" | "selectordinal" | "select")
+    }
+}
+
+impl fmt::Display for Argument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.kind.is_empty() {
+            write!(f, "{{{}}}", self.name)
+        } else {
+            write!(f, "{{{}, {}}}", self.name, self.kind)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IcuError {
+    /// 1-based character position.
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for IcuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+/// Parses an ICU MessageFormat pattern and returns its arguments, nested
+/// ones included, in order of appearance.
+pub fn arguments(text: &str) -> Result<Vec<Argument>, IcuError> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        pos: 0,
+        arguments: Vec::new(),
+    };
+    parser.message(false, false)?;
+    Ok(parser.arguments)
+}
+
+/// Whether the text parses as an ICU message with a `plural`, `select` or
+/// `selectordinal` argument, as opposed to one with only `{name}`s, which
+/// other placeholder syntaxes share.
+pub fn is_icu_message(text: &str) -> bool {
+    arguments(text).is_ok_and(|arguments| arguments.iter().any(Argument::has_branches))
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    arguments: Vec<Argument>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn error(&self, at: usize, message: &str) -> IcuError {
+        IcuError {
+            position: at + 1,
+            message: message.to_string(),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn identifier(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), IcuError> {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(self.pos, &format!("expected '{}'", expected)))
+        }
+    }
+
+    /// Text and arguments up to the end, or up to the `}` closing a branch
+    /// when `nested`.
+    fn message(&mut self, in_plural: bool, nested: bool) -> Result<(), IcuError> {
+        loop {
+            match self.peek() {
+                None if nested => return Err(self.error(self.pos, "unclosed branch")),
+                None => return Ok(()),
+                Some('}') if nested => return Ok(()),
+                Some('}') => return Err(self.error(self.pos, "unmatched '}'")),
+                Some('{') => self.argument()?,
+                Some('\'') => self.quoted(in_plural),
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    /// `''` is an apostrophe; an apostrophe before a syntax character starts
+    /// a quoted literal up to the next single one; any other is literal.
+    fn quoted(&mut self, in_plural: bool) {
+        self.pos += 1;
+        match self.peek() {
+            Some('\'') => self.pos += 1,
+            Some('{' | '}' | '|') => self.skip_quoted(),
+            Some('#') if in_plural => self.skip_quoted(),
+            _ => {}
+        }
+    }
+
+    fn skip_quoted(&mut self) {
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            if c == '\'' {
+                if self.peek() == Some('\'') {
+                    self.pos += 1;
+                } else {
+                    return;
+                }
+            }
+        }
+    }
+
+    fn argument(&mut self) -> Result<(), IcuError> {
+        let start = self.pos;
+        self.pos += 1;
+        self.skip_whitespace();
+        let name = self.identifier();
+        if name.is_empty() {
+            return Err(self.error(self.pos, "expected an argument name"));
+        }
+        self.skip_whitespace();
+
+        let mut argument = Argument {
+            name,
+            kind: String::new(),
+            selectors: Vec::new(),
+        };
+        match self.peek() {
+            Some('}') => self.pos += 1,
+            Some(',') => {
+                self.pos += 1;
+                self.skip_whitespace();
+                argument.kind = self.identifier();
+                if argument.kind.is_empty() {
+                    return Err(self.error(self.pos, "expected an argument type"));
+                }
+                self.skip_whitespace();
+                if argument.has_branches() {
+                    self.expect(',')?;
+                    // Register before the branches, which may hold more.
+                    let index = self.arguments.len();
+                    let kind = argument.kind.clone();
+                    self.arguments.push(argument);
+                    self.arguments[index].selectors = self.branches(start, &kind)?;
+                    return Ok(());
+                }
+                match self.peek() {
+                    Some('}') => self.pos += 1,
+                    Some(',') => self.style(start)?,
+                    _ => return Err(self.error(self.pos, "expected ',' or '}'")),
+                }
+            }
+            None => return Err(self.error(start, "unclosed argument")),
+            _ => return Err(self.error(self.pos, "expected ',' or '}'")),
+        }
+        self.arguments.push(argument);
+        Ok(())
+    }
+
+    /// The style of a `number`, `date`... argument, up to its closing brace.
+    fn style(&mut self, start: usize) -> Result<(), IcuError> {
+        let mut depth = 0;
+        while let Some(c) = self.peek() {
+            match c {
+                '\'' => {
+                    self.quoted(false);
+                    continue;
+                }
+                '{' => depth += 1,
+                '}' if depth == 0 => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                '}' => depth -= 1,
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        Err(self.error(start, "unclosed argument"))
+    }
+
+    /// `selector {message}` pairs up to the argument's closing brace, with
+    /// an `offset:` first for plurals.
+    fn branches(&mut self, start: usize, kind: &str) -> Result<Vec<String>, IcuError> {
+        let plural = kind != "select";
+        let mut selectors: Vec<String> = Vec::new();
+        self.skip_whitespace();
+        if plural && self.chars[self.pos..].starts_with(&['o', 'f', 'f', 's', 'e', 't', ':']) {
+            self.pos += 7;
+            self.skip_whitespace();
+            if self.identifier().parse::<u32>().is_err() {
+                return Err(self.error(self.pos, "expected an offset number"));
+            }
+        }
+
+        loop {
+            self.skip_whitespace();
+            let at = self.pos;
+            let selector = match self.peek() {
+                Some('}') => break,
+                None => return Err(self.error(start, "unclosed argument")),
+                Some('=') if plural => {
+                    self.pos += 1;
+                    let number = self.identifier();
+                    if number.parse::<f64>().is_err() {
+                        return Err(self.error(at, "expected a number after '='"));
+                    }
+                    format!("={}", number)
+                }
+                Some(_) => self.identifier(),
+            };
+            if selector.is_empty() {
+                return Err(self.error(at, "expected a selector"));
+            }
+            if selectors.contains(&selector) {
+                return Err(self.error(at, &format!("duplicate selector '{}'", selector)));
+            }
+            self.skip_whitespace();
+            self.expect('{')?;
+            self.message(plural, true)?;
+            self.expect('}')?;
+            selectors.push(selector);
+        }
+        self.pos += 1;
+
+        if !selectors.iter().any(|selector| selector == "other") {
+            return Err(self.error(
+                start,
+                &format!("{} argument without an 'other' branch", kind),
+            ));
+        }
+        Ok(selectors)
+    }
+}
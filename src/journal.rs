@@ -0,0 +1,80 @@
+use std::{
+    collections::HashSet,
+    ffi::OsString,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{combined::combined_key, translations::TranslationUnit};
+
+/// Messages of an output that a run has already translated and written, kept
+/// next to it as `<output>.progress` until the language finishes, so a rerun
+/// after an interruption continues after the last written batch. Each line
+/// is one JSON-encoded message key.
+pub struct ProgressJournal {
+    path: PathBuf,
+    done: HashSet<String>,
+}
+
+impl ProgressJournal {
+    pub fn open(output_path: &Path) -> Result<Self, String> {
+        let mut path = OsString::from(output_path.as_os_str());
+        path.push(".progress");
+        let path = PathBuf::from(path);
+
+        let done = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read progress journal {:?}: {}", path, e))?;
+            // A line cut short by the interruption is simply not done.
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        } else {
+            HashSet::new()
+        };
+
+        Ok(Self { path, done })
+    }
+
+    pub fn len(&self) -> usize {
+        self.done.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.done.is_empty()
+    }
+
+    pub fn contains(&self, unit: &TranslationUnit) -> bool {
+        self.done.contains(&combined_key(unit))
+    }
+
+    pub fn record(&mut self, units: &[TranslationUnit]) -> Result<(), String> {
+        let mut lines = String::new();
+        for unit in units {
+            let key = combined_key(unit);
+            lines.push_str(&serde_json::to_string(&key).map_err(|e| e.to_string())?);
+            lines.push('\n');
+            self.done.insert(key);
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Failed to open progress journal {:?}: {}", self.path, e))?;
+        file.write_all(lines.as_bytes())
+            .and_then(|()| file.sync_data())
+            .map_err(|e| format!("Failed to write progress journal {:?}: {}", self.path, e))
+    }
+
+    /// Removes the journal once every message of the language was handled.
+    pub fn finish(self) -> Result<(), String> {
+        if self.path.exists() {
+            fs::remove_file(&self.path)
+                .map_err(|e| format!("Failed to remove progress journal {:?}: {}", self.path, e))?;
+        }
+        Ok(())
+    }
+}
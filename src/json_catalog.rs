@@ -0,0 +1,460 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::Write,
+    path::Path,
+};
+
+use serde::Serialize;
+use serde_json::{Map, Value, ser::PrettyFormatter};
+
+use crate::{
+    configs::DocumentFormat,
+    errors::PoLlmError,
+    files::{FileGuard, write_atomically},
+    plurals::{cldr_forms, plural_forms_or_default},
+    translations::{Document, FormatAdapter, TranslationUnit, WriteOptions, document_as},
+};
+
+/// Suffixes i18next appends to a key for each CLDR plural category.
+const PLURAL_CATEGORIES: &[&str] = &["zero", "one", "two", "few", "many", "other"];
+
+/// An i18next-style JSON catalog: nested objects whose string leaves are
+/// addressed by their dot-separated path.
+#[derive(Debug, Clone)]
+pub struct JsonCatalog {
+    root: Map<String, Value>,
+    /// One level of indentation, `None` for single-line files.
+    indent: Option<String>,
+    trailing_newline: bool,
+}
+
+/// A message of the catalog: a plain string, or the i18next plural variants
+/// (`key_one`, `key_other`, ...) grouped under their base key.
+enum Entry {
+    Single {
+        path: String,
+        text: String,
+    },
+    Plural {
+        base: String,
+        /// `(category, text)` pairs in document order.
+        forms: Vec<(&'static str, String)>,
+    },
+}
+
+impl JsonCatalog {
+    pub fn read(path: &Path) -> Result<Self, PoLlmError> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| PoLlmError::io(format!("Failed to read JSON catalog {:?}", path), e))?;
+        Self::parse(&text).map_err(|e| {
+            PoLlmError::Parse(format!("Failed to parse JSON catalog {:?}: {}", path, e))
+        })
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            root: Map::new(),
+            indent: None,
+            trailing_newline: true,
+        }
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+        // Outputs are created empty before their first write.
+        if text.trim().is_empty() {
+            return Ok(Self::empty());
+        }
+
+        let root = match serde_json::from_str(text).map_err(|e| e.to_string())? {
+            Value::Object(map) => map,
+            _ => return Err("top-level value is not an object".to_string()),
+        };
+        // The first indented line sits one level deep.
+        let indent = text.lines().skip(1).find_map(|line| {
+            let width = line.len() - line.trim_start().len();
+            (width > 0 && width < line.len()).then(|| line[..width].to_string())
+        });
+
+        Ok(Self {
+            root,
+            indent,
+            trailing_newline: text.ends_with('\n'),
+        })
+    }
+
+    fn render(&self) -> Result<String, PoLlmError> {
+        let mut bytes = Vec::new();
+        match &self.indent {
+            Some(indent) => {
+                let mut serializer = serde_json::Serializer::with_formatter(
+                    &mut bytes,
+                    PrettyFormatter::with_indent(indent.as_bytes()),
+                );
+                self.root.serialize(&mut serializer)
+            }
+            None => serde_json::to_writer(&mut bytes, &self.root),
+        }
+        .map_err(|e| PoLlmError::io("Failed to render JSON catalog", e.into()))?;
+
+        let mut text = String::from_utf8(bytes)
+            .map_err(|e| PoLlmError::Validation(format!("Failed to render JSON catalog: {}", e)))?;
+        if self.trailing_newline {
+            text.push('\n');
+        }
+        Ok(text)
+    }
+
+    /// String leaves as `(path, text)`, in document order.
+    fn strings(&self) -> Vec<(String, &str)> {
+        let mut found = Vec::new();
+        flatten(&self.root, "", &mut found);
+        found
+    }
+
+    fn entries(&self) -> Vec<Entry> {
+        let strings = self.strings();
+        let paths: HashSet<&str> = strings.iter().map(|(path, _)| path.as_str()).collect();
+
+        let mut entries = Vec::new();
+        let mut plurals: HashMap<&str, usize> = HashMap::new();
+        for (path, text) in &strings {
+            match plural_variant(path) {
+                // A lone `_one` key without `_other` is an ordinary key.
+                Some((base, category)) if paths.contains(format!("{}_other", base).as_str()) => {
+                    match plurals.get(base) {
+                        Some(&index) => {
+                            if let Entry::Plural { forms, .. } = &mut entries[index] {
+                                forms.push((category, text.to_string()));
+                            }
+                        }
+                        None => {
+                            plurals.insert(base, entries.len());
+                            entries.push(Entry::Plural {
+                                base: base.to_string(),
+                                forms: vec![(category, text.to_string())],
+                            });
+                        }
+                    }
+                }
+                _ => entries.push(Entry::Single {
+                    path: path.clone(),
+                    text: text.to_string(),
+                }),
+            }
+        }
+        entries
+    }
+
+    fn get(&self, path: &str) -> Option<&str> {
+        let mut segments: Vec<&str> = path.split('.').collect();
+        let last = segments.pop()?;
+        let mut map = &self.root;
+        for segment in segments {
+            map = map.get(segment)?.as_object()?;
+        }
+        map.get(last)?.as_str()
+    }
+
+    fn is_filled(&self, path: &str) -> bool {
+        self.get(path).is_some_and(|text| !text.is_empty())
+    }
+
+    /// Sets a string leaf, creating the objects on its path. An existing key
+    /// keeps its position; a new one is appended to its object.
+    fn set(&mut self, path: &str, text: &str) -> Result<(), String> {
+        let mut segments: Vec<&str> = path.split('.').collect();
+        let last = segments.pop().unwrap_or_default();
+        let mut map = &mut self.root;
+        for segment in segments {
+            map = map
+                .entry(segment)
+                .or_insert_with(|| Value::Object(Map::new()))
+                .as_object_mut()
+                .ok_or_else(|| format!("'{}' is not an object in key '{}'", segment, path))?;
+        }
+        map.insert(last.to_string(), Value::String(text.to_string()));
+        Ok(())
+    }
+
+    fn remove(&mut self, path: &str) {
+        let mut segments: Vec<&str> = path.split('.').collect();
+        let Some(last) = segments.pop() else {
+            return;
+        };
+        let mut map = &mut self.root;
+        for segment in segments {
+            match map.get_mut(segment).and_then(Value::as_object_mut) {
+                Some(child) => map = child,
+                None => return,
+            }
+        }
+        map.shift_remove(last);
+    }
+}
+
+fn flatten<'a>(map: &'a Map<String, Value>, prefix: &str, found: &mut Vec<(String, &'a str)>) {
+    for (key, value) in map {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match value {
+            Value::String(text) => found.push((path, text)),
+            Value::Object(child) => flatten(child, &path, found),
+            _ => {}
+        }
+    }
+}
+
+/// Splits `key_few` into `("key", "few")`.
+fn plural_variant(key: &str) -> Option<(&str, &'static str)> {
+    let (base, suffix) = key.rsplit_once('_')?;
+    let category = PLURAL_CATEGORIES.iter().find(|c| **c == suffix)?;
+    Some((base, category))
+}
+
+/// Reorders an output catalog's keys to follow its template and adopts the
+/// template's indentation, so each target file diffs cleanly against the
+/// source. Keys missing from the template keep their order after the others,
+/// and plural variants the source language lacks sit next to their siblings.
+pub fn align_to_template(output_path: &Path, template_path: &Path) -> Result<(), PoLlmError> {
+    let template = JsonCatalog::read(template_path)?;
+    let current = fs::read_to_string(output_path)
+        .map_err(|e| PoLlmError::io(format!("Failed to read JSON catalog {:?}", output_path), e))?;
+    let mut catalog = JsonCatalog::parse(&current).map_err(|e| {
+        PoLlmError::Parse(format!(
+            "Failed to parse JSON catalog {:?}: {}",
+            output_path, e
+        ))
+    })?;
+
+    sort_like(&mut catalog.root, &template.root);
+    catalog.indent = template.indent;
+    catalog.trailing_newline = template.trailing_newline;
+
+    let text = catalog.render()?;
+    if text != current {
+        write_atomically(output_path, |writer| writer.write_all(text.as_bytes()))
+            .map_err(|e| PoLlmError::io("Failed to write JSON catalog", e))?;
+    }
+    Ok(())
+}
+
+fn sort_like(map: &mut Map<String, Value>, template: &Map<String, Value>) {
+    let positions: HashMap<&str, usize> = template
+        .keys()
+        .enumerate()
+        .map(|(i, key)| (key.as_str(), i))
+        .collect();
+    let mut groups: HashMap<&str, usize> = HashMap::new();
+    for (key, &position) in &positions {
+        if let Some((base, _)) = plural_variant(key) {
+            let first = groups.entry(base).or_insert(position);
+            *first = (*first).min(position);
+        }
+    }
+    let rank = |key: &str| match plural_variant(key).and_then(|(base, category)| {
+        let category = PLURAL_CATEGORIES.iter().position(|c| *c == category)?;
+        Some((*groups.get(base)?, category))
+    }) {
+        Some(rank) => rank,
+        None => (positions.get(key).copied().unwrap_or(usize::MAX), 0),
+    };
+
+    let mut entries: Vec<(String, Value)> = std::mem::take(map).into_iter().collect();
+    entries.sort_by_key(|(key, _)| rank(key));
+    for (key, mut value) in entries {
+        if let (Value::Object(child), Some(Value::Object(template_child))) =
+            (&mut value, template.get(&key))
+        {
+            sort_like(child, template_child);
+        }
+        map.insert(key, value);
+    }
+}
+
+/// Outputs start empty; an entry counts as translated once its key is present
+/// and non-empty in the output. Plural entries are checked by their `_other`
+/// variant, which every language has.
+pub struct JsonCatalogAdapter;
+
+impl FormatAdapter for JsonCatalogAdapter {
+    fn format(&self) -> DocumentFormat {
+        DocumentFormat::Json
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["json"]
+    }
+
+    fn create_output(
+        &self,
+        _input_path: &Path,
+        output_path: &Path,
+        _target_lang: &str,
+    ) -> Result<(), PoLlmError> {
+        fs::File::create(output_path)
+            .map(|_| ())
+            .map_err(|e| PoLlmError::io(format!("Failed to create {:?}", output_path), e))
+    }
+
+    fn load_template(&self, path: &Path) -> Result<Document, PoLlmError> {
+        Ok(Box::new(JsonCatalog::read(path)?))
+    }
+
+    /// Outputs start empty rather than as a copy of their input, so a
+    /// missing output means nothing is translated yet.
+    fn load_existing(
+        &self,
+        output_path: &Path,
+        _template: &Document,
+    ) -> Result<Document, PoLlmError> {
+        if output_path.exists() {
+            return Ok(Box::new(JsonCatalog::read(output_path)?));
+        }
+        println!("         Output doesn't exist, starting from an empty catalog");
+        Ok(Box::new(JsonCatalog::empty()))
+    }
+
+    fn extract_units(
+        &self,
+        existing: &Document,
+        template: &Document,
+        skip_translated: bool,
+    ) -> Vec<TranslationUnit> {
+        let document: &JsonCatalog = document_as(existing);
+        let template: &JsonCatalog = document_as(template);
+        template
+            .entries()
+            .into_iter()
+            .filter_map(|entry| {
+                let (context, msg_id, msg_id_plural) = match entry {
+                    Entry::Single { path, text } => (path, text, None),
+                    Entry::Plural { base, forms } => {
+                        let form = |category: &str| {
+                            forms.iter().find(|(c, _)| *c == category).map(|(_, t)| t)
+                        };
+                        let singular = form("one").or(forms.first().map(|(_, t)| t))?;
+                        let plural = form("other")?;
+                        (base, singular.clone(), Some(plural.clone()))
+                    }
+                };
+                let is_plural = msg_id_plural.is_some();
+
+                let probe = if is_plural {
+                    format!("{}_other", context)
+                } else {
+                    context.clone()
+                };
+                if msg_id.trim().is_empty() || (skip_translated && document.is_filled(&probe)) {
+                    return None;
+                }
+
+                Some(TranslationUnit {
+                    msg_id,
+                    msg_id_plural,
+                    msg_str: (!is_plural).then(String::new),
+                    msg_str_plural: is_plural.then(Vec::new),
+                    context: Some(context),
+                    comments: None,
+                    rationale: None,
+                    failure: None,
+                    warnings: Vec::new(),
+                    findings: Vec::new(),
+                    attribution: None,
+                    format_flags: Vec::new(),
+                    references: String::new(),
+                    fuzzy: false,
+                    previous: None,
+                })
+            })
+            .collect()
+    }
+
+    fn write_translations(
+        &self,
+        translations: Vec<TranslationUnit>,
+        target_lang: &str,
+        output: &FileGuard,
+        options: &WriteOptions,
+    ) -> Result<(), PoLlmError> {
+        let output_path = output.path();
+        let mut catalog = JsonCatalog::read(output_path)?;
+        let categories = plural_forms_or_default(target_lang).categories;
+
+        let mut expected: Vec<(String, String)> = Vec::new();
+        for unit in translations {
+            let Some(path) = unit.context.clone().filter(|_| unit.failure.is_none()) else {
+                continue;
+            };
+
+            match (&unit.msg_id_plural, &unit.msg_str_plural) {
+                (Some(_), Some(forms)) => {
+                    if forms.iter().all(String::is_empty) {
+                        continue;
+                    }
+                    let pairs = cldr_forms(categories, forms);
+
+                    for category in PLURAL_CATEGORIES {
+                        if !pairs.iter().any(|(c, _)| c == category) {
+                            catalog.remove(&format!("{}_{}", path, category));
+                        }
+                    }
+                    for (category, form) in pairs {
+                        let key = format!("{}_{}", path, category);
+                        catalog.set(&key, form).map_err(PoLlmError::Parse)?;
+                        expected.push((key, form.to_string()));
+                    }
+                }
+                (Some(_), None) => {}
+                (None, _) => {
+                    let text = unit.msg_str.unwrap_or_default();
+                    if text.is_empty() {
+                        continue;
+                    }
+                    catalog.set(&path, &text).map_err(PoLlmError::Parse)?;
+                    expected.push((path, text));
+                }
+            }
+        }
+
+        let text = catalog.render()?;
+        write_atomically(output_path, |writer| writer.write_all(text.as_bytes()))
+            .map_err(|e| PoLlmError::io("Failed to write JSON catalog", e))?;
+
+        if options.verify_roundtrip {
+            let reparsed = JsonCatalog::read(output_path).map_err(|e| {
+                PoLlmError::Validation(format!("Round-trip check failed, cannot re-parse: {}", e))
+            })?;
+            let differing = expected
+                .iter()
+                .filter(|(path, text)| reparsed.get(path) != Some(text.as_str()))
+                .count();
+            if differing > 0 {
+                return Err(PoLlmError::Validation(format!(
+                    "Round-trip check failed: {} value(s) read back differently",
+                    differing
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish_output(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+        _options: &WriteOptions,
+    ) -> Result<(), PoLlmError> {
+        align_to_template(output_path, input_path).map_err(|e| {
+            e.context(format!(
+                "Failed to reorder {:?} like its input",
+                output_path
+            ))
+        })
+    }
+}
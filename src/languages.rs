@@ -0,0 +1,296 @@
+use std::{collections::HashSet, sync::LazyLock};
+
+use regex::Regex;
+
+/// Starts the reason of every translation failed as the wrong language.
+pub const WRONG_LANGUAGE: &str = "wrong language?";
+
+/// Letters needed before the script of a translation is judged.
+const MIN_LETTERS: usize = 4;
+
+/// Scripts, each a named group matching one letter.
+const SCRIPTS: &[&str] = &[
+    "Latin",
+    "Cyrillic",
+    "Greek",
+    "Armenian",
+    "Georgian",
+    "Arabic",
+    "Hebrew",
+    "Devanagari",
+    "Bengali",
+    "Gurmukhi",
+    "Gujarati",
+    "Tamil",
+    "Telugu",
+    "Kannada",
+    "Malayalam",
+    "Sinhala",
+    "Thai",
+    "Lao",
+    "Khmer",
+    "Myanmar",
+    "Ethiopic",
+    "Hangul",
+    "Hiragana",
+    "Katakana",
+    "Han",
+];
+
+static SCRIPT: LazyLock<Regex> = LazyLock::new(|| {
+    let groups: Vec<String> = SCRIPTS
+        .iter()
+        .map(|script| format!(r"(?P<{0}>\p{{{0}}})", script))
+        .collect();
+    Regex::new(&groups.join("|")).unwrap()
+});
+
+/// Placeholders, markup, entities and URLs, whose letters belong to no
+/// language.
+static NOT_TEXT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{[^{}]*\}|<[^<>]*>|%(?:\d+\$)?[-+ #0-9.]*[a-zA-Z@]|&#?\w+;|\$\w+|\S+://\S+")
+        .unwrap()
+});
+
+static WORD: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\p{L}[\p{L}\p{M}]*").unwrap());
+
+/// Frequent words particular to a language, by its primary subtag.
+const STOPWORDS: &[(&str, &str, &[&str])] = &[
+    (
+        "en",
+        "English",
+        &[
+            "the", "and", "of", "with", "this", "that", "your", "you", "are", "from", "please",
+            "cannot", "these", "which", "there",
+        ],
+    ),
+    (
+        "es",
+        "Spanish",
+        &[
+            "el", "los", "las", "y", "usted", "pero", "también", "puede", "hay", "sus", "muy",
+            "desea", "ningún",
+        ],
+    ),
+    (
+        "fr",
+        "French",
+        &[
+            "les", "des", "du", "et", "est", "une", "pour", "dans", "avec", "vous", "pas", "sur",
+            "cette", "sont", "votre", "être",
+        ],
+    ),
+    (
+        "de",
+        "German",
+        &[
+            "der", "das", "und", "ist", "nicht", "mit", "eine", "für", "auf", "werden", "wird",
+            "können", "oder", "sich", "ihre", "diese",
+        ],
+    ),
+    (
+        "it",
+        "Italian",
+        &[
+            "gli", "della", "degli", "che", "è", "sono", "non", "questo", "questa", "nel", "alla",
+            "essere", "anche", "più",
+        ],
+    ),
+    (
+        "pt",
+        "Portuguese",
+        &[
+            "não", "você", "os", "dos", "é", "com", "em", "também", "são", "seu", "ao", "pelo",
+            "isso", "uma",
+        ],
+    ),
+    (
+        "nl",
+        "Dutch",
+        &[
+            "het", "een", "van", "niet", "zijn", "met", "voor", "op", "dat", "deze", "wordt",
+            "kunnen", "naar", "ook", "bij", "uw",
+        ],
+    ),
+    (
+        "pl",
+        "Polish",
+        &[
+            "nie", "się", "jest", "że", "oraz", "dla", "przez", "może", "już", "aby", "jeśli",
+            "został",
+        ],
+    ),
+];
+
+/// Characters written differently in Simplified and Traditional Chinese,
+/// pairwise.
+const SIMPLIFIED: &str =
+    "这们说国时会来对个为发后关开门东车长书见语话读写认设点击选择项页删载错误码户号务间网络数据";
+const TRADITIONAL: &str =
+    "這們說國時會來對個為發後關開門東車長書見語話讀寫認設點擊選擇項頁刪載錯誤碼戶號務間網絡數據";
+
+/// Checks that a translation is written in its target language, cheaply:
+/// most of its letters must be in the language's script, discounting words
+/// kept from the source such as product names; Latin-script languages must
+/// not read as another language by its frequent words; and Chinese must use
+/// the characters of its variant.
+pub fn check_language(
+    source: &str,
+    translation: &str,
+    source_lang: &str,
+    target_lang: &str,
+) -> Result<(), String> {
+    let tag = target_lang.replace('_', "-").to_lowercase();
+    let primary = tag.split('-').next().unwrap_or_default();
+    if primary == primary_subtag(source_lang) {
+        return Ok(());
+    }
+
+    let source_words: HashSet<String> = WORD
+        .find_iter(source)
+        .map(|word| word.as_str().to_lowercase())
+        .collect();
+    let text = NOT_TEXT.replace_all(translation, " ");
+    let new_text: Vec<&str> = WORD
+        .find_iter(&text)
+        .map(|word| word.as_str())
+        .filter(|word| !source_words.contains(&word.to_lowercase()))
+        .collect();
+
+    let mut counts = vec![0usize; SCRIPTS.len()];
+    for captures in SCRIPT.captures_iter(&new_text.concat()) {
+        if let Some(index) = SCRIPTS
+            .iter()
+            .position(|script| captures.name(script).is_some())
+        {
+            counts[index] += 1;
+        }
+    }
+    let total: usize = counts.iter().sum();
+    let expected = expected_scripts(primary, &tag);
+    let matching: usize = SCRIPTS
+        .iter()
+        .zip(&counts)
+        .filter(|(script, _)| expected.contains(script))
+        .map(|(_, count)| count)
+        .sum();
+    if total >= MIN_LETTERS && matching * 2 < total {
+        let (dominant, _) = SCRIPTS
+            .iter()
+            .zip(&counts)
+            .max_by_key(|(_, count)| **count)
+            .unwrap_or((&"", &0));
+        return Err(format!(
+            "{} written in {} script, expected {}",
+            WRONG_LANGUAGE,
+            dominant,
+            expected.join(" or ")
+        ));
+    }
+
+    if expected == ["Latin"] {
+        check_stopwords(translation, primary)?;
+    }
+    if primary == "zh" {
+        check_chinese_variant(translation, &tag)?;
+    }
+    Ok(())
+}
+
+fn primary_subtag(lang: &str) -> String {
+    lang.split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase()
+}
+
+fn expected_scripts(primary: &str, tag: &str) -> &'static [&'static str] {
+    match primary {
+        "sr" if tag.contains("latn") => &["Latin"],
+        "ru" | "uk" | "be" | "bg" | "sr" | "mk" | "kk" | "ky" | "mn" | "tg" => &["Cyrillic"],
+        "el" => &["Greek"],
+        "hy" => &["Armenian"],
+        "ka" => &["Georgian"],
+        "ar" | "fa" | "ur" | "ps" | "ckb" => &["Arabic"],
+        "he" | "yi" => &["Hebrew"],
+        "hi" | "mr" | "ne" | "sa" => &["Devanagari"],
+        "bn" | "as" => &["Bengali"],
+        "pa" => &["Gurmukhi"],
+        "gu" => &["Gujarati"],
+        "ta" => &["Tamil"],
+        "te" => &["Telugu"],
+        "kn" => &["Kannada"],
+        "ml" => &["Malayalam"],
+        "si" => &["Sinhala"],
+        "th" => &["Thai"],
+        "lo" => &["Lao"],
+        "km" => &["Khmer"],
+        "my" => &["Myanmar"],
+        "am" | "ti" => &["Ethiopic"],
+        "ko" => &["Hangul", "Han"],
+        "ja" => &["Hiragana", "Katakana", "Han"],
+        "zh" => &["Han"],
+        _ => &["Latin"],
+    }
+}
+
+/// A translation with two or more frequent words of another language and
+/// none of its own; three for languages without a list of their own.
+fn check_stopwords(translation: &str, primary: &str) -> Result<(), String> {
+    let words: Vec<String> = WORD
+        .find_iter(translation)
+        .map(|word| word.as_str().to_lowercase())
+        .collect();
+    let hits = |stopwords: &[&str]| {
+        words
+            .iter()
+            .filter(|word| stopwords.contains(&word.as_str()))
+            .count()
+    };
+
+    let own = STOPWORDS.iter().find(|(lang, _, _)| *lang == primary);
+    if own.is_some_and(|(_, _, stopwords)| hits(stopwords) > 0) {
+        return Ok(());
+    }
+    let needed = if own.is_some() { 2 } else { 3 };
+    let likeliest = STOPWORDS
+        .iter()
+        .filter(|(lang, _, _)| *lang != primary)
+        .map(|(_, name, stopwords)| (name, hits(stopwords)))
+        .max_by_key(|(_, hits)| *hits);
+    match likeliest {
+        Some((name, hits)) if hits >= needed => {
+            Err(format!("{} reads as {}", WRONG_LANGUAGE, name))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Traditional characters in Simplified Chinese or the other way round,
+/// with none of the expected ones. A bare `zh` may be either.
+fn check_chinese_variant(translation: &str, tag: &str) -> Result<(), String> {
+    let traditional = ["hant", "tw", "hk", "mo"]
+        .iter()
+        .any(|subtag| tag.split('-').any(|part| part == *subtag));
+    let simplified = ["hans", "cn", "sg"]
+        .iter()
+        .any(|subtag| tag.split('-').any(|part| part == *subtag));
+    let count = |characters: &str| {
+        translation
+            .chars()
+            .filter(|c| characters.contains(*c))
+            .count()
+    };
+
+    let (expected, other, name) = match (simplified, traditional) {
+        (true, false) => (count(SIMPLIFIED), count(TRADITIONAL), "Traditional"),
+        (false, true) => (count(TRADITIONAL), count(SIMPLIFIED), "Simplified"),
+        _ => return Ok(()),
+    };
+    if expected == 0 && other >= 2 {
+        Err(format!("{} written in {} Chinese", WRONG_LANGUAGE, name))
+    } else {
+        Ok(())
+    }
+}
@@ -1,3 +1,34 @@
+pub mod android;
+pub mod apple;
+pub mod backups;
+pub mod bidi;
+pub mod charsets;
+pub mod combined;
+pub mod compendium;
 pub mod configs;
+pub mod errors;
+pub mod events;
+pub mod files;
+pub mod formats;
+pub mod hooks;
+pub mod icu;
+pub mod journal;
+pub mod json_catalog;
+pub mod languages;
+pub mod linguist;
+pub mod memory;
+pub mod mo_writer;
+pub mod msgfmt;
+pub mod overrides;
+pub mod plurals;
+pub mod po_writer;
+pub mod report;
+pub mod resx;
+pub mod review_csv;
+pub mod tmx;
 pub mod translations;
 pub mod translators;
+pub mod usage;
+pub mod validations;
+pub mod xliff;
+pub mod yaml;
@@ -0,0 +1,486 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::Write,
+    ops::Range,
+    path::Path,
+};
+
+use roxmltree::{Document, Node, ParsingOptions};
+
+use crate::{
+    configs::DocumentFormat,
+    errors::PoLlmError,
+    files::{FileGuard, write_atomically},
+    translations::{
+        self, FormatAdapter, TranslationUnit, WriteOptions, document_as, existing_or_template,
+    },
+    xliff::{inner_range, start_tag_end},
+};
+
+/// Messages lupdate keeps for reference after they left the sources; they are
+/// neither translated nor rewritten.
+const RETIRED: &[&str] = &["vanished", "obsolete"];
+
+/// Context name, source text and comments; Qt tells apart messages with the
+/// same source in one context by their disambiguation comment.
+type MessageKey = (Option<String>, String, Option<String>);
+
+/// A Qt Linguist `.ts` file.
+#[derive(Debug, Clone)]
+pub struct LinguistDocument {
+    text: String,
+}
+
+/// One `<message>` with the nodes needed to read and rewrite it.
+struct Message<'a, 'input> {
+    key: MessageKey,
+    numerus: bool,
+    node: Node<'a, 'input>,
+    translation: Option<Node<'a, 'input>>,
+    translated: bool,
+}
+
+impl LinguistDocument {
+    pub fn read(path: &Path) -> Result<Self, PoLlmError> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| PoLlmError::io(format!("Failed to read TS file {:?}", path), e))?;
+        Self::parse(text).map_err(|e| e.context(format!("Failed to parse TS file {:?}", path)))
+    }
+
+    pub fn parse(text: String) -> Result<Self, PoLlmError> {
+        let document = parse_ts(&text).map_err(|e| PoLlmError::Parse(e.to_string()))?;
+        let root = document.root_element();
+        if !root.has_tag_name("TS") {
+            return Err(PoLlmError::Parse(format!(
+                "root element is <{}>, not <TS>",
+                root.tag_name().name()
+            )));
+        }
+        Ok(Self { text })
+    }
+
+    fn units(&self) -> Vec<(MessageKey, bool, bool)> {
+        let Ok(document) = parse_ts(&self.text) else {
+            return Vec::new();
+        };
+        messages(&document)
+            .into_iter()
+            .map(|message| (message.key, message.numerus, message.translated))
+            .collect()
+    }
+
+    fn translated_keys(&self) -> HashSet<MessageKey> {
+        self.units()
+            .into_iter()
+            .filter(|(_, _, translated)| *translated)
+            .map(|(key, _, _)| key)
+            .collect()
+    }
+}
+
+fn parse_ts(text: &str) -> Result<Document<'_>, roxmltree::Error> {
+    // lupdate writes `<!DOCTYPE TS>`.
+    Document::parse_with_options(
+        text,
+        ParsingOptions {
+            allow_dtd: true,
+            ..ParsingOptions::default()
+        },
+    )
+}
+
+pub(crate) fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| n.has_tag_name(name))
+}
+
+pub(crate) fn text_of(node: Node) -> String {
+    node.descendants()
+        .filter_map(|n| n.is_text().then(|| n.text()).flatten())
+        .collect()
+}
+
+fn messages<'a, 'input>(document: &'a Document<'input>) -> Vec<Message<'a, 'input>> {
+    let mut messages = Vec::new();
+    for context in document
+        .root_element()
+        .children()
+        .filter(|n| n.has_tag_name("context"))
+    {
+        let name = child(context, "name")
+            .map(text_of)
+            .filter(|name| !name.is_empty());
+
+        for node in context.children().filter(|n| n.has_tag_name("message")) {
+            let Some(source) = child(node, "source") else {
+                continue;
+            };
+            let translation = child(node, "translation");
+            let kind = translation.and_then(|t| t.attribute("type"));
+            if kind.is_some_and(|kind| RETIRED.contains(&kind)) {
+                continue;
+            }
+
+            let comments: Vec<String> = ["comment", "extracomment"]
+                .into_iter()
+                .filter_map(|name| child(node, name))
+                .map(|comment| text_of(comment).trim().to_string())
+                .filter(|comment| !comment.is_empty())
+                .collect();
+            let translated = translation.is_some_and(|translation| {
+                kind != Some("unfinished") && !text_of(translation).trim().is_empty()
+            });
+
+            messages.push(Message {
+                key: (
+                    name.clone(),
+                    text_of(source),
+                    (!comments.is_empty()).then(|| comments.join("\n")),
+                ),
+                numerus: node.attribute("numerus") == Some("yes"),
+                node,
+                translation,
+                translated,
+            });
+        }
+    }
+    messages
+}
+
+/// Outputs start as a copy of their input; a message counts as translated
+/// once its `<translation>` has text and is no longer marked unfinished.
+pub struct LinguistAdapter;
+
+impl FormatAdapter for LinguistAdapter {
+    fn format(&self) -> DocumentFormat {
+        DocumentFormat::Ts
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["ts"]
+    }
+
+    fn load_template(&self, path: &Path) -> Result<translations::Document, PoLlmError> {
+        Ok(Box::new(LinguistDocument::read(path)?))
+    }
+
+    fn load_existing(
+        &self,
+        output_path: &Path,
+        template: &translations::Document,
+    ) -> Result<translations::Document, PoLlmError> {
+        existing_or_template(output_path, template, LinguistDocument::read)
+    }
+
+    fn extract_units(
+        &self,
+        existing: &translations::Document,
+        template: &translations::Document,
+        skip_translated: bool,
+    ) -> Vec<TranslationUnit> {
+        let document: &LinguistDocument = document_as(existing);
+        let template: &LinguistDocument = document_as(template);
+        let translated = if skip_translated {
+            document.translated_keys()
+        } else {
+            HashSet::new()
+        };
+
+        let mut seen = HashSet::new();
+        template
+            .units()
+            .into_iter()
+            .filter(|(key, _, _)| !key.1.trim().is_empty() && !translated.contains(key))
+            .filter(|(key, _, _)| seen.insert(key.clone()))
+            .map(
+                |((context, msg_id, comments), numerus, _)| TranslationUnit {
+                    // Qt has a single source for all numerus forms.
+                    msg_id_plural: numerus.then(|| msg_id.clone()),
+                    msg_id,
+                    msg_str: (!numerus).then(String::new),
+                    msg_str_plural: numerus.then(Vec::new),
+                    context,
+                    comments,
+                    rationale: None,
+                    failure: None,
+                    warnings: Vec::new(),
+                    findings: Vec::new(),
+                    attribution: None,
+                    format_flags: Vec::new(),
+                    references: String::new(),
+                    fuzzy: false,
+                    previous: None,
+                },
+            )
+            .collect()
+    }
+
+    fn write_translations(
+        &self,
+        translations: Vec<TranslationUnit>,
+        target_lang: &str,
+        output: &FileGuard,
+        options: &WriteOptions,
+    ) -> Result<(), PoLlmError> {
+        let output_path = output.path();
+        let document = LinguistDocument::read(output_path)?;
+
+        // Failed entries stay unfinished rather than losing what they hold.
+        let targets: HashMap<MessageKey, Vec<String>> = translations
+            .into_iter()
+            .filter(|unit| unit.failure.is_none())
+            .filter_map(|unit| {
+                let forms = match unit.msg_id_plural {
+                    Some(_) => unit.msg_str_plural?,
+                    None => vec![unit.msg_str?],
+                };
+                Some(((unit.context, unit.msg_id, unit.comments), forms))
+            })
+            .filter(|(_, forms)| !forms.is_empty() && forms.iter().all(|f| !f.is_empty()))
+            .collect();
+
+        let text =
+            write_translations(&document.text, &targets, target_lang).map_err(PoLlmError::Parse)?;
+
+        write_atomically(output_path, |writer| writer.write_all(text.as_bytes()))
+            .map_err(|e| PoLlmError::io("Failed to write TS file", e))?;
+
+        if options.verify_roundtrip {
+            let reparsed = LinguistDocument::read(output_path).map_err(|e| {
+                PoLlmError::Validation(format!("Round-trip check failed, cannot re-parse: {}", e))
+            })?;
+            let translated = reparsed.translated_keys();
+            let missing = targets
+                .keys()
+                .filter(|key| !translated.contains(*key))
+                .count();
+            if missing > 0 {
+                return Err(PoLlmError::Validation(format!(
+                    "Round-trip check failed: {} written translation(s) still unfinished",
+                    missing
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Fills in `<translation>` for every message with a translation and drops
+/// its `type="unfinished"`, leaving the rest of the file as it was. A TS
+/// file without a `language` attribute gets the target language.
+fn write_translations(
+    text: &str,
+    targets: &HashMap<MessageKey, Vec<String>>,
+    target_lang: &str,
+) -> Result<String, String> {
+    let document = parse_ts(text).map_err(|e| e.to_string())?;
+
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+    for message in messages(&document) {
+        let Some(forms) = targets.get(&message.key) else {
+            continue;
+        };
+
+        let last_child = message.node.children().rfind(|n| n.is_element());
+        let indent = match message.translation.or(last_child) {
+            Some(node) => indent_before(text, node.range().start),
+            None => "",
+        };
+        let body = if message.numerus {
+            let form_indent = message
+                .translation
+                .and_then(|node| child(node, "numerusform"))
+                .map_or_else(
+                    || format!("{}    ", indent),
+                    |form| indent_before(text, form.range().start).to_string(),
+                );
+            let forms: String = forms
+                .iter()
+                .map(|form| {
+                    format!(
+                        "\n{}<numerusform>{}</numerusform>",
+                        form_indent,
+                        escape_xml(form)
+                    )
+                })
+                .collect();
+            format!("{}\n{}", forms, indent)
+        } else {
+            escape_xml(&forms[0])
+        };
+
+        match message.translation {
+            Some(node) => {
+                let tag_end = start_tag_end(text, node.range().start);
+                if text[..tag_end].ends_with("/>") {
+                    edits.push((tag_end - 2..tag_end, format!(">{}</translation>", body)));
+                } else {
+                    edits.push((inner_range(text, node), body));
+                }
+                if let Some(kind) = node.attribute_node("type") {
+                    let range = kind.range();
+                    let start = text[..range.start].trim_end().len();
+                    edits.push((start..range.end, String::new()));
+                }
+            }
+            None => {
+                let at = last_child.ok_or("message without children")?.range().end;
+                edits.push((
+                    at..at,
+                    format!("\n{}<translation>{}</translation>", indent, body),
+                ));
+            }
+        }
+    }
+
+    let root = document.root_element();
+    match root.attribute_node("language") {
+        Some(language) if !language.value().is_empty() => {}
+        Some(language) => edits.push((language.range(), format!("language=\"{}\"", target_lang))),
+        None => {
+            let tag_end = start_tag_end(text, root.range().start);
+            edits.push((
+                tag_end - 1..tag_end - 1,
+                format!(" language=\"{}\"", escape_xml(target_lang)),
+            ));
+        }
+    }
+
+    // Apply from the end so earlier offsets stay valid.
+    edits.sort_by(|(a, _), (b, _)| b.start.cmp(&a.start).then(b.end.cmp(&a.end)));
+    let mut result = text.to_string();
+    for (range, replacement) in edits {
+        result.replace_range(range, &replacement);
+    }
+
+    parse_ts(&result).map_err(|e| format!("Rewritten document is not valid XML: {}", e))?;
+    Ok(result)
+}
+
+/// Whitespace between the start of the line and `pos`, or nothing when the
+/// element does not start its line.
+pub(crate) fn indent_before(text: &str, pos: usize) -> &str {
+    let line_start = text[..pos].rfind('\n').map_or(0, |i| i + 1);
+    let indent = &text[line_start..pos];
+    if indent.trim().is_empty() { indent } else { "" }
+}
+
+pub(crate) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<!DOCTYPE TS>
+<TS version="2.1">
+<context>
+    <name>MainWindow</name>
+    <message>
+        <location filename="../main.cpp" line="12"/>
+        <source>&amp;Open</source>
+        <translation type="unfinished"></translation>
+    </message>
+    <message numerus="yes">
+        <source>%n file(s)</source>
+        <translation type="unfinished">
+            <numerusform></numerusform>
+            <numerusform></numerusform>
+        </translation>
+    </message>
+    <message>
+        <source>Open</source>
+        <comment>state</comment>
+        <translation type="unfinished"/>
+    </message>
+    <message>
+        <source>Quit</source>
+        <translation type="vanished">Quitter</translation>
+    </message>
+</context>
+</TS>
+"#;
+
+    const TRANSLATED: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<!DOCTYPE TS>
+<TS version="2.1" language="fr">
+<context>
+    <name>MainWindow</name>
+    <message>
+        <location filename="../main.cpp" line="12"/>
+        <source>&amp;Open</source>
+        <translation>&amp;Ouvrir</translation>
+    </message>
+    <message numerus="yes">
+        <source>%n file(s)</source>
+        <translation>
+            <numerusform>%n fichier</numerusform>
+            <numerusform>%n fichiers</numerusform>
+        </translation>
+    </message>
+    <message>
+        <source>Open</source>
+        <comment>state</comment>
+        <translation>Ouvert</translation>
+    </message>
+    <message>
+        <source>Quit</source>
+        <translation type="vanished">Quitter</translation>
+    </message>
+</context>
+</TS>
+"#;
+
+    fn key(msg_id: &str, comments: Option<&str>) -> MessageKey {
+        (
+            Some("MainWindow".to_string()),
+            msg_id.to_string(),
+            comments.map(String::from),
+        )
+    }
+
+    #[test]
+    fn units_leave_out_retired_messages() {
+        let document: translations::Document =
+            Box::new(LinguistDocument::parse(SOURCE.to_string()).unwrap());
+        let units = LinguistAdapter.extract_units(&document, &document, true);
+        let keys: Vec<_> = units
+            .iter()
+            .map(|unit| (unit.msg_id.as_str(), unit.is_plural()))
+            .collect();
+        assert_eq!(
+            keys,
+            [("&Open", false), ("%n file(s)", true), ("Open", false)]
+        );
+        assert_eq!(units[2].comments.as_deref(), Some("state"));
+    }
+
+    #[test]
+    fn translations_match_the_golden_file() {
+        let targets = HashMap::from([
+            (key("&Open", None), vec!["&Ouvrir".to_string()]),
+            (
+                key("%n file(s)", None),
+                vec!["%n fichier".to_string(), "%n fichiers".to_string()],
+            ),
+            (key("Open", Some("state")), vec!["Ouvert".to_string()]),
+        ]);
+        assert_eq!(
+            write_translations(SOURCE, &targets, "fr").unwrap(),
+            TRANSLATED
+        );
+
+        let document: translations::Document =
+            Box::new(LinguistDocument::parse(TRANSLATED.to_string()).unwrap());
+        assert!(
+            LinguistAdapter
+                .extract_units(&document, &document, true)
+                .is_empty()
+        );
+    }
+}
@@ -1,27 +1,76 @@
 use anyhow::{Context, Result};
 use async_openai::{Client, config::OpenAIConfig};
-use clap::Parser;
+use backoff::ExponentialBackoff;
+use clap::{Parser, Subcommand};
 use futures::stream::{self, StreamExt};
-use glob::glob;
+use glob::{MatchOptions, Pattern, glob_with};
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use po_llm::{
-    configs::AppConfig,
-    translations::{GettextAdapter, Translatable},
-    translators::{DryRunTranslator, LlmTranslator, Translator},
+    android::android_qualifier,
+    backups::BackupManager,
+    charsets::read_catalog,
+    combined::{combined_key, write_combined},
+    compendium::{Compendium, compendium_path},
+    configs::{
+        AppConfig, Backend, DocumentFormat, LengthMode, OversizedMode, RuleSeverity,
+        TranslationConfig,
+    },
+    events::{Event, EventLog},
+    files::{FileLocks, InputKind},
+    formats::adapter_for,
+    hooks::run_post_file,
+    journal::ProgressJournal,
+    languages::WRONG_LANGUAGE,
+    memory::TermMemory,
+    msgfmt::check_po_file,
+    overrides::Overrides,
+    plurals::plural_forms_or_default,
+    report::{RetryList, ValidationReport},
+    review_csv::{ReviewRow, read_review_csv, review_rows, write_review_csv},
+    tmx::{TmxLanguage, TranslationMemory, write_tmx},
+    translations::{
+        FormatAdapter, GettextAdapter, PreviousSources, TranslationUnit, WriteOptions,
+        attribution_pattern,
+    },
+    translators::{
+        BisectingTranslator, CommandTranslator, DeepLTranslator, DryRunTranslator, LlmTranslator,
+        TranslationResult, Translator,
+    },
+    usage::{RetryBudget, TokenUsage, estimate_output_tokens, output_token_limit},
+    validations::{
+        SAME_AS_SOURCE, STRAY_CHARACTERS, audit_catalog, check_length, validate_import,
+        validate_translations,
+    },
 };
+use rand::{SeedableRng, rngs::StdRng};
+use regex::Regex;
+use reqwest::header::HeaderName;
 use std::{
-    fs::{self, File},
+    collections::{BTreeMap, HashMap, HashSet},
+    env, fs,
+    ops::AddAssign,
     path::{Path, PathBuf},
-    sync::Arc,
-    time::Instant,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
 };
 
 #[derive(Parser)]
 #[command(name = "po-llm")]
 #[command(about = "Translate PO files using LLM", long_about = None)]
+#[command(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
 struct Args {
-    #[arg(value_parser = check_file_exists, help = "Path to TOML configuration file")]
-    config_path: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[arg(
+        required = true,
+        value_parser = check_file_exists,
+        help = "Path to TOML configuration file"
+    )]
+    config_path: Option<PathBuf>,
 
     #[arg(short, long, help = "Dry run mode (no actual translation)")]
     dry_run: bool,
@@ -29,6 +78,134 @@ struct Args {
     #[arg(short, long, help = "Force write even in dry run mode")]
     force_write: bool,
 
+    #[arg(
+        long,
+        conflicts_with = "force_write",
+        help = "Print why each message would or wouldn't be sent to the LLM, without translating"
+    )]
+    explain: bool,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Apply the overrides from [profiles.NAME] in the config file"
+    )]
+    profile: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        value_parser = check_file_exists,
+        help = "Load environment variables from this file instead of .env next to the config file"
+    )]
+    env_file: Option<PathBuf>,
+
+    #[arg(long, help = "Back up existing output files before overwriting them")]
+    backup: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        value_parser = check_file_exists,
+        help = "Process only this input file instead of the glob matches (repeatable)"
+    )]
+    only_files: Vec<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "LANG",
+        help = "Translate only this configured target language (repeatable)"
+    )]
+    only_lang: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Fill in the untranslated entries of the .po inputs, writing each back to itself; templates are skipped"
+    )]
+    in_place: bool,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Write a PO per language listing untranslated messages to DIR, without translating"
+    )]
+    export_untranslated: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "After the run, write the translations it produced to a TMX 1.4 file"
+    )]
+    export_tmx: Option<PathBuf>,
+
+    #[arg(
+        long,
+        requires = "export_tmx",
+        help = "Also export the translations already present in the PO outputs"
+    )]
+    export_tmx_all: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "After the run, write every validation finding to a JSON report"
+    )]
+    validation_report: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        value_parser = check_file_exists,
+        help = "Translate again only the messages that failed in the run that wrote this --validation-report"
+    )]
+    retry_from: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = parse_sample,
+        help = "Translate only a random sample of each language's messages: a count, or a fraction such as 0.1"
+    )]
+    sample: Option<SampleSize>,
+
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Seed for --sample; the same seed picks the same messages again"
+    )]
+    seed: u64,
+
+    #[arg(
+        long,
+        help = "Allow several inputs or languages to write to the same output file (writes are serialized)"
+    )]
+    allow_collisions: bool,
+
+    #[arg(
+        long,
+        help = "Fail unless each output contains every POT message exactly once after the run"
+    )]
+    strict_coverage: bool,
+
+    #[arg(
+        long,
+        help = "Re-parse each written file and fail it if it differs from what was written"
+    )]
+    verify_roundtrip: bool,
+
+    #[arg(
+        long,
+        help = "Write progress events as newline-delimited JSON to file descriptor 3"
+    )]
+    json_logs: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write progress events as newline-delimited JSON to this file instead of fd 3"
+    )]
+    events_file: Option<PathBuf>,
+
     #[arg(
         long,
         default_value_t = 4,
@@ -44,484 +221,3421 @@ struct Args {
     lang_concurrent: usize,
 }
 
-fn check_file_exists(s: &str) -> Result<PathBuf, String> {
-    let path = PathBuf::from(s);
-    if path.exists() {
-        Ok(path)
-    } else {
-        Err(format!("File '{}' not found", s))
-    }
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Check existing PO files for quality issues without translating")]
+    Audit {
+        #[arg(required = true, help = "PO files or glob patterns to check")]
+        patterns: Vec<String>,
+
+        #[arg(long, help = "Also fail when entries are untranslated or fuzzy")]
+        fail_on_incomplete: bool,
+
+        #[arg(
+            long,
+            value_name = "PATH",
+            value_parser = check_file_exists,
+            help = "Run the validation pipeline of this TOML configuration over the translations"
+        )]
+        config: Option<PathBuf>,
+
+        #[arg(
+            long,
+            value_name = "NAME",
+            requires = "config",
+            help = "Apply the overrides from [profiles.NAME] in the config file"
+        )]
+        profile: Option<String>,
+    },
+
+    #[command(about = "Write a CSV per target language listing every message and its translation")]
+    ExportCsv {
+        #[arg(value_parser = check_file_exists, help = "Path to TOML configuration file")]
+        config_path: PathBuf,
+
+        #[arg(help = "Directory to write <lang>.csv files to")]
+        dir: PathBuf,
+
+        #[arg(
+            long,
+            value_name = "NAME",
+            help = "Apply the overrides from [profiles.NAME] in the config file"
+        )]
+        profile: Option<String>,
+    },
+
+    #[command(about = "Apply the translations of edited CSVs from export-csv to the PO files")]
+    ImportCsv {
+        #[arg(value_parser = check_file_exists, help = "Path to TOML configuration file")]
+        config_path: PathBuf,
+
+        #[arg(
+            required = true,
+            value_parser = check_file_exists,
+            help = "CSV files named <lang>.csv, as written by export-csv"
+        )]
+        files: Vec<PathBuf>,
+
+        #[arg(
+            long,
+            value_name = "NAME",
+            help = "Apply the overrides from [profiles.NAME] in the config file"
+        )]
+        profile: Option<String>,
+    },
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let start_time = Instant::now();
-    let args = Args::parse();
-
-    println!("🌍 PO-LLM Translator");
+fn audit(
+    patterns: &[String],
+    fail_on_incomplete: bool,
+    config_path: Option<&Path>,
+    profile: Option<&str>,
+) -> Result<()> {
+    println!("🔍 PO-LLM Audit");
+
+    let config = match config_path {
+        Some(config_path) => {
+            let config_str = fs::read_to_string(config_path)?;
+            let config = AppConfig::from_toml(&config_str, profile)
+                .map_err(|e| anyhow::anyhow!("Failed to load configuration: {}", e))?;
+            config
+                .validate()
+                .map_err(|e| anyhow::anyhow!("Invalid configuration: {}", e))?;
+            Some(config)
+        }
+        None => None,
+    };
+    let pipeline = config
+        .as_ref()
+        .map(|config| (&config.translation, &config.validation));
+
+    let mut paths = Vec::new();
+    for pattern in patterns {
+        let matches: Vec<PathBuf> = glob_with(pattern, MatchOptions::new())
+            .context(format!("Invalid pattern: {}", pattern))?
+            .filter_map(Result::ok)
+            .collect();
+        if matches.is_empty() {
+            println!("⚠️  No files found matching pattern: {}", pattern);
+        }
+        paths.extend(matches);
+    }
 
-    let config_str = fs::read_to_string(&args.config_path)?;
-    let config: AppConfig = toml::from_str(&config_str)?;
+    let mut unreadable = 0;
+    let mut total_messages = 0;
+    let mut total_untranslated = 0;
+    let mut total_fuzzy = 0;
+    let mut total_locked = 0;
+    let mut total_issues = 0;
+    let mut total_warnings = 0;
+
+    for path in &paths {
+        println!("\n📄 {}", path.display());
+        let catalog = match read_catalog(path) {
+            Ok(catalog) => catalog,
+            Err(e) => {
+                println!("   └─ ❌ Failed to parse: {}", e);
+                unreadable += 1;
+                continue;
+            }
+        };
 
-    println!("⚙️  Configuration");
-    println!("   └─ Config file: {}", args.config_path.display());
-    println!("   └─ Model: {}", config.llm.model);
-    println!(
-        "   └─ Target languages: {}",
-        config.translation.target_languages.join(", ")
-    );
-    println!("   └─ Batch size: {}", config.translation.batch_size);
-    println!("   └─ Skip translated: {}", config.project.skip_translated);
-    println!(
-        "   └─ Mode: {}",
-        if args.dry_run {
-            "🔍 DRY RUN"
-        } else {
-            "🚀 PRODUCTION"
+        let mut report = audit_catalog(&catalog, pipeline);
+        match check_po_file(path) {
+            Ok(problems) => report.issues.extend(problems),
+            Err(e) => report.issues.push(e),
+        }
+        println!("   ├─ Messages: {}", report.messages);
+        println!("   ├─ Untranslated: {}", report.untranslated);
+        println!("   ├─ Fuzzy: {}", report.fuzzy);
+        println!("   ├─ Locked: {}", report.locked.len());
+        for label in &report.locked {
+            println!("   │  🔒 {}", label);
+        }
+        if pipeline.is_some() {
+            println!("   ├─ Warnings: {}", report.warnings.len());
+            for warning in &report.warnings {
+                println!("   │  • {}", warning);
+            }
+        }
+        println!("   └─ Issues: {}", report.issues.len());
+        for issue in &report.issues {
+            println!("      • {}", issue);
         }
-    );
 
-    let config_dir = args.config_path.parent().unwrap_or(Path::new("."));
-    let pattern = config_dir
-        .join(&config.project.base_path)
-        .join(&config.translation.input_pattern);
+        total_messages += report.messages;
+        total_untranslated += report.untranslated;
+        total_fuzzy += report.fuzzy;
+        total_locked += report.locked.len();
+        total_issues += report.issues.len();
+        total_warnings += report.warnings.len();
+    }
 
-    let paths: Vec<PathBuf> = glob(pattern.to_str().unwrap())?
-        .filter_map(Result::ok)
-        .collect();
+    println!();
+    println!("─────────────────────────────────────────");
+    println!("📊 Summary");
+    println!("   ├─ Files audited: {}", paths.len());
+    if unreadable > 0 {
+        println!("   ├─ Files failed to parse: {}", unreadable);
+    }
+    println!("   ├─ Messages: {}", total_messages);
+    println!("   ├─ Untranslated: {}", total_untranslated);
+    println!("   ├─ Fuzzy: {}", total_fuzzy);
+    println!("   ├─ Locked: {}", total_locked);
+    if pipeline.is_some() {
+        println!("   ├─ Warnings: {}", total_warnings);
+    }
+    println!("   └─ Issues: {}", total_issues);
+    println!("─────────────────────────────────────────\n");
 
-    if paths.is_empty() {
-        println!("⚠️  No files found matching pattern: {}", pattern.display());
-        return Ok(());
+    let incomplete = fail_on_incomplete && (total_untranslated > 0 || total_fuzzy > 0);
+    if unreadable > 0 || total_issues > 0 || incomplete {
+        println!("❌ Audit found problems");
+        std::process::exit(1);
     }
+    println!("✅ No problems found");
+    Ok(())
+}
 
-    println!("📁 Found {} file(s) to process", paths.len());
-    for (i, path) in paths.iter().enumerate() {
-        println!("   {}. {}", i + 1, path.display());
+/// The PO outputs of a configuration's templates, as
+/// `(template, language, output)`, along with the config and its base path.
+type ReviewTargets = (AppConfig, PathBuf, Vec<(PathBuf, String, PathBuf)>);
+
+fn review_targets(config_path: &Path, profile: Option<&str>) -> Result<ReviewTargets> {
+    let config_str = fs::read_to_string(config_path)?;
+    let config = AppConfig::from_toml(&config_str, profile)
+        .map_err(|e| anyhow::anyhow!("Failed to load configuration: {}", e))?;
+    config
+        .validate()
+        .map_err(|e| anyhow::anyhow!("Invalid configuration: {}", e))?;
+
+    let base_dir = config_path
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join(&config.project.base_path);
+
+    let mut targets = Vec::new();
+    for input in find_inputs(&config.translation, &base_dir)? {
+        let group = &config.translation.inputs[input.group];
+        if InputKind::of(&input.path) == InputKind::Catalog
+            || config.translation.format_of(group, &input.path) != DocumentFormat::Gettext
+        {
+            println!(
+                "⚠️  Skipping {}: only POT templates are supported",
+                input.path.display()
+            );
+            continue;
+        }
+        for lang in &config.translation.target_languages {
+            let output = output_path_for(&config.translation, &input, lang)
+                .context("Failed to build output path")?;
+            targets.push((input.path.clone(), lang.clone(), output));
+        }
     }
-    println!("\n─────────────────────────────────────────\n");
+    Ok((config, base_dir, targets))
+}
 
-    let multi_progress = Arc::new(MultiProgress::new());
-    let main_pb = multi_progress.add(ProgressBar::new(paths.len() as u64));
-    main_pb.set_style(
-        ProgressStyle::default_bar()
-            .template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} files | {msg}")
-            .unwrap()
-            .progress_chars("█▓▒░ "),
-    );
-    main_pb.set_message("Starting...");
+/// How a PO file is named in review CSVs.
+fn review_file_name(base_dir: &Path, output: &Path) -> String {
+    output
+        .strip_prefix(base_dir)
+        .unwrap_or(output)
+        .to_string_lossy()
+        .into_owned()
+}
 
-    let results: Vec<_> = stream::iter(paths)
-        .map(|path| {
-            let config = &config;
-            let args = &args;
-            let multi_progress = Arc::clone(&multi_progress);
-            let main_pb = main_pb.clone();
+fn export_csv(config_path: &Path, dir: &Path, profile: Option<&str>) -> Result<()> {
+    println!("📤 PO-LLM CSV Export");
+    let (config, base_dir, targets) = review_targets(config_path, profile)?;
+    fs::create_dir_all(dir).context(format!("Failed to create directory {:?}", dir))?;
+
+    let mut total = 0;
+    for lang in &config.translation.target_languages {
+        let mut rows = Vec::new();
+        for (input, _, output) in targets.iter().filter(|(_, l, _)| l == lang) {
+            let pot = read_catalog(input)
+                .map_err(|e| anyhow::anyhow!("Failed to parse POT file {:?}: {}", input, e))?;
+            let po =
+                if output.exists() {
+                    Some(read_catalog(output).map_err(|e| {
+                        anyhow::anyhow!("Failed to parse PO file {:?}: {}", output, e)
+                    })?)
+                } else {
+                    None
+                };
+            rows.extend(review_rows(
+                &review_file_name(&base_dir, output),
+                &pot,
+                po.as_ref(),
+            ));
+        }
 
-            async move {
-                let filename = path.file_name().unwrap().to_string_lossy().to_string();
+        let path = dir.join(format!("{}.csv", lang));
+        write_review_csv(&path, &rows, plural_forms_or_default(lang).nplurals)
+            .map_err(|e| anyhow::anyhow!(e))?;
+        println!(
+            "   └─ {}: {} message(s) → {}",
+            lang,
+            rows.len(),
+            path.display()
+        );
+        total += rows.len();
+    }
 
-                println!("\n🔄 Processing file: {}", filename);
+    println!("\n✅ Exported {} message(s) to {}", total, dir.display());
+    Ok(())
+}
 
-                let file_pb = multi_progress.add(ProgressBar::new(
-                    config.translation.target_languages.len() as u64,
-                ));
-                file_pb.set_style(
-                    ProgressStyle::default_bar()
-                        .template(&format!(
-                            "  📄 {} {{spinner:.green}} [{{bar:30.cyan/blue}}] {{pos}}/{{len}} langs | {{msg}}",
-                            filename
-                        ))
-                        .unwrap()
-                        .progress_chars("█▓▒░ "),
+async fn import_csv(config_path: &Path, files: &[PathBuf], profile: Option<&str>) -> Result<()> {
+    println!("📥 PO-LLM CSV Import");
+    let (config, base_dir, targets) = review_targets(config_path, profile)?;
+    let write_options = WriteOptions {
+        wrap: config.output.wrap,
+        charset: config.translation.output_charset.clone(),
+        verify_roundtrip: false,
+        compile_mo: config.output.compile_mo,
+        attribution: None,
+    };
+    let file_locks = FileLocks::new();
+
+    let mut applied = 0;
+    let mut skipped = 0;
+    let mut rejected = 0;
+    for csv_path in files {
+        let lang = csv_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .filter(|lang| {
+                config
+                    .translation
+                    .target_languages
+                    .iter()
+                    .any(|l| l == lang)
+            })
+            .context(format!(
+                "{:?} must be named after a target language ({}), e.g. {}.csv",
+                csv_path,
+                config.translation.target_languages.join(", "),
+                config
+                    .translation
+                    .target_languages
+                    .first()
+                    .map_or("fr", String::as_str)
+            ))?;
+        let nplurals = plural_forms_or_default(lang).nplurals;
+
+        println!("\n📄 {} [{}]", csv_path.display(), lang);
+        let mut by_file: BTreeMap<String, Vec<ReviewRow>> = BTreeMap::new();
+        for row in read_review_csv(csv_path).map_err(|e| anyhow::anyhow!(e))? {
+            by_file.entry(row.file.clone()).or_default().push(row);
+        }
+
+        for (file, rows) in by_file {
+            let Some((input, _, output)) = targets
+                .iter()
+                .find(|(_, l, output)| l == lang && review_file_name(&base_dir, output) == file)
+            else {
+                println!(
+                    "   ❌ {}: not an output of this configuration, {} row(s) rejected",
+                    file,
+                    rows.len()
                 );
+                rejected += rows.len();
+                continue;
+            };
 
-                let res = translate_file(
-                    config,
-                    &path,
-                    file_pb.clone(),
-                    args.lang_concurrent,
-                    args.dry_run,
-                    args.force_write,
+            let pot = read_catalog(input)
+                .map_err(|e| anyhow::anyhow!("Failed to parse POT file {:?}: {}", input, e))?;
+            let po =
+                if output.exists() {
+                    Some(read_catalog(output).map_err(|e| {
+                        anyhow::anyhow!("Failed to parse PO file {:?}: {}", output, e)
+                    })?)
+                } else {
+                    None
+                };
+            // Locked entries are left out, so edits to them are rejected.
+            let units: HashMap<(Option<String>, String), TranslationUnit> =
+                GettextAdapter::extract_messages(
+                    po.as_ref().unwrap_or(&pot),
+                    &pot,
+                    &PreviousSources::new(),
+                    false,
                 )
-                .await;
-
-                match &res {
-                    Ok(stats) => {
-                        let msg = if stats.total_failed > 0 {
-                            format!(
-                                "✅ {} translated, ⚠️  {} failed",
-                                stats.total_translated, stats.total_failed
-                            )
-                        } else {
-                            format!("✅ {} messages", stats.total_translated)
-                        };
-                        file_pb.finish_with_message(msg);
-                    }
-                    Err(e) => {
-                        let error_msg = format!("❌ Error: {}", e);
-                        file_pb.finish_with_message(error_msg.clone());
-                        eprintln!("\n❌ File processing failed: {}\n   Error: {}\n", filename, e);
+                .into_iter()
+                .map(|unit| ((unit.context.clone(), unit.msg_id.clone()), unit))
+                .collect();
+
+            let mut accepted = Vec::new();
+            for row in rows {
+                if row.msgstr.iter().all(String::is_empty) {
+                    skipped += 1;
+                    continue;
+                }
+                let label = match &row.context {
+                    Some(context) => format!("[{}] {}", context, row.msgid),
+                    None => row.msgid.clone(),
+                };
+                let reject = |reason: &str| println!("   ❌ '{}': {}", label, reason);
+
+                if pot
+                    .find_message(
+                        row.context.as_deref(),
+                        &row.msgid,
+                        row.msgid_plural.as_deref(),
+                    )
+                    .is_none()
+                {
+                    reject("not in the template");
+                    rejected += 1;
+                    continue;
+                }
+                let Some(mut unit) = units
+                    .get(&(row.context.clone(), row.msgid.clone()))
+                    .cloned()
+                else {
+                    reject("the entry is locked");
+                    rejected += 1;
+                    continue;
+                };
+
+                let current = po.as_ref().and_then(|po| {
+                    po.find_message(
+                        row.context.as_deref(),
+                        &row.msgid,
+                        row.msgid_plural.as_deref(),
+                    )
+                    .filter(|message| !message.is_fuzzy())
+                });
+                let unchanged = if unit.is_plural() {
+                    if row.msgstr.len() < nplurals
+                        || row.msgstr[..nplurals].iter().any(String::is_empty)
+                    {
+                        reject(&format!("expected {} plural forms", nplurals));
+                        rejected += 1;
+                        continue;
                     }
+                    let forms = row.msgstr[..nplurals].to_vec();
+                    let unchanged =
+                        current.is_some_and(|message| message.msgstr_plural().ok() == Some(&forms));
+                    unit.msg_str_plural = Some(forms);
+                    unchanged
+                } else {
+                    let text = row.msgstr[0].clone();
+                    let unchanged =
+                        current.is_some_and(|message| message.msgstr().ok() == Some(&text));
+                    unit.msg_str = Some(text);
+                    unchanged
+                };
+                if unchanged {
+                    skipped += 1;
+                    continue;
                 }
 
-                main_pb.inc(1);
-                main_pb.set_message(format!("Processing... ({} completed)", main_pb.position()));
-                res
+                if let Err(reason) =
+                    validate_import(&unit, lang, &config.translation, &config.validation)
+                {
+                    reject(&reason);
+                    rejected += 1;
+                    continue;
+                }
+                accepted.push(unit);
             }
-        })
-        .buffer_unordered(args.file_concurrent)
-        .collect()
-        .await;
-
-    main_pb.finish_with_message("✨ Complete");
-
-    let total_ok = results.iter().filter(|r| r.is_ok()).count();
-    let total_err = results.len() - total_ok;
-    let total_translated: usize = results
-        .iter()
-        .filter_map(|r| r.as_ref().ok())
-        .map(|s| s.total_translated)
-        .sum();
-    let total_failed: usize = results
-        .iter()
-        .filter_map(|r| r.as_ref().ok())
-        .map(|s| s.total_failed)
-        .sum();
 
-    let duration = start_time.elapsed();
+            if accepted.is_empty() {
+                println!("   └─ {}: nothing to apply", file);
+                continue;
+            }
+            if let Some(parent) = output.parent() {
+                fs::create_dir_all(parent)
+                    .context(format!("Failed to create output directory: {:?}", parent))?;
+            }
+            let count = accepted.len();
+            let guard = file_locks.lock(output).await;
+            GettextAdapter
+                .write_translations(accepted, lang, &guard, &write_options)
+                .and_then(|()| GettextAdapter.finish_output(input, output, &write_options))
+                .map_err(|e| {
+                    anyhow::anyhow!("Failed to write translations to {:?}: {}", output, e)
+                })?;
+            println!("   └─ {}: {} translation(s) applied", file, count);
+            applied += count;
+        }
+    }
 
     println!();
     println!("─────────────────────────────────────────");
     println!("📊 Summary");
-    println!("   ├─ Files processed: {} / {}", total_ok, results.len());
-    println!("   ├─ Files failed: {}", total_err);
-    println!("   ├─ Messages translated: {}", total_translated);
-    if total_failed > 0 {
-        println!("   ├─ Messages failed: {}", total_failed);
-    }
-    println!("   └─ Duration: {:.2}s", duration.as_secs_f64());
+    println!("   ├─ Applied: {}", applied);
+    println!("   ├─ Skipped (empty or unchanged): {}", skipped);
+    println!("   └─ Rejected: {}", rejected);
     println!("─────────────────────────────────────────\n");
 
-    if total_err > 0 {
-        println!("❌ Errors encountered:");
-        for (i, result) in results.iter().enumerate() {
-            if let Err(e) = result {
-                println!("   {}. {}", i + 1, e);
+    if rejected > 0 {
+        println!("⚠️  Some rows were rejected; fix them and import again");
+    } else {
+        println!("✅ Import completed");
+    }
+    Ok(())
+}
+
+fn match_options(translation: &TranslationConfig) -> MatchOptions {
+    MatchOptions {
+        case_sensitive: !translation.glob_case_insensitive,
+        require_literal_separator: false,
+        require_literal_leading_dot: !translation.glob_include_hidden,
+    }
+}
+
+/// An input file and the index of the `translation.inputs` group that
+/// found it.
+struct Input {
+    path: PathBuf,
+    group: usize,
+}
+
+/// The files of every input group, in group order. A file matched by more
+/// than one group belongs to the first.
+fn find_inputs(translation: &TranslationConfig, base_dir: &Path) -> Result<Vec<Input>> {
+    let mut seen = HashSet::new();
+    let mut inputs = Vec::new();
+    for (index, group) in translation.inputs.iter().enumerate() {
+        let pattern = base_dir.join(&group.pattern);
+        for path in
+            glob_with(pattern.to_str().unwrap(), match_options(translation))?.filter_map(Result::ok)
+        {
+            if seen.insert(path.clone()) {
+                inputs.push(Input { path, group: index });
+            } else {
+                println!(
+                    "⚠️  {} also matches input \"{}\"; using the first input that matched",
+                    path.display(),
+                    group.pattern
+                );
             }
         }
-        println!();
     }
+    Ok(inputs)
+}
 
-    if total_err > 0 {
-        println!("❌ Translation completed with errors");
-        std::process::exit(1);
-    } else if total_failed > 0 {
-        println!("⚠️  Translation completed with some failed messages");
-    } else if total_translated == 0 {
-        println!(
-            "⚠️  No messages were translated (check your input files and skip_translated setting)"
-        );
+/// The first group whose pattern matches a file given with `--only-files`,
+/// or the first group when none does.
+fn group_of(translation: &TranslationConfig, base_dir: &Path, path: &Path) -> usize {
+    translation
+        .inputs
+        .iter()
+        .position(|group| {
+            Pattern::new(&base_dir.join(&group.pattern).to_string_lossy())
+                .is_ok_and(|pattern| pattern.matches_path_with(path, match_options(translation)))
+        })
+        .unwrap_or(0)
+}
+
+fn check_file_exists(s: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(s);
+    if path.exists() {
+        Ok(path)
     } else {
-        println!("✅ Translation completed successfully!");
+        Err(format!("File '{}' not found", s))
     }
-
-    Ok(())
 }
 
-struct FileStats {
-    total_translated: usize,
-    total_failed: usize,
+#[derive(Debug, Clone, Copy)]
+enum SampleSize {
+    Count(usize),
+    Fraction(f64),
 }
 
-async fn translate_file(
-    config: &AppConfig,
-    input_path: &PathBuf,
-    file_pb: ProgressBar,
-    lang_concurrent: usize,
+impl SampleSize {
+    fn of(self, total: usize) -> usize {
+        match self {
+            SampleSize::Count(count) => count.min(total),
+            SampleSize::Fraction(fraction) => {
+                ((total as f64 * fraction).ceil() as usize).min(total)
+            }
+        }
+    }
+}
+
+fn parse_sample(s: &str) -> Result<SampleSize, String> {
+    match (s.parse::<usize>(), s.parse::<f64>()) {
+        (Ok(count), _) if count > 0 => Ok(SampleSize::Count(count)),
+        (Err(_), Ok(fraction)) if fraction > 0.0 && fraction < 1.0 => {
+            Ok(SampleSize::Fraction(fraction))
+        }
+        _ => Err(format!(
+            "expected a positive count or a fraction between 0 and 1, got '{}'",
+            s
+        )),
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let start_time = Instant::now();
+    let mut args = Args::parse();
+    // Explaining goes through the pipeline up to the LLM calls, as a dry run.
+    args.dry_run |= args.explain;
+
+    match &args.command {
+        Some(Command::Audit {
+            patterns,
+            fail_on_incomplete,
+            config,
+            profile,
+        }) => {
+            return audit(
+                patterns,
+                *fail_on_incomplete,
+                config.as_deref(),
+                profile.as_deref(),
+            );
+        }
+        Some(Command::ExportCsv {
+            config_path,
+            dir,
+            profile,
+        }) => return export_csv(config_path, dir, profile.as_deref()),
+        Some(Command::ImportCsv {
+            config_path,
+            files,
+            profile,
+        }) => return import_csv(config_path, files, profile.as_deref()).await,
+        None => {}
+    }
+    let config_path = args
+        .config_path
+        .clone()
+        .context("Missing configuration file path")?;
+
+    println!("🌍 PO-LLM Translator");
+
+    // Variables already set in the environment take precedence over the file.
+    let env_file = args
+        .env_file
+        .clone()
+        .unwrap_or_else(|| config_path.parent().unwrap_or(Path::new("")).join(".env"));
+    if env_file.exists() {
+        dotenvy::from_path(&env_file)
+            .context(format!("Failed to load environment file {:?}", env_file))?;
+        println!("🔑 Loaded environment from {}", env_file.display());
+    }
+
+    let config_str = fs::read_to_string(&config_path)?;
+    let mut config = AppConfig::from_toml(&config_str, args.profile.as_deref())
+        .map_err(|e| anyhow::anyhow!("Failed to load configuration: {}", e))?;
+
+    if config.llm.api_key.is_empty()
+        && let Ok(api_key) = env::var("OPENAI_API_KEY")
+    {
+        config.llm.api_key = api_key;
+    }
+    if config.deepl.api_key.is_empty()
+        && let Ok(api_key) = env::var("DEEPL_API_KEY")
+    {
+        config.deepl.api_key = api_key;
+    }
+    // Dry-run output is the source marked, written in no target language
+    // and by no model.
+    if args.dry_run {
+        config.translation.attribution.clear();
+        config.translation.detect_wrong_language = false;
+        config
+            .validation
+            .rules
+            .insert("wrong-language".to_string(), RuleSeverity::Off);
+    }
+    if config.llm.backend != Backend::DeepL && config.llm.api_key.is_empty() && !args.dry_run {
+        return Err(anyhow::anyhow!(
+            "No API key: set api_key in [llm] or OPENAI_API_KEY in the environment or .env file"
+        ));
+    }
+
+    if !args.only_lang.is_empty() {
+        let unknown: Vec<&str> = args
+            .only_lang
+            .iter()
+            .filter(|lang| !config.translation.target_languages.contains(lang))
+            .map(String::as_str)
+            .collect();
+        if !unknown.is_empty() {
+            return Err(anyhow::anyhow!(
+                "--only-lang {} not in target_languages ({})",
+                unknown.join(", "),
+                config.translation.target_languages.join(", ")
+            ));
+        }
+        config
+            .translation
+            .target_languages
+            .retain(|lang| args.only_lang.contains(lang));
+    }
+
+    config
+        .validate()
+        .map_err(|e| anyhow::anyhow!("Invalid configuration: {}", e))?;
+
+    println!("⚙️  Configuration");
+    println!("   └─ Config file: {}", config_path.display());
+    if let Some(profile) = &args.profile {
+        println!("   └─ Profile: {}", profile);
+    }
+    println!("   └─ Model: {}", config.llm.model);
+    if let (Backend::Command, Some(command)) = (config.llm.backend, &config.llm.command) {
+        println!("   └─ Translation command: {}", command);
+    }
+    if config.llm.backend == Backend::DeepL {
+        println!("   └─ DeepL API: {}", config.deepl.api_base());
+    }
+    println!(
+        "   └─ Target languages: {}",
+        config.translation.target_languages.join(", ")
+    );
+    if config.translation.inputs.len() > 1 {
+        for group in &config.translation.inputs {
+            println!(
+                "   └─ Input: {} → {}",
+                group.pattern,
+                group.output_pattern()
+            );
+        }
+    }
+    println!("   └─ Batch size: {}", config.translation.batch_size);
+    if config.llm.backend == Backend::OpenAi {
+        warn_batch_output(&config);
+    }
+    println!("   └─ Skip translated: {}", config.project.skip_translated);
+    if args.in_place {
+        println!("   └─ In place: .po inputs are their own outputs");
+    }
+
+    let match_options = match_options(&config.translation);
+    println!(
+        "   └─ File matching: {}, {}",
+        if match_options.case_sensitive {
+            "case-sensitive"
+        } else {
+            "case-insensitive"
+        },
+        if match_options.require_literal_leading_dot {
+            "skipping hidden files"
+        } else {
+            "including hidden files"
+        }
+    );
+    let backups = (args.backup || config.output.backup)
+        .then(|| BackupManager::new(&config.output.backup_suffix, config.output.backup_keep));
+    if backups.is_some() {
+        println!(
+            "   └─ Backups: {} (keep {})",
+            config.output.backup_suffix, config.output.backup_keep
+        );
+    }
+
+    println!(
+        "   └─ Mode: {}",
+        if args.explain {
+            "🔎 EXPLAIN"
+        } else if args.dry_run {
+            "🔍 DRY RUN"
+        } else {
+            "🚀 PRODUCTION"
+        }
+    );
+
+    let config_dir = config_path.parent().unwrap_or(Path::new("."));
+
+    let term_memory = match &config.translation.term_memory_path {
+        Some(path) => {
+            let path = config_dir.join(path);
+            let memory = TermMemory::load(&path).map_err(|e| anyhow::anyhow!(e))?;
+            println!(
+                "   └─ Term memory: {} term(s) from {}",
+                memory.len(),
+                path.display()
+            );
+            for lang in &config.translation.target_languages {
+                if !memory
+                    .languages()
+                    .iter()
+                    .any(|l| l.eq_ignore_ascii_case(lang))
+                {
+                    println!("      ⚠️  No column for {} in the term memory", lang);
+                }
+            }
+            Some(memory)
+        }
+        None => None,
+    };
+
+    let compendium_pattern = config
+        .translation
+        .compendium_pattern
+        .as_ref()
+        .map(|pattern| config_dir.join(pattern).to_string_lossy().to_string());
+    if let Some(pattern) = &compendium_pattern {
+        println!(
+            "   └─ Compendium: {}{}",
+            pattern,
+            if config.translation.update_compendium {
+                " (updated with new translations)"
+            } else {
+                ""
+            }
+        );
+    }
+
+    let translation_memory = if config.translation.translation_memory.is_empty() {
+        None
+    } else {
+        let paths: Vec<PathBuf> = config
+            .translation
+            .translation_memory
+            .iter()
+            .map(|path| config_dir.join(path))
+            .collect();
+        let memory = TranslationMemory::load(&paths, &config.translation.source_language)
+            .map_err(|e| anyhow::anyhow!(e))?;
+        println!(
+            "   └─ Translation memory: {} segment(s) from {} file(s)",
+            memory.len(),
+            paths.len()
+        );
+        Some(memory)
+    };
+
+    let overrides = match &config.translation.overrides_file {
+        Some(path) => {
+            let path = config_dir.join(path);
+            let overrides = Overrides::load(&path).map_err(|e| anyhow::anyhow!(e))?;
+            println!(
+                "   └─ Overrides: {} message(s) from {}",
+                overrides.len(),
+                path.display()
+            );
+            for lang in overrides.languages() {
+                if !config
+                    .translation
+                    .target_languages
+                    .iter()
+                    .any(|l| l.eq_ignore_ascii_case(lang))
+                {
+                    println!("      ⚠️  Overrides for {} match no target language", lang);
+                }
+            }
+            Some(overrides)
+        }
+        None => None,
+    };
+
+    let base_dir = config_dir.join(&config.project.base_path);
+    let paths: Vec<Input> = if args.only_files.is_empty() {
+        find_inputs(&config.translation, &base_dir)?
+    } else {
+        args.only_files
+            .iter()
+            .map(|path| Input {
+                group: group_of(&config.translation, &base_dir, path),
+                path: path.clone(),
+            })
+            .collect()
+    };
+
+    if paths.is_empty() {
+        let patterns: Vec<String> = config
+            .translation
+            .inputs
+            .iter()
+            .map(|group| base_dir.join(&group.pattern).display().to_string())
+            .collect();
+        println!(
+            "⚠️  No files found matching pattern: {}",
+            patterns.join(", ")
+        );
+        return Ok(());
+    }
+
+    let retry = match &args.retry_from {
+        Some(path) => {
+            let report = ValidationReport::load(path).map_err(|e| anyhow::anyhow!(e))?;
+            let retry = RetryList::new(&report.failures);
+            if retry.is_empty() {
+                println!("✅ {} lists no failures to retry", path.display());
+                return Ok(());
+            }
+            for file in retry.files() {
+                if !paths.iter().any(|input| input.path == file) {
+                    println!(
+                        "⚠️  {} failed in the earlier run but is not an input of this one",
+                        file.display()
+                    );
+                }
+            }
+            Some(retry)
+        }
+        None => None,
+    };
+    let paths: Vec<Input> = match &retry {
+        Some(retry) => paths
+            .into_iter()
+            .filter(|input| retry.has_file(&input.path))
+            .collect(),
+        None => paths,
+    };
+
+    println!("📁 Found {} file(s) to process", paths.len());
+    for (i, input) in paths.iter().enumerate() {
+        println!(
+            "   {}. {} ({})",
+            i + 1,
+            input.path.display(),
+            InputKind::of(&input.path)
+        );
+    }
+
+    let paths: Vec<Input> = if args.in_place {
+        let (catalogs, templates): (Vec<_>, Vec<_>) = paths
+            .into_iter()
+            .partition(|input| InputKind::of(&input.path) == InputKind::Catalog);
+        if !templates.is_empty() {
+            println!(
+                "⚠️  Skipping {} template input(s); --in-place only fills .po files",
+                templates.len()
+            );
+        }
+        catalogs
+    } else if config.translation.translate_po_inputs {
+        paths
+    } else {
+        let (catalogs, templates): (Vec<_>, Vec<_>) = paths
+            .into_iter()
+            .partition(|input| InputKind::of(&input.path) == InputKind::Catalog);
+        if !catalogs.is_empty() {
+            println!(
+                "⚠️  Skipping {} .po input(s); set translate_po_inputs = true to fill them in place",
+                catalogs.len()
+            );
+        }
+        templates
+    };
+
+    if paths.is_empty() {
+        println!("⚠️  No input files left to process");
+        return Ok(());
+    }
+
+    if let Some(overrides) = &overrides {
+        warn_unmatched_overrides(overrides, &paths, &config.translation);
+    }
+
+    let collisions = find_output_collisions(&paths, &config.translation)?;
+
+    if !collisions.is_empty() {
+        let header = if args.allow_collisions {
+            "⚠️  Output path collisions (allowed, writes to each file are serialized):"
+        } else {
+            "❌ Output path collisions detected:"
+        };
+        println!("{}", header);
+        for (output, sources) in &collisions {
+            println!("   {}", output.display());
+            for (input, lang) in sources {
+                println!("      ← {} [{}]", input.display(), lang);
+            }
+        }
+
+        if !args.allow_collisions {
+            return Err(anyhow::anyhow!(
+                "{} output file(s) would be written by more than one input/language. Add {{name}} and {{lang}} to output_pattern, or pass --allow-collisions to merge them intentionally.",
+                collisions.len()
+            ));
+        }
+    }
+
+    let write_options = WriteOptions {
+        wrap: config.output.wrap,
+        charset: config.translation.output_charset.clone(),
+        verify_roundtrip: args.verify_roundtrip,
+        compile_mo: config.output.compile_mo,
+        attribution: (!config.translation.attribution.trim().is_empty())
+            .then(|| attribution_pattern(&config.translation.attribution)),
+    };
+
+    if let Some(export_dir) = &args.export_untranslated {
+        let total = export_untranslated(
+            &config,
+            &paths,
+            &config_dir.join(&config.project.base_path),
+            export_dir,
+            &write_options,
+        )?;
+        println!(
+            "\n✅ Exported {} untranslated message(s) to {}",
+            total,
+            export_dir.display()
+        );
+        return Ok(());
+    }
+
+    println!("\n─────────────────────────────────────────\n");
+
+    let multi_progress = Arc::new(MultiProgress::new());
+    let main_pb = multi_progress.add(ProgressBar::new(paths.len() as u64));
+    main_pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} files | {msg}")
+            .unwrap()
+            .progress_chars("█▓▒░ "),
+    );
+    main_pb.set_message("Starting...");
+
+    let passthrough = Regex::new(&config.translation.passthrough_pattern)
+        .context("Invalid passthrough_pattern")?;
+    let events = match (&args.events_file, args.json_logs) {
+        (Some(path), _) => Some(EventLog::create(path).map_err(|e| anyhow::anyhow!(e))?),
+        (None, true) => Some(EventLog::fd3().map_err(|e| anyhow::anyhow!(e))?),
+        (None, false) => None,
+    };
+    let file_locks = FileLocks::new();
+    let ctx = RunContext {
+        config: &config,
+        dry_run: args.dry_run,
+        force_write: args.force_write,
+        explain: args.explain,
+        strict_coverage: args.strict_coverage,
+        lang_concurrent: args.lang_concurrent,
+        backups: backups.as_ref(),
+        file_locks: &file_locks,
+        passthrough,
+        term_memory: term_memory.as_ref(),
+        translation_memory: translation_memory.as_ref(),
+        compendium_pattern,
+        overrides: overrides.as_ref(),
+        write_options,
+        only_lang: &args.only_lang,
+        sample: args.sample,
+        seed: args.seed,
+        export_tmx_all: args.export_tmx_all,
+        usage: Arc::new(TokenUsage::default()),
+        retries: Arc::new(RetryBudget::new(config.llm.max_total_retries)),
+        budget_exceeded: AtomicBool::new(false),
+        events: events.as_ref(),
+        report: Mutex::new(ValidationReport::default()),
+        retry: retry.as_ref(),
+    };
+    ctx.emit(Event::RunStarted {
+        files: paths.len(),
+        languages: &config.translation.target_languages,
+    });
+
+    let merged = if config.translation.merge_inputs && !args.dry_run {
+        Some(merge_inputs(&ctx, &paths).await?)
+    } else {
+        None
+    };
+    let merged = merged.as_ref();
+
+    let (groups, results): (Vec<usize>, Vec<_>) = stream::iter(paths)
+        .map(|input| {
+            let config = &config;
+            let ctx = &ctx;
+            let multi_progress = Arc::clone(&multi_progress);
+            let main_pb = main_pb.clone();
+
+            async move {
+                let filename = input.path.file_name().unwrap().to_string_lossy().to_string();
+
+                println!("\n🔄 Processing file: {}", filename);
+                ctx.emit(Event::FileStarted { file: &input.path });
+
+                let file_pb = multi_progress.add(ProgressBar::new(
+                    config.translation.target_languages.len() as u64,
+                ));
+                file_pb.set_style(
+                    ProgressStyle::default_bar()
+                        .template(&format!(
+                            "  📄 {} {{spinner:.green}} [{{bar:30.cyan/blue}}] {{pos}}/{{len}} langs | {{msg}}",
+                            filename
+                        ))
+                        .unwrap()
+                        .progress_chars("█▓▒░ "),
+                );
+
+                let res = translate_file(ctx, &input, merged, file_pb.clone()).await;
+
+                match &res {
+                    Ok(stats) => {
+                        ctx.emit(Event::FileFinished {
+                            file: &input.path,
+                            translated: stats.counts.translated,
+                            failed: stats.counts.failed,
+                        });
+                        let mut msg = if stats.counts.failed > 0 {
+                            format!(
+                                "✅ {} translated, ⚠️  {} failed",
+                                stats.counts.translated, stats.counts.failed
+                            )
+                        } else {
+                            format!("✅ {} messages", stats.counts.translated)
+                        };
+                        if stats.counts.auto_copied > 0 {
+                            msg.push_str(&format!(", 📋 {} auto-copied", stats.counts.auto_copied));
+                        }
+                        file_pb.finish_with_message(msg);
+                    }
+                    Err(e) => {
+                        ctx.emit(Event::FileFailed {
+                            file: &input.path,
+                            error: format!("{:#}", e),
+                        });
+                        let error_msg = format!("❌ Error: {}", e);
+                        file_pb.finish_with_message(error_msg.clone());
+                        eprintln!("\n❌ File processing failed: {}\n   Error: {}\n", filename, e);
+                    }
+                }
+
+                main_pb.inc(1);
+                main_pb.set_message(format!("Processing... ({} completed)", main_pb.position()));
+                (input.group, res)
+            }
+        })
+        .buffer_unordered(args.file_concurrent)
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .unzip();
+
+    main_pb.finish_with_message("✨ Complete");
+
+    let total_ok = results.iter().filter(|r| r.is_ok()).count();
+    let total_err = results.len() - total_ok;
+    let mut totals = MessageCounts::default();
+    let mut timings = PhaseTimings::default();
+    for stats in results.iter().filter_map(|r| r.as_ref().ok()) {
+        totals += stats.counts;
+        timings += stats.timings;
+    }
+
+    let duration = start_time.elapsed();
+    ctx.emit(Event::RunFinished {
+        files: results.len(),
+        files_failed: total_err,
+        translated: totals.translated,
+        failed: totals.failed,
+        duration_secs: duration.as_secs_f64(),
+    });
+
+    println!();
+    println!("─────────────────────────────────────────");
+    println!("📊 Summary");
+    println!("   ├─ Files processed: {} / {}", total_ok, results.len());
+    println!("   ├─ Files failed: {}", total_err);
+    println!("   ├─ Messages translated: {}", totals.translated);
+    if totals.failed > 0 {
+        println!("   ├─ Messages failed: {}", totals.failed);
+    }
+    if totals.auto_copied > 0 {
+        println!("   ├─ Messages auto-copied: {}", totals.auto_copied);
+    }
+    if let Some(tag) = &config.translation.require_tag
+        && totals.untagged > 0
+    {
+        println!(
+            "   ├─ Messages left untouched without the tag {}: {}",
+            tag, totals.untagged
+        );
+    }
+    if totals.oversized > 0 {
+        println!(
+            "   ├─ Messages skipped over skip_above_tokens: {}",
+            totals.oversized
+        );
+    }
+    if totals.same_as_source > 0 {
+        println!(
+            "   ├─ Translations failed as same as source: {}",
+            totals.same_as_source
+        );
+    }
+    if totals.stray_characters > 0 {
+        println!(
+            "   ├─ Translations with stray characters: {}",
+            totals.stray_characters
+        );
+    }
+    if totals.locked > 0 {
+        println!("   ├─ Locked entries kept: {}", totals.locked);
+    }
+    if totals.overridden > 0 {
+        println!("   ├─ Messages overridden: {}", totals.overridden);
+    }
+    let report = ctx.report.lock().unwrap();
+    if !report.totals.is_empty() {
+        println!(
+            "   ├─ Validation findings: {}",
+            ValidationReport::summary(&report.totals)
+        );
+    }
+    if !report.fixed.is_empty() {
+        println!(
+            "   ├─ Auto-fixed: {}",
+            ValidationReport::summary(&report.fixed)
+        );
+    }
+    if args.sample.is_some() {
+        let clean = totals.translated.saturating_sub(totals.warned);
+        println!(
+            "   ├─ Sample: {} message(s), {} clean, {} with warnings, {} failed ({:.1}% clean)",
+            totals.sampled,
+            clean,
+            totals.warned,
+            totals.failed,
+            if totals.sampled > 0 {
+                clean as f64 * 100.0 / totals.sampled as f64
+            } else {
+                0.0
+            }
+        );
+    }
+    if totals.compendium_hits > 0 {
+        println!("   ├─ Compendium hits: {}", totals.compendium_hits);
+    }
+    if translation_memory.is_some() {
+        let looked_up = totals.tm_hits + totals.memory_hits + totals.translated + totals.failed;
+        println!(
+            "   ├─ Translation memory hits: {} / {} ({:.1}%)",
+            totals.tm_hits,
+            looked_up,
+            if looked_up > 0 {
+                totals.tm_hits as f64 * 100.0 / looked_up as f64
+            } else {
+                0.0
+            }
+        );
+    }
+    if term_memory.is_some() {
+        let looked_up = totals.memory_hits + totals.translated + totals.failed;
+        println!(
+            "   ├─ Term memory hits: {} / {} ({:.1}%)",
+            totals.memory_hits,
+            looked_up,
+            if looked_up > 0 {
+                totals.memory_hits as f64 * 100.0 / looked_up as f64
+            } else {
+                0.0
+            }
+        );
+    }
+    if config.translation.inputs.len() > 1 {
+        println!("   ├─ By input:");
+        for (index, group) in config.translation.inputs.iter().enumerate() {
+            let group_results: Vec<&Result<FileStats>> = groups
+                .iter()
+                .zip(&results)
+                .filter(|(g, _)| **g == index)
+                .map(|(_, r)| r)
+                .collect();
+            let ok: Vec<&FileStats> = group_results
+                .iter()
+                .filter_map(|r| r.as_ref().ok())
+                .collect();
+            let branch = if index + 1 == config.translation.inputs.len() {
+                "└─"
+            } else {
+                "├─"
+            };
+            println!(
+                "   │  {} {}: {} / {} file(s), {} translated, {} failed",
+                branch,
+                group.pattern,
+                ok.len(),
+                group_results.len(),
+                ok.iter().map(|s| s.counts.translated).sum::<usize>(),
+                ok.iter().map(|s| s.counts.failed).sum::<usize>()
+            );
+        }
+    }
+    let mut by_language: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+    for stats in results.iter().filter_map(|r| r.as_ref().ok()) {
+        for (lang, translated, failed) in &stats.languages {
+            let entry = by_language.entry(lang).or_default();
+            entry.0 += translated;
+            entry.1 += failed;
+        }
+    }
+    if by_language.len() > 1 {
+        println!("   ├─ By language:");
+        for (i, (lang, (translated, failed))) in by_language.iter().enumerate() {
+            let branch = if i + 1 == by_language.len() {
+                "└─"
+            } else {
+                "├─"
+            };
+            println!(
+                "   │  {} {}: {} translated, {} failed",
+                branch, lang, translated, failed
+            );
+        }
+    }
+    if ctx.usage.requests() > 0 {
+        let cost = match config.llm.pricing() {
+            Some(pricing) => format!(" (${:.4})", ctx.usage.cost(&pricing)),
+            None => String::new(),
+        };
+        println!(
+            "   ├─ Tokens: {} prompt, {} completion{}",
+            ctx.usage.prompt_tokens(),
+            ctx.usage.completion_tokens(),
+            cost
+        );
+    }
+    if ctx.retries.used() > 0 {
+        let limit = match ctx.retries.limit() {
+            Some(limit) => format!(" of {}", limit),
+            None => String::new(),
+        };
+        println!("   ├─ Retries: {}{}", ctx.retries.used(), limit);
+    }
+    println!(
+        "   ├─ Phases: parse {:.2}s, extract {:.2}s, llm {:.2}s, write {:.2}s",
+        timings.parse.as_secs_f64(),
+        timings.extract.as_secs_f64(),
+        timings.llm.as_secs_f64(),
+        timings.write.as_secs_f64()
+    );
+    println!("   └─ Duration: {:.2}s", duration.as_secs_f64());
+    println!("─────────────────────────────────────────\n");
+
+    if let Some(backups) = &backups {
+        let created = backups.created();
+        if !created.is_empty() {
+            println!("🗄️  Backups created:");
+            for (i, path) in created.iter().enumerate() {
+                println!("   {}. {}", i + 1, path.display());
+            }
+            println!();
+        }
+    }
+
+    if let Some(path) = &args.validation_report {
+        report.write(path).map_err(|e| anyhow::anyhow!(e))?;
+        println!(
+            "🧪 Wrote {} validation finding(s) to {}",
+            report.findings.len(),
+            path.display()
+        );
+        println!();
+    }
+
+    if let Some(path) = &args.export_tmx {
+        let languages: Vec<TmxLanguage> = results
+            .iter()
+            .filter_map(|r| r.as_ref().ok())
+            .flat_map(|stats| &stats.exported)
+            .flat_map(|(lang, written, existing)| {
+                [
+                    TmxLanguage {
+                        lang,
+                        model: config.llm.model.for_language(lang),
+                        units: written,
+                    },
+                    TmxLanguage {
+                        lang,
+                        model: None,
+                        units: existing,
+                    },
+                ]
+            })
+            .collect();
+        let exported = write_tmx(path, &config.translation.source_language, &languages)
+            .map_err(|e| anyhow::anyhow!(e))?;
+        println!(
+            "🧾 Exported {} translation unit(s) to {}",
+            exported,
+            path.display()
+        );
+        println!();
+    }
+
+    if total_err > 0 {
+        println!("❌ Errors encountered:");
+        for (i, result) in results.iter().enumerate() {
+            if let Err(e) = result {
+                println!("   {}. {}", i + 1, e);
+            }
+        }
+        println!();
+    }
+
+    if ctx.budget_exceeded.load(Ordering::Relaxed) {
+        println!(
+            "❌ Budget exceeded: max_spend_usd is ${:.2}; remaining batches were not sent",
+            config.llm.max_spend_usd.unwrap_or_default()
+        );
+        std::process::exit(1);
+    } else if total_err > 0 {
+        println!("❌ Translation completed with errors");
+        std::process::exit(1);
+    } else if totals.failed > 0 {
+        println!("⚠️  Translation completed with some failed messages");
+    } else if totals.translated == 0
+        && totals.auto_copied == 0
+        && totals.memory_hits == 0
+        && totals.tm_hits == 0
+        && totals.compendium_hits == 0
+    {
+        println!(
+            "⚠️  No messages were translated (check your input files and skip_translated setting)"
+        );
+    } else {
+        println!("✅ Translation completed successfully!");
+    }
+
+    Ok(())
+}
+
+struct RunContext<'a> {
+    config: &'a AppConfig,
     dry_run: bool,
     force_write: bool,
+    explain: bool,
+    strict_coverage: bool,
+    lang_concurrent: usize,
+    backups: Option<&'a BackupManager>,
+    file_locks: &'a FileLocks,
+    passthrough: Regex,
+    term_memory: Option<&'a TermMemory>,
+    translation_memory: Option<&'a TranslationMemory>,
+    compendium_pattern: Option<String>,
+    overrides: Option<&'a Overrides>,
+    write_options: WriteOptions,
+    only_lang: &'a [String],
+    sample: Option<SampleSize>,
+    seed: u64,
+    export_tmx_all: bool,
+    usage: Arc<TokenUsage>,
+    retries: Arc<RetryBudget>,
+    budget_exceeded: AtomicBool,
+    events: Option<&'a EventLog>,
+    report: Mutex<ValidationReport>,
+    /// With `--retry-from`, the only messages translated.
+    retry: Option<&'a RetryList>,
+}
+
+impl RunContext<'_> {
+    fn emit(&self, event: Event<'_>) {
+        if let Some(events) = self.events {
+            events.emit(&event);
+        }
+    }
+}
+
+struct FileStats {
+    counts: MessageCounts,
+    /// Translated and failed messages per language.
+    languages: Vec<(String, usize, usize)>,
+    /// Per language, the messages written by this run and, with
+    /// `--export-tmx-all`, the other translations found in the output.
+    exported: Vec<(String, Vec<TranslationUnit>, Vec<TranslationUnit>)>,
+    timings: PhaseTimings,
+}
+
+#[derive(Default)]
+struct LangStats {
+    counts: MessageCounts,
+    review: Vec<TranslationUnit>,
+    lang: String,
+    written: Vec<TranslationUnit>,
+    existing: Vec<TranslationUnit>,
+    timings: PhaseTimings,
+}
+
+/// The messages of a language, a file or the run, by what became of them.
+#[derive(Default, Clone, Copy)]
+struct MessageCounts {
+    translated: usize,
+    failed: usize,
+    untagged: usize,
+    auto_copied: usize,
+    oversized: usize,
+    same_as_source: usize,
+    stray_characters: usize,
+    memory_hits: usize,
+    tm_hits: usize,
+    compendium_hits: usize,
+    locked: usize,
+    overridden: usize,
+    sampled: usize,
+    warned: usize,
+}
+
+impl AddAssign for MessageCounts {
+    fn add_assign(&mut self, other: Self) {
+        self.translated += other.translated;
+        self.failed += other.failed;
+        self.untagged += other.untagged;
+        self.auto_copied += other.auto_copied;
+        self.oversized += other.oversized;
+        self.same_as_source += other.same_as_source;
+        self.stray_characters += other.stray_characters;
+        self.memory_hits += other.memory_hits;
+        self.tm_hits += other.tm_hits;
+        self.compendium_hits += other.compendium_hits;
+        self.locked += other.locked;
+        self.overridden += other.overridden;
+        self.sampled += other.sampled;
+        self.warned += other.warned;
+    }
+}
+
+/// Time spent per phase, summed over languages and files, so it can exceed the
+/// wall-clock duration when they run concurrently.
+#[derive(Default, Clone, Copy)]
+struct PhaseTimings {
+    parse: Duration,
+    extract: Duration,
+    llm: Duration,
+    write: Duration,
+}
+
+impl AddAssign for PhaseTimings {
+    fn add_assign(&mut self, other: Self) {
+        self.parse += other.parse;
+        self.extract += other.extract;
+        self.llm += other.llm;
+        self.write += other.write;
+    }
+}
+
+async fn translate_file(
+    ctx: &RunContext<'_>,
+    input: &Input,
+    merged: Option<&SharedTranslations>,
+    file_pb: ProgressBar,
 ) -> Result<FileStats> {
-    let langs = config.translation.target_languages.clone();
+    let input_path = &input.path;
+    let langs = match InputKind::of(input_path) {
+        InputKind::Template => ctx.config.translation.target_languages.clone(),
+        InputKind::Catalog => {
+            let lang = catalog_language(input_path)?;
+            if ctx.only_lang.is_empty() || ctx.only_lang.contains(&lang) {
+                vec![lang]
+            } else {
+                Vec::new()
+            }
+        }
+    };
+    let langs: Vec<String> = match ctx.retry {
+        Some(retry) => langs
+            .into_iter()
+            .filter(|lang| retry.has_language(input_path, lang))
+            .collect(),
+        None => langs,
+    };
+    file_pb.set_length(langs.len() as u64);
+
+    println!("   Languages to translate: {:?}", langs);
+
+    let shared = if merged.is_none()
+        && ctx.config.llm.multi_language
+        && !ctx.dry_run
+        && langs.len() > 1
+        && InputKind::of(input_path) == InputKind::Template
+    {
+        Some(share_translations(ctx, input, &langs).await?)
+    } else {
+        None
+    };
+    let shared = merged.or(shared.as_ref());
+
+    let results: Vec<_> = stream::iter(langs)
+        .map(|lang| {
+            let pb = file_pb.clone();
+
+            async move {
+                pb.set_message(format!("starting {}", lang));
+
+                println!("      🌐 Starting translation for: {}", lang);
+
+                let result = translate_single_language(&lang, ctx, input, shared, &pb).await;
+
+                match &result {
+                    Ok(stats) => {
+                        ctx.emit(Event::LanguageFinished {
+                            file: &input.path,
+                            lang: &lang,
+                            translated: stats.counts.translated,
+                            failed: stats.counts.failed,
+                        });
+                        let auto_copied = if stats.counts.auto_copied > 0 {
+                            format!(", 📋 {} auto-copied", stats.counts.auto_copied)
+                        } else {
+                            String::new()
+                        };
+                        if stats.counts.failed > 0 {
+                            pb.println(format!(
+                                "      {} - ✅ {} translated, ⚠️  {} failed{}",
+                                lang, stats.counts.translated, stats.counts.failed, auto_copied
+                            ));
+                        } else if stats.counts.translated > 0
+                            || stats.counts.auto_copied > 0
+                            || stats.counts.overridden > 0
+                        {
+                            pb.println(format!(
+                                "      {} - ✅ {} translated{}",
+                                lang, stats.counts.translated, auto_copied
+                            ));
+                        } else {
+                            pb.println(format!("      {} - ℹ️  No messages to translate", lang));
+                        }
+                    }
+                    Err(e) => {
+                        ctx.emit(Event::LanguageFailed {
+                            file: &input.path,
+                            lang: &lang,
+                            error: format!("{:#}", e),
+                        });
+                        ctx.report.lock().unwrap().add_failed_language(
+                            &input.path,
+                            &lang,
+                            format!("{:#}", e),
+                        );
+                        let error_msg = format!("      {} - ❌ {}", lang, e);
+                        pb.println(error_msg.clone());
+                        eprintln!(
+                            "\n❌ Language translation failed: {}\n   Error: {:?}\n",
+                            lang, e
+                        );
+                    }
+                }
+
+                pb.inc(1);
+                result
+            }
+        })
+        .buffer_unordered(ctx.lang_concurrent)
+        .collect()
+        .await;
+
+    let mut counts = MessageCounts::default();
+    let mut timings = PhaseTimings::default();
+    for stats in results.iter().filter_map(|r| r.as_ref().ok()) {
+        counts += stats.counts;
+        timings += stats.timings;
+    }
+
+    let languages = results
+        .iter()
+        .filter_map(|r| r.as_ref().ok())
+        .map(|s| (s.lang.clone(), s.counts.translated, s.counts.failed))
+        .collect();
+
+    let all_failed = results.iter().all(|r| r.is_err());
+    if all_failed && !results.is_empty() {
+        return Err(anyhow::anyhow!(
+            "All language translations failed. Check your LLM configuration and API connectivity."
+        ));
+    }
+
+    if let Some(pattern) = &ctx.config.translation.combined_output
+        && (!ctx.dry_run || ctx.force_write)
+    {
+        write_combined_output(ctx, input_path, pattern, &results).await?;
+    }
+
+    let exported = results
+        .into_iter()
+        .filter_map(Result::ok)
+        .map(|s| (s.lang, s.written, s.existing))
+        .collect();
+
+    Ok(FileStats {
+        counts,
+        languages,
+        exported,
+        timings,
+    })
+}
+
+async fn translate_single_language(
+    target_lang: &str,
+    ctx: &RunContext<'_>,
+    input: &Input,
+    shared: Option<&SharedTranslations>,
+    pb: &ProgressBar,
+) -> Result<LangStats> {
+    let input_path = &input.path;
+    let format = ctx
+        .config
+        .translation
+        .format_of(&ctx.config.translation.inputs[input.group], input_path);
+    let adapter = adapter_for(format);
+    let output_path = match InputKind::of(input_path) {
+        InputKind::Template => output_path_for(&ctx.config.translation, input, target_lang)
+            .context("Failed to build output path")?,
+        InputKind::Catalog => input_path.to_path_buf(),
+    };
+
+    println!("         Input:  {}", input_path.display());
+    println!("         Output: {}", output_path.display());
+    ctx.emit(Event::LanguageStarted {
+        file: input_path,
+        lang: target_lang,
+        output: &output_path,
+    });
+
+    if !ctx.dry_run || ctx.force_write {
+        if let Some(parent) = output_path.parent() {
+            fs::create_dir_all(parent)
+                .context(format!("Failed to create output directory: {:?}", parent))?;
+        }
+        if !output_path.exists() {
+            adapter
+                .create_output(input_path, &output_path, target_lang)
+                .map_err(|e| {
+                    anyhow::anyhow!("Failed to create output file {:?}: {}", output_path, e)
+                })?;
+        }
+    }
+
+    let mut stats = process_single_lang(
+        target_lang,
+        ctx,
+        input_path,
+        &output_path,
+        format,
+        shared,
+        pb,
+    )
+    .await?;
+
+    if !ctx.dry_run || ctx.force_write {
+        let _guard = ctx.file_locks.lock(&output_path).await;
+        adapter
+            .finish_output(input_path, &output_path, &ctx.write_options)
+            .map_err(|e| anyhow::anyhow!(e))?;
+    }
+
+    if !ctx.dry_run || ctx.force_write {
+        write_review(target_lang, &output_path, &stats.review, &ctx.write_options)?;
+    }
+
+    if ctx.export_tmx_all && output_path.exists() {
+        let written: HashSet<_> = stats.written.iter().map(combined_key).collect();
+        stats.existing = adapter
+            .existing_translations(&output_path)
+            .map_err(|e| anyhow::anyhow!(e))?
+            .into_iter()
+            .filter(|unit| !written.contains(&combined_key(unit)))
+            .collect();
+    }
+
+    if ctx.strict_coverage
+        && (!ctx.dry_run || ctx.force_write)
+        && let Some(result) = adapter.verify_coverage(input_path, &output_path)
+    {
+        result
+            .map_err(|e| anyhow::anyhow!("Coverage check failed for {:?}: {}", output_path, e))?;
+        println!(
+            "         🔎 Coverage verified for {}",
+            output_path.display()
+        );
+    }
+
+    if ctx.config.output.check_po && (!ctx.dry_run || ctx.force_write) && output_path.exists() {
+        let _guard = ctx.file_locks.lock(&output_path).await;
+        let problems = match adapter.check_output(&output_path) {
+            Some(result) => result.map_err(|e| anyhow::anyhow!(e))?,
+            None => Vec::new(),
+        };
+        if !problems.is_empty() {
+            for problem in &problems {
+                eprintln!("         ❌ {}: {}", output_path.display(), problem);
+            }
+            if let Some(backup) = ctx.backups.and_then(|b| b.backup_of(&output_path)) {
+                fs::copy(&backup, &output_path).context(format!(
+                    "Failed to restore {:?} from {:?}",
+                    output_path, backup
+                ))?;
+                println!(
+                    "         🗄️  Restored {} from {}",
+                    output_path.display(),
+                    backup.display()
+                );
+            }
+            anyhow::bail!(
+                "PO check failed for {:?}: {} problem(s), the first: {}",
+                output_path,
+                problems.len(),
+                problems[0]
+            );
+        }
+    }
+
+    if let Some(template) = &ctx.config.hooks.post_file
+        && (!ctx.dry_run || ctx.force_write)
+    {
+        let _guard = ctx.file_locks.lock(&output_path).await;
+        let output = run_post_file(template, &output_path, target_lang)
+            .await
+            .map_err(|e| anyhow::anyhow!(e))?;
+        println!("         🪝 Ran hook: {}", output.command);
+        for line in output.stdout.lines().chain(output.stderr.lines()) {
+            println!("            {}", line);
+        }
+    }
+
+    if let Some(pattern) = &ctx.compendium_pattern
+        && ctx.config.translation.update_compendium
+        && adapter.uses_compendium()
+        && (!ctx.dry_run || ctx.force_write)
+    {
+        update_compendium(
+            ctx,
+            &compendium_path(pattern, target_lang),
+            target_lang,
+            &stats,
+        )
+        .await?;
+    }
+
+    Ok(stats)
+}
+
+/// Warns about translations in the output that the compendium translates
+/// differently; the output's translation is kept.
+fn report_compendium_conflicts(
+    compendium: &Compendium,
+    format: DocumentFormat,
+    output_path: &Path,
+    compendium_path: &Path,
+) -> Result<()> {
+    if compendium.is_empty() || !output_path.exists() {
+        return Ok(());
+    }
+    let conflicts: Vec<TranslationUnit> = adapter_for(format)
+        .existing_translations(output_path)
+        .map_err(|e| anyhow::anyhow!(e))?
+        .into_iter()
+        .filter(|unit| compendium.conflicts_with(unit))
+        .collect();
+    if !conflicts.is_empty() {
+        println!(
+            "         ⚠️  {} translation(s) differ from the compendium {}; keeping the PO's:",
+            conflicts.len(),
+            compendium_path.display()
+        );
+        for unit in conflicts.iter().take(5) {
+            println!("            - {}", unit.label());
+        }
+    }
+    Ok(())
+}
 
-    println!("   Languages to translate: {:?}", langs);
+/// Adds the translations written for this language that the compendium
+/// lacks. Ones it translates differently are reported, not overwritten.
+async fn update_compendium(
+    ctx: &RunContext<'_>,
+    path: &Path,
+    target_lang: &str,
+    stats: &LangStats,
+) -> Result<()> {
+    let guard = ctx.file_locks.lock(path).await;
+    let compendium = Compendium::load(path).map_err(|e| anyhow::anyhow!(e))?;
+
+    let mut conflicts = Vec::new();
+    let mut added = Vec::new();
+    for unit in stats.written.iter().filter(|unit| unit.failure.is_none()) {
+        if compendium.conflicts_with(unit) {
+            conflicts.push(unit.label());
+        } else if compendium.translate(unit).is_none() {
+            added.push(unit.clone());
+        }
+    }
 
-    let results: Vec<_> = stream::iter(langs)
-        .map(|lang| {
-            let config = config;
-            let pb = file_pb.clone();
-            let input_path = input_path.clone();
+    if !conflicts.is_empty() {
+        println!(
+            "         ⚠️  {} translation(s) differ from the compendium {}; not updated:",
+            conflicts.len(),
+            path.display()
+        );
+        for label in conflicts.iter().take(5) {
+            println!("            - {}", label);
+        }
+    }
+    if added.is_empty() {
+        return Ok(());
+    }
 
-            async move {
-                pb.set_message(format!("starting {}", lang));
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context(format!(
+            "Failed to create compendium directory: {:?}",
+            parent
+        ))?;
+    }
+    let count = added.len();
+    GettextAdapter
+        .write_translations(added, target_lang, &guard, &ctx.write_options)
+        .map_err(|e| anyhow::anyhow!("Failed to update compendium {:?}: {}", path, e))?;
+    println!(
+        "         📖 Added {} translation(s) to the compendium {}",
+        count,
+        path.display()
+    );
+    Ok(())
+}
 
-                println!("      🌐 Starting translation for: {}", lang);
+async fn process_single_lang(
+    target_lang: &str,
+    ctx: &RunContext<'_>,
+    input_path: &Path,
+    output_path: &Path,
+    format: DocumentFormat,
+    shared: Option<&SharedTranslations>,
+    pb: &ProgressBar,
+) -> Result<LangStats> {
+    let config = ctx.config;
+    let dry_run = ctx.dry_run;
+
+    let loaded = load_messages(
+        input_path,
+        output_path,
+        format,
+        config.project.skip_translated || InputKind::of(input_path) == InputKind::Catalog,
+        config.translation.translate_comments,
+        ctx.overrides.map(|o| (o, target_lang)),
+    )?;
+    if loaded.header_context.is_some() && config.project.use_header_context {
+        println!("         📝 Using the template's header comments as context");
+    }
+    let project_context = project_context(config, loaded.header_context);
+    let (units, not_failed): (Vec<_>, Vec<_>) = loaded.units.into_iter().partition(|unit| {
+        ctx.retry
+            .is_none_or(|retry| retry.contains(input_path, target_lang, unit))
+    });
+    let (messages, untagged): (Vec<_>, Vec<_>) = units.into_iter().partition(|unit| {
+        config
+            .translation
+            .require_tag
+            .as_deref()
+            .is_none_or(|tag| unit.has_tag(tag))
+    });
+    let (passthrough, messages): (Vec<_>, Vec<_>) = messages
+        .into_iter()
+        .partition(|unit| unit.is_passthrough(&ctx.passthrough));
+    let (oversized, messages): (Vec<_>, Vec<_>) = messages.into_iter().partition(|unit| {
+        config
+            .translation
+            .skip_above_tokens
+            .is_some_and(|max_tokens| unit.is_oversized(max_tokens))
+    });
+
+    // With --explain, why each message leaves the pipeline, by combined_key.
+    let mut reasons = ctx.explain.then(HashMap::new);
+    explain(&mut reasons, &loaded.overridden, "override");
+    explain(
+        &mut reasons,
+        &not_failed,
+        "skipped (did not fail in the --retry-from run)",
+    );
+    explain(&mut reasons, &untagged, "skipped (without require_tag)");
+    explain(
+        &mut reasons,
+        &passthrough,
+        "copied (matches passthrough_pattern)",
+    );
+    explain(&mut reasons, &oversized, "skipped (over skip_above_tokens)");
+
+    let mut stats = LangStats {
+        counts: MessageCounts {
+            untagged: untagged.len(),
+            auto_copied: passthrough.len(),
+            oversized: oversized.len(),
+            locked: loaded.locked,
+            overridden: loaded.overridden.len(),
+            ..Default::default()
+        },
+        lang: target_lang.to_string(),
+        timings: loaded.timings,
+        ..Default::default()
+    };
+
+    if !loaded.overridden.is_empty() {
+        println!(
+            "         ✍️  Applying {} override(s)",
+            loaded.overridden.len()
+        );
+        if !dry_run || ctx.force_write {
+            write_translations(
+                ctx,
+                target_lang,
+                output_path,
+                format,
+                loaded.overridden,
+                &mut stats,
+            )
+            .await?;
+        }
+    }
+
+    if !untagged.is_empty() {
+        println!(
+            "         🏷️  Leaving {} message(s) without the tag {} untouched",
+            untagged.len(),
+            config
+                .translation
+                .require_tag
+                .as_deref()
+                .unwrap_or_default()
+        );
+    }
+
+    if !passthrough.is_empty() {
+        println!(
+            "         📋 Auto-copying {} message(s) without translatable text",
+            passthrough.len()
+        );
+        if !dry_run || ctx.force_write {
+            let nplurals = plural_forms_or_default(target_lang).nplurals;
+            let copied: Vec<_> = passthrough
+                .into_iter()
+                .map(|unit| unit.copied_from_source(nplurals))
+                .collect();
+            write_translations(ctx, target_lang, output_path, format, copied, &mut stats).await?;
+        }
+    }
+
+    if !oversized.is_empty() {
+        println!(
+            "         🐘 Skipping {} message(s) over skip_above_tokens",
+            oversized.len()
+        );
+        if !dry_run || ctx.force_write {
+            let nplurals = plural_forms_or_default(target_lang).nplurals;
+            let skipped: Vec<_> = oversized
+                .into_iter()
+                .map(|unit| match config.translation.on_oversized {
+                    OversizedMode::Copy => unit.copied_from_source(nplurals),
+                    OversizedMode::Empty => unit.emptied(nplurals),
+                })
+                .collect();
+            write_translations(ctx, target_lang, output_path, format, skipped, &mut stats).await?;
+        }
+    }
+
+    let compendium = match &ctx.compendium_pattern {
+        Some(pattern) if adapter_for(format).uses_compendium() => {
+            let path = compendium_path(pattern, target_lang);
+            let compendium = Compendium::load(&path).map_err(|e| anyhow::anyhow!(e))?;
+            report_compendium_conflicts(&compendium, format, output_path, &path)?;
+            Some(compendium)
+        }
+        _ => None,
+    };
+    let messages = match compendium {
+        Some(compendium) => {
+            let mut matched = Vec::new();
+            let mut rest = Vec::new();
+            for unit in messages {
+                match compendium.translate(&unit) {
+                    Some(unit) => matched.push(unit),
+                    None => rest.push(unit),
+                }
+            }
+
+            stats.counts.compendium_hits = matched.len();
+            explain(&mut reasons, &matched, "compendium hit");
+            println!(
+                "         📖 Compendium: {} of {} message(s) matched exactly",
+                matched.len(),
+                matched.len() + rest.len()
+            );
+            if !matched.is_empty() && (!dry_run || ctx.force_write) {
+                write_translations(ctx, target_lang, output_path, format, matched, &mut stats)
+                    .await?;
+            }
+            rest
+        }
+        None => messages,
+    };
+
+    let messages = match ctx.translation_memory {
+        Some(memory) => {
+            let mut remembered = Vec::new();
+            let mut rest = Vec::new();
+            for unit in messages {
+                match memory.exact(&unit.msg_id, unit.context.as_deref(), target_lang) {
+                    Some(translation) if !unit.is_plural() => {
+                        let mut unit = unit;
+                        unit.msg_str = Some(translation.to_string());
+                        remembered.push(unit);
+                    }
+                    _ => rest.push(unit),
+                }
+            }
 
-                let result = translate_single_language(
-                    &lang,
-                    config,
-                    dry_run,
-                    force_write,
-                    &input_path,
-                    &pb,
+            stats.counts.tm_hits = remembered.len();
+            explain(&mut reasons, &remembered, "translation memory hit");
+            println!(
+                "         🗃️  Translation memory: {} of {} message(s) matched exactly",
+                remembered.len(),
+                remembered.len() + rest.len()
+            );
+            if !remembered.is_empty() && (!dry_run || ctx.force_write) {
+                write_translations(
+                    ctx,
+                    target_lang,
+                    output_path,
+                    format,
+                    remembered,
+                    &mut stats,
                 )
-                .await;
+                .await?;
+            }
+            rest
+        }
+        None => messages,
+    };
 
-                match &result {
-                    Ok((translated, failed)) => {
-                        if *failed > 0 {
-                            pb.println(format!(
-                                "      {} - ✅ {} translated, ⚠️  {} failed",
-                                lang, translated, failed
-                            ));
-                        } else if *translated > 0 {
-                            pb.println(format!("      {} - ✅ {} translated", lang, translated));
-                        } else {
-                            pb.println(format!("      {} - ℹ️  No messages to translate", lang));
+    let messages = match ctx.term_memory {
+        Some(memory) => {
+            let mut remembered = Vec::new();
+            let mut rest = Vec::new();
+            for unit in messages {
+                match memory.exact(&unit.msg_id, target_lang) {
+                    Some(translation) if !unit.is_plural() => {
+                        let mut unit = unit;
+                        unit.msg_str = Some(translation.to_string());
+                        remembered.push(unit);
+                    }
+                    _ => rest.push(unit),
+                }
+            }
+
+            stats.counts.memory_hits = remembered.len();
+            explain(&mut reasons, &remembered, "term memory hit");
+            println!(
+                "         📚 Term memory: {} of {} message(s) matched exactly",
+                remembered.len(),
+                remembered.len() + rest.len()
+            );
+            if !remembered.is_empty() && (!dry_run || ctx.force_write) {
+                write_translations(
+                    ctx,
+                    target_lang,
+                    output_path,
+                    format,
+                    remembered,
+                    &mut stats,
+                )
+                .await?;
+            }
+            rest
+        }
+        None => messages,
+    };
+
+    // Messages written by an earlier run of this language that was cut short.
+    let mut journal = ProgressJournal::open(output_path).map_err(|e| anyhow::anyhow!(e))?;
+    let messages = if journal.is_empty() {
+        messages
+    } else {
+        let (completed, messages): (Vec<_>, Vec<_>) = messages
+            .into_iter()
+            .partition(|unit| journal.contains(unit));
+        println!(
+            "         ⏯️  Resuming: skipping {} message(s) completed in an interrupted run",
+            completed.len()
+        );
+        explain(
+            &mut reasons,
+            &completed,
+            "skipped (completed in an interrupted run)",
+        );
+        messages
+    };
+    let writes = !dry_run || ctx.force_write;
+
+    let messages = match ctx.sample {
+        Some(size) => {
+            let total = messages.len();
+            let unsampled = reasons.is_some().then(|| messages.clone());
+            let sampled = sample_messages(messages, size, ctx.seed);
+            stats.counts.sampled = sampled.len();
+            if let Some(unsampled) = unsampled {
+                explain(&mut reasons, &sampled, "sent to LLM");
+                explain(&mut reasons, &unsampled, "skipped (not in --sample)");
+            }
+            println!(
+                "         🎲 Sampled {} of {} message(s) (seed {})",
+                sampled.len(),
+                total,
+                ctx.seed
+            );
+            sampled
+        }
+        None => messages,
+    };
+
+    explain(&mut reasons, &messages, "sent to LLM");
+    if let Some(reasons) = reasons {
+        // Everything else extracted from the template was already translated.
+        let all = load_messages(
+            input_path,
+            output_path,
+            format,
+            false,
+            config.translation.translate_comments,
+            None,
+        )?;
+        println!("         🔎 Explain ({}):", target_lang);
+        for unit in &all.units {
+            let reason = reasons
+                .get(&combined_key(unit))
+                .copied()
+                .unwrap_or("skipped (already translated)");
+            println!("            {}: {}", reason, unit.label());
+        }
+        return Ok(stats);
+    }
+
+    println!("         Messages to translate: {}", messages.len());
+
+    if messages.is_empty() {
+        pb.println(format!(
+            "         ℹ️  No messages to translate for {}",
+            target_lang
+        ));
+        if writes {
+            journal.finish().map_err(|e| anyhow::anyhow!(e))?;
+        }
+        return Ok(stats);
+    }
+    let batches: Vec<_> = messages.chunks(config.translation.batch_size).collect();
+    let total_batches = batches.len();
+
+    println!(
+        "         Batches: {} (size: {})",
+        total_batches, config.translation.batch_size
+    );
+
+    let mut all_translated_for_preview = Vec::new();
+    let attribution = config
+        .translation
+        .attribution
+        .trim()
+        .replace(
+            "{model}",
+            config
+                .llm
+                .model
+                .for_language(target_lang)
+                .unwrap_or_default(),
+        )
+        .replace(
+            "{date}",
+            &chrono::Local::now().format("%Y-%m-%d").to_string(),
+        );
+
+    let mut completed = true;
+    for (batch_idx, batch) in batches.into_iter().enumerate() {
+        let batch_num = batch_idx + 1;
+
+        if !dry_run && over_budget(ctx) {
+            println!(
+                "         💸 Budget reached, skipping {} remaining batch(es) for {}",
+                total_batches - batch_idx,
+                target_lang
+            );
+            completed = false;
+            break;
+        }
+
+        pb.set_message(format!(
+            "{} (batch {}/{})",
+            target_lang, batch_num, total_batches
+        ));
+
+        println!(
+            "         📦 Processing batch {}/{} ({} messages)",
+            batch_num,
+            total_batches,
+            batch.len()
+        );
+
+        let (shared_units, batch) = match shared {
+            Some(shared) => shared.take(target_lang, batch),
+            None => (Vec::new(), batch.to_vec()),
+        };
+        let batch = batch.as_slice();
+        if !shared_units.is_empty() {
+            println!(
+                "         🔗 {} message(s) from shared requests",
+                shared_units.len()
+            );
+        }
+
+        let started = Instant::now();
+        let mut translations = translate_batch(
+            ctx,
+            target_lang,
+            batch,
+            &project_context,
+            pb,
+            batch_num,
+            total_batches,
+        )
+        .await?;
+
+        stats.timings.llm += started.elapsed();
+        translations.translated.extend(shared_units);
+
+        let mut translations = validate_translations(
+            translations,
+            target_lang,
+            &config.translation,
+            &config.validation,
+            format,
+        );
+        let (wrong_language, failed): (Vec<_>, Vec<_>) = translations
+            .failed_translated
+            .into_iter()
+            .partition(|unit| {
+                unit.failure
+                    .as_deref()
+                    .is_some_and(|reason| reason.starts_with(WRONG_LANGUAGE))
+            });
+        translations.failed_translated = failed;
+        // A batch mostly in another language is the model confused, which
+        // one more request usually fixes.
+        if wrong_language.len() * 2
+            > translations.translated.len()
+                + translations.failed_translated.len()
+                + wrong_language.len()
+        {
+            println!(
+                "         🔀 {} message(s) came back in the wrong language, retrying them once",
+                wrong_language.len()
+            );
+            let requests: Vec<TranslationUnit> = wrong_language
+                .iter()
+                .map(|unit| TranslationUnit {
+                    failure: None,
+                    ..unit.clone()
+                })
+                .collect();
+            let started = Instant::now();
+            let retried = translate_batch(
+                ctx,
+                target_lang,
+                &requests,
+                &project_context,
+                pb,
+                batch_num,
+                total_batches,
+            )
+            .await;
+            stats.timings.llm += started.elapsed();
+
+            match retried {
+                Ok(retried) => {
+                    let retried = validate_translations(
+                        retried,
+                        target_lang,
+                        &config.translation,
+                        &config.validation,
+                        format,
+                    );
+                    translations.translated.extend(retried.translated);
+                    translations
+                        .failed_translated
+                        .extend(retried.failed_translated);
+                }
+                Err(e) => {
+                    eprintln!(
+                        "      ⚠️  Retrying the wrong-language messages failed in {}: {:#}",
+                        target_lang, e
+                    );
+                    translations.failed_translated.extend(wrong_language);
+                }
+            }
+        } else {
+            translations.failed_translated.extend(wrong_language);
+        }
+        if config.translation.on_too_long == LengthMode::Reprompt {
+            let (too_long, failed): (Vec<_>, Vec<_>) = translations
+                .failed_translated
+                .into_iter()
+                .partition(|unit| {
+                    unit.failure.is_some()
+                        && check_length(unit, target_lang, &config.translation).err()
+                            == unit.failure
+                });
+            translations.failed_translated = failed;
+
+            if !too_long.is_empty() {
+                println!(
+                    "         ✂️  Asking again for shorter translations of {} message(s)",
+                    too_long.len()
+                );
+                let requests: Vec<TranslationUnit> = too_long.iter().map(shorter_request).collect();
+                let started = Instant::now();
+                let retried = translate_batch(
+                    ctx,
+                    target_lang,
+                    &requests,
+                    &project_context,
+                    pb,
+                    batch_num,
+                    total_batches,
+                )
+                .await;
+                stats.timings.llm += started.elapsed();
+
+                match retried {
+                    Ok(mut retried) => {
+                        let comments: HashMap<String, Option<String>> = too_long
+                            .iter()
+                            .map(|unit| (combined_key(unit), unit.comments.clone()))
+                            .collect();
+                        for unit in retried
+                            .translated
+                            .iter_mut()
+                            .chain(&mut retried.failed_translated)
+                        {
+                            if let Some(original) = comments.get(&combined_key(unit)) {
+                                unit.comments = original.clone();
+                            }
                         }
+                        let retried = validate_translations(
+                            retried,
+                            target_lang,
+                            &config.translation,
+                            &config.validation,
+                            format,
+                        );
+                        translations.translated.extend(retried.translated);
+                        translations
+                            .failed_translated
+                            .extend(retried.failed_translated);
                     }
                     Err(e) => {
-                        let error_msg = format!("      {} - ❌ {}", lang, e);
-                        pb.println(error_msg.clone());
                         eprintln!(
-                            "\n❌ Language translation failed: {}\n   Error: {:?}\n",
-                            lang, e
+                            "      ⚠️  Asking for shorter translations failed in {}: {:#}",
+                            target_lang, e
                         );
+                        translations.failed_translated.extend(too_long);
                     }
                 }
+            }
+        }
+        if let Some(previous) = &ctx.write_options.attribution {
+            translations.translated = translations
+                .translated
+                .into_iter()
+                .map(|unit| unit.attributed(&attribution, previous))
+                .collect();
+        }
 
-                pb.inc(1);
-                result
+        stats.counts.translated += translations.translated.len();
+        stats.counts.failed += translations.failed_translated.len();
+        stats.counts.same_as_source += translations
+            .failed_translated
+            .iter()
+            .filter(|unit| unit.failure.as_deref() == Some(SAME_AS_SOURCE))
+            .count();
+        stats.counts.stray_characters += translations
+            .translated
+            .iter()
+            .flat_map(|unit| &unit.warnings)
+            .chain(
+                translations
+                    .failed_translated
+                    .iter()
+                    .filter_map(|unit| unit.failure.as_ref()),
+            )
+            .filter(|reason| reason.starts_with(STRAY_CHARACTERS))
+            .count();
+        stats.counts.warned += translations
+            .translated
+            .iter()
+            .filter(|unit| !unit.warnings.is_empty())
+            .count();
+        ctx.report.lock().unwrap().add(
+            input_path,
+            target_lang,
+            translations
+                .translated
+                .iter()
+                .chain(&translations.failed_translated),
+        );
+
+        println!(
+            "         ✓ Batch {}: {} translated, {} failed",
+            batch_num,
+            translations.translated.len(),
+            translations.failed_translated.len()
+        );
+        ctx.emit(Event::BatchFinished {
+            file: input_path,
+            lang: target_lang,
+            batch: batch_num,
+            total_batches,
+            translated: translations.translated.len(),
+            failed: translations.failed_translated.len(),
+        });
+
+        if !translations.translated.is_empty() {
+            for entry in &translations.translated {
+                pb.println(format!("      ✓ {}", entry));
             }
-        })
-        .buffer_unordered(lang_concurrent)
-        .collect()
-        .await;
+        }
+
+        if !translations.failed_translated.is_empty() {
+            for entry in &translations.failed_translated {
+                pb.println(format!("      ✗ {}", entry));
+            }
+        }
+
+        stats
+            .review
+            .extend(translations.failed_translated.iter().cloned());
+        stats.review.extend(
+            translations
+                .translated
+                .iter()
+                .filter(|unit| !unit.warnings.is_empty())
+                .cloned(),
+        );
+
+        if dry_run {
+            all_translated_for_preview.extend(translations.translated.clone());
+        }
+
+        if writes && !translations.translated.is_empty() {
+            let count = translations.translated.len();
+            write_translations(
+                ctx,
+                target_lang,
+                output_path,
+                format,
+                translations.translated.clone(),
+                &mut stats,
+            )
+            .await?;
+            journal
+                .record(&translations.translated)
+                .map_err(|e| anyhow::anyhow!(e))?;
+
+            println!("         💾 Saved {} translations to file", count);
+        }
+
+        if config.translation.write_failed_as_empty
+            && writes
+            && !translations.failed_translated.is_empty()
+        {
+            let nplurals = plural_forms_or_default(target_lang).nplurals;
+            let failed = translations
+                .failed_translated
+                .into_iter()
+                .map(|unit| unit.emptied(nplurals))
+                .collect();
+            write_translations(ctx, target_lang, output_path, format, failed, &mut stats).await?;
+        }
+    }
+
+    // A run cut short by an error never gets here and keeps the journal.
+    if writes && completed {
+        journal.finish().map_err(|e| anyhow::anyhow!(e))?;
+    }
+
+    if dry_run && !all_translated_for_preview.is_empty() {
+        pb.println(format!("\n      ╭─ Dry Run Preview ({}) ─╮", target_lang));
+        for (i, entry) in all_translated_for_preview.iter().take(3).enumerate() {
+            pb.println(format!("      │ {:02}. {}", i + 1, entry));
+        }
+        if all_translated_for_preview.len() > 3 {
+            pb.println(format!(
+                "      │ ... and {} more",
+                all_translated_for_preview.len() - 3
+            ));
+        }
+        pb.println("      ╰────────────────────────────╯\n");
+    }
+
+    Ok(stats)
+}
+
+/// The entry to send again when its translation was too long, with the
+/// rejected translation and the reason among its comments.
+fn shorter_request(unit: &TranslationUnit) -> TranslationUnit {
+    let rejected: Vec<&str> = unit
+        .translation_pairs()
+        .into_iter()
+        .map(|(_, translation)| translation)
+        .collect();
+    let note = format!(
+        "The translation \"{}\" was rejected: {}. Give a shorter one.",
+        rejected.join(" / "),
+        unit.failure.as_deref().unwrap_or_default()
+    );
+
+    let mut request = unit.clone();
+    request.failure = None;
+    request.comments = Some(match &unit.comments {
+        Some(comments) => format!("{}\n{}", comments, note),
+        None => note,
+    });
+    request
+}
+
+/// Translates a batch with the configured backend, or pretends to in a dry
+/// run.
+async fn translate_batch(
+    ctx: &RunContext<'_>,
+    target_lang: &str,
+    batch: &[TranslationUnit],
+    project_context: &str,
+    pb: &ProgressBar,
+    batch_num: usize,
+    total_batches: usize,
+) -> Result<TranslationResult> {
+    let config = ctx.config;
+    Ok(if batch.is_empty() {
+        TranslationResult {
+            translated: Vec::new(),
+            failed_translated: Vec::new(),
+        }
+    } else if ctx.dry_run {
+        DryRunTranslator
+            .translate(target_lang, batch, &config.llm.custom_prompt)
+            .await
+            .context(format!(
+                "Dry run translation failed for batch {}",
+                batch_num
+            ))?
+    } else if let (Backend::Command, Some(command)) = (config.llm.backend, &config.llm.command) {
+        let translator = CommandTranslator {
+            command: command.clone(),
+            model: config
+                .llm
+                .model
+                .for_language(target_lang)
+                .context(format!("No model configured for language {}", target_lang))?
+                .to_string(),
+            project_context: project_context.to_string(),
+        };
+
+        if config.translation.bisect_failed_batches {
+            BisectingTranslator { inner: translator }
+                .translate(target_lang, batch, &config.llm.custom_prompt)
+                .await
+        } else {
+            translator
+                .translate(target_lang, batch, &config.llm.custom_prompt)
+                .await
+        }
+        .context(format!(
+            "Translation command failed for batch {} in language {}",
+            batch_num, target_lang
+        ))?
+    } else if config.llm.backend == Backend::DeepL {
+        let translator = DeepLTranslator {
+            client: reqwest::Client::new(),
+            api_base: config.deepl.api_base().to_string(),
+            api_key: config.deepl.api_key.clone(),
+            source_lang: config.translation.source_language.clone(),
+            formality: config.deepl.formality.clone(),
+            glossary_id: config.deepl.glossaries.get(target_lang).cloned(),
+            project_context: project_context.to_string(),
+            retries: Some(ctx.retries.clone()),
+        };
+
+        if config.translation.bisect_failed_batches {
+            BisectingTranslator { inner: translator }
+                .translate(target_lang, batch, &config.llm.custom_prompt)
+                .await
+        } else {
+            translator
+                .translate(target_lang, batch, &config.llm.custom_prompt)
+                .await
+        }
+        .context(format!(
+            "DeepL translation failed for batch {} in language {}",
+            batch_num, target_lang
+        ))?
+    } else {
+        let mut llm = openai_translator(
+            ctx,
+            config
+                .llm
+                .model
+                .for_language(target_lang)
+                .context(format!("No model configured for language {}", target_lang))?,
+            project_context.to_string(),
+        )?;
+        llm.term_hints = term_hints(ctx.term_memory, batch, target_lang);
+        llm.suggestions = tm_suggestions(
+            ctx.translation_memory,
+            batch,
+            target_lang,
+            config.translation.translation_memory_threshold,
+        );
+        llm.on_progress = Some(Arc::new({
+            let pb = pb.clone();
+            let target_lang = target_lang.to_string();
+            let batch_len = batch.len();
+            move |received| {
+                pb.set_message(format!(
+                    "{} (batch {}/{}, {}/{} received)",
+                    target_lang, batch_num, total_batches, received, batch_len
+                ))
+            }
+        }));
 
-    let total_translated: usize = results
-        .iter()
-        .filter_map(|r| r.as_ref().ok())
-        .map(|(t, _)| *t)
-        .sum();
-    let total_failed: usize = results
+        if config.translation.bisect_failed_batches {
+            BisectingTranslator { inner: llm }
+                .translate(target_lang, batch, &config.llm.custom_prompt)
+                .await
+        } else {
+            llm.translate(target_lang, batch, &config.llm.custom_prompt)
+                .await
+        }
+        .context(format!(
+            "LLM translation failed for batch {} in language {}",
+            batch_num, target_lang
+        ))?
+    })
+}
+
+/// Warns when a full batch likely needs more output tokens than a model can
+/// write in one response, which ends in truncated JSON.
+fn warn_batch_output(config: &AppConfig) {
+    let mut models: Vec<&str> = config
+        .translation
+        .target_languages
         .iter()
-        .filter_map(|r| r.as_ref().ok())
-        .map(|(_, f)| *f)
-        .sum();
+        .filter_map(|lang| config.llm.model.for_language(lang))
+        .collect();
+    models.sort_unstable();
+    models.dedup();
 
-    let all_failed = results.iter().all(|r| r.is_err());
-    if all_failed && !results.is_empty() {
-        return Err(anyhow::anyhow!(
-            "All language translations failed. Check your LLM configuration and API connectivity."
-        ));
+    // A multi-language request answers for every language at once.
+    let languages = if config.llm.multi_language {
+        config.translation.target_languages.len().max(1)
+    } else {
+        1
+    };
+    let per_message = estimate_output_tokens(languages, config.translation.store_rationale);
+    let expected = per_message * config.translation.batch_size;
+    for model in models {
+        if let Some(limit) = output_token_limit(model)
+            && expected > limit
+        {
+            println!(
+                "   ⚠️  batch_size {} may exceed the output limit of {} ({} tokens, about {} expected); responses may be cut off, a batch_size of {} or less is safer",
+                config.translation.batch_size,
+                model,
+                limit,
+                expected,
+                (limit / per_message).max(1)
+            );
+        }
     }
+}
 
-    Ok(FileStats {
-        total_translated,
-        total_failed,
+/// A translator for the OpenAI-compatible API, without the hints and
+/// progress reporting specific to a batch.
+fn openai_translator(
+    ctx: &RunContext<'_>,
+    model: &str,
+    project_context: String,
+) -> Result<LlmTranslator<OpenAIConfig>> {
+    let config = ctx.config;
+    let mut openai = OpenAIConfig::new()
+        .with_api_base(&config.llm.api_base)
+        .with_api_key(&config.llm.api_key);
+    for (name, value) in &config.llm.headers {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .context(format!("Invalid [llm] header name {:?}", name))?;
+        openai = openai
+            .with_header(name, value.as_str())
+            .context("Invalid [llm] header value")?;
+    }
+    // Retries are done by the translator so they count against the run's
+    // budget; the client's own backoff would retry unseen.
+    let client = Client::with_config(openai).with_backoff(ExponentialBackoff {
+        max_elapsed_time: Some(Duration::ZERO),
+        ..Default::default()
+    });
+
+    Ok(LlmTranslator {
+        client,
+        model: model.to_string(),
+        system_prompt: config.llm.system_prompt.clone(),
+        all_target_langs: config.translation.target_languages.clone(),
+        project_context,
+        store_rationale: config.translation.store_rationale,
+        markdown_aware: config.translation.markdown_aware,
+        schema_dialect: config.llm.schema_dialect,
+        stop: config.llm.stop.clone(),
+        frequency_penalty: config.llm.frequency_penalty,
+        presence_penalty: config.llm.presence_penalty,
+        term_hints: Vec::new(),
+        suggestions: HashMap::new(),
+        accelerator_markers: config.translation.accelerator_markers.clone(),
+        stream: config.llm.stream,
+        on_progress: None,
+        usage: Some(ctx.usage.clone()),
+        retries: Some(ctx.retries.clone()),
     })
 }
 
-async fn translate_single_language(
-    target_lang: &str,
-    config: &AppConfig,
-    dry_run: bool,
-    force_write: bool,
-    input_path: &PathBuf,
-    pb: &ProgressBar,
-) -> Result<(usize, usize)> {
-    let output_path =
-        build_output_path(input_path, target_lang, &config.translation.output_pattern)
-            .context("Failed to build output path")?;
+fn over_budget(ctx: &RunContext<'_>) -> bool {
+    let (Some(cap), Some(pricing)) = (ctx.config.llm.max_spend_usd, ctx.config.llm.pricing())
+    else {
+        return false;
+    };
 
-    println!("         Input:  {}", input_path.display());
-    println!("         Output: {}", output_path.display());
+    if ctx.usage.projected_cost(&pricing) > cap {
+        ctx.budget_exceeded.store(true, Ordering::Relaxed);
+    }
+    ctx.budget_exceeded.load(Ordering::Relaxed)
+}
 
-    if !dry_run || force_write {
-        if let Some(parent) = output_path.parent() {
-            fs::create_dir_all(parent)
-                .context(format!("Failed to create output directory: {:?}", parent))?;
-        }
-        if !output_path.exists() {
-            File::create(&output_path)
-                .context(format!("Failed to create output file: {:?}", output_path))?;
+fn write_review(
+    target_lang: &str,
+    output_path: &Path,
+    units: &[TranslationUnit],
+    options: &WriteOptions,
+) -> Result<()> {
+    let review_path = output_path.with_extension("review.po");
+
+    if units.is_empty() {
+        if review_path.exists() {
+            fs::remove_file(&review_path).context(format!(
+                "Failed to remove stale review file {:?}",
+                review_path
+            ))?;
         }
+        return Ok(());
     }
 
-    process_single_lang(
-        target_lang,
-        config,
-        dry_run,
-        force_write,
-        input_path,
-        &output_path,
-        pb,
-    )
-    .await
+    GettextAdapter::write_review(units, target_lang, &review_path, options)
+        .map_err(|e| anyhow::anyhow!("Failed to write review file {:?}: {}", review_path, e))?;
+    println!(
+        "         📝 {} message(s) to review → {}",
+        units.len(),
+        review_path.display()
+    );
+    Ok(())
 }
 
-async fn process_single_lang(
+async fn write_translations(
+    ctx: &RunContext<'_>,
     target_lang: &str,
-    config: &AppConfig,
-    dry_run: bool,
-    force_write: bool,
-    input_path: &Path,
     output_path: &Path,
-    pb: &ProgressBar,
-) -> Result<(usize, usize)> {
-    let pot = polib::po_file::parse(input_path)
-        .context(format!("Failed to parse POT file: {:?}", input_path))?;
+    format: DocumentFormat,
+    units: Vec<TranslationUnit>,
+    stats: &mut LangStats,
+) -> Result<()> {
+    let output = ctx.file_locks.lock(output_path).await;
+    let started = Instant::now();
+    stats.written.extend(units.iter().cloned());
+
+    if let Some(backups) = ctx.backups
+        && let Some(backup_path) = backups
+            .backup_once(output_path)
+            .context(format!("Failed to back up {:?}", output_path))?
+    {
+        println!("         🗄️  Backed up to {}", backup_path.display());
+    }
 
-    println!("         POT messages: {}", pot.count());
+    let result =
+        adapter_for(format).write_translations(units, target_lang, &output, &ctx.write_options);
+    stats.timings.write += started.elapsed();
 
-    let po = if output_path.exists() {
-        match polib::po_file::parse(output_path) {
-            Ok(po) => {
-                println!("         PO messages: {}", po.count());
-                po
-            }
-            Err(e) => {
-                eprintln!(
-                    "         ⚠️  Failed to parse existing PO file, using POT as template: {}",
-                    e
-                );
-                pot.clone()
-            }
-        }
-    } else {
-        println!("         PO file doesn't exist, using POT as template");
-        pot.clone()
-    };
+    result.map_err(|e| anyhow::anyhow!("Failed to write translations to {:?}: {}", output_path, e))
+}
 
-    let messages = GettextAdapter::extract_messages(po, pot, config.project.skip_translated);
+async fn write_combined_output(
+    ctx: &RunContext<'_>,
+    input_path: &Path,
+    pattern: &str,
+    results: &[Result<LangStats>],
+) -> Result<()> {
+    let path = build_output_path(input_path, "", pattern)
+        .context("Failed to build combined output path")?;
+    let languages: Vec<(&str, &[TranslationUnit])> = results
+        .iter()
+        .filter_map(|r| r.as_ref().ok())
+        .map(|stats| (stats.lang.as_str(), stats.written.as_slice()))
+        .collect();
 
-    println!("         Messages to translate: {}", messages.len());
+    let _guard = ctx.file_locks.lock(&path).await;
+    let written = write_combined(&path, &languages)
+        .map_err(|e| anyhow::anyhow!("Failed to write combined output {:?}: {}", path, e))?;
+    println!(
+        "   🧩 Combined {} message(s) for {} language(s) → {}",
+        written,
+        languages.len(),
+        path.display()
+    );
+    Ok(())
+}
 
-    if messages.is_empty() {
-        pb.println(format!(
-            "         ℹ️  No messages to translate for {}",
-            target_lang
-        ));
-        return Ok((0, 0));
+/// The project context, followed by the template's header comments when
+/// `use_header_context` is set.
+fn project_context(config: &AppConfig, header: Option<String>) -> String {
+    match header {
+        Some(header) if config.project.use_header_context => {
+            [config.project.context.trim(), &header]
+                .into_iter()
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n\n")
+        }
+        _ => config.project.context.clone(),
     }
+}
 
-    let mut total_translated = 0;
-    let mut total_failed = 0;
-    let batches: Vec<_> = messages.chunks(config.translation.batch_size).collect();
-    let total_batches = batches.len();
+/// Translations made for several languages at once with `multi_language`,
+/// by language and `combined_key`, which the languages' batches take instead
+/// of requesting them again.
+#[derive(Default)]
+struct SharedTranslations(Mutex<HashMap<String, HashMap<String, TranslationUnit>>>);
+
+impl SharedTranslations {
+    /// Splits a batch into the shared translations of its messages and the
+    /// messages still to translate. Translations stay for other inputs with
+    /// the same message.
+    fn take(
+        &self,
+        lang: &str,
+        batch: &[TranslationUnit],
+    ) -> (Vec<TranslationUnit>, Vec<TranslationUnit>) {
+        let mut shared = self.0.lock().unwrap();
+        let Some(translations) = shared.get_mut(lang) else {
+            return (Vec::new(), batch.to_vec());
+        };
+        let mut found = Vec::new();
+        let mut rest = Vec::new();
+        for unit in batch {
+            match translations.get(&combined_key(unit)) {
+                Some(translation) => found.push(translation.clone()),
+                None => rest.push(unit.clone()),
+            }
+        }
+        (found, rest)
+    }
+}
 
-    println!(
-        "         Batches: {} (size: {})",
-        total_batches, config.translation.batch_size
-    );
+/// With `multi_language`, translates the messages that several of the
+/// input's languages still need in requests covering all those languages.
+/// Whatever fails here is left to each language's own requests.
+async fn share_translations(
+    ctx: &RunContext<'_>,
+    input: &Input,
+    langs: &[String],
+) -> Result<SharedTranslations> {
+    let config = ctx.config;
+    let input_path = &input.path;
+    let format = config
+        .translation
+        .format_of(&config.translation.inputs[input.group], input_path);
+
+    let mut pending: Vec<(TranslationUnit, Vec<String>)> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut header_context = None;
+    for lang in langs {
+        let output_path = output_path_for(&config.translation, input, lang)
+            .context("Failed to build output path")?;
+        let loaded = load_messages(
+            input_path,
+            &output_path,
+            format,
+            config.project.skip_translated,
+            config.translation.translate_comments,
+            ctx.overrides.map(|o| (o, lang.as_str())),
+        )?;
+        header_context = loaded.header_context;
+
+        for unit in loaded.units {
+            if !needs_llm(ctx, &unit, lang) {
+                continue;
+            }
+            let key = combined_key(&unit);
+            match positions.get(&key) {
+                Some(&index) => pending[index].1.push(lang.clone()),
+                None => {
+                    positions.insert(key, pending.len());
+                    pending.push((unit, vec![lang.clone()]));
+                }
+            }
+        }
+    }
+    pending.retain(|(_, langs)| langs.len() > 1);
 
-    let mut all_translated_for_preview = Vec::new();
+    let shared = SharedTranslations::default();
+    if pending.is_empty() {
+        return Ok(shared);
+    }
+    let Some(model) = config.llm.model.for_language(&langs[0]) else {
+        return Ok(shared);
+    };
+    let llm = openai_translator(ctx, model, project_context(config, header_context))?;
 
+    let batches: Vec<_> = pending.chunks(config.translation.batch_size).collect();
+    let total_batches = batches.len();
     for (batch_idx, batch) in batches.into_iter().enumerate() {
-        let batch_num = batch_idx + 1;
-
-        pb.set_message(format!(
-            "{} (batch {}/{})",
-            target_lang, batch_num, total_batches
-        ));
-
+        if over_budget(ctx) {
+            break;
+        }
         println!(
-            "         📦 Processing batch {}/{} ({} messages)",
-            batch_num,
+            "      🔗 Shared batch {}/{}: {} message(s) needed by several languages",
+            batch_idx + 1,
             total_batches,
             batch.len()
         );
+        match llm
+            .translate_languages(batch, &config.llm.custom_prompt)
+            .await
+        {
+            Ok(translated) => {
+                let mut shared = shared.0.lock().unwrap();
+                for (lang, units) in translated {
+                    let translations = shared.entry(lang).or_default();
+                    for unit in units {
+                        translations.insert(combined_key(&unit), unit);
+                    }
+                }
+            }
+            Err(e) => eprintln!(
+                "      ⚠️  Shared batch {} failed, its languages will translate it separately: {:#}",
+                batch_idx + 1,
+                e
+            ),
+        }
+    }
+    Ok(shared)
+}
 
-        let translations = if dry_run {
-            DryRunTranslator
-                .translate(target_lang, batch, &config.llm.custom_prompt)
-                .await
-                .context(format!(
-                    "Dry run translation failed for batch {}",
-                    batch_num
-                ))?
-        } else {
-            let client = Client::with_config(
-                OpenAIConfig::new()
-                    .with_api_base(&config.llm.api_base)
-                    .with_api_key(&config.llm.api_key),
-            );
-
-            let llm = LlmTranslator {
-                client,
-                model: config.llm.model.clone(),
-                system_prompt: config.llm.system_prompt.clone(),
-                project_context: config.project.context.clone(),
-            };
+/// Whether a message is left for the LLM, not to the steps that fill it
+/// without one.
+fn needs_llm(ctx: &RunContext<'_>, unit: &TranslationUnit, lang: &str) -> bool {
+    let config = ctx.config;
+    let remembered = !unit.is_plural()
+        && (ctx.translation_memory.is_some_and(|memory| {
+            memory
+                .exact(&unit.msg_id, unit.context.as_deref(), lang)
+                .is_some()
+        }) || ctx
+            .term_memory
+            .is_some_and(|memory| memory.exact(&unit.msg_id, lang).is_some()));
+    let oversized = config
+        .translation
+        .skip_above_tokens
+        .is_some_and(|max_tokens| unit.is_oversized(max_tokens));
+    let untagged = config
+        .translation
+        .require_tag
+        .as_deref()
+        .is_some_and(|tag| !unit.has_tag(tag));
+    !(remembered || oversized || untagged || unit.is_passthrough(&ctx.passthrough))
+}
 
-            llm.translate(target_lang, batch, &config.llm.custom_prompt)
-                .await
-                .context(format!(
-                    "LLM translation failed for batch {} in language {}",
-                    batch_num, target_lang
-                ))?
-        };
+/// With `merge_inputs`, translates the messages of every template input as
+/// one set per language, each message (by context and msgid) once, so that
+/// inputs sharing a string get the same translation. Each input then writes
+/// its own output from these; whatever fails here is left to its own
+/// requests.
+async fn merge_inputs(ctx: &RunContext<'_>, inputs: &[Input]) -> Result<SharedTranslations> {
+    let config = ctx.config;
+    let templates: Vec<&Input> = inputs
+        .iter()
+        .filter(|input| InputKind::of(&input.path) == InputKind::Template)
+        .collect();
+    let shared = SharedTranslations::default();
+    if templates.len() < 2 {
+        return Ok(shared);
+    }
 
-        total_translated += translations.translated.len();
-        total_failed += translations.failed_translated.len();
+    for lang in &config.translation.target_languages {
+        let mut pending: Vec<TranslationUnit> = Vec::new();
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut repeated = 0;
+        let mut header_context = None;
+        for input in &templates {
+            let format = config
+                .translation
+                .format_of(&config.translation.inputs[input.group], &input.path);
+            let output_path = output_path_for(&config.translation, input, lang)
+                .context("Failed to build output path")?;
+            let loaded = load_messages(
+                &input.path,
+                &output_path,
+                format,
+                config.project.skip_translated,
+                config.translation.translate_comments,
+                ctx.overrides.map(|o| (o, lang.as_str())),
+            )?;
+            header_context = header_context.or(loaded.header_context);
+
+            for unit in loaded.units {
+                if !needs_llm(ctx, &unit, lang) {
+                    continue;
+                }
+                if seen.insert(combined_key(&unit)) {
+                    pending.push(unit);
+                } else {
+                    repeated += 1;
+                }
+            }
+        }
+        if pending.is_empty() {
+            continue;
+        }
 
         println!(
-            "         ✓ Batch {}: {} translated, {} failed",
-            batch_num,
-            translations.translated.len(),
-            translations.failed_translated.len()
+            "      🧩 Merged {} inputs for {}: {} message(s), {} more shared by several inputs",
+            templates.len(),
+            lang,
+            pending.len(),
+            repeated
         );
-
-        if !translations.translated.is_empty() {
-            for entry in &translations.translated {
-                pb.println(format!("      ✓ {}", entry));
+        let project_context = project_context(config, header_context);
+        let pb = ProgressBar::hidden();
+        let batches: Vec<_> = pending.chunks(config.translation.batch_size).collect();
+        let total_batches = batches.len();
+        for (batch_idx, batch) in batches.into_iter().enumerate() {
+            if over_budget(ctx) {
+                break;
+            }
+            match translate_batch(
+                ctx,
+                lang,
+                batch,
+                &project_context,
+                &pb,
+                batch_idx + 1,
+                total_batches,
+            )
+            .await
+            {
+                Ok(translated) => {
+                    let mut shared = shared.0.lock().unwrap();
+                    let translations = shared.entry(lang.clone()).or_default();
+                    for unit in translated.translated {
+                        translations.insert(combined_key(&unit), unit);
+                    }
+                }
+                Err(e) => eprintln!(
+                    "      ⚠️  Merged batch {} failed in {}, its inputs will translate it separately: {:#}",
+                    batch_idx + 1,
+                    lang,
+                    e
+                ),
             }
         }
+    }
+    Ok(shared)
+}
 
-        if !translations.failed_translated.is_empty() {
-            for entry in &translations.failed_translated {
-                pb.println(format!("      ✗ {}", entry));
-            }
+/// Records why the units leave the pipeline, when explaining; the first
+/// reason given for a message stands.
+fn explain(
+    reasons: &mut Option<HashMap<String, &'static str>>,
+    units: &[TranslationUnit],
+    reason: &'static str,
+) {
+    if let Some(reasons) = reasons {
+        for unit in units {
+            reasons.entry(combined_key(unit)).or_insert(reason);
         }
+    }
+}
 
-        if dry_run {
-            all_translated_for_preview.extend(translations.translated.clone());
+/// Picks a random subset of the messages, keeping their order. The same
+/// seed and messages always give the same subset.
+fn sample_messages(
+    messages: Vec<TranslationUnit>,
+    size: SampleSize,
+    seed: u64,
+) -> Vec<TranslationUnit> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let picked: HashSet<usize> =
+        rand::seq::index::sample(&mut rng, messages.len(), size.of(messages.len()))
+            .into_iter()
+            .collect();
+    messages
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| picked.contains(i))
+        .map(|(_, unit)| unit)
+        .collect()
+}
+
+fn term_hints(
+    memory: Option<&TermMemory>,
+    batch: &[TranslationUnit],
+    target_lang: &str,
+) -> Vec<(String, String)> {
+    let Some(memory) = memory else {
+        return Vec::new();
+    };
+    let sources: Vec<&str> = batch.iter().map(|unit| unit.msg_id.as_str()).collect();
+
+    memory
+        .hints(&sources, target_lang)
+        .into_iter()
+        .map(|(term, translation)| (term.to_string(), translation.to_string()))
+        .collect()
+}
+
+fn tm_suggestions(
+    memory: Option<&TranslationMemory>,
+    batch: &[TranslationUnit],
+    target_lang: &str,
+    threshold: f64,
+) -> HashMap<String, Vec<(String, String)>> {
+    let Some(memory) = memory else {
+        return HashMap::new();
+    };
+
+    batch
+        .iter()
+        .filter_map(|unit| {
+            let suggestions: Vec<(String, String)> = memory
+                .suggestions(&unit.msg_id, target_lang, threshold)
+                .into_iter()
+                .map(|(source, translation)| (source.to_string(), translation.to_string()))
+                .collect();
+            (!suggestions.is_empty()).then(|| (unit.msg_id.clone(), suggestions))
+        })
+        .collect()
+}
+
+struct LoadedMessages {
+    units: Vec<TranslationUnit>,
+    overridden: Vec<TranslationUnit>,
+    locked: usize,
+    header_context: Option<String>,
+    timings: PhaseTimings,
+}
+
+fn load_messages(
+    input_path: &Path,
+    output_path: &Path,
+    format: DocumentFormat,
+    skip_translated: bool,
+    translate_comments: bool,
+    overrides: Option<(&Overrides, &str)>,
+) -> Result<LoadedMessages> {
+    let started = Instant::now();
+    let adapter = adapter_for(format);
+    let template = adapter
+        .load_template(input_path)
+        .map_err(|e| anyhow::anyhow!(e))?;
+    let existing = adapter
+        .load_existing(output_path, &template)
+        .map_err(|e| anyhow::anyhow!(e))?;
+    let locked = adapter.locked_count(&existing);
+    let header_context = adapter.header_context(&template);
+    let extract = |skip| {
+        let mut units = adapter.extract_units(&existing, &template, skip);
+        if translate_comments {
+            units.extend(adapter.extract_comments(&existing, &template, skip));
         }
+        units
+    };
+    if locked > 0 {
+        println!("         🔒 Locked entries: {}", locked);
+    }
+    let mut timings = PhaseTimings {
+        parse: started.elapsed(),
+        ..Default::default()
+    };
+    let started = Instant::now();
+
+    let Some((overrides, lang)) = overrides else {
+        let units = extract(skip_translated);
+        timings.extract = started.elapsed();
+        return Ok(LoadedMessages {
+            units,
+            overridden: Vec::new(),
+            locked,
+            header_context,
+            timings,
+        });
+    };
 
-        if !dry_run || force_write {
-            if !translations.translated.is_empty() {
-                GettextAdapter::apply_translations(
-                    translations.translated.clone(),
-                    target_lang,
-                    output_path,
-                )
+    // Overrides win over existing translations, so match them against every
+    // unlocked entry rather than only the ones left to translate.
+    let nplurals = plural_forms_or_default(lang).nplurals;
+    let overridden: Vec<TranslationUnit> = extract(false)
+        .iter()
+        .filter_map(|unit| overrides.apply(lang, unit, nplurals))
+        .collect();
+    let units = extract(skip_translated)
+        .into_iter()
+        .filter(|unit| overrides.get(lang, unit).is_none())
+        .collect();
+    timings.extract = started.elapsed();
+
+    Ok(LoadedMessages {
+        units,
+        overridden,
+        locked,
+        header_context,
+        timings,
+    })
+}
+
+fn warn_unmatched_overrides(
+    overrides: &Overrides,
+    inputs: &[Input],
+    translation: &TranslationConfig,
+) {
+    let mut known = HashSet::new();
+    for input in inputs {
+        let path = &input.path;
+        let adapter = adapter_for(translation.format_of(&translation.inputs[input.group], path));
+        let units = adapter
+            .load_template(path)
+            .map(|template| adapter.extract_units(&template, &template, false));
+        match units {
+            Ok(units) => known.extend(units.into_iter().map(|unit| (unit.context, unit.msg_id))),
+            Err(e) => eprintln!("⚠️  Failed to read {:?} for override check: {}", path, e),
+        }
+    }
+
+    for (lang, context, msgid) in overrides.keys() {
+        if !known.contains(&(context.map(String::from), msgid.to_string())) {
+            match context {
+                Some(context) => println!(
+                    "⚠️  Override for {} matches no message: [{}] {:?}",
+                    lang, context, msgid
+                ),
+                None => println!("⚠️  Override for {} matches no message: {:?}", lang, msgid),
+            }
+        }
+    }
+}
+
+fn export_untranslated(
+    config: &AppConfig,
+    inputs: &[Input],
+    base_dir: &Path,
+    export_dir: &Path,
+    write_options: &WriteOptions,
+) -> Result<usize> {
+    let mut total = 0;
+
+    for input in inputs {
+        let input_path = &input.path;
+        let relative = input_path
+            .strip_prefix(base_dir)
+            .unwrap_or_else(|_| Path::new(input_path.file_name().unwrap_or_default()))
+            .with_extension("po");
+
+        for lang in &config.translation.target_languages {
+            let output_path = output_path_for(&config.translation, input, lang)
+                .context("Failed to build output path")?;
+
+            println!("\n🔄 {} [{}]", input_path.display(), lang);
+            let format = config
+                .translation
+                .format_of(&config.translation.inputs[input.group], input_path);
+            let messages =
+                load_messages(input_path, &output_path, format, true, false, None)?.units;
+
+            let export_path = export_dir.join(lang).join(&relative);
+            if let Some(parent) = export_path.parent() {
+                fs::create_dir_all(parent)
+                    .context(format!("Failed to create export directory: {:?}", parent))?;
+            }
+
+            GettextAdapter::write_untranslated(&messages, lang, &export_path, write_options)
                 .map_err(|e| {
-                    anyhow::anyhow!("Failed to write translations to {:?}: {}", output_path, e)
+                    anyhow::anyhow!(
+                        "Failed to write untranslated messages to {:?}: {}",
+                        export_path,
+                        e
+                    )
                 })?;
 
-                println!(
-                    "         💾 Saved {} translations to file",
-                    translations.translated.len()
-                );
-            }
+            println!(
+                "         📝 {} untranslated message(s) → {}",
+                messages.len(),
+                export_path.display()
+            );
+            total += messages.len();
         }
     }
 
-    if dry_run && !all_translated_for_preview.is_empty() {
-        pb.println(format!("\n      ╭─ Dry Run Preview ({}) ─╮", target_lang));
-        for (i, entry) in all_translated_for_preview.iter().take(3).enumerate() {
-            pb.println(format!("      │ {:02}. {}", i + 1, entry));
+    Ok(total)
+}
+
+type OutputCollisions = BTreeMap<PathBuf, Vec<(PathBuf, String)>>;
+
+fn find_output_collisions(
+    inputs: &[Input],
+    translation: &TranslationConfig,
+) -> Result<OutputCollisions> {
+    let mut outputs: OutputCollisions = BTreeMap::new();
+
+    for input in inputs {
+        let path = &input.path;
+        if InputKind::of(path) == InputKind::Catalog {
+            outputs
+                .entry(path.clone())
+                .or_default()
+                .push((path.clone(), "in place".to_string()));
+            continue;
         }
-        if all_translated_for_preview.len() > 3 {
-            pb.println(format!(
-                "      │ ... and {} more",
-                all_translated_for_preview.len() - 3
-            ));
+        for lang in &translation.target_languages {
+            let output = output_path_for(translation, input, lang)
+                .context(format!("Failed to build output path for {:?}", path))?;
+            outputs
+                .entry(output)
+                .or_default()
+                .push((path.clone(), lang.clone()));
         }
-        pb.println("      ╰────────────────────────────╯\n".to_string());
     }
 
-    Ok((total_translated, total_failed))
+    outputs.retain(|_, sources| sources.len() > 1);
+    Ok(outputs)
+}
+
+fn catalog_language(path: &Path) -> Result<String> {
+    let catalog = read_catalog(path)
+        .map_err(|e| anyhow::anyhow!("Failed to parse PO file {:?}: {}", path, e))?;
+    let lang = catalog.metadata.language.trim();
+    if lang.is_empty() {
+        return Err(anyhow::anyhow!(
+            "{:?} has no Language header, so it cannot be translated in place",
+            path
+        ));
+    }
+    Ok(lang.to_string())
+}
+
+/// The path the format dictates, such as an Apple `{lang}.lproj` directory,
+/// or else `output_pattern`.
+fn output_path_for(
+    translation: &TranslationConfig,
+    input: &Input,
+    target_lang: &str,
+) -> Result<PathBuf> {
+    let group = &translation.inputs[input.group];
+    let adapter = adapter_for(translation.format_of(group, &input.path));
+    match adapter
+        .output_path(&input.path, target_lang)
+        .map_err(|e| anyhow::anyhow!(e))?
+    {
+        Some(path) => Ok(path),
+        None => build_output_path(&input.path, target_lang, group.output_pattern()),
+    }
 }
 
 fn build_output_path(input_path: &Path, target_lang: &str, pattern: &str) -> Result<PathBuf> {
@@ -530,9 +3644,101 @@ fn build_output_path(input_path: &Path, target_lang: &str, pattern: &str) -> Res
         .and_then(|s| s.to_str())
         .context("Invalid filename")?;
 
-    Ok(input_path.parent().unwrap().join(
-        pattern
-            .replace("{lang}", target_lang)
-            .replace("{name}", stem),
-    ))
+    let substituted = pattern
+        .replace("{lang}", target_lang)
+        .replace("{android_lang}", &android_qualifier(target_lang))
+        .replace("{name}", stem);
+
+    let has_drive_prefix = substituted.len() >= 2
+        && substituted.as_bytes()[0].is_ascii_alphabetic()
+        && substituted.as_bytes()[1] == b':';
+    if substituted.starts_with('/') || substituted.starts_with('\\') || has_drive_prefix {
+        return Err(anyhow::anyhow!(
+            "output_pattern must be relative to the input file, got absolute path '{}' (from pattern '{}')",
+            substituted,
+            pattern
+        ));
+    }
+
+    let mut output_path = input_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let mut has_file_name = false;
+
+    for component in substituted.split(['/', '\\']) {
+        match component {
+            "" | "." => continue,
+            ".." => {
+                return Err(anyhow::anyhow!(
+                    "output_pattern must not contain '..' segments, got '{}' (from pattern '{}')",
+                    substituted,
+                    pattern
+                ));
+            }
+            _ => {
+                output_path.push(component);
+                has_file_name = true;
+            }
+        }
+    }
+
+    if !has_file_name || substituted.ends_with(['/', '\\']) {
+        return Err(anyhow::anyhow!(
+            "output_pattern must end with a file name, got '{}'",
+            pattern
+        ));
+    }
+
+    Ok(output_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_patterns_split_on_either_separator() {
+        let input = Path::new("po").join("app.pot");
+        let expected: PathBuf = ["po", "locale", "pt-BR", "LC_MESSAGES", "app.po"]
+            .iter()
+            .collect();
+        for pattern in [
+            "locale/{lang}/LC_MESSAGES/{name}.po",
+            r"locale\{lang}\LC_MESSAGES\{name}.po",
+            r"./locale/{lang}\LC_MESSAGES//{name}.po",
+        ] {
+            assert_eq!(
+                build_output_path(&input, "pt-BR", pattern).unwrap(),
+                expected,
+                "{}",
+                pattern
+            );
+        }
+        assert_eq!(
+            build_output_path(Path::new("app.pot"), "fr", "{lang}.po").unwrap(),
+            Path::new("fr.po")
+        );
+    }
+
+    #[test]
+    fn output_patterns_stay_below_the_input() {
+        let input = Path::new("po/app.pot");
+        for pattern in [
+            "/tmp/{lang}.po",
+            r"\\server\share\{lang}.po",
+            r"C:\locale\{lang}.po",
+            "c:{lang}.po",
+            "../{lang}.po",
+            r"locale\..\..\{lang}.po",
+            "locale/{lang}/",
+            ".",
+        ] {
+            assert!(
+                build_output_path(input, "fr", pattern).is_err(),
+                "{}",
+                pattern
+            );
+        }
+    }
 }
@@ -0,0 +1,129 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+const MAX_HINTS: usize = 8;
+const NEAR_MATCH_THRESHOLD: f64 = 0.6;
+
+pub struct TermMemory {
+    languages: Vec<String>,
+    entries: HashMap<String, HashMap<String, String>>,
+}
+
+impl TermMemory {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_path(path)
+            .map_err(|e| format!("Failed to open term memory {:?}: {}", path, e))?;
+
+        let headers: Vec<String> = reader
+            .headers()
+            .map_err(|e| format!("Failed to read term memory header: {}", e))?
+            .iter()
+            .map(|h| h.trim().to_string())
+            .collect();
+        let source_column = headers
+            .iter()
+            .position(|h| h.eq_ignore_ascii_case("source") || h.eq_ignore_ascii_case("msgid"))
+            .unwrap_or(0);
+
+        let mut entries: HashMap<String, HashMap<String, String>> = HashMap::new();
+        for (line, record) in reader.records().enumerate() {
+            let record = record
+                .map_err(|e| format!("Failed to read term memory row {}: {}", line + 2, e))?;
+            let Some(source) = record.get(source_column).filter(|s| !s.is_empty()) else {
+                continue;
+            };
+
+            let translations = entries.entry(source.to_string()).or_default();
+            for (column, value) in record.iter().enumerate() {
+                if column != source_column && !value.is_empty() {
+                    translations.insert(headers[column].to_lowercase(), value.to_string());
+                }
+            }
+        }
+
+        let languages = headers
+            .into_iter()
+            .enumerate()
+            .filter(|(column, _)| *column != source_column)
+            .map(|(_, h)| h)
+            .collect();
+
+        Ok(Self { languages, entries })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn languages(&self) -> &[String] {
+        &self.languages
+    }
+
+    pub fn exact(&self, source: &str, lang: &str) -> Option<&str> {
+        self.entries
+            .get(source)
+            .and_then(|t| t.get(&lang.to_lowercase()))
+            .map(String::as_str)
+    }
+
+    pub fn hints<'a>(&'a self, sources: &[&str], lang: &str) -> Vec<(&'a str, &'a str)> {
+        let lang = lang.to_lowercase();
+        let sources: Vec<HashSet<String>> = sources.iter().map(|s| words(s)).collect();
+
+        let mut hints: Vec<(f64, &str, &str)> = self
+            .entries
+            .iter()
+            .filter_map(|(term, translations)| {
+                let translation = translations.get(&lang)?;
+                let term_words = words(term);
+
+                let score = sources
+                    .iter()
+                    .map(|source_words| {
+                        if !term_words.is_empty() && term_words.is_subset(source_words) {
+                            1.0
+                        } else {
+                            similarity(&term_words, source_words)
+                        }
+                    })
+                    .fold(0.0, f64::max);
+
+                (score >= NEAR_MATCH_THRESHOLD).then_some((
+                    score,
+                    term.as_str(),
+                    translation.as_str(),
+                ))
+            })
+            .collect();
+
+        hints.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+        hints
+            .into_iter()
+            .take(MAX_HINTS)
+            .map(|(_, term, translation)| (term, translation))
+            .collect()
+    }
+}
+
+pub(crate) fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+pub(crate) fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let shared = a.intersection(b).count() as f64;
+    shared / a.union(b).count() as f64
+}
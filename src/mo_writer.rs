@@ -0,0 +1,192 @@
+use std::io::{self, Write};
+
+use polib::catalog::Catalog;
+
+use crate::charsets::set_charset;
+
+const MAGIC: u32 = 0x950412de;
+/// Size of the fixed header: magic, revision, count and four table fields.
+const HEADER_SIZE: usize = 28;
+
+/// An original string (context, msgid and plural msgid) and its translation.
+type Entry = (Vec<u8>, Vec<u8>);
+
+/// Writes the catalog as a GNU MO file, the way `msgfmt` compiles it:
+/// the header entry first, then every translated, non-fuzzy message, sorted
+/// by msgid so the runtime can binary-search it. Strings are UTF-8, and the
+/// header says so whatever the PO file's charset.
+pub fn write_mo<W: Write>(catalog: &Catalog, writer: &mut W) -> io::Result<()> {
+    let mut metadata = catalog.metadata.clone();
+    set_charset(&mut metadata, "UTF-8");
+
+    let mut entries: Vec<Entry> = vec![(Vec::new(), metadata.export_for_mo().into_bytes())];
+    for message in catalog.messages() {
+        if message.msgid().is_empty() || message.is_fuzzy() || !message.is_translated() {
+            continue;
+        }
+
+        let mut original = Vec::new();
+        if let Some(context) = message.msgctxt() {
+            original.extend_from_slice(context.as_bytes());
+            original.push(4);
+        }
+        original.extend_from_slice(message.msgid().as_bytes());
+        let translation = if message.is_plural() {
+            original.push(0);
+            original.extend_from_slice(message.msgid_plural().unwrap_or_default().as_bytes());
+            message
+                .msgstr_plural()
+                .map(|forms| forms.join("\0"))
+                .unwrap_or_default()
+        } else {
+            message.msgstr().unwrap_or_default().to_string()
+        };
+        entries.push((original, translation.into_bytes()));
+    }
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let count = entries.len();
+    let originals_offset = HEADER_SIZE;
+    let translations_offset = originals_offset + 8 * count;
+    let strings_offset = translations_offset + 8 * count;
+
+    let mut header = Vec::with_capacity(strings_offset);
+    for value in [
+        MAGIC,
+        0,
+        count as u32,
+        originals_offset as u32,
+        translations_offset as u32,
+        // No hash table; lookups fall back to the sorted originals.
+        0,
+        strings_offset as u32,
+    ] {
+        header.extend_from_slice(&value.to_le_bytes());
+    }
+
+    let mut strings = Vec::new();
+    let mut descriptors = |pick: fn(&Entry) -> &Vec<u8>| {
+        let mut table = Vec::with_capacity(8 * count);
+        for entry in &entries {
+            let bytes = pick(entry);
+            table.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+            table.extend_from_slice(&((strings_offset + strings.len()) as u32).to_le_bytes());
+            strings.extend_from_slice(bytes);
+            strings.push(0);
+        }
+        table
+    };
+    let originals = descriptors(|(original, _)| original);
+    let translations = descriptors(|(_, translation)| translation);
+
+    writer.write_all(&header)?;
+    writer.write_all(&originals)?;
+    writer.write_all(&translations)?;
+    writer.write_all(&strings)?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use polib::po_file;
+
+    use super::*;
+
+    const PO: &str = r#"msgid ""
+msgstr ""
+"Content-Type: text/plain; charset=ISO-8859-1\n"
+"Plural-Forms: nplurals=2; plural=(n > 1);\n"
+
+msgid "Save"
+msgstr "Enregistrer"
+
+msgctxt "menu"
+msgid "Open"
+msgstr "Ouvrir"
+
+msgid "One file"
+msgid_plural "%d files"
+msgstr[0] "Un fichier"
+msgstr[1] "%d fichiers"
+
+#, fuzzy
+msgid "Close"
+msgstr "Fermer"
+
+msgid "Quit"
+msgstr ""
+"#;
+
+    fn compiled() -> Vec<u8> {
+        let catalog = po_file::parse_from_reader(PO.as_bytes()).unwrap();
+        let mut bytes = Vec::new();
+        write_mo(&catalog, &mut bytes).unwrap();
+        bytes
+    }
+
+    /// The `(original, translation)` pairs of a little-endian MO file.
+    fn entries(bytes: &[u8]) -> Vec<(String, String)> {
+        let word = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap()) as usize;
+        assert_eq!(word(0), MAGIC as usize);
+        let string = |table: usize, i: usize| {
+            let (length, offset) = (word(table + 8 * i), word(table + 8 * i + 4));
+            assert_eq!(bytes[offset + length], 0);
+            String::from_utf8(bytes[offset..offset + length].to_vec()).unwrap()
+        };
+        (0..word(8))
+            .map(|i| (string(word(12), i), string(word(16), i)))
+            .collect()
+    }
+
+    #[test]
+    fn translated_messages_are_compiled_sorted() {
+        let entries = entries(&compiled());
+        let originals: Vec<&str> = entries.iter().map(|(o, _)| o.as_str()).collect();
+        assert_eq!(
+            originals,
+            ["", "One file\0%d files", "Save", "menu\u{4}Open"]
+        );
+        assert!(entries[0].1.contains("charset=UTF-8"));
+        assert_eq!(entries[1].1, "Un fichier\0%d fichiers");
+        assert_eq!(entries[3].1, "Ouvrir");
+    }
+
+    #[test]
+    fn msgunfmt_reads_what_msgfmt_writes() {
+        let dir = env::temp_dir().join(format!("po-llm-mo-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (po, ours, theirs) = (
+            dir.join("fr.po"),
+            dir.join("ours.mo"),
+            dir.join("theirs.mo"),
+        );
+        fs::write(&po, PO).unwrap();
+        fs::write(&ours, compiled()).unwrap();
+
+        let run = |program: &str, args: &[&std::path::Path]| {
+            process::Command::new(program)
+                .args(args)
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .map(|output| output.stdout)
+        };
+        let compiled_by_msgfmt =
+            run("msgfmt", &[std::path::Path::new("-o"), &theirs, &po]).is_some();
+        let decoded = compiled_by_msgfmt
+            .then(|| Some((run("msgunfmt", &[&ours])?, run("msgunfmt", &[&theirs])?)))
+            .flatten();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let Some((ours, theirs)) = decoded else {
+            eprintln!("msgfmt or msgunfmt not found, checking the MO layout only");
+            return;
+        };
+        let theirs = String::from_utf8(theirs)
+            .unwrap()
+            .replace("charset=ISO-8859-1", "charset=UTF-8");
+        assert_eq!(String::from_utf8(ours).unwrap(), theirs);
+    }
+}
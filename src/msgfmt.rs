@@ -0,0 +1,502 @@
+use std::{collections::HashMap, fs, path::Path, sync::LazyLock};
+
+use regex::Regex;
+
+use crate::{
+    charsets::{decode, resolve_charset},
+    translations::{PreviousSources, PreviousTranslation},
+    validations::check_format_flags,
+};
+
+/// The format flags `msgfmt --check-format` checks, all others being left
+/// alone.
+const CHECKED_FORMATS: &[&str] = &["c-format", "python-format", "python-brace-format"];
+
+/// A message as written in the file, before any parser merges or drops it.
+#[derive(Default)]
+struct RawEntry {
+    line: usize,
+    flags: Vec<String>,
+    msgctxt: Option<String>,
+    msgid: Option<String>,
+    msgid_plural: Option<String>,
+    msgstr: Option<String>,
+    /// `msgstr[N]` forms by index, in the order written.
+    msgstr_plural: Vec<(usize, String)>,
+    /// The `#| msgid` and `#| msgid_plural` msgmerge keeps on a fuzzy
+    /// message whose source changed.
+    previous_msgid: Option<String>,
+    previous_msgid_plural: Option<String>,
+}
+
+impl RawEntry {
+    fn is_fuzzy(&self) -> bool {
+        self.flags.iter().any(|flag| flag == "fuzzy")
+    }
+
+    fn forms(&self) -> Vec<&str> {
+        match &self.msgstr {
+            Some(msgstr) => vec![msgstr.as_str()],
+            None => self
+                .msgstr_plural
+                .iter()
+                .map(|(_, form)| form.as_str())
+                .collect(),
+        }
+    }
+
+    fn is_translated(&self) -> bool {
+        let forms = self.forms();
+        !forms.is_empty() && forms.iter().all(|form| !form.is_empty())
+    }
+
+    fn label(&self) -> String {
+        let msgid = self.msgid.as_deref().unwrap_or_default();
+        match &self.msgctxt {
+            Some(ctxt) => format!("line {}: {:?} (context {:?})", self.line, msgid, ctxt),
+            None => format!("line {}: {:?}", self.line, msgid),
+        }
+    }
+}
+
+/// Which string the continuation lines of an entry belong to.
+#[derive(Clone, Copy)]
+enum Field {
+    Msgctxt,
+    Msgid,
+    MsgidPlural,
+    Msgstr,
+    MsgstrPlural,
+    PreviousMsgid,
+    PreviousMsgidPlural,
+}
+
+static MSGSTR_INDEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^msgstr\[(\d+)\]\s*(.*)$").unwrap());
+static NPLURALS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"nplurals\s*=\s*(\d+)\s*;\s*plural\s*=").unwrap());
+
+/// Checks a written PO file the way `msgfmt --check-format --check-header`
+/// would, for the problems that matter most: a missing, fuzzy or
+/// charset-less header, a `Plural-Forms` that plural messages cannot use,
+/// duplicate messages, plural forms other than declared, leading and
+/// trailing newlines that differ from the source, and format strings of
+/// flagged messages that do not match. Obsolete messages are left out,
+/// and the content of fuzzy and untranslated ones is not checked, as
+/// msgfmt leaves them out of the MO file.
+pub fn check_po_file(path: &Path) -> Result<Vec<String>, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    let (text, warning) = decode(&bytes);
+    let mut problems: Vec<String> = warning.into_iter().collect();
+    let entries = parse_entries(&text).map_err(|e| format!("{:?}: {}", path, e))?;
+    problems.extend(check_entries(&entries));
+    Ok(problems)
+}
+
+/// The previous source and translation of the fuzzy messages of the PO
+/// file at `path` that carry `#| msgid` comments, by `(context, msgid)`.
+pub fn previous_sources(path: &Path) -> Result<PreviousSources, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    let (text, _) = decode(&bytes);
+    let entries = parse_entries(&text).map_err(|e| format!("{:?}: {}", path, e))?;
+    Ok(entries
+        .into_iter()
+        .filter(|entry| entry.is_fuzzy() && entry.is_translated())
+        .filter_map(|entry| {
+            let previous = PreviousTranslation {
+                msg_id: entry.previous_msgid.clone()?,
+                msg_id_plural: entry.previous_msgid_plural.clone(),
+                translation: entry.forms().into_iter().map(String::from).collect(),
+            };
+            Some(((entry.msgctxt, entry.msgid.unwrap_or_default()), previous))
+        })
+        .collect())
+}
+
+/// The `(context, msgid)` of every message of the PO file at `path` as
+/// written, duplicates included, leaving out the header and obsolete
+/// messages.
+pub fn message_keys(path: &Path) -> Result<Vec<(Option<String>, String)>, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    let (text, _) = decode(&bytes);
+    let entries = parse_entries(&text).map_err(|e| format!("{:?}: {}", path, e))?;
+    Ok(entries
+        .into_iter()
+        .map(|entry| (entry.msgctxt, entry.msgid.unwrap_or_default()))
+        .filter(|(ctxt, msgid)| ctxt.is_some() || !msgid.is_empty())
+        .collect())
+}
+
+fn check_entries(entries: &[RawEntry]) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    let (header, messages) = match entries.first() {
+        Some(first) if first.msgid.as_deref() == Some("") && first.msgctxt.is_none() => {
+            (Some(first), &entries[1..])
+        }
+        _ => {
+            problems.push("no header entry".to_string());
+            (None, entries)
+        }
+    };
+    let fields: HashMap<String, String> = header
+        .and_then(|header| header.msgstr.as_deref())
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+    if let Some(header) = header {
+        if header.is_fuzzy() {
+            problems.push(format!(
+                "line {}: the header entry is marked fuzzy, so msgfmt ignores it",
+                header.line
+            ));
+        }
+        let charset = fields
+            .get("content-type")
+            .and_then(|content_type| content_type.split_once("charset="))
+            .map(|(_, charset)| charset.trim());
+        match charset {
+            None => problems.push("header has no charset in Content-Type".to_string()),
+            Some(charset) if charset.eq_ignore_ascii_case("CHARSET") => {
+                problems.push("header charset is still the template's CHARSET".to_string())
+            }
+            Some(charset) => {
+                if let Err(e) = resolve_charset(charset) {
+                    problems.push(format!("header {}", e));
+                }
+            }
+        }
+    }
+
+    let plural_forms = fields.get("plural-forms");
+    let nplurals = plural_forms
+        .and_then(|forms| NPLURALS.captures(forms))
+        .and_then(|caps| caps[1].parse::<usize>().ok());
+    match plural_forms {
+        Some(forms) if nplurals.is_none() => problems.push(format!(
+            "header Plural-Forms {:?} lacks nplurals=N; plural=EXPRESSION;",
+            forms
+        )),
+        None if messages.iter().any(|m| m.msgid_plural.is_some()) => {
+            problems.push("plural messages need a Plural-Forms header field".to_string())
+        }
+        _ => {}
+    }
+
+    let mut seen: HashMap<(Option<&str>, &str), usize> = HashMap::new();
+    for message in messages {
+        let label = message.label();
+        let msgid = message.msgid.as_deref().unwrap_or_default();
+        if let Some(first) = seen.insert((message.msgctxt.as_deref(), msgid), message.line) {
+            problems.push(format!(
+                "{}: duplicate message definition, first defined at line {}",
+                label, first
+            ));
+        }
+
+        match (&message.msgid_plural, &message.msgstr) {
+            (Some(_), Some(_)) => {
+                problems.push(format!("{}: plural message with a plain msgstr", label));
+                continue;
+            }
+            (None, None) if !message.msgstr_plural.is_empty() => {
+                problems.push(format!("{}: msgstr[N] without msgid_plural", label));
+                continue;
+            }
+            (Some(_), None) => {
+                let indices: Vec<usize> = message.msgstr_plural.iter().map(|(i, _)| *i).collect();
+                if indices.iter().enumerate().any(|(i, index)| i != *index) {
+                    problems.push(format!(
+                        "{}: plural forms are not numbered 0, 1, 2, ...",
+                        label
+                    ));
+                } else if let Some(nplurals) = nplurals
+                    && message.forms().iter().any(|form| !form.is_empty())
+                    && indices.len() != nplurals
+                {
+                    problems.push(format!(
+                        "{}: {} plural form(s), Plural-Forms declares {}",
+                        label,
+                        indices.len(),
+                        nplurals
+                    ));
+                }
+            }
+            _ => {}
+        }
+
+        if message.is_fuzzy() || !message.is_translated() {
+            continue;
+        }
+        let plural_source = message.msgid_plural.as_deref().unwrap_or(msgid);
+        let formats: Vec<String> = message
+            .flags
+            .iter()
+            .filter(|flag| CHECKED_FORMATS.contains(&flag.as_str()))
+            .cloned()
+            .collect();
+        for (i, form) in message.forms().into_iter().enumerate() {
+            let source = if i == 0 { msgid } else { plural_source };
+            let field = match message.msgstr {
+                Some(_) => "msgstr".to_string(),
+                None => format!("msgstr[{}]", i),
+            };
+            if source.starts_with('\n') != form.starts_with('\n') {
+                problems.push(format!(
+                    "{}: msgid and {} do not both begin with \\n",
+                    label, field
+                ));
+            }
+            if source.ends_with('\n') != form.ends_with('\n') {
+                problems.push(format!(
+                    "{}: msgid and {} do not both end with \\n",
+                    label, field
+                ));
+            }
+            if !formats.is_empty()
+                && let Err(reason) = check_format_flags(&formats, source, form)
+            {
+                problems.push(format!("{}: {}", label, reason));
+            }
+        }
+    }
+
+    problems
+}
+
+/// Splits the file into its entries, obsolete ones left out.
+fn parse_entries(text: &str) -> Result<Vec<RawEntry>, String> {
+    let mut entries = Vec::new();
+    let mut entry = RawEntry::default();
+    let mut field: Option<Field> = None;
+    let mut obsolete = false;
+
+    let finish = |entry: &mut RawEntry, entries: &mut Vec<RawEntry>, obsolete: bool| {
+        let done = std::mem::take(entry);
+        if done.msgid.is_some() && !obsolete {
+            entries.push(done);
+        }
+    };
+
+    for (i, line) in text.lines().enumerate() {
+        let number = i + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            finish(&mut entry, &mut entries, obsolete);
+            field = None;
+            obsolete = false;
+            continue;
+        }
+
+        let (line, is_obsolete) = match line.strip_prefix("#~") {
+            Some(rest) if !rest.starts_with('|') => (rest.trim_start(), true),
+            _ => (line, false),
+        };
+        if line.starts_with('#') {
+            // A comment after a message starts the next one.
+            if entry.msgstr.is_some() || !entry.msgstr_plural.is_empty() {
+                finish(&mut entry, &mut entries, obsolete);
+                field = None;
+                obsolete = false;
+            }
+            if let Some(flags) = line.strip_prefix("#,") {
+                entry
+                    .flags
+                    .extend(flags.split(',').map(|flag| flag.trim().to_string()));
+            } else if let Some(previous) = line.strip_prefix("#|") {
+                read_previous(&mut entry, &mut field, previous.trim());
+            }
+            continue;
+        }
+        if line.starts_with('"') {
+            let value = unquote(line).map_err(|e| format!("line {}: {}", number, e))?;
+            let target = match field {
+                Some(Field::Msgctxt) => entry.msgctxt.as_mut(),
+                Some(Field::Msgid) => entry.msgid.as_mut(),
+                Some(Field::MsgidPlural) => entry.msgid_plural.as_mut(),
+                Some(Field::Msgstr) => entry.msgstr.as_mut(),
+                Some(Field::MsgstrPlural) => entry.msgstr_plural.last_mut().map(|(_, s)| s),
+                Some(Field::PreviousMsgid | Field::PreviousMsgidPlural) | None => None,
+            };
+            match target {
+                Some(target) => target.push_str(&value),
+                None => return Err(format!("line {}: string without a keyword", number)),
+            }
+            continue;
+        }
+
+        let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let starts_message = matches!(keyword, "msgctxt" | "msgid");
+        if starts_message && (entry.msgstr.is_some() || !entry.msgstr_plural.is_empty()) {
+            finish(&mut entry, &mut entries, obsolete);
+        }
+        if starts_message && entry.msgid.is_none() && entry.msgctxt.is_none() {
+            entry.line = number;
+            obsolete = is_obsolete;
+        }
+        let value =
+            |rest: &str| unquote(rest.trim()).map_err(|e| format!("line {}: {}", number, e));
+        match keyword {
+            "msgctxt" => {
+                entry.msgctxt = Some(value(rest)?);
+                field = Some(Field::Msgctxt);
+            }
+            "msgid" => {
+                entry.msgid = Some(value(rest)?);
+                field = Some(Field::Msgid);
+            }
+            "msgid_plural" => {
+                entry.msgid_plural = Some(value(rest)?);
+                field = Some(Field::MsgidPlural);
+            }
+            "msgstr" => {
+                entry.msgstr = Some(value(rest)?);
+                field = Some(Field::Msgstr);
+            }
+            _ => match MSGSTR_INDEX.captures(line) {
+                Some(caps) => {
+                    let index = caps[1]
+                        .parse()
+                        .map_err(|e| format!("line {}: {}", number, e))?;
+                    entry.msgstr_plural.push((index, value(&caps[2])?));
+                    field = Some(Field::MsgstrPlural);
+                }
+                None => return Err(format!("line {}: unknown keyword {:?}", number, keyword)),
+            },
+        }
+    }
+    finish(&mut entry, &mut entries, obsolete);
+
+    Ok(entries)
+}
+
+/// Reads a `#|` line into the previous source of `entry`. Being a
+/// comment, a line that does not parse is left alone, as msgfmt does, and
+/// so is `#| msgctxt`.
+fn read_previous(entry: &mut RawEntry, field: &mut Option<Field>, line: &str) {
+    if line.starts_with('"') {
+        let target = match field {
+            Some(Field::PreviousMsgid) => entry.previous_msgid.as_mut(),
+            Some(Field::PreviousMsgidPlural) => entry.previous_msgid_plural.as_mut(),
+            _ => None,
+        };
+        if let (Some(target), Ok(value)) = (target, unquote(line)) {
+            target.push_str(&value);
+        }
+        return;
+    }
+
+    let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let value = unquote(rest.trim()).ok();
+    *field = match keyword {
+        "msgid" => {
+            entry.previous_msgid = value;
+            Some(Field::PreviousMsgid)
+        }
+        "msgid_plural" => {
+            entry.previous_msgid_plural = value;
+            Some(Field::PreviousMsgidPlural)
+        }
+        _ => None,
+    };
+}
+
+/// The text of a PO string literal, escapes resolved.
+fn unquote(literal: &str) -> Result<String, String> {
+    let inner = literal
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .ok_or_else(|| format!("expected a quoted string, found {:?}", literal))?;
+
+    let mut text = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => text.push('\n'),
+            Some('t') => text.push('\t'),
+            Some('r') => text.push('\r'),
+            Some('a') => text.push('\u{7}'),
+            Some('b') => text.push('\u{8}'),
+            Some('f') => text.push('\u{c}'),
+            Some('v') => text.push('\u{b}'),
+            Some(c @ ('"' | '\\')) => text.push(c),
+            Some(c) => return Err(format!("invalid escape sequence \\{}", c)),
+            None => return Err("string ends with a backslash".to_string()),
+        }
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::*;
+
+    #[test]
+    fn message_keys_keep_duplicates_polib_merges() {
+        let path = env::temp_dir().join(format!("po-llm-keys-{}.po", process::id()));
+        fs::write(
+            &path,
+            "msgid \"\"\nmsgstr \"Content-Type: text/plain; charset=UTF-8\\n\"\n\n\
+             msgid \"Open\"\nmsgstr \"Ouvrir\"\n\n\
+             msgctxt \"menu\"\nmsgid \"Open\"\nmsgstr \"\"\n\n\
+             msgid \"Open\"\nmsgstr \"Ouvrir\"\n\n\
+             #~ msgid \"Close\"\n#~ msgstr \"Fermer\"\n",
+        )
+        .unwrap();
+        let keys = message_keys(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            keys.unwrap(),
+            vec![
+                (None, "Open".to_string()),
+                (Some("menu".to_string()), "Open".to_string()),
+                (None, "Open".to_string()),
+            ]
+        );
+    }
+
+    fn problems(text: &str) -> Vec<String> {
+        check_entries(&parse_entries(text).unwrap())
+    }
+
+    #[test]
+    fn well_formed_catalogs_have_no_problems() {
+        let text = "msgid \"\"\nmsgstr \"\"\n\
+                    \"Content-Type: text/plain; charset=UTF-8\\n\"\n\
+                    \"Plural-Forms: nplurals=2; plural=(n > 1);\\n\"\n\n\
+                    #, c-format\nmsgid \"%d file\"\nmsgid_plural \"%d files\"\n\
+                    msgstr[0] \"%d fichier\"\nmsgstr[1] \"%d fichiers\"\n\n\
+                    #, fuzzy, c-format\nmsgid \"%s saved\"\nmsgstr \"enregistré\"\n";
+        assert!(problems(text).is_empty(), "{:?}", problems(text));
+    }
+
+    #[test]
+    fn problems_msgfmt_would_report_are_listed() {
+        let text = "msgid \"\"\nmsgstr \"\"\n\
+                    \"Content-Type: text/plain; charset=CHARSET\\n\"\n\
+                    \"Plural-Forms: nplurals=2; plural=(n > 1);\\n\"\n\n\
+                    #, c-format\nmsgid \"%s saved\"\nmsgstr \"enregistré\"\n\n\
+                    msgid \"Line\\n\"\nmsgstr \"Ligne\"\n\n\
+                    msgid \"%d file\"\nmsgid_plural \"%d files\"\n\
+                    msgstr[0] \"%d fichier\"\n\n\
+                    msgid \"Line\\n\"\nmsgstr \"Ligne\\n\"\n";
+        assert_eq!(
+            problems(text),
+            [
+                "header charset is still the template's CHARSET",
+                "line 7: \"%s saved\": printf conversions changed: missing argument 1 (%s)",
+                "line 10: \"Line\\n\": msgid and msgstr do not both end with \\n",
+                "line 13: \"%d file\": 1 plural form(s), Plural-Forms declares 2",
+                "line 17: \"Line\\n\": duplicate message definition, first defined at line 10",
+            ]
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use serde::Deserialize;
+
+use crate::translations::TranslationUnit;
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum OverrideText {
+    Single(String),
+    Plural(Vec<String>),
+}
+
+#[derive(Deserialize, Debug)]
+struct OverrideEntry {
+    msgid: String,
+    msgctxt: Option<String>,
+    msgstr: OverrideText,
+}
+
+type OverrideKey = (Option<String>, String);
+
+pub struct Overrides {
+    languages: HashMap<String, HashMap<OverrideKey, OverrideText>>,
+}
+
+impl Overrides {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read overrides file {:?}: {}", path, e))?;
+
+        let raw: HashMap<String, Vec<OverrideEntry>> =
+            if path.extension().is_some_and(|ext| ext == "json") {
+                serde_json::from_str(&content).map_err(|e| e.to_string())
+            } else {
+                toml::from_str(&content).map_err(|e| e.to_string())
+            }
+            .map_err(|e| format!("Failed to parse overrides file {:?}: {}", path, e))?;
+
+        let languages = raw
+            .into_iter()
+            .map(|(lang, entries)| {
+                let entries = entries
+                    .into_iter()
+                    .map(|entry| ((entry.msgctxt, entry.msgid), entry.msgstr))
+                    .collect();
+                (lang.to_lowercase(), entries)
+            })
+            .collect();
+
+        Ok(Self { languages })
+    }
+
+    pub fn len(&self) -> usize {
+        self.languages.values().map(HashMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn languages(&self) -> impl Iterator<Item = &str> {
+        self.languages.keys().map(String::as_str)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = (&str, Option<&str>, &str)> {
+        self.languages.iter().flat_map(|(lang, entries)| {
+            entries
+                .keys()
+                .map(move |(ctxt, msgid)| (lang.as_str(), ctxt.as_deref(), msgid.as_str()))
+        })
+    }
+
+    pub fn get(&self, lang: &str, unit: &TranslationUnit) -> Option<&OverrideText> {
+        self.languages
+            .get(&lang.to_lowercase())?
+            .get(&(unit.context.clone(), unit.msg_id.clone()))
+    }
+
+    pub fn apply(
+        &self,
+        lang: &str,
+        unit: &TranslationUnit,
+        nplurals: usize,
+    ) -> Option<TranslationUnit> {
+        let text = self.get(lang, unit)?;
+        let mut unit = unit.clone();
+
+        match (unit.is_plural(), text) {
+            (true, OverrideText::Plural(forms)) => unit.msg_str_plural = Some(forms.clone()),
+            (true, OverrideText::Single(text)) => {
+                unit.msg_str_plural = Some(vec![text.clone(); nplurals.max(1)])
+            }
+            (false, OverrideText::Single(text)) => unit.msg_str = Some(text.clone()),
+            (false, OverrideText::Plural(forms)) => {
+                unit.msg_str = Some(forms.first().cloned().unwrap_or_default())
+            }
+        }
+
+        Some(unit)
+    }
+}
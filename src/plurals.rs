@@ -0,0 +1,230 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PluralForms {
+    pub nplurals: usize,
+    pub formula: &'static str,
+    /// CLDR plural category of each form, in gettext order.
+    pub categories: &'static [&'static str],
+}
+
+impl fmt::Display for PluralForms {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "nplurals={}; plural={};", self.nplurals, self.formula)
+    }
+}
+
+const ONE: PluralForms = PluralForms {
+    nplurals: 1,
+    formula: "0",
+    categories: &["other"],
+};
+const TWO: PluralForms = PluralForms {
+    nplurals: 2,
+    formula: "(n != 1)",
+    categories: &["one", "other"],
+};
+const TWO_FRENCH: PluralForms = PluralForms {
+    nplurals: 2,
+    formula: "(n > 1)",
+    categories: &["one", "other"],
+};
+const THREE_SLAVIC: PluralForms = PluralForms {
+    nplurals: 3,
+    formula: "(n%10==1 && n%100!=11 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2)",
+    categories: &["one", "few", "many"],
+};
+const THREE_POLISH: PluralForms = PluralForms {
+    nplurals: 3,
+    formula: "(n==1 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2)",
+    categories: &["one", "few", "many"],
+};
+const THREE_CZECH: PluralForms = PluralForms {
+    nplurals: 3,
+    formula: "(n==1 ? 0 : n>=2 && n<=4 ? 1 : 2)",
+    categories: &["one", "few", "other"],
+};
+const THREE_LITHUANIAN: PluralForms = PluralForms {
+    nplurals: 3,
+    formula: "(n%10==1 && n%100!=11 ? 0 : n%10>=2 && (n%100<10 || n%100>=20) ? 1 : 2)",
+    categories: &["one", "few", "other"],
+};
+const THREE_LATVIAN: PluralForms = PluralForms {
+    nplurals: 3,
+    formula: "(n%10==1 && n%100!=11 ? 0 : n != 0 ? 1 : 2)",
+    categories: &["one", "other", "zero"],
+};
+const THREE_ROMANIAN: PluralForms = PluralForms {
+    nplurals: 3,
+    formula: "(n==1 ? 0 : (n==0 || (n%100 > 0 && n%100 < 20)) ? 1 : 2)",
+    categories: &["one", "few", "other"],
+};
+const FOUR_SLOVENIAN: PluralForms = PluralForms {
+    nplurals: 4,
+    formula: "(n%100==1 ? 0 : n%100==2 ? 1 : n%100==3 || n%100==4 ? 2 : 3)",
+    categories: &["one", "two", "few", "other"],
+};
+const FOUR_WELSH: PluralForms = PluralForms {
+    nplurals: 4,
+    formula: "(n==1 ? 0 : n==2 ? 1 : (n != 8 && n != 11) ? 2 : 3)",
+    categories: &["one", "two", "other", "many"],
+};
+const FIVE_IRISH: PluralForms = PluralForms {
+    nplurals: 5,
+    formula: "(n==1 ? 0 : n==2 ? 1 : n>=3 && n<=6 ? 2 : n>=7 && n<=10 ? 3 : 4)",
+    categories: &["one", "two", "few", "many", "other"],
+};
+const SIX_ARABIC: PluralForms = PluralForms {
+    nplurals: 6,
+    formula: "(n==0 ? 0 : n==1 ? 1 : n==2 ? 2 : n%100>=3 && n%100<=10 ? 3 : n%100>=11 ? 4 : 5)",
+    categories: &["zero", "one", "two", "few", "many", "other"],
+};
+
+// (language code, English name, plural forms)
+const LANGUAGES: &[(&str, &str, PluralForms)] = &[
+    ("af", "afrikaans", TWO),
+    ("ar", "arabic", SIX_ARABIC),
+    ("be", "belarusian", THREE_SLAVIC),
+    ("bg", "bulgarian", TWO),
+    ("bn", "bengali", TWO),
+    ("bs", "bosnian", THREE_SLAVIC),
+    ("ca", "catalan", TWO),
+    ("cs", "czech", THREE_CZECH),
+    ("cy", "welsh", FOUR_WELSH),
+    ("da", "danish", TWO),
+    ("de", "german", TWO),
+    ("el", "greek", TWO),
+    ("en", "english", TWO),
+    ("eo", "esperanto", TWO),
+    ("es", "spanish", TWO),
+    ("et", "estonian", TWO),
+    ("eu", "basque", TWO),
+    ("fa", "persian", TWO_FRENCH),
+    ("fi", "finnish", TWO),
+    ("fil", "filipino", TWO_FRENCH),
+    ("fr", "french", TWO_FRENCH),
+    ("ga", "irish", FIVE_IRISH),
+    ("gl", "galician", TWO),
+    ("he", "hebrew", TWO),
+    ("hi", "hindi", TWO_FRENCH),
+    ("hr", "croatian", THREE_SLAVIC),
+    ("hu", "hungarian", TWO),
+    ("id", "indonesian", ONE),
+    ("is", "icelandic", TWO),
+    ("it", "italian", TWO),
+    ("ja", "japanese", ONE),
+    ("km", "khmer", ONE),
+    ("ko", "korean", ONE),
+    ("lo", "lao", ONE),
+    ("lt", "lithuanian", THREE_LITHUANIAN),
+    ("lv", "latvian", THREE_LATVIAN),
+    ("ms", "malay", ONE),
+    ("my", "burmese", ONE),
+    ("nb", "norwegian", TWO),
+    ("nl", "dutch", TWO),
+    ("nn", "nynorsk", TWO),
+    ("no", "norwegian", TWO),
+    ("pl", "polish", THREE_POLISH),
+    ("pt", "portuguese", TWO),
+    ("pt_br", "brazilian portuguese", TWO_FRENCH),
+    ("ro", "romanian", THREE_ROMANIAN),
+    ("ru", "russian", THREE_SLAVIC),
+    ("sk", "slovak", THREE_CZECH),
+    ("sl", "slovenian", FOUR_SLOVENIAN),
+    ("sq", "albanian", TWO),
+    ("sr", "serbian", THREE_SLAVIC),
+    ("sv", "swedish", TWO),
+    ("th", "thai", ONE),
+    ("tr", "turkish", TWO),
+    ("uk", "ukrainian", THREE_SLAVIC),
+    ("vi", "vietnamese", ONE),
+    ("zh", "chinese", ONE),
+];
+
+pub fn plural_forms(lang: &str) -> Option<PluralForms> {
+    let lang = lang
+        .split('(')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+    let code = lang.replace('-', "_");
+    let code = code.split(['.', '@']).next().unwrap_or_default();
+
+    let by_code = |code: &str| {
+        LANGUAGES
+            .iter()
+            .find(|(c, _, _)| *c == code)
+            .map(|(_, _, forms)| *forms)
+    };
+    let by_name = |name: &str| {
+        LANGUAGES
+            .iter()
+            .find(|(_, n, _)| *n == name)
+            .map(|(_, _, forms)| *forms)
+    };
+
+    by_name(&lang)
+        .or_else(|| by_code(code))
+        .or_else(|| by_code(code.split('_').next().unwrap_or_default()))
+        .or_else(|| lang.split_whitespace().find_map(by_name))
+}
+
+pub fn plural_forms_or_default(lang: &str) -> PluralForms {
+    plural_forms(lang).unwrap_or(TWO)
+}
+
+/// The forms of a translation by CLDR category, for formats that look plural
+/// forms up by category. Those fall back to `other` for any category they do
+/// not find, so languages without one, such as Russian, get their last form
+/// as `other` as well.
+pub fn cldr_forms<'a>(
+    categories: &[&'static str],
+    forms: &'a [String],
+) -> Vec<(&'static str, &'a str)> {
+    let mut pairs: Vec<(&'static str, &'a str)> = categories
+        .iter()
+        .copied()
+        .zip(forms.iter().map(String::as_str))
+        .collect();
+    if !pairs.iter().any(|(category, _)| *category == "other")
+        && let Some(last) = forms.last()
+    {
+        pairs.push(("other", last));
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forms(texts: &[&str]) -> Vec<String> {
+        texts.iter().map(|text| text.to_string()).collect()
+    }
+
+    #[test]
+    fn languages_without_other_repeat_their_last_form() {
+        let ru = plural_forms_or_default("ru");
+        let forms = forms(&["файл", "файла", "файлов"]);
+        assert_eq!(
+            cldr_forms(ru.categories, &forms),
+            [
+                ("one", "файл"),
+                ("few", "файла"),
+                ("many", "файлов"),
+                ("other", "файлов")
+            ]
+        );
+    }
+
+    #[test]
+    fn languages_with_other_keep_their_forms() {
+        let de = plural_forms_or_default("de");
+        let forms = forms(&["Datei", "Dateien"]);
+        assert_eq!(
+            cldr_forms(de.categories, &forms),
+            [("one", "Datei"), ("other", "Dateien")]
+        );
+    }
+}
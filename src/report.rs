@@ -0,0 +1,279 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::translations::TranslationUnit;
+
+/// Bumped whenever a field of the report changes meaning or goes away.
+pub const REPORT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Written anyway, and listed for review.
+    Warning,
+    /// Failed the translation.
+    Error,
+}
+
+/// A validation rule a translation broke, such as `placeholders` or
+/// `too-long`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub rule: String,
+    pub severity: Severity,
+    pub message: String,
+    /// Corrected by `autofix`; the translation no longer breaks the rule.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub fixed: bool,
+}
+
+impl Finding {
+    pub fn warning(rule: &str, message: impl Into<String>) -> Self {
+        Self {
+            rule: rule.to_string(),
+            severity: Severity::Warning,
+            message: message.into(),
+            fixed: false,
+        }
+    }
+
+    pub fn error(rule: &str, message: impl Into<String>) -> Self {
+        Self {
+            rule: rule.to_string(),
+            severity: Severity::Error,
+            message: message.into(),
+            fixed: false,
+        }
+    }
+
+    /// The finding as corrected by `autofix`, which leaves it a warning.
+    pub fn fixed(self) -> Self {
+        Self {
+            severity: Severity::Warning,
+            fixed: true,
+            ..self
+        }
+    }
+}
+
+/// A finding with the message it was found on.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReportEntry {
+    pub file: PathBuf,
+    pub language: String,
+    pub msgid: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    pub rule: String,
+    pub severity: Severity,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub fixed: bool,
+    /// The translation checked, one string per plural form.
+    pub translation: Vec<String>,
+}
+
+/// A message a run left untranslated, or with no `msgid`, a language of
+/// a file that failed as a whole.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FailureEntry {
+    pub file: PathBuf,
+    pub language: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub msgid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    pub reason: String,
+}
+
+/// Every validation finding of a run, written by `--validation-report`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ValidationReport {
+    pub version: u32,
+    pub findings: Vec<ReportEntry>,
+    /// Findings per rule, those auto-fixed left out.
+    pub totals: BTreeMap<String, usize>,
+    /// Findings auto-fixed per rule.
+    #[serde(default)]
+    pub fixed: BTreeMap<String, usize>,
+    /// What `--retry-from` translates again.
+    #[serde(default)]
+    pub failures: Vec<FailureEntry>,
+}
+
+impl Default for ValidationReport {
+    fn default() -> Self {
+        Self {
+            version: REPORT_VERSION,
+            findings: Vec::new(),
+            totals: BTreeMap::new(),
+            fixed: BTreeMap::new(),
+            failures: Vec::new(),
+        }
+    }
+}
+
+impl ValidationReport {
+    /// Adds the findings of translations of `file` into `language`.
+    pub fn add<'a>(
+        &mut self,
+        file: &Path,
+        language: &str,
+        units: impl IntoIterator<Item = &'a TranslationUnit>,
+    ) {
+        for unit in units {
+            if let Some(reason) = &unit.failure {
+                self.failures.push(FailureEntry {
+                    file: file.to_path_buf(),
+                    language: language.to_string(),
+                    msgid: Some(unit.msg_id.clone()),
+                    context: unit.context.clone(),
+                    reason: reason.clone(),
+                });
+            }
+            let translation = match (&unit.msg_str_plural, &unit.msg_str) {
+                (Some(plurals), _) => plurals.clone(),
+                (None, Some(translation)) => vec![translation.clone()],
+                (None, None) => Vec::new(),
+            };
+            for finding in &unit.findings {
+                let totals = if finding.fixed {
+                    &mut self.fixed
+                } else {
+                    &mut self.totals
+                };
+                *totals.entry(finding.rule.clone()).or_default() += 1;
+                self.findings.push(ReportEntry {
+                    file: file.to_path_buf(),
+                    language: language.to_string(),
+                    msgid: unit.msg_id.clone(),
+                    context: unit.context.clone(),
+                    rule: finding.rule.clone(),
+                    severity: finding.severity,
+                    message: finding.message.clone(),
+                    fixed: finding.fixed,
+                    translation: translation.clone(),
+                });
+            }
+        }
+    }
+
+    /// Records a language of `file` that failed before its messages were
+    /// all tried, such as on an API error.
+    pub fn add_failed_language(&mut self, file: &Path, language: &str, reason: String) {
+        self.failures.push(FailureEntry {
+            file: file.to_path_buf(),
+            language: language.to_string(),
+            msgid: None,
+            context: None,
+            reason,
+        });
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read validation report {:?}: {}", path, e))?;
+        let report: Self = serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse validation report {:?}: {}", path, e))?;
+        if report.version > REPORT_VERSION {
+            return Err(format!(
+                "Validation report {:?} is version {}, newer than this po-llm reads ({})",
+                path, report.version, REPORT_VERSION
+            ));
+        }
+        Ok(report)
+    }
+
+    /// Totals in one line, most frequent rule first, such as
+    /// `placeholders: 12, too-long: 3`.
+    pub fn summary(totals: &BTreeMap<String, usize>) -> String {
+        let mut totals: Vec<(&String, &usize)> = totals.iter().collect();
+        totals.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        totals
+            .iter()
+            .map(|(rule, count)| format!("{}: {}", rule, count))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to encode validation report: {}", e))?;
+        fs::write(path, json)
+            .map_err(|e| format!("Failed to write validation report {:?}: {}", path, e))
+    }
+}
+
+/// The `(context, msgid)` of messages.
+type MessageKeys = BTreeSet<(Option<String>, String)>;
+
+/// The failures of a previous run, translated again by `--retry-from`.
+#[derive(Debug, Default)]
+pub struct RetryList {
+    /// Per file and language, the failed messages, or `None` when the
+    /// language failed as a whole.
+    failures: BTreeMap<(PathBuf, String), Option<MessageKeys>>,
+}
+
+impl RetryList {
+    pub fn new(failures: &[FailureEntry]) -> Self {
+        let mut list = Self::default();
+        for failure in failures {
+            let key = (failure.file.clone(), failure.language.clone());
+            match &failure.msgid {
+                None => {
+                    list.failures.insert(key, None);
+                }
+                Some(msgid) => {
+                    if let Some(messages) = list
+                        .failures
+                        .entry(key)
+                        .or_insert_with(|| Some(BTreeSet::new()))
+                    {
+                        messages.insert((failure.context.clone(), msgid.clone()));
+                    }
+                }
+            }
+        }
+        list
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    pub fn has_file(&self, file: &Path) -> bool {
+        self.failures.keys().any(|(path, _)| path == file)
+    }
+
+    pub fn has_language(&self, file: &Path, language: &str) -> bool {
+        self.failures
+            .contains_key(&(file.to_path_buf(), language.to_string()))
+    }
+
+    /// The files with failures.
+    pub fn files(&self) -> BTreeSet<&Path> {
+        self.failures
+            .keys()
+            .map(|(path, _)| path.as_path())
+            .collect()
+    }
+
+    /// Whether a message of `file` in `language` failed, counting every
+    /// message of a language that failed as a whole.
+    pub fn contains(&self, file: &Path, language: &str, unit: &TranslationUnit) -> bool {
+        match self
+            .failures
+            .get(&(file.to_path_buf(), language.to_string()))
+        {
+            Some(None) => true,
+            Some(Some(messages)) => messages.contains(&(unit.context.clone(), unit.msg_id.clone())),
+            None => false,
+        }
+    }
+}
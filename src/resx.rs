@@ -0,0 +1,338 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use roxmltree::{Document, Node};
+
+use crate::{
+    configs::DocumentFormat,
+    errors::PoLlmError,
+    files::{FileGuard, write_atomically},
+    linguist::{child, escape_xml, indent_before, text_of},
+    translations::{self, FormatAdapter, TranslationUnit, WriteOptions, document_as},
+    xliff::{inner_range, start_tag_end},
+};
+
+/// A .NET `.resx` resource file.
+#[derive(Debug, Clone)]
+pub struct ResxDocument {
+    text: String,
+}
+
+/// A string resource: `<data name="..."><value>...</value></data>`.
+struct Entry<'a, 'input> {
+    name: String,
+    value: String,
+    comment: Option<String>,
+    node: Node<'a, 'input>,
+}
+
+impl ResxDocument {
+    pub fn read(path: &Path) -> Result<Self, PoLlmError> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| PoLlmError::io(format!("Failed to read resx file {:?}", path), e))?;
+        Self::parse(text).map_err(|e| e.context(format!("Failed to parse resx file {:?}", path)))
+    }
+
+    pub fn parse(text: String) -> Result<Self, PoLlmError> {
+        let text = match text.strip_prefix('\u{feff}') {
+            Some(stripped) => stripped.to_string(),
+            None => text,
+        };
+        let document = Document::parse(&text).map_err(|e| PoLlmError::Parse(e.to_string()))?;
+        let root = document.root_element();
+        if !root.has_tag_name("root") {
+            return Err(PoLlmError::Parse(format!(
+                "root element is <{}>, not <root>",
+                root.tag_name().name()
+            )));
+        }
+        Ok(Self { text })
+    }
+
+    /// The same file without its resources: the schema, `<resheader>`s and
+    /// anything else a culture-specific file shares with the neutral one.
+    pub fn preamble(&self) -> Result<Self, PoLlmError> {
+        let document = Document::parse(&self.text).map_err(|e| PoLlmError::Parse(e.to_string()))?;
+        let mut text = self.text.clone();
+        let resources: Vec<Range<usize>> = document
+            .root_element()
+            .children()
+            .filter(|n| n.has_tag_name("data"))
+            .map(|n| n.range())
+            .collect();
+        for range in resources.into_iter().rev() {
+            // Take the line break and indentation before the element along.
+            let start = text[..range.start].trim_end().len();
+            text.replace_range(start..range.end, "");
+        }
+        Ok(Self { text })
+    }
+
+    fn strings(&self) -> Vec<(String, String, Option<String>)> {
+        let Ok(document) = Document::parse(&self.text) else {
+            return Vec::new();
+        };
+        entries(&document)
+            .into_iter()
+            .map(|entry| (entry.name, entry.value, entry.comment))
+            .collect()
+    }
+
+    fn translated_names(&self) -> HashSet<String> {
+        self.strings()
+            .into_iter()
+            .filter(|(_, value, _)| !value.is_empty())
+            .map(|(name, _, _)| name)
+            .collect()
+    }
+}
+
+/// String resources in document order. Entries with a `type` or `mimetype`
+/// hold file references, images and other objects, and are left out.
+fn entries<'a, 'input>(document: &'a Document<'input>) -> Vec<Entry<'a, 'input>> {
+    document
+        .root_element()
+        .children()
+        .filter(|n| n.has_tag_name("data"))
+        .filter(|n| n.attribute("type").is_none() && n.attribute("mimetype").is_none())
+        .filter_map(|node| {
+            Some(Entry {
+                name: node.attribute("name")?.to_string(),
+                value: child(node, "value").map(text_of).unwrap_or_default(),
+                comment: child(node, "comment")
+                    .map(|comment| text_of(comment).trim().to_string())
+                    .filter(|comment| !comment.is_empty()),
+                node,
+            })
+        })
+        .collect()
+}
+
+/// Writes the culture-specific file for a neutral one: its header with no
+/// resources yet.
+pub fn copy_preamble(template_path: &Path, output_path: &Path) -> Result<(), PoLlmError> {
+    let preamble = ResxDocument::read(template_path)?.preamble()?;
+    write_atomically(output_path, |writer| {
+        writer.write_all(preamble.text.as_bytes())
+    })
+    .map_err(|e| PoLlmError::io("Failed to write resx file", e))
+}
+
+/// Outputs start with the input's header and no resources; an entry counts
+/// as translated once its name is present with a non-empty value.
+pub struct ResxAdapter;
+
+impl FormatAdapter for ResxAdapter {
+    fn format(&self) -> DocumentFormat {
+        DocumentFormat::Resx
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["resx"]
+    }
+
+    fn load_template(&self, path: &Path) -> Result<translations::Document, PoLlmError> {
+        Ok(Box::new(ResxDocument::read(path)?))
+    }
+
+    /// `Name.resx` gets a `Name.{lang}.resx` sibling.
+    fn output_path(
+        &self,
+        input_path: &Path,
+        target_lang: &str,
+    ) -> Result<Option<PathBuf>, PoLlmError> {
+        let stem = input_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .ok_or_else(|| {
+                PoLlmError::io(
+                    format!("Invalid filename {:?}", input_path),
+                    io::ErrorKind::InvalidInput.into(),
+                )
+            })?;
+        Ok(Some(
+            input_path.with_file_name(format!("{}.{}.resx", stem, target_lang)),
+        ))
+    }
+
+    fn create_output(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+        _target_lang: &str,
+    ) -> Result<(), PoLlmError> {
+        copy_preamble(input_path, output_path)
+    }
+
+    fn load_existing(
+        &self,
+        output_path: &Path,
+        template: &translations::Document,
+    ) -> Result<translations::Document, PoLlmError> {
+        if output_path.exists() {
+            return Ok(Box::new(ResxDocument::read(output_path)?));
+        }
+        println!("         Output doesn't exist, starting from an empty resource file");
+        let template: &ResxDocument = document_as(template);
+        Ok(Box::new(template.preamble()?))
+    }
+
+    fn extract_units(
+        &self,
+        existing: &translations::Document,
+        template: &translations::Document,
+        skip_translated: bool,
+    ) -> Vec<TranslationUnit> {
+        let document: &ResxDocument = document_as(existing);
+        let template: &ResxDocument = document_as(template);
+        let translated = if skip_translated {
+            document.translated_names()
+        } else {
+            HashSet::new()
+        };
+
+        template
+            .strings()
+            .into_iter()
+            .filter(|(name, value, _)| !value.trim().is_empty() && !translated.contains(name))
+            .map(|(name, value, comment)| TranslationUnit {
+                msg_id: value,
+                msg_id_plural: None,
+                msg_str: Some(String::new()),
+                msg_str_plural: None,
+                context: Some(name),
+                comments: comment,
+                rationale: None,
+                failure: None,
+                warnings: Vec::new(),
+                findings: Vec::new(),
+                attribution: None,
+                format_flags: Vec::new(),
+                references: String::new(),
+                fuzzy: false,
+                previous: None,
+            })
+            .collect()
+    }
+
+    fn write_translations(
+        &self,
+        translations: Vec<TranslationUnit>,
+        _target_lang: &str,
+        output: &FileGuard,
+        options: &WriteOptions,
+    ) -> Result<(), PoLlmError> {
+        let output_path = output.path();
+        let document = ResxDocument::read(output_path)?;
+
+        let targets: Vec<(String, String)> = translations
+            .into_iter()
+            .filter(|unit| unit.failure.is_none())
+            .filter_map(|unit| Some((unit.context?, unit.msg_str?)))
+            .filter(|(_, text)| !text.is_empty())
+            .collect();
+
+        let text = write_values(&document.text, &targets).map_err(PoLlmError::Parse)?;
+
+        write_atomically(output_path, |writer| writer.write_all(text.as_bytes()))
+            .map_err(|e| PoLlmError::io("Failed to write resx file", e))?;
+
+        if options.verify_roundtrip {
+            let reparsed = ResxDocument::read(output_path).map_err(|e| {
+                PoLlmError::Validation(format!("Round-trip check failed, cannot re-parse: {}", e))
+            })?;
+            let values: HashMap<String, String> = reparsed
+                .strings()
+                .into_iter()
+                .map(|(name, value, _)| (name, value))
+                .collect();
+            let differing = targets
+                .iter()
+                .filter(|(name, text)| values.get(name) != Some(text))
+                .count();
+            if differing > 0 {
+                return Err(PoLlmError::Validation(format!(
+                    "Round-trip check failed: {} value(s) read back differently",
+                    differing
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Replaces the value of each named resource, appending the ones the file
+/// does not have yet before `</root>` in the file's indentation.
+fn write_values(text: &str, targets: &[(String, String)]) -> Result<String, String> {
+    let document = Document::parse(text).map_err(|e| e.to_string())?;
+    let root = document.root_element();
+    let existing: HashMap<String, Node> = entries(&document)
+        .into_iter()
+        .map(|entry| (entry.name, entry.node))
+        .collect();
+
+    let indent = root
+        .children()
+        .find(|n| n.is_element())
+        .map_or("  ", |n| indent_before(text, n.range().start));
+    let close_start = text[..root.range().end]
+        .rfind("</")
+        .ok_or("<root> has no closing tag")?;
+    let mut appended = String::new();
+    if !text[..close_start].ends_with('\n') {
+        appended.push('\n');
+    }
+
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+    let mut added = HashSet::new();
+    for (name, value) in targets {
+        let escaped = escape_xml(value);
+        match existing.get(name) {
+            Some(node) => match child(*node, "value") {
+                Some(value_node) => {
+                    let tag_end = start_tag_end(text, value_node.range().start);
+                    if text[..tag_end].ends_with("/>") {
+                        edits.push((tag_end - 2..tag_end, format!(">{}</value>", escaped)));
+                    } else {
+                        edits.push((inner_range(text, value_node), escaped));
+                    }
+                }
+                None => {
+                    let at = inner_range(text, *node).start;
+                    edits.push((
+                        at..at,
+                        format!("\n{0}{0}<value>{1}</value>\n{0}", indent, escaped),
+                    ));
+                }
+            },
+            None if added.insert(name) => {
+                appended.push_str(&format!(
+                    "{0}<data name=\"{1}\" xml:space=\"preserve\">\n{0}{0}<value>{2}</value>\n{0}</data>\n",
+                    indent,
+                    escape_xml(name).replace('"', "&quot;"),
+                    escaped
+                ));
+            }
+            None => {}
+        }
+    }
+    if !added.is_empty() {
+        edits.push((close_start..close_start, appended));
+    }
+
+    // Apply from the end so earlier offsets stay valid.
+    edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+    let mut result = text.to_string();
+    for (range, replacement) in edits {
+        result.replace_range(range, &replacement);
+    }
+
+    Document::parse(&result).map_err(|e| format!("Rewritten document is not valid XML: {}", e))?;
+    Ok(result)
+}
@@ -0,0 +1,144 @@
+use std::path::Path;
+
+use polib::catalog::Catalog;
+
+/// One message of a review spreadsheet with its current translation.
+pub struct ReviewRow {
+    /// The PO file the message belongs to, relative to the project base path.
+    pub file: String,
+    pub context: Option<String>,
+    pub msgid: String,
+    pub msgid_plural: Option<String>,
+    /// The translation, one cell per plural form for plural messages.
+    pub msgstr: Vec<String>,
+    pub status: &'static str,
+}
+
+/// Every message of a template with its translation in `po`, if any.
+pub fn review_rows(file: &str, pot: &Catalog, po: Option<&Catalog>) -> Vec<ReviewRow> {
+    pot.messages()
+        .filter(|msg| !msg.msgid().is_empty())
+        .map(|msg| {
+            let current = po.and_then(|po| {
+                po.find_message(msg.msgctxt(), msg.msgid(), msg.msgid_plural().ok())
+            });
+            let msgstr = match current {
+                Some(current) if msg.is_plural() => {
+                    current.msgstr_plural().cloned().unwrap_or_default()
+                }
+                Some(current) => vec![current.msgstr().unwrap_or_default().to_string()],
+                None => Vec::new(),
+            };
+            let status = match current {
+                Some(current) if current.is_fuzzy() => "fuzzy",
+                Some(current) if current.is_translated() => "translated",
+                _ => "untranslated",
+            };
+
+            ReviewRow {
+                file: file.to_string(),
+                context: msg.msgctxt().map(String::from),
+                msgid: msg.msgid().to_string(),
+                msgid_plural: msg.msgid_plural().ok().map(String::from),
+                msgstr,
+                status,
+            }
+        })
+        .collect()
+}
+
+/// Writes rows with a `msgstr` column, then `msgstr[1]` up to the last
+/// plural form of the language.
+pub fn write_review_csv(path: &Path, rows: &[ReviewRow], nplurals: usize) -> Result<(), String> {
+    let mut writer =
+        csv::Writer::from_path(path).map_err(|e| format!("Failed to create {:?}: {}", path, e))?;
+
+    let mut header = vec![
+        "file".to_string(),
+        "context".to_string(),
+        "msgid".to_string(),
+        "msgid_plural".to_string(),
+        "msgstr".to_string(),
+    ];
+    header.extend((1..nplurals).map(|i| format!("msgstr[{}]", i)));
+    header.push("status".to_string());
+    writer.write_record(&header).map_err(|e| e.to_string())?;
+
+    for row in rows {
+        let mut record = vec![
+            row.file.as_str(),
+            row.context.as_deref().unwrap_or_default(),
+            row.msgid.as_str(),
+            row.msgid_plural.as_deref().unwrap_or_default(),
+        ];
+        record.extend((0..nplurals.max(1)).map(|i| row.msgstr.get(i).map_or("", String::as_str)));
+        record.push(row.status);
+        writer.write_record(&record).map_err(|e| e.to_string())?;
+    }
+
+    writer
+        .flush()
+        .map_err(|e| format!("Failed to write {:?}: {}", path, e))
+}
+
+/// Reads rows back from an edited spreadsheet. Columns are found by name, so
+/// reviewers may reorder them or add their own.
+pub fn read_review_csv(path: &Path) -> Result<Vec<ReviewRow>, String> {
+    let mut reader =
+        csv::Reader::from_path(path).map_err(|e| format!("Failed to open {:?}: {}", path, e))?;
+    let headers = reader
+        .headers()
+        .map_err(|e| format!("Failed to read the header of {:?}: {}", path, e))?
+        .clone();
+    // Spreadsheet programs often save with a byte order mark.
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h.trim_start_matches('\u{feff}').trim() == name)
+    };
+
+    let (Some(file), Some(msgid), Some(msgstr)) =
+        (column("file"), column("msgid"), column("msgstr"))
+    else {
+        return Err(format!(
+            "{:?} needs at least the file, msgid and msgstr columns",
+            path
+        ));
+    };
+    let context = column("context");
+    let msgid_plural = column("msgid_plural");
+    let mut forms = vec![msgstr];
+    while let Some(index) = column(&format!("msgstr[{}]", forms.len())) {
+        forms.push(index);
+    }
+
+    let mut rows = Vec::new();
+    for (line, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| format!("Failed to read row {}: {}", line + 2, e))?;
+        let cell = |index: Option<usize>| {
+            index
+                .and_then(|i| record.get(i))
+                .filter(|value| !value.is_empty())
+                .map(String::from)
+        };
+
+        let msgid_plural = cell(msgid_plural);
+        let count = if msgid_plural.is_some() {
+            forms.len()
+        } else {
+            1
+        };
+        rows.push(ReviewRow {
+            file: cell(Some(file)).unwrap_or_default(),
+            context: cell(context),
+            msgid: cell(Some(msgid)).unwrap_or_default(),
+            msgid_plural,
+            msgstr: forms[..count]
+                .iter()
+                .map(|&i| cell(Some(i)).unwrap_or_default())
+                .collect(),
+            status: "",
+        });
+    }
+    Ok(rows)
+}
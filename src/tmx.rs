@@ -0,0 +1,474 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::Write,
+    path::Path,
+};
+
+use polib::catalog::Catalog;
+use roxmltree::{Document, Node, ParsingOptions};
+
+use crate::{
+    files::write_atomically,
+    linguist::{child, escape_xml, text_of},
+    memory::{similarity, words},
+    translations::{TranslationUnit, format_flags},
+};
+
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+const MAX_SUGGESTIONS: usize = 3;
+
+/// One language's translations for a TMX file. `model` names the model that
+/// produced them, for translations made in this run.
+pub struct TmxLanguage<'a> {
+    pub lang: &'a str,
+    pub model: Option<&'a str>,
+    pub units: &'a [TranslationUnit],
+}
+
+/// Writes a TMX 1.4 file with one `<tu>` per translated message and
+/// language. Plural forms become separate units with an `x-plural-index`
+/// property, and msgctxt an `x-context` property. Returns the number of
+/// units written.
+pub fn write_tmx(
+    path: &Path,
+    source_lang: &str,
+    languages: &[TmxLanguage],
+) -> Result<usize, String> {
+    let created = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut text = String::new();
+    text.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    text.push_str("<!DOCTYPE tmx SYSTEM \"tmx14.dtd\">\n");
+    text.push_str("<tmx version=\"1.4\">\n");
+    text.push_str(&format!(
+        "  <header creationtool=\"po-llm\" creationtoolversion=\"{}\" segtype=\"sentence\" o-tmf=\"PO\" adminlang=\"en\" srclang=\"{}\" datatype=\"plaintext\" creationdate=\"{}\"/>\n",
+        env!("CARGO_PKG_VERSION"),
+        attribute(source_lang),
+        created
+    ));
+    text.push_str("  <body>\n");
+
+    let mut written = 0;
+    for language in languages {
+        for unit in language.units.iter().filter(|unit| unit.failure.is_none()) {
+            let pairs = match &unit.msg_str {
+                Some(msg_str) if !unit.is_plural() => {
+                    vec![(unit.msg_id.as_str(), msg_str.as_str())]
+                }
+                _ => unit.translation_pairs(),
+            };
+            for (index, (source, target)) in pairs.into_iter().enumerate() {
+                if target.is_empty() {
+                    continue;
+                }
+
+                match language.model {
+                    Some(model) => text.push_str(&format!(
+                        "    <tu creationdate=\"{}\" creationid=\"{}\">\n",
+                        created,
+                        attribute(model)
+                    )),
+                    None => text.push_str("    <tu>\n"),
+                }
+                if let Some(context) = &unit.context {
+                    text.push_str(&format!(
+                        "      <prop type=\"x-context\">{}</prop>\n",
+                        escape_xml(context)
+                    ));
+                }
+                if unit.is_plural() {
+                    text.push_str(&format!(
+                        "      <prop type=\"x-plural-index\">{}</prop>\n",
+                        index
+                    ));
+                }
+                for (lang, segment) in [(source_lang, source), (language.lang, target)] {
+                    text.push_str(&format!(
+                        "      <tuv xml:lang=\"{}\"><seg>{}</seg></tuv>\n",
+                        attribute(lang),
+                        escape_xml(segment)
+                    ));
+                }
+                text.push_str("    </tu>\n");
+                written += 1;
+            }
+        }
+    }
+
+    text.push_str("  </body>\n</tmx>\n");
+    write_atomically(path, |writer| writer.write_all(text.as_bytes()))
+        .map_err(|e| format!("Failed to write TMX file {:?}: {}", path, e))?;
+    Ok(written)
+}
+
+fn attribute(value: &str) -> String {
+    escape_xml(value).replace('"', "&quot;")
+}
+
+/// The translated, non-fuzzy messages of a PO file.
+pub fn catalog_translations(catalog: &Catalog) -> Vec<TranslationUnit> {
+    catalog
+        .messages()
+        .filter(|msg| !msg.msgid().is_empty() && msg.is_translated() && !msg.is_fuzzy())
+        .map(|msg| TranslationUnit {
+            msg_id: msg.msgid().to_string(),
+            msg_id_plural: msg.msgid_plural().ok().map(String::from),
+            msg_str: msg.msgstr().ok().map(String::from),
+            msg_str_plural: msg.msgstr_plural().ok().cloned(),
+            context: msg.msgctxt().map(String::from),
+            comments: None,
+            rationale: None,
+            failure: None,
+            warnings: Vec::new(),
+            findings: Vec::new(),
+            attribution: None,
+            format_flags: format_flags(msg.flags()),
+            references: msg.source().to_string(),
+            fuzzy: false,
+            previous: None,
+        })
+        .collect()
+}
+
+struct MemoryEntry {
+    context: Option<String>,
+    lang: String,
+    translation: String,
+}
+
+/// Translations loaded from TMX files, looked up by source text and target
+/// language. Language tags match case-insensitively and by their primary
+/// subtag, so a `de-DE` entry serves `de` and the other way round.
+pub struct TranslationMemory {
+    entries: HashMap<String, Vec<MemoryEntry>>,
+    words: Vec<(String, HashSet<String>)>,
+}
+
+impl TranslationMemory {
+    /// Loads the TMX files in order; an earlier file wins over a later one.
+    /// The source segment of each unit is the one in `source_lang` (or the
+    /// file's or unit's `srclang`); plural forms exported by po-llm are
+    /// skipped, and an `x-context` property ties an entry to that msgctxt.
+    pub fn load(paths: &[impl AsRef<Path>], source_lang: &str) -> Result<Self, String> {
+        let mut entries: HashMap<String, Vec<MemoryEntry>> = HashMap::new();
+        for path in paths {
+            let path = path.as_ref();
+            let text = fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read translation memory {:?}: {}", path, e))?;
+            let document = Document::parse_with_options(
+                &text,
+                ParsingOptions {
+                    allow_dtd: true,
+                    ..ParsingOptions::default()
+                },
+            )
+            .map_err(|e| format!("Failed to parse translation memory {:?}: {}", path, e))?;
+
+            let root = document.root_element();
+            let file_srclang = child(root, "header")
+                .and_then(|header| header.attribute("srclang"))
+                .filter(|lang| *lang != "*all*");
+            let Some(body) = child(root, "body") else {
+                return Err(format!("Translation memory {:?} has no <body>", path));
+            };
+
+            for tu in body.children().filter(|n| n.has_tag_name("tu")) {
+                let props: Vec<(&str, String)> = tu
+                    .children()
+                    .filter(|n| n.has_tag_name("prop"))
+                    .filter_map(|prop| Some((prop.attribute("type")?, text_of(prop))))
+                    .collect();
+                if props.iter().any(|(kind, _)| *kind == "x-plural-index") {
+                    continue;
+                }
+                let context = props
+                    .iter()
+                    .find(|(kind, _)| *kind == "x-context")
+                    .map(|(_, value)| value.clone());
+
+                let srclang = tu
+                    .attribute("srclang")
+                    .filter(|lang| *lang != "*all*")
+                    .or(file_srclang)
+                    .unwrap_or(source_lang);
+                let segments: Vec<(String, String)> = tu
+                    .children()
+                    .filter(|n| n.has_tag_name("tuv"))
+                    .filter_map(|tuv| {
+                        let lang = tuv_lang(tuv)?;
+                        let seg = child(tuv, "seg")?;
+                        Some((normalize_tag(lang), text_of(seg)))
+                    })
+                    .collect();
+                let Some(source) = segments
+                    .iter()
+                    .find(|(lang, _)| same_language(lang, srclang))
+                    .map(|(_, seg)| seg.clone())
+                    .filter(|seg| !seg.is_empty())
+                else {
+                    continue;
+                };
+
+                let known = entries.entry(source).or_default();
+                for (lang, translation) in segments {
+                    if same_language(&lang, srclang) || translation.is_empty() {
+                        continue;
+                    }
+                    if !known
+                        .iter()
+                        .any(|entry| entry.lang == lang && entry.context == context)
+                    {
+                        known.push(MemoryEntry {
+                            context: context.clone(),
+                            lang,
+                            translation,
+                        });
+                    }
+                }
+            }
+        }
+
+        entries.retain(|_, known| !known.is_empty());
+        let words = entries
+            .keys()
+            .map(|source| (source.clone(), words(source)))
+            .collect();
+        Ok(Self { entries, words })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The translation of exactly this source into `lang`, preferring an
+    /// entry for the same language tag over one for a sibling region.
+    pub fn exact(&self, source: &str, context: Option<&str>, lang: &str) -> Option<&str> {
+        let lang = normalize_tag(lang);
+        let candidates: Vec<&MemoryEntry> = self
+            .entries
+            .get(source)?
+            .iter()
+            .filter(|entry| entry.context.is_none() || entry.context.as_deref() == context)
+            .collect();
+        candidates
+            .iter()
+            .find(|entry| entry.lang == lang)
+            .or_else(|| {
+                candidates
+                    .iter()
+                    .find(|entry| same_language(&entry.lang, &lang))
+            })
+            .map(|entry| entry.translation.as_str())
+    }
+
+    /// The closest entries to `source` with a translation into `lang`, most
+    /// similar first, for entries at least `threshold` similar.
+    pub fn suggestions(&self, source: &str, lang: &str, threshold: f64) -> Vec<(&str, &str)> {
+        let source_words = words(source);
+        let mut matches: Vec<(f64, &str, &str)> = self
+            .words
+            .iter()
+            .filter(|(candidate, _)| candidate != source)
+            .filter_map(|(candidate, candidate_words)| {
+                let score = similarity(&source_words, candidate_words);
+                if score < threshold {
+                    return None;
+                }
+                let translation = self.exact(candidate, None, lang)?;
+                Some((score, candidate.as_str(), translation))
+            })
+            .collect();
+
+        matches.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+        matches
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, source, translation)| (source, translation))
+            .collect()
+    }
+}
+
+fn tuv_lang<'a>(tuv: Node<'a, '_>) -> Option<&'a str> {
+    // TMX 1.1 used a plain `lang` attribute.
+    tuv.attribute((XML_NAMESPACE, "lang"))
+        .or_else(|| tuv.attribute("lang"))
+}
+
+fn normalize_tag(lang: &str) -> String {
+    lang.trim().replace('_', "-").to_lowercase()
+}
+
+fn same_language(a: &str, b: &str) -> bool {
+    let primary = |lang: &str| {
+        normalize_tag(lang)
+            .split('-')
+            .next()
+            .unwrap_or_default()
+            .to_string()
+    };
+    primary(a) == primary(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use serde_json::json;
+
+    use super::*;
+
+    fn unit(value: serde_json::Value) -> TranslationUnit {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn exported(name: &str) -> (std::path::PathBuf, usize) {
+        let path = env::temp_dir().join(format!("po-llm-{}-{}.tmx", name, process::id()));
+        let units = [
+            unit(json!({ "msg_id": "Open", "msg_str": "Ouvrir", "context": "menu" })),
+            unit(json!({
+                "msg_id": "One file",
+                "msg_id_plural": "%d files",
+                "msg_str_plural": ["Un fichier", "%d fichiers"],
+            })),
+            unit(json!({ "msg_id": "Save <b>&</b>", "msg_str": "Enregistrer <b>&</b>" })),
+            unit(json!({ "msg_id": "Quit", "msg_str": "" })),
+        ];
+        let written = write_tmx(
+            &path,
+            "en",
+            &[TmxLanguage {
+                lang: "fr",
+                model: Some("m \"1\""),
+                units: &units,
+            }],
+        )
+        .unwrap();
+        (path, written)
+    }
+
+    #[test]
+    fn export_follows_the_tmx_14_dtd() {
+        let (path, written) = exported("dtd");
+        let text = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(written, 4);
+
+        let document = Document::parse_with_options(
+            &text,
+            ParsingOptions {
+                allow_dtd: true,
+                ..ParsingOptions::default()
+            },
+        )
+        .unwrap();
+        assert!(text.contains("<!DOCTYPE tmx SYSTEM \"tmx14.dtd\">"));
+        let root = document.root_element();
+        assert_eq!(root.tag_name().name(), "tmx");
+        assert_eq!(root.attribute("version"), Some("1.4"));
+
+        // tmx: (header, body)
+        let children: Vec<&str> = root
+            .children()
+            .filter(|n| n.is_element())
+            .map(|n| n.tag_name().name())
+            .collect();
+        assert_eq!(children, ["header", "body"]);
+        let header = child(root, "header").unwrap();
+        for required in [
+            "creationtool",
+            "creationtoolversion",
+            "segtype",
+            "o-tmf",
+            "adminlang",
+            "srclang",
+            "datatype",
+        ] {
+            assert!(header.attribute(required).is_some(), "{}", required);
+        }
+        assert_eq!(header.attribute("segtype"), Some("sentence"));
+
+        let tus: Vec<Node> = child(root, "body")
+            .unwrap()
+            .children()
+            .filter(|n| n.is_element())
+            .collect();
+        assert_eq!(tus.len(), 4);
+        for tu in &tus {
+            assert_eq!(tu.tag_name().name(), "tu");
+            assert_eq!(tu.attribute("creationid"), Some("m \"1\""));
+            let date = tu.attribute("creationdate").unwrap();
+            assert!(chrono::NaiveDateTime::parse_from_str(date, "%Y%m%dT%H%M%SZ").is_ok());
+
+            // tu: ((note|prop)*, tuv+), each tuv holding one seg
+            let names: Vec<&str> = tu
+                .children()
+                .filter(|n| n.is_element())
+                .map(|n| n.tag_name().name())
+                .collect();
+            let props = names.iter().take_while(|name| **name == "prop").count();
+            assert!(
+                names[props..].iter().all(|name| *name == "tuv"),
+                "{:?}",
+                names
+            );
+            for tuv in tu.children().filter(|n| n.has_tag_name("tuv")) {
+                assert!(tuv.attribute((XML_NAMESPACE, "lang")).is_some());
+                let segs: Vec<Node> = tuv.children().filter(|n| n.is_element()).collect();
+                assert_eq!(segs.len(), 1);
+                assert_eq!(segs[0].tag_name().name(), "seg");
+            }
+        }
+        assert!(text.contains("<seg>Enregistrer &lt;b&gt;&amp;&lt;/b&gt;</seg>"));
+        assert!(text.contains("<prop type=\"x-plural-index\">1</prop>"));
+    }
+
+    #[test]
+    fn exports_load_back_as_a_memory() {
+        let (exported, _) = exported("memory");
+        let other = env::temp_dir().join(format!("po-llm-memory-legacy-{}.tmx", process::id()));
+        fs::write(
+            &other,
+            r#"<?xml version="1.0"?>
+<tmx version="1.1">
+  <header srclang="EN-US"/>
+  <body>
+    <tu>
+      <tuv lang="en_US"><seg>Open</seg></tuv>
+      <tuv lang="fr_CA"><seg>Ouvrir (CA)</seg></tuv>
+      <tuv lang="de"><seg>Öffnen</seg></tuv>
+    </tu>
+    <tu>
+      <tuv lang="en"><seg>Save the file</seg></tuv>
+      <tuv lang="de-DE"><seg>Datei speichern</seg></tuv>
+    </tu>
+  </body>
+</tmx>
+"#,
+        )
+        .unwrap();
+        let memory = TranslationMemory::load(&[&exported, &other], "en");
+        fs::remove_file(&exported).unwrap();
+        fs::remove_file(&other).unwrap();
+        let memory = memory.unwrap();
+
+        // The export's plural forms are left out.
+        assert_eq!(memory.len(), 3);
+        assert_eq!(memory.exact("One file", None, "fr"), None);
+        // An earlier file wins, and its x-context ties the entry to it.
+        assert_eq!(memory.exact("Open", Some("menu"), "fr"), Some("Ouvrir"));
+        assert_eq!(memory.exact("Open", None, "fr-CA"), Some("Ouvrir (CA)"));
+        assert_eq!(memory.exact("Open", None, "DE_at"), Some("Öffnen"));
+        assert_eq!(
+            memory.exact("Save <b>&</b>", None, "fr"),
+            Some("Enregistrer <b>&</b>")
+        );
+        assert_eq!(
+            memory.suggestions("Save the files", "de", 0.5),
+            [("Save the file", "Datei speichern")]
+        );
+    }
+}
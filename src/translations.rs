@@ -1,6 +1,33 @@
-use std::{collections::HashSet, path::Path};
+use std::{
+    any::Any,
+    collections::{HashMap, HashSet},
+    fs,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
 
-use polib::{catalog::Catalog, message::Message, metadata::CatalogMetadata, po_file};
+use polib::{
+    catalog::Catalog,
+    message::{Message, MessageFlags, MessageMutView, MessageView},
+    metadata::CatalogMetadata,
+    po_file,
+};
+use regex::Regex;
+
+use crate::{
+    charsets::{encode, read_catalog, set_charset},
+    configs::{DocumentFormat, WrapMode},
+    errors::PoLlmError,
+    files::{FileGuard, write_atomically},
+    mo_writer::write_mo,
+    msgfmt::{check_po_file, message_keys, previous_sources},
+    plurals::plural_forms_or_default,
+    po_writer::write_catalog,
+    report::Finding,
+    tmx::catalog_translations,
+    usage::estimate_tokens,
+    validations::{check_coverage, check_roundtrip},
+};
 
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize, schemars::JsonSchema)]
 pub struct TranslationUnit {
@@ -13,17 +40,53 @@ pub struct TranslationUnit {
     pub msg_str_plural: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub context: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comments: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rationale: Option<String>,
+    #[serde(skip)]
+    pub failure: Option<String>,
+    #[serde(skip)]
+    pub warnings: Vec<String>,
+    /// The validation rules the translation broke, for the report.
+    #[serde(skip)]
+    pub findings: Vec<Finding>,
+    #[serde(skip)]
+    pub attribution: Option<String>,
+    /// The message's gettext `*-format` flags, such as `c-format` or
+    /// `no-python-format`, which decide the placeholders to check.
+    #[serde(skip)]
+    pub format_flags: Vec<String>,
+    /// The message's `#:` source references, written back with it.
+    #[serde(skip)]
+    pub references: String,
+    /// Translated, but to be written as needing review, like a failure.
+    #[serde(skip)]
+    pub fuzzy: bool,
+    /// The source and translation of a fuzzy message before its source
+    /// changed, to be updated rather than translated anew.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous: Option<PreviousTranslation>,
 }
 
+/// What a fuzzy message was translated as before its source changed, from
+/// the `#| msgid` comments msgmerge leaves on it.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize, schemars::JsonSchema)]
+pub struct PreviousTranslation {
+    pub msg_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub msg_id_plural: Option<String>,
+    /// One string per plural form.
+    pub translation: Vec<String>,
+}
+
+/// Previous translations by `(context, msgid)`.
+pub type PreviousSources = HashMap<(Option<String>, String), PreviousTranslation>;
+
 use std::fmt;
 
 impl fmt::Display for TranslationUnit {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let context = match &self.context {
-            Some(c) => format!("[{}] ", c),
-            None => "".to_string(),
-        };
-
         let translation = if let Some(s) = &self.msg_str {
             s.as_str()
         } else if let Some(plural_vec) = &self.msg_str_plural {
@@ -32,57 +95,597 @@ impl fmt::Display for TranslationUnit {
             "No translations"
         };
 
-        let id_display = if self.msg_id_plural.is_some() {
-            format!("{} (plural)", self.msg_id)
+        let plural = if self.msg_id_plural.is_some() {
+            " (plural)"
         } else {
-            self.msg_id.clone()
+            ""
         };
 
-        write!(f, "{}{} => {}", context, id_display, translation)
+        write!(f, "{}{} => {}", self.label(), plural, translation)
     }
 }
 
 impl TranslationUnit {
+    /// The msgid prefixed with its context, telling apart messages that
+    /// share a msgid in log lines.
+    pub fn label(&self) -> String {
+        match &self.context {
+            Some(context) => format!("[{}] {}", context, self.msg_id),
+            None => self.msg_id.clone(),
+        }
+    }
+
     pub fn is_plural(&self) -> bool {
         self.msg_id_plural.is_some()
     }
+
+    pub fn has_format_flag(&self, flag: &str) -> bool {
+        self.format_flags.iter().any(|f| f == flag)
+    }
+
+    pub fn translation_pairs(&self) -> Vec<(&str, &str)> {
+        if let Some(plurals) = &self.msg_str_plural {
+            let plural_source = self.msg_id_plural.as_deref().unwrap_or(&self.msg_id);
+            plurals
+                .iter()
+                .enumerate()
+                .map(|(i, s)| {
+                    let source = if i == 0 { &self.msg_id } else { plural_source };
+                    (source, s.as_str())
+                })
+                .collect()
+        } else if let Some(msg_str) = &self.msg_str {
+            vec![(self.msg_id.as_str(), msg_str.as_str())]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Whether the source text is estimated at more than `max_tokens`.
+    pub fn is_oversized(&self, max_tokens: usize) -> bool {
+        let plural = self.msg_id_plural.as_deref().unwrap_or_default();
+        estimate_tokens(&self.msg_id) + estimate_tokens(plural) > max_tokens
+    }
+
+    /// Whether the message's comments carry `tag` as one of their words, such
+    /// as `#. i18n:ready`.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.comments
+            .as_deref()
+            .is_some_and(|comments| comments.split_whitespace().any(|word| word == tag))
+    }
+
+    pub fn is_passthrough(&self, pattern: &Regex) -> bool {
+        pattern.is_match(&self.msg_id)
+            && self
+                .msg_id_plural
+                .as_deref()
+                .is_none_or(|plural| pattern.is_match(plural))
+    }
+
+    pub fn copied_from_source(mut self, nplurals: usize) -> Self {
+        if let Some(plural) = &self.msg_id_plural {
+            let mut forms = vec![plural.clone(); nplurals.max(1)];
+            forms[0] = self.msg_id.clone();
+            self.msg_str_plural = Some(forms);
+        } else {
+            self.msg_str = Some(self.msg_id.clone());
+        }
+        self
+    }
+
+    pub fn emptied(mut self, nplurals: usize) -> Self {
+        if self.is_plural() {
+            self.msg_str_plural = Some(vec![String::new(); nplurals.max(1)]);
+        } else {
+            self.msg_str = Some(String::new());
+        }
+        self.rationale = None;
+        self.attribution = None;
+        self
+    }
+
+    pub fn attributed(mut self, attribution: &str, previous: &Regex) -> Self {
+        self.comments = self.comments.and_then(|comments| {
+            let kept: Vec<&str> = comments
+                .lines()
+                .filter(|line| !previous.is_match(line.trim()))
+                .collect();
+            (!kept.is_empty()).then(|| kept.join("\n"))
+        });
+        self.attribution = Some(attribution.to_string());
+        self
+    }
+
+    pub fn failed(mut self, reason: impl Into<String>) -> Self {
+        self.failure = Some(reason.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WriteOptions {
+    pub wrap: Option<WrapMode>,
+    pub charset: Option<String>,
+    pub verify_roundtrip: bool,
+    /// Compile each PO output to a sibling `.mo` once it is complete.
+    pub compile_mo: bool,
+    /// Matches the attribution comments of earlier runs, which an entry's
+    /// new attribution replaces.
+    pub attribution: Option<Regex>,
 }
 
-pub trait Translatable {
-    fn extract_messages(
-        po_data: Catalog,
-        pot_data: Catalog,
+/// A parsed file, in the representation of the adapter that loaded it.
+pub type Document = Box<dyn Any + Send + Sync>;
+
+/// A file format the pipeline can translate. Adapters are listed in
+/// `formats::ADAPTERS`, which picks one per input by its configured format or
+/// its extension.
+pub trait FormatAdapter: Send + Sync {
+    fn format(&self) -> DocumentFormat;
+
+    /// Lowercase file extensions, without the dot, that select this format
+    /// when none is configured.
+    fn extensions(&self) -> &'static [&'static str];
+
+    /// Where the translation of `input_path` goes when the format dictates
+    /// it; `None` falls back to `output_pattern`.
+    fn output_path(
+        &self,
+        _input_path: &Path,
+        _target_lang: &str,
+    ) -> Result<Option<PathBuf>, PoLlmError> {
+        Ok(None)
+    }
+
+    /// Writes the output file a translation starts from, by default a copy
+    /// of the input whose targets are filled in later.
+    fn create_output(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+        _target_lang: &str,
+    ) -> Result<(), PoLlmError> {
+        fs::copy(input_path, output_path)
+            .map(|_| ())
+            .map_err(|e| PoLlmError::io(format!("Failed to copy {:?}", input_path), e))
+    }
+
+    fn load_template(&self, path: &Path) -> Result<Document, PoLlmError>;
+
+    /// The output as it stands, or what a new output would start as when it
+    /// does not exist yet.
+    fn load_existing(
+        &self,
+        output_path: &Path,
+        template: &Document,
+    ) -> Result<Document, PoLlmError>;
+
+    /// The template's messages, less the ones `existing` already translates
+    /// when `skip_translated` is set.
+    fn extract_units(
+        &self,
+        existing: &Document,
+        template: &Document,
         skip_translated: bool,
     ) -> Vec<TranslationUnit>;
 
-    fn apply_translations(
+    fn write_translations(
+        &self,
         translations: Vec<TranslationUnit>,
         target_lang: &str,
-        output_path: &Path,
-    ) -> Result<(), String>;
+        output: &FileGuard,
+        options: &WriteOptions,
+    ) -> Result<(), PoLlmError>;
+
+    /// Runs once every translation of a language has been written.
+    fn finish_output(
+        &self,
+        _input_path: &Path,
+        _output_path: &Path,
+        _options: &WriteOptions,
+    ) -> Result<(), PoLlmError> {
+        Ok(())
+    }
+
+    /// Entries of `existing` that must never be retranslated.
+    fn locked_count(&self, _existing: &Document) -> usize {
+        0
+    }
+
+    /// Project context found in the template itself.
+    fn header_context(&self, _template: &Document) -> Option<String> {
+        None
+    }
+
+    /// Checks that the output holds every template message exactly once;
+    /// `None` when the format has no such check.
+    fn verify_coverage(
+        &self,
+        _input_path: &Path,
+        _output_path: &Path,
+    ) -> Option<Result<(), PoLlmError>> {
+        None
+    }
+
+    /// Units for translatable comments of the template, translated along
+    /// with its messages when `translate_comments` is set.
+    fn extract_comments(
+        &self,
+        _existing: &Document,
+        _template: &Document,
+        _skip_translated: bool,
+    ) -> Vec<TranslationUnit> {
+        Vec::new()
+    }
+
+    /// The finished translations of the output at `output_path`, for TMX
+    /// exports and compendium conflicts.
+    fn existing_translations(
+        &self,
+        _output_path: &Path,
+    ) -> Result<Vec<TranslationUnit>, PoLlmError> {
+        Ok(Vec::new())
+    }
+
+    /// Problems a compiler of the format would find in the output at
+    /// `output_path`; `None` when the format has no such check.
+    fn check_output(&self, _output_path: &Path) -> Option<Result<Vec<String>, PoLlmError>> {
+        None
+    }
+
+    /// Whether `compendium_pattern` applies to outputs of this format.
+    fn uses_compendium(&self) -> bool {
+        false
+    }
+}
+
+/// The concrete document behind `document`, for the adapter that loaded it.
+pub(crate) fn document_as<T: 'static>(document: &Document) -> &T {
+    document
+        .downcast_ref()
+        .expect("document was loaded by another format's adapter")
+}
+
+/// The output when it exists and parses, the template otherwise; for formats
+/// whose outputs start as a copy of their input.
+pub(crate) fn existing_or_template<T>(
+    output_path: &Path,
+    template: &Document,
+    read: fn(&Path) -> Result<T, PoLlmError>,
+) -> Result<Document, PoLlmError>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    let template: &T = document_as(template);
+    let document = if fs::metadata(output_path).is_ok_and(|m| m.len() > 0) {
+        match read(output_path) {
+            Ok(document) => document,
+            Err(e) => {
+                eprintln!(
+                    "         ⚠️  Failed to parse existing output, using input as template: {}",
+                    e
+                );
+                template.clone()
+            }
+        }
+    } else {
+        println!("         Output doesn't exist, using input as template");
+        template.clone()
+    };
+    Ok(Box::new(document))
 }
 
 pub struct GettextAdapter;
 
-impl Translatable for GettextAdapter {
-    fn extract_messages(
-        po_data: Catalog,
-        pot_data: Catalog,
+impl FormatAdapter for GettextAdapter {
+    fn format(&self) -> DocumentFormat {
+        DocumentFormat::Gettext
+    }
+
+    /// Gettext is also the fallback for unknown extensions.
+    fn extensions(&self) -> &'static [&'static str] {
+        &["po", "pot"]
+    }
+
+    fn create_output(
+        &self,
+        _input_path: &Path,
+        output_path: &Path,
+        _target_lang: &str,
+    ) -> Result<(), PoLlmError> {
+        fs::File::create(output_path)
+            .map(|_| ())
+            .map_err(|e| PoLlmError::io(format!("Failed to create {:?}", output_path), e))
+    }
+
+    fn load_template(&self, path: &Path) -> Result<Document, PoLlmError> {
+        let pot = read_catalog(path)
+            .map_err(|e| e.context(format!("Failed to parse POT file {:?}", path)))?;
+        Ok(Box::new(pot))
+    }
+
+    fn load_existing(
+        &self,
+        output_path: &Path,
+        template: &Document,
+    ) -> Result<Document, PoLlmError> {
+        let pot: &Catalog = document_as(template);
+        println!("         POT messages: {}", pot.count());
+
+        let po = if output_path.exists() {
+            match read_catalog(output_path) {
+                Ok(po) => {
+                    println!("         PO messages: {}", po.count());
+                    let previous = previous_sources(output_path).unwrap_or_else(|e| {
+                        eprintln!("         ⚠️  Failed to read previous msgids: {}", e);
+                        PreviousSources::new()
+                    });
+                    if !previous.is_empty() {
+                        println!("         Fuzzy with previous msgid: {}", previous.len());
+                    }
+                    ExistingCatalog {
+                        catalog: po,
+                        previous,
+                    }
+                }
+                Err(e) => {
+                    eprintln!(
+                        "         ⚠️  Failed to parse existing PO file, using POT as template: {}",
+                        e
+                    );
+                    ExistingCatalog::from(pot.clone())
+                }
+            }
+        } else {
+            println!("         PO file doesn't exist, using POT as template");
+            ExistingCatalog::from(pot.clone())
+        };
+        Ok(Box::new(po))
+    }
+
+    fn extract_units(
+        &self,
+        existing: &Document,
+        template: &Document,
+        skip_translated: bool,
+    ) -> Vec<TranslationUnit> {
+        let po: &ExistingCatalog = document_as(existing);
+        Self::extract_messages(
+            &po.catalog,
+            document_as(template),
+            &po.previous,
+            skip_translated,
+        )
+    }
+
+    fn write_translations(
+        &self,
+        translations: Vec<TranslationUnit>,
+        target_lang: &str,
+        output: &FileGuard,
+        options: &WriteOptions,
+    ) -> Result<(), PoLlmError> {
+        let output_path = output.path();
+        let metadata = default_metadata(target_lang)?;
+
+        // An output that does not parse is left as it is rather than
+        // replaced, with every translation it holds, by a new catalog.
+        let mut catalog = if fs::metadata(output_path).is_ok_and(|m| m.len() > 0) {
+            read_catalog(output_path).map_err(|e| {
+                PoLlmError::Parse(format!(
+                    "Failed to parse existing PO file {:?}, not writing it: {}",
+                    output_path, e
+                ))
+            })?
+        } else {
+            Catalog::new(metadata)
+        };
+
+        for translation in translations {
+            if let Some(key) = translation
+                .context
+                .as_deref()
+                .and_then(|context| context.strip_prefix(COMMENT_CONTEXT))
+            {
+                write_translated_comment(&mut catalog, key, &translation);
+                continue;
+            }
+
+            // A failed entry must not blank out a translation that is already there.
+            if translation.failure.is_some()
+                && catalog
+                    .find_message(
+                        translation.context.as_deref(),
+                        &translation.msg_id,
+                        translation.msg_id_plural.as_deref(),
+                    )
+                    .is_some_and(|msg| msg.is_translated())
+            {
+                continue;
+            }
+
+            match catalog.find_message_mut(
+                translation.context.as_deref(),
+                &translation.msg_id,
+                translation.msg_id_plural.as_deref(),
+            ) {
+                Some(mut message) => {
+                    fill_message(&mut message, translation, options.attribution.as_ref())
+                }
+                None => catalog.append_or_update(build_message(translation)),
+            }
+        }
+
+        write_po(catalog, output_path, options)
+    }
+
+    fn finish_output(
+        &self,
+        _input_path: &Path,
+        output_path: &Path,
+        options: &WriteOptions,
+    ) -> Result<(), PoLlmError> {
+        if !options.compile_mo {
+            return Ok(());
+        }
+        let catalog = read_catalog(output_path)?;
+        let mo_path = output_path.with_extension("mo");
+        write_atomically(&mo_path, |writer| write_mo(&catalog, writer))
+            .map_err(|e| PoLlmError::io(format!("Failed to write MO file {:?}", mo_path), e))
+    }
+
+    fn locked_count(&self, existing: &Document) -> usize {
+        let po: &ExistingCatalog = document_as(existing);
+        po.catalog.messages().filter(|msg| is_locked(*msg)).count()
+    }
+
+    /// The comments above the header entry, without the placeholders
+    /// xgettext writes and the copyright and author lines.
+    fn header_context(&self, template: &Document) -> Option<String> {
+        const BOILERPLATE: &[&str] = &[
+            "SOME DESCRIPTIVE TITLE.",
+            "This file is distributed under the same license as the PACKAGE package.",
+        ];
+        let pot: &Catalog = document_as(template);
+        let lines: Vec<&str> = pot
+            .preheader
+            .iter()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !BOILERPLATE.contains(line))
+            .filter(|line| !line.starts_with("Copyright") && !line.contains('@'))
+            .collect();
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+
+    fn verify_coverage(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+    ) -> Option<Result<(), PoLlmError>> {
+        let check = || {
+            let pot = message_keys(input_path).map_err(PoLlmError::Parse)?;
+            let po = message_keys(output_path).map_err(|e| {
+                PoLlmError::Validation(format!("Failed to re-parse output file: {}", e))
+            })?;
+            check_coverage(&pot, &po).map_err(PoLlmError::Validation)
+        };
+        Some(check())
+    }
+
+    /// A unit per message of `pot_data` with a `translatable-comment:`
+    /// extracted comment, sent with a context naming the message. Comments
+    /// whose message already has a translated comment are skipped when
+    /// `skip_translated` is set.
+    fn extract_comments(
+        &self,
+        existing: &Document,
+        template: &Document,
+        skip_translated: bool,
+    ) -> Vec<TranslationUnit> {
+        let po_data = &document_as::<ExistingCatalog>(existing).catalog;
+        let pot_data: &Catalog = document_as(template);
+
+        pot_data
+            .messages()
+            .filter(|msg| !msg.msgid().is_empty())
+            .filter_map(|msg| {
+                let text = translatable_comment(msg)?;
+                let translated = po_data
+                    .find_message(msg.msgctxt(), msg.msgid(), msg.msgid_plural().ok())
+                    .is_some_and(|po_msg| {
+                        is_locked(po_msg)
+                            || (skip_translated
+                                && !translated_comment_lines(po_msg.translator_comments())
+                                    .is_empty())
+                    });
+                (!translated).then(|| TranslationUnit {
+                    msg_id: text,
+                    msg_id_plural: None,
+                    msg_str: Some(String::new()),
+                    msg_str_plural: None,
+                    context: Some(format!("{}{}", COMMENT_CONTEXT, message_key(msg))),
+                    comments: None,
+                    rationale: None,
+                    failure: None,
+                    warnings: Vec::new(),
+                    findings: Vec::new(),
+                    attribution: None,
+                    format_flags: Vec::new(),
+                    references: String::new(),
+                    fuzzy: false,
+                    previous: None,
+                })
+            })
+            .collect()
+    }
+
+    fn existing_translations(
+        &self,
+        output_path: &Path,
+    ) -> Result<Vec<TranslationUnit>, PoLlmError> {
+        let catalog = read_catalog(output_path)
+            .map_err(|e| e.context(format!("Failed to parse PO file {:?}", output_path)))?;
+        Ok(catalog_translations(&catalog))
+    }
+
+    fn check_output(&self, output_path: &Path) -> Option<Result<Vec<String>, PoLlmError>> {
+        Some(check_po_file(output_path).map_err(PoLlmError::Validation))
+    }
+
+    fn uses_compendium(&self) -> bool {
+        true
+    }
+}
+
+/// The output of a gettext translation, with the previous sources of its
+/// fuzzy messages, which polib does not read.
+struct ExistingCatalog {
+    catalog: Catalog,
+    previous: PreviousSources,
+}
+
+impl From<Catalog> for ExistingCatalog {
+    fn from(catalog: Catalog) -> Self {
+        Self {
+            catalog,
+            previous: PreviousSources::new(),
+        }
+    }
+}
+
+impl GettextAdapter {
+    /// The messages of `pot_data` to translate, given the output `po_data`;
+    /// locked entries are always left out. Fuzzy messages with a
+    /// `previous` translation are translated again even when
+    /// `skip_translated` is set, and carry it as a hint.
+    pub fn extract_messages(
+        po_data: &Catalog,
+        pot_data: &Catalog,
+        previous: &PreviousSources,
         skip_translated: bool,
     ) -> Vec<TranslationUnit> {
         let translated_ids: HashSet<_> = po_data
             .messages()
-            .filter(|msg| skip_translated && msg.is_translated())
+            .filter(|msg| {
+                let outdated = previous
+                    .contains_key(&(msg.msgctxt().map(String::from), msg.msgid().to_string()));
+                is_locked(*msg) || (skip_translated && msg.is_translated() && !outdated)
+            })
             .map(|msg| (msg.msgid().to_string(), msg.msgctxt().map(String::from)))
             .collect();
 
         pot_data
             .messages()
+            .filter(|msg| !msg.msgid().is_empty())
             .filter(|msg| {
                 let key = (msg.msgid().to_string(), msg.msgctxt().map(String::from));
                 !translated_ids.contains(&key)
             })
             .map(|msg| {
+                let previous = previous
+                    .get(&(msg.msgctxt().map(String::from), msg.msgid().to_string()))
+                    .cloned();
                 if msg.is_plural() {
                     TranslationUnit {
                         msg_id: msg.msgid().to_string(),
@@ -90,6 +693,16 @@ impl Translatable for GettextAdapter {
                         msg_str: None,
                         msg_str_plural: Some(vec![]),
                         context: msg.msgctxt().map(String::from),
+                        comments: extracted_comments(msg),
+                        rationale: None,
+                        failure: None,
+                        warnings: Vec::new(),
+                        findings: Vec::new(),
+                        attribution: None,
+                        format_flags: format_flags(msg.flags()),
+                        references: msg.source().to_string(),
+                        fuzzy: false,
+                        previous: previous.clone(),
                     }
                 } else {
                     TranslationUnit {
@@ -98,63 +711,638 @@ impl Translatable for GettextAdapter {
                         msg_str: Some(String::new()),
                         msg_str_plural: None,
                         context: msg.msgctxt().map(String::from),
+                        comments: extracted_comments(msg),
+                        rationale: None,
+                        failure: None,
+                        warnings: Vec::new(),
+                        findings: Vec::new(),
+                        attribution: None,
+                        format_flags: format_flags(msg.flags()),
+                        references: msg.source().to_string(),
+                        fuzzy: false,
+                        previous,
                     }
                 }
             })
             .collect()
     }
 
-    fn apply_translations(
-        translations: Vec<TranslationUnit>,
+    pub fn write_untranslated(
+        units: &[TranslationUnit],
+        target_lang: &str,
+        output_path: &Path,
+        options: &WriteOptions,
+    ) -> Result<(), PoLlmError> {
+        let metadata = default_metadata(target_lang)?;
+        let nplurals = metadata.plural_rules.nplurals;
+        let mut catalog = Catalog::new(metadata);
+
+        for unit in units {
+            catalog.append_or_update(build_message(unit.clone().emptied(nplurals)));
+        }
+
+        write_po(catalog, output_path, options)
+    }
+
+    pub fn write_review(
+        units: &[TranslationUnit],
         target_lang: &str,
         output_path: &Path,
-    ) -> Result<(), String> {
-        let metadata_content = format!(
-            "Project-Id-Version: 1.0\n\
-             Last-Translator: PO-LLM\n\
-             Language-Team: PO-LLM\n\
-             Language: {}\n\
-             MIME-Version: 1.0\n\
-             Content-Type: text/plain; charset=UTF-8\n\
-             Content-Transfer-Encoding: 8bit\n\
-             Plural-Forms: nplurals=2; plural=(n != 1);\n",
-            target_lang
+        options: &WriteOptions,
+    ) -> Result<(), PoLlmError> {
+        let metadata = default_metadata(target_lang)?;
+        let nplurals = metadata.plural_rules.nplurals;
+        let mut catalog = Catalog::new(metadata);
+
+        for unit in units {
+            let mut unit = unit.clone();
+            let mut notes: Vec<String> = unit
+                .failure
+                .take()
+                .map(|reason| format!("Failed: {}", reason))
+                .into_iter()
+                .collect();
+            notes.extend(unit.warnings.iter().map(|w| format!("Check: {}", w)));
+
+            if unit.translation_pairs().iter().all(|(_, t)| t.is_empty()) {
+                unit = unit.emptied(nplurals);
+            }
+
+            let mut message = build_message(unit);
+            *message.translator_comments_mut() = notes.join("\n");
+            message.flags_mut().add_flag("fuzzy");
+            catalog.append_or_update(message);
+        }
+
+        write_po(catalog, output_path, options)
+    }
+}
+
+/// Matches comments produced by an attribution template, whatever model and date
+/// were filled in.
+pub fn attribution_pattern(template: &str) -> Regex {
+    let pattern = regex::escape(template.trim())
+        .replace(r"\{model\}", ".*")
+        .replace(r"\{date\}", ".*");
+    Regex::new(&format!("^{}$", pattern)).expect("escaped template is a valid regex")
+}
+
+pub const LOCK_FLAG: &str = "x-po-llm-locked";
+pub const LOCK_COMMENT: &str = "po-llm:lock";
+
+pub fn is_locked(message: &dyn MessageView) -> bool {
+    message.flags().contains(LOCK_FLAG) || message.translator_comments().contains(LOCK_COMMENT)
+}
+
+fn write_po(
+    mut catalog: Catalog,
+    output_path: &Path,
+    options: &WriteOptions,
+) -> Result<(), PoLlmError> {
+    let charset = options.charset.as_deref().unwrap_or("UTF-8");
+    set_charset(&mut catalog.metadata, charset);
+
+    let mut rendered = BufWriter::new(Vec::new());
+    match options.wrap {
+        Some(wrap) => write_catalog(&catalog, &mut rendered, wrap),
+        None => po_file::write(&catalog, &mut rendered),
+    }
+    .map_err(|e| PoLlmError::io("Failed to write PO file", e))?;
+
+    let rendered = rendered
+        .into_inner()
+        .map_err(|e| PoLlmError::io("Failed to write PO file", e.into_error()))?;
+    let text = String::from_utf8(rendered)
+        .map_err(|e| PoLlmError::Validation(format!("Failed to write PO file: {}", e)))?;
+    let bytes = encode(&text, charset)
+        .map_err(|e| PoLlmError::Validation(format!("Failed to write PO file: {}", e)))?;
+
+    write_atomically(output_path, |writer| writer.write_all(&bytes))
+        .map_err(|e| PoLlmError::io("Failed to write PO file", e))?;
+
+    if options.verify_roundtrip {
+        let reparsed = read_catalog(output_path)
+            .map_err(|e| e.context("Round-trip check failed, cannot re-parse"))?;
+        check_roundtrip(&catalog, &reparsed)
+            .map_err(|e| PoLlmError::Validation(format!("Round-trip check failed: {}", e)))?;
+    }
+
+    Ok(())
+}
+
+fn default_metadata(target_lang: &str) -> Result<CatalogMetadata, PoLlmError> {
+    let metadata_content = format!(
+        "Project-Id-Version: 1.0\n\
+         Last-Translator: PO-LLM\n\
+         Language-Team: PO-LLM\n\
+         Language: {}\n\
+         MIME-Version: 1.0\n\
+         Content-Type: text/plain; charset=UTF-8\n\
+         Content-Transfer-Encoding: 8bit\n\
+         Plural-Forms: {}\n",
+        target_lang,
+        plural_forms_or_default(target_lang)
+    );
+
+    CatalogMetadata::parse(&metadata_content)
+        .map_err(|e| PoLlmError::Parse(format!("Failed to parse metadata: {}", e)))
+}
+
+fn build_message(translation: TranslationUnit) -> Message {
+    let comments = build_extracted_comments(&translation);
+    let translator_comments = translation.attribution.clone().unwrap_or_default();
+    let msgctxt = translation.context.clone().unwrap_or_default();
+    let mut flags = MessageFlags::new();
+    if translation.failure.is_some() || translation.fuzzy {
+        flags.add_flag("fuzzy");
+    }
+    for flag in &translation.format_flags {
+        flags.add_flag(flag);
+    }
+
+    if translation.is_plural() {
+        let msgid_plural = translation.msg_id_plural.unwrap_or_default();
+        let msgstr_plural = translation.msg_str_plural.unwrap_or_default();
+
+        Message::build_plural()
+            .with_translator_comments(translator_comments)
+            .with_extracted_comments(comments)
+            .with_source(translation.references)
+            .with_flags(flags)
+            .with_msgctxt(msgctxt)
+            .with_msgid(translation.msg_id)
+            .with_msgid_plural(msgid_plural)
+            .with_msgstr_plural(msgstr_plural)
+            .done()
+    } else {
+        let msgstr = translation.msg_str.unwrap_or_default();
+
+        Message::build_singular()
+            .with_translator_comments(translator_comments)
+            .with_extracted_comments(comments)
+            .with_source(translation.references)
+            .with_flags(flags)
+            .with_msgctxt(msgctxt)
+            .with_msgid(translation.msg_id)
+            .with_msgstr(msgstr)
+            .done()
+    }
+}
+
+/// Fills a translation into the output's entry for it, keeping the entry's
+/// references, flags and translator comments but for an earlier
+/// attribution, which the translation's replaces.
+fn fill_message(
+    message: &mut dyn MessageMutView,
+    translation: TranslationUnit,
+    previous_attribution: Option<&Regex>,
+) {
+    *message.extracted_comments_mut() = build_extracted_comments(&translation);
+    if message.source().is_empty() {
+        *message.source_mut() = translation.references.clone();
+    }
+
+    let mut flags = MessageFlags::new();
+    if translation.failure.is_some() || translation.fuzzy {
+        flags.add_flag("fuzzy");
+    }
+    for flag in message.flags().iter().chain(&translation.format_flags) {
+        if flag != "fuzzy" {
+            flags.add_flag(flag);
+        }
+    }
+    *message.flags_mut() = flags;
+
+    if let Some(attribution) = &translation.attribution {
+        let comments = message.translator_comments_mut();
+        let mut lines: Vec<String> = comments
+            .lines()
+            .filter(|line| !previous_attribution.is_some_and(|p| p.is_match(line.trim())))
+            .map(String::from)
+            .collect();
+        lines.push(attribution.clone());
+        *comments = lines.join("\n");
+    }
+
+    if let Some(forms) = translation.msg_str_plural {
+        if let Ok(msgstr_plural) = message.msgstr_plural_mut() {
+            *msgstr_plural = forms;
+        }
+    } else if let Some(msgstr) = translation.msg_str {
+        let _ = message.set_msgstr(msgstr);
+    }
+}
+
+/// Marks an extracted comment line as user-facing text to translate.
+const TRANSLATABLE_COMMENT: &str = "translatable-comment:";
+/// Starts the translator comment holding a translated comment.
+const TRANSLATED_COMMENT: &str = "translated-comment:";
+/// Context of a translatable comment's unit, followed by its message's key.
+const COMMENT_CONTEXT: &str = "translatable-comment of ";
+
+/// The text of the message's `translatable-comment:` lines, joined.
+fn translatable_comment(msg: &dyn MessageView) -> Option<String> {
+    let lines: Vec<&str> = msg
+        .extracted_comments()
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix(TRANSLATABLE_COMMENT))
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    (!lines.is_empty()).then(|| lines.join(" "))
+}
+
+fn translated_comment_lines(comments: &str) -> String {
+    comments
+        .lines()
+        .filter(|line| line.starts_with(TRANSLATED_COMMENT))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The message's msgctxt and msgid, as shown in a comment unit's context.
+fn message_key(msg: &dyn MessageView) -> String {
+    match msg.msgctxt() {
+        Some(context) => format!("[{}] {}", context, msg.msgid()),
+        None => msg.msgid().to_string(),
+    }
+}
+
+/// Replaces the translated comment of the message `key` names. Comments
+/// whose translation failed, or whose message is not in the output, are left
+/// alone.
+fn write_translated_comment(catalog: &mut Catalog, key: &str, unit: &TranslationUnit) {
+    let text = match &unit.msg_str {
+        Some(text) if unit.failure.is_none() && !text.trim().is_empty() => text,
+        _ => return,
+    };
+    let Some(mut message) = catalog.messages_mut().find(|msg| message_key(msg) == key) else {
+        eprintln!(
+            "         ⚠️  No entry for the translated comment of '{}' in the output; skipped",
+            key
         );
+        return;
+    };
 
-        let metadata = CatalogMetadata::parse(&metadata_content)
-            .map_err(|e| format!("Failed to parse metadata: {}", e))?;
+    let comments = message.translator_comments_mut();
+    let mut lines: Vec<String> = comments
+        .lines()
+        .filter(|line| !line.starts_with(TRANSLATED_COMMENT))
+        .map(String::from)
+        .collect();
+    lines.push(format!(
+        "{} {}",
+        TRANSLATED_COMMENT,
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    ));
+    *comments = lines.join("\n");
+}
 
-        let mut catalog = if output_path.exists() {
-            polib::po_file::parse(output_path).unwrap_or(Catalog::new(metadata))
-        } else {
-            Catalog::new(metadata)
+/// Starts the extracted comment holding the model's rationale.
+const RATIONALE_COMMENT: &str = "Rationale:";
+/// Starts the extracted comment giving why a translation failed.
+const FAILURE_COMMENT: &str = "TRANSLATION FAILED:";
+
+/// The message's extracted comments, without the rationale and failure
+/// lines an earlier run wrote, which are not the developers'.
+fn extracted_comments(msg: &dyn MessageView) -> Option<String> {
+    let comments: Vec<&str> = msg
+        .extracted_comments()
+        .lines()
+        .filter(|line| {
+            let line = line.trim_start();
+            !line.starts_with(RATIONALE_COMMENT) && !line.starts_with(FAILURE_COMMENT)
+        })
+        .collect();
+    let comments = comments.join("\n");
+    let comments = comments.trim();
+    if comments.is_empty() {
+        None
+    } else {
+        Some(comments.to_string())
+    }
+}
+
+/// The `*-format` flags among a message's flags.
+pub fn format_flags(flags: &MessageFlags) -> Vec<String> {
+    flags
+        .iter()
+        .filter(|flag| flag.ends_with("-format"))
+        .cloned()
+        .collect()
+}
+
+fn build_extracted_comments(unit: &TranslationUnit) -> String {
+    let mut lines: Vec<String> = unit.comments.iter().cloned().collect();
+    if let Some(rationale) = &unit.rationale {
+        let rationale = rationale.lines().next().unwrap_or("").trim();
+        if !rationale.is_empty() {
+            lines.push(format!("{} {}", RATIONALE_COMMENT, rationale));
+        }
+    }
+    if let Some(reason) = &unit.failure {
+        lines.push(format!("{} {}", FAILURE_COMMENT, reason));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::files::FileLocks;
+
+    #[tokio::test]
+    async fn attribution_is_a_translator_comment() {
+        let path = env::temp_dir().join(format!("po-llm-attribution-{}.po", process::id()));
+        let unit: TranslationUnit = serde_json::from_value(json!({
+            "msg_id": "Open",
+            "msg_str": "Ouvrir",
+            "comments": "Toolbar button",
+        }))
+        .unwrap();
+        let previous = attribution_pattern("Translated by po-llm ({model}, {date})");
+        let unit = unit.attributed("Translated by po-llm (m, 2026-01-01)", &previous);
+
+        let locks = FileLocks::new();
+        let guard = locks.lock(&path).await;
+        GettextAdapter
+            .write_translations(
+                vec![unit],
+                "fr",
+                &guard,
+                &WriteOptions {
+                    wrap: Some(WrapMode::Gettext),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(text.ends_with(
+            "\n\n# Translated by po-llm (m, 2026-01-01)\n\
+             #. Toolbar button\n\
+             msgid \"Open\"\n\
+             msgstr \"Ouvrir\"\n"
+        ));
+    }
+
+    fn catalog(entries: &str) -> Catalog {
+        let text = format!(
+            "msgid \"\"\nmsgstr \"Content-Type: text/plain; charset=UTF-8\\n\"\n\n{}",
+            entries
+        );
+        po_file::parse_from_reader(text.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn header_and_trivia_are_not_sent() {
+        let pot = catalog(
+            "msgid \"Open\"\nmsgstr \"\"\n\n\
+             msgid \"42\"\nmsgstr \"\"\n\n\
+             msgid \"🎉✨\"\nmsgstr \"\"\n\n\
+             msgid \" \\t\"\nmsgstr \"\"\n\n\
+             msgid \"1\"\nmsgid_plural \"2+\"\nmsgstr[0] \"\"\nmsgstr[1] \"\"\n",
+        );
+        let units =
+            GettextAdapter::extract_messages(&catalog(""), &pot, &PreviousSources::new(), true);
+        let ids: Vec<&str> = units.iter().map(|unit| unit.msg_id.as_str()).collect();
+        assert_eq!(ids, ["Open", "42", "🎉✨", " \t", "1"]);
+
+        let pattern = Regex::new(r"^[^\p{L}]*$").unwrap();
+        let (trivia, sent): (Vec<_>, Vec<_>) = units
+            .into_iter()
+            .partition(|unit| unit.is_passthrough(&pattern));
+        assert_eq!(sent.len(), 1);
+        let copied: Vec<_> = trivia
+            .into_iter()
+            .map(|unit| unit.copied_from_source(3))
+            .collect();
+        assert_eq!(copied[0].msg_str.as_deref(), Some("42"));
+        assert_eq!(copied[1].msg_str.as_deref(), Some("🎉✨"));
+        assert_eq!(copied[2].msg_str.as_deref(), Some(" \t"));
+        assert_eq!(
+            copied[3].msg_str_plural.as_deref(),
+            Some(&["1".to_string(), "2+".to_string(), "2+".to_string()][..])
+        );
+    }
+
+    #[tokio::test]
+    async fn messages_sharing_a_msgid_stay_apart() {
+        let unit = |context: &str, translation: &str| -> TranslationUnit {
+            serde_json::from_value(json!({
+                "msg_id": "Open",
+                "msg_str": translation,
+                "context": context,
+            }))
+            .unwrap()
         };
+        let menu = unit("menu", "Ouvrir");
+        let state = unit("state", "Ouvert");
+        assert_eq!(menu.label(), "[menu] Open");
+        assert_eq!(state.to_string(), "[state] Open => Ouvert");
 
-        for translation in translations {
-            let message = if translation.is_plural() {
-                let msgid_plural = translation.msg_id_plural.unwrap_or_default();
-                let msgstr_plural = translation.msg_str_plural.unwrap_or_default();
-
-                Message::build_plural()
-                    .with_msgid(translation.msg_id)
-                    .with_msgid_plural(msgid_plural)
-                    .with_msgstr_plural(msgstr_plural)
-                    .done()
-            } else {
-                let msgstr = translation.msg_str.unwrap_or_default();
-
-                Message::build_singular()
-                    .with_msgid(translation.msg_id)
-                    .with_msgstr(msgstr)
-                    .done()
-            };
+        let path = env::temp_dir().join(format!("po-llm-contexts-{}.po", process::id()));
+        let locks = FileLocks::new();
+        let guard = locks.lock(&path).await;
+        GettextAdapter
+            .write_translations(vec![menu, state], "fr", &guard, &WriteOptions::default())
+            .unwrap();
+        let catalog = read_catalog(&path).unwrap();
+        fs::remove_file(&path).unwrap();
 
-            catalog.append_or_update(message);
+        let translation = |context| {
+            catalog
+                .find_message(Some(context), "Open", None)
+                .and_then(|msg| msg.msgstr().ok().map(String::from))
+        };
+        assert_eq!(translation("menu").as_deref(), Some("Ouvrir"));
+        assert_eq!(translation("state").as_deref(), Some("Ouvert"));
+    }
+
+    #[tokio::test]
+    async fn format_flags_and_references_are_written_back() {
+        let pot = catalog(
+            "#: src/a.c:1 src/b.c:7\n#, c-format\nmsgid \"%s saved\"\nmsgstr \"\"\n\n\
+             #: src/a.c:9\n#, python-format\nmsgid \"%(n)d file\"\nmsgid_plural \"%(n)d files\"\n\
+             msgstr[0] \"\"\nmsgstr[1] \"\"\n",
+        );
+        let mut units =
+            GettextAdapter::extract_messages(&catalog(""), &pot, &PreviousSources::new(), true);
+        units[0].msg_str = Some("%s enregistré".to_string());
+        units[1].msg_str_plural = Some(vec![
+            "%(n)d fichier".to_string(),
+            "%(n)d fichiers".to_string(),
+        ]);
+
+        let path = env::temp_dir().join(format!("po-llm-flags-{}.po", process::id()));
+        let locks = FileLocks::new();
+        let guard = locks.lock(&path).await;
+        GettextAdapter
+            .write_translations(
+                units,
+                "fr",
+                &guard,
+                &WriteOptions {
+                    wrap: Some(WrapMode::Gettext),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(text.ends_with(
+            "\n\n#: src/a.c:1 src/b.c:7\n\
+             #, c-format\n\
+             msgid \"%s saved\"\n\
+             msgstr \"%s enregistré\"\n\
+             \n\
+             #: src/a.c:9\n\
+             #, python-format\n\
+             msgid \"%(n)d file\"\n\
+             msgid_plural \"%(n)d files\"\n\
+             msgstr[0] \"%(n)d fichier\"\n\
+             msgstr[1] \"%(n)d fichiers\"\n"
+        ));
+    }
+
+    #[tokio::test]
+    async fn in_place_entries_keep_their_metadata() {
+        let path = env::temp_dir().join(format!("po-llm-in-place-{}.po", process::id()));
+        fs::write(
+            &path,
+            "msgid \"\"\nmsgstr \"\"\n\
+             \"Content-Type: text/plain; charset=UTF-8\\n\"\n\
+             \"Plural-Forms: nplurals=2; plural=(n > 1);\\n\"\n\n\
+             # Reviewed by Jane\n\
+             # Translated by po-llm (old, 2025-01-01)\n\
+             #. Toolbar button\n\
+             #: src/a.c:1\n\
+             #, fuzzy, c-format\n\
+             msgid \"%s saved\"\n\
+             msgstr \"%s sauvé\"\n\n\
+             # Keep short\n\
+             #: src/a.c:9\n\
+             msgid \"Quit\"\n\
+             msgstr \"Quitter\"\n",
+        )
+        .unwrap();
+        let po = read_catalog(&path).unwrap();
+        let mut units = GettextAdapter::extract_messages(&po, &po, &PreviousSources::new(), false);
+        assert_eq!(units.len(), 2);
+        units.truncate(1);
+        units[0].msg_str = Some("%s enregistré".to_string());
+        let previous = attribution_pattern("Translated by po-llm ({model}, {date})");
+        let unit = units
+            .remove(0)
+            .attributed("Translated by po-llm (m, 2026-01-01)", &previous);
+
+        let locks = FileLocks::new();
+        let guard = locks.lock(&path).await;
+        GettextAdapter
+            .write_translations(
+                vec![unit],
+                "fr",
+                &guard,
+                &WriteOptions {
+                    wrap: Some(WrapMode::Gettext),
+                    attribution: Some(previous),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(
+            text.ends_with(
+                "\n\n# Reviewed by Jane\n\
+                 # Translated by po-llm (m, 2026-01-01)\n\
+                 #. Toolbar button\n\
+                 #: src/a.c:1\n\
+                 #, c-format\n\
+                 msgid \"%s saved\"\n\
+                 msgstr \"%s enregistré\"\n\
+                 \n\
+                 # Keep short\n\
+                 #: src/a.c:9\n\
+                 msgid \"Quit\"\n\
+                 msgstr \"Quitter\"\n"
+            ),
+            "{}",
+            text
+        );
+    }
+
+    #[tokio::test]
+    async fn earlier_failure_and_rationale_lines_are_not_kept() {
+        let path = env::temp_dir().join(format!("po-llm-retry-{}.po", process::id()));
+        let entry = "#. Button label\n\
+                     #. TRANSLATION FAILED: timeout\n\
+                     #. Rationale: shorter\n\
+                     #, fuzzy\n\
+                     msgid \"Save\"\n\
+                     msgstr \"\"\n";
+        let po = catalog(entry);
+        let units = GettextAdapter::extract_messages(&po, &po, &PreviousSources::new(), true);
+        assert_eq!(units[0].comments.as_deref(), Some("Button label"));
+
+        let locks = FileLocks::new();
+        let mut written = Vec::new();
+        for failure in [Some("timeout"), None] {
+            fs::write(
+                &path,
+                format!(
+                    "msgid \"\"\nmsgstr \"Content-Type: text/plain; charset=UTF-8\\n\"\n\n{}",
+                    entry
+                ),
+            )
+            .unwrap();
+            let mut unit = units[0].clone();
+            unit.msg_str = Some(if failure.is_some() { "" } else { "Enregistrer" }.to_string());
+            unit.failure = failure.map(String::from);
+            let guard = locks.lock(&path).await;
+            GettextAdapter
+                .write_translations(vec![unit], "fr", &guard, &WriteOptions::default())
+                .unwrap();
+            let catalog = read_catalog(&path).unwrap();
+            let message = catalog.find_message(None, "Save", None).unwrap();
+            written.push(message.extracted_comments().to_string());
         }
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            written,
+            ["Button label\nTRANSLATION FAILED: timeout", "Button label"]
+        );
+    }
 
-        po_file::write_to_file(&catalog, output_path)
-            .map_err(|e| format!("Failed to write PO file: {}", e))?;
+    #[tokio::test]
+    async fn written_output_is_checked_against_its_format_flags() {
+        let pot = catalog("#, c-format\nmsgid \"%s saved\"\nmsgstr \"\"\n");
+        let mut units =
+            GettextAdapter::extract_messages(&catalog(""), &pot, &PreviousSources::new(), true);
+        units[0].msg_str = Some("%d enregistré".to_string());
 
-        Ok(())
+        let path = env::temp_dir().join(format!("po-llm-checked-{}.po", process::id()));
+        let locks = FileLocks::new();
+        let guard = locks.lock(&path).await;
+        GettextAdapter
+            .write_translations(units, "fr", &guard, &WriteOptions::default())
+            .unwrap();
+        let problems = GettextAdapter.check_output(&path).unwrap().unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+        assert!(
+            problems[0].contains("\"%s saved\": printf conversions changed"),
+            "{}",
+            problems[0]
+        );
     }
 }
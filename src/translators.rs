@@ -1,17 +1,54 @@
-use anyhow::Result;
 use async_openai::{
     Client,
     config::Config,
+    error::OpenAIError,
     types::chat::{
         ChatCompletionRequestSystemMessage, ChatCompletionRequestUserMessage,
-        CreateChatCompletionRequestArgs, ResponseFormat, ResponseFormatJsonSchema,
+        ChatCompletionStreamOptions, CompletionUsage, CreateChatCompletionRequest,
+        CreateChatCompletionRequestArgs, FinishReason, ResponseFormat, ResponseFormatJsonSchema,
+        StopConfiguration,
     },
 };
 use async_trait::async_trait;
+use futures::StreamExt;
 use schemars::schema_for;
-use std::collections::HashMap;
+use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    process::Stdio,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    combined::combined_key,
+    configs::SchemaDialect,
+    errors::PoLlmError,
+    hooks::shell,
+    plurals::plural_forms_or_default,
+    translations::TranslationUnit,
+    usage::{RetryBudget, TokenUsage},
+    validations::accelerator_marker,
+};
 
-use crate::translations::TranslationUnit;
+/// Retries of a single request before its error is returned.
+const MAX_RETRIES_PER_REQUEST: u32 = 6;
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// API error types and codes that retrying cannot fix.
+const PERMANENT_ERRORS: &[&str] = &[
+    "invalid_request_error",
+    "authentication_error",
+    "permission_error",
+    "not_found_error",
+    "insufficient_quota",
+    "invalid_api_key",
+    "model_not_found",
+    "context_length_exceeded",
+];
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
 pub struct TranslationResult {
@@ -26,7 +63,7 @@ pub trait Translator {
         target_lang: &str,
         translation_units: &[TranslationUnit],
         custom_prompt: &Option<String>,
-    ) -> Result<TranslationResult>;
+    ) -> Result<TranslationResult, PoLlmError>;
 }
 
 pub struct DryRunTranslator;
@@ -38,7 +75,9 @@ impl Translator for DryRunTranslator {
         target_lang: &str,
         translation_units: &[TranslationUnit],
         _custom_prompt: &Option<String>,
-    ) -> Result<TranslationResult> {
+    ) -> Result<TranslationResult, PoLlmError> {
+        let nplurals = plural_forms_or_default(target_lang).nplurals;
+
         Ok(TranslationResult {
             translated: translation_units
                 .iter()
@@ -46,14 +85,15 @@ impl Translator for DryRunTranslator {
                     let mut result = unit.clone();
 
                     if unit.is_plural() {
-                        result.msg_str_plural = Some(vec![
-                            format!("[DRY:{}] {}", target_lang, unit.msg_id),
-                            format!(
-                                "[DRY:{}] {}",
-                                target_lang,
-                                unit.msg_id_plural.as_ref().unwrap()
-                            ),
-                        ]);
+                        let plural = unit.msg_id_plural.as_ref().unwrap();
+                        result.msg_str_plural = Some(
+                            (0..nplurals)
+                                .map(|i| {
+                                    let source = if i == 0 { &unit.msg_id } else { plural };
+                                    format!("[DRY:{}] {}", target_lang, source)
+                                })
+                                .collect(),
+                        );
                     } else {
                         result.msg_str = Some(format!("[DRY:{}] {}", target_lang, unit.msg_id));
                     }
@@ -66,64 +106,295 @@ impl Translator for DryRunTranslator {
     }
 }
 
-pub struct LlmTranslator<T: Config> {
-    pub client: Client<T>,
-    pub model: String,
-    pub system_prompt: String,
-    pub project_context: String,
+pub struct BisectingTranslator<T> {
+    pub inner: T,
 }
 
 #[async_trait]
-impl<M> Translator for LlmTranslator<M>
+impl<T> Translator for BisectingTranslator<T>
 where
-    M: Config,
+    T: Translator + Send + Sync,
 {
     async fn translate(
         &self,
         target_lang: &str,
         translation_units: &[TranslationUnit],
         custom_prompt: &Option<String>,
-    ) -> Result<TranslationResult> {
-        if translation_units.is_empty() {
-            return Ok(TranslationResult {
-                translated: vec![],
-                failed_translated: vec![],
-            });
+    ) -> Result<TranslationResult, PoLlmError> {
+        let total = translation_units.len();
+
+        let (mut translated, retry) = match self
+            .inner
+            .translate(target_lang, translation_units, custom_prompt)
+            .await
+        {
+            Ok(result) if total <= 1 || result.failed_translated.len() * 2 < total => {
+                return Ok(result);
+            }
+            Ok(result) => {
+                eprintln!(
+                    "      ⚠️  {} of {} messages failed in {}, retrying them in smaller batches",
+                    result.failed_translated.len(),
+                    total,
+                    target_lang
+                );
+                (result.translated, result.failed_translated)
+            }
+            Err(e) if total == 1 => {
+                let unit = &translation_units[0];
+                eprintln!(
+                    "      ⚠️  Isolated failing message '{}' in {}: {:#}",
+                    unit.label(),
+                    target_lang,
+                    e
+                );
+                return Ok(TranslationResult {
+                    translated: Vec::new(),
+                    failed_translated: vec![unit.clone().failed(format!("{:#}", e))],
+                });
+            }
+            Err(e) => {
+                eprintln!(
+                    "      ⚠️  Batch of {} messages failed in {}, splitting it: {:#}",
+                    total, target_lang, e
+                );
+                (Vec::new(), translation_units.to_vec())
+            }
+        };
+
+        let retry: Vec<TranslationUnit> = retry
+            .into_iter()
+            .map(|mut unit| {
+                unit.failure = None;
+                unit
+            })
+            .collect();
+        let (left, right) = retry.split_at(retry.len().div_ceil(2));
+
+        let mut failed = Vec::new();
+        for half in [left, right] {
+            if half.is_empty() {
+                continue;
+            }
+            let result = self.translate(target_lang, half, custom_prompt).await?;
+            translated.extend(result.translated);
+            failed.extend(result.failed_translated);
+        }
+
+        Ok(TranslationResult {
+            translated,
+            failed_translated: failed,
+        })
+    }
+}
+
+pub struct LlmTranslator<T: Config> {
+    pub client: Client<T>,
+    pub model: String,
+    pub system_prompt: String,
+    pub all_target_langs: Vec<String>,
+    pub project_context: String,
+    pub store_rationale: bool,
+    pub markdown_aware: bool,
+    pub schema_dialect: SchemaDialect,
+    pub stop: Option<Vec<String>>,
+    pub frequency_penalty: Option<f32>,
+    pub presence_penalty: Option<f32>,
+    pub term_hints: Vec<(String, String)>,
+    /// Similar translation memory entries, by msgid.
+    pub suggestions: HashMap<String, Vec<(String, String)>>,
+    pub accelerator_markers: Vec<char>,
+    pub stream: bool,
+    pub on_progress: Option<Arc<dyn Fn(usize) + Send + Sync>>,
+    pub usage: Option<Arc<TokenUsage>>,
+    pub retries: Option<Arc<RetryBudget>>,
+}
+
+impl<M: Config> LlmTranslator<M> {
+    async fn receive_stream(
+        &self,
+        mut request: CreateChatCompletionRequest,
+        target_lang: &str,
+        expected: usize,
+    ) -> Result<String, PoLlmError> {
+        request.stream = Some(true);
+        request.stream_options = Some(ChatCompletionStreamOptions {
+            include_usage: Some(true),
+            include_obfuscation: None,
+        });
+
+        let mut stream = self
+            .with_retries(target_lang, || async {
+                self.client.chat().create_stream(request.clone()).await
+            })
+            .await
+            .map_err(|e| api_error(target_lang, e))?;
+
+        let mut content = String::new();
+        let mut counter = CompletedObjects::default();
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| api_error(target_lang, e))?;
+
+            if let Some(usage) = &chunk.usage {
+                self.record_usage(usage);
+            }
+
+            for choice in chunk.choices {
+                if let Some(delta) = choice.delta.content {
+                    let before = counter.completed;
+                    counter.feed(&delta);
+                    content.push_str(&delta);
+
+                    if counter.completed != before
+                        && let Some(on_progress) = &self.on_progress
+                    {
+                        on_progress(counter.completed);
+                    }
+                }
+
+                if choice.finish_reason == Some(FinishReason::Length) {
+                    return Err(PoLlmError::Schema(format!(
+                        "LLM response for language '{}' was truncated (finish_reason = length) after {} of {} translations. Reduce batch_size or raise the model's output token limit.",
+                        target_lang, counter.completed, expected
+                    )));
+                }
+            }
+        }
+
+        if content.is_empty() {
+            return Err(empty_response_error(target_lang));
         }
+        Ok(content)
+    }
+
+    /// Sends a request, retrying rate limits, server errors and dropped
+    /// connections with exponential backoff while the run's retry budget lasts.
+    async fn with_retries<T, F, Fut>(&self, target_lang: &str, send: F) -> Result<T, OpenAIError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, OpenAIError>>,
+    {
+        let mut delay = INITIAL_RETRY_DELAY;
+        let mut attempt = 0;
+        loop {
+            let error = match send().await {
+                Err(e) if is_transient(&e) && attempt < MAX_RETRIES_PER_REQUEST => e,
+                result => return result,
+            };
+            if let Some(budget) = &self.retries
+                && !budget.take()
+            {
+                eprintln!(
+                    "      ⚠️  Retry budget of {} exhausted, not retrying {}",
+                    budget.limit().unwrap_or_default(),
+                    target_lang
+                );
+                return Err(error);
+            }
 
+            attempt += 1;
+            eprintln!(
+                "      🔁 Transient API error for {} ({}), retrying in {}s ({}/{})",
+                target_lang,
+                error,
+                delay.as_secs(),
+                attempt,
+                MAX_RETRIES_PER_REQUEST
+            );
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(MAX_RETRY_DELAY);
+        }
+    }
+
+    /// The prompt lines describing one message.
+    fn unit_prompt(&self, idx: usize, unit: &TranslationUnit) -> String {
         let mut prompt = String::new();
-        for (idx, unit) in translation_units.iter().enumerate() {
-            prompt.push_str(&format!("**Index**: {}\n", idx));
-            prompt.push_str(&format!("Source: {}\n", unit.msg_id));
-            if let Some(ctx) = &unit.context {
-                prompt.push_str(&format!("Context: {}\n", ctx));
+        prompt.push_str(&format!("**Index**: {}\n", idx));
+        prompt.push_str(&format!("Source: {}\n", unit.msg_id));
+        if let Some(ctx) = &unit.context {
+            prompt.push_str(&format!("Context: {}\n", ctx));
+        }
+        if let Some(plural) = &unit.msg_id_plural {
+            prompt.push_str(&format!("Plural Source: {}\n", plural));
+        }
+        if let Some(comments) = &unit.comments {
+            prompt.push_str(&format!("Developer Comments: {}\n", comments));
+        }
+        if let Some(previous) = &unit.previous {
+            prompt.push_str(&format!("Previous Source: {}\n", previous.msg_id));
+            if let Some(plural) = &previous.msg_id_plural {
+                prompt.push_str(&format!("Previous Plural Source: {}\n", plural));
             }
-            if let Some(plural) = &unit.msg_id_plural {
-                prompt.push_str(&format!("Plural Source: {}\n", plural));
+            for (i, form) in previous.translation.iter().enumerate() {
+                if previous.translation.len() == 1 {
+                    prompt.push_str(&format!("Previous Translation: {}\n", form));
+                } else {
+                    prompt.push_str(&format!("Previous Translation [{}]: {}\n", i, form));
+                }
             }
-            prompt.push_str("\n---\n");
+            prompt.push_str(
+                "Update: the source changed from Previous Source; revise Previous Translation only where the change requires it\n",
+            );
+        }
+        for (source, translation) in self.suggestions.get(&unit.msg_id).into_iter().flatten() {
+            prompt.push_str(&format!(
+                "Similar (translation memory): {} → {}\n",
+                source, translation
+            ));
         }
+        if let Some(marker) = accelerator_marker(&unit.msg_id, &self.accelerator_markers) {
+            prompt.push_str(&format!(
+                "Accelerator: keep exactly one '{0}' before a suitable letter of the translation ('{0}{0}' is a literal '{0}')\n",
+                marker
+            ));
+        }
+        prompt
+    }
 
+    /// The system prompt for `target_lang`, with the optional sections
+    /// shared by every request.
+    fn system_content(&self, target_lang: &str, custom_prompt: &Option<String>) -> String {
         let custom_prompt_text = match custom_prompt {
             Some(content) => format!("## User Instructions:\n{}\n", content),
             None => String::new(),
         };
 
-        let system_content = self
+        let mut system_content = self
             .system_prompt
             .replace("{target_lang}", target_lang)
+            .replace("{all_target_langs}", &self.all_target_langs.join(", "))
             .replace("{project_context}", &self.project_context)
             .replace("{custom_prompt}", &custom_prompt_text);
 
-        #[derive(schemars::JsonSchema, serde::Deserialize)]
-        struct LlmResponseUnit {
-            index: usize,
-            msg_str: Option<String>,
-            msg_str_plural: Option<Vec<String>>,
+        if self.markdown_aware {
+            system_content.push_str(
+                "\n\nMarkdown: Source texts may contain Markdown. Keep the structure intact: translate link text but never the URL in [text](url), leave `code spans` and fenced code blocks untouched, and keep list markers and heading levels.",
+            );
         }
 
-        let schema_value = schema_for!(Vec<LlmResponseUnit>).to_value();
+        if !self.term_hints.is_empty() {
+            system_content.push_str(
+                "\n\nTerminology: Use these approved translations when the terms appear:",
+            );
+            for (term, translation) in &self.term_hints {
+                system_content.push_str(&format!("\n- {} → {}", term, translation));
+            }
+        }
 
+        system_content
+    }
+
+    /// Sends the prompts with the response schema and returns the response
+    /// content, streamed or not.
+    async fn complete(
+        &self,
+        system_content: String,
+        prompt: String,
+        schema_value: Value,
+        target_lang: &str,
+        expected: usize,
+    ) -> Result<String, PoLlmError> {
         let schema = ResponseFormat::JsonSchema {
             json_schema: ResponseFormatJsonSchema {
                 description: None,
@@ -133,7 +404,7 @@ where
             },
         };
 
-        let request = CreateChatCompletionRequestArgs::default()
+        let mut request = CreateChatCompletionRequestArgs::default()
             .model(&self.model)
             .messages([
                 ChatCompletionRequestSystemMessage::from(system_content).into(),
@@ -141,48 +412,324 @@ where
             ])
             .response_format(schema)
             .build()
-            .map_err(|e| anyhow::anyhow!("Failed to build API request: {}", e))?;
-
-        let response = self
-            .client
-            .chat()
-            .create(request)
-            .await
-            .map_err(|e| {
-                anyhow::anyhow!(
-                    "LLM API call failed for language '{}': {}. Check your API key, base URL, and network connectivity.",
-                    target_lang,
-                    e
-                )
+            .map_err(|e| PoLlmError::Api {
+                status: None,
+                message: format!("Failed to build API request: {}", e),
             })?;
 
-        let content = response
-            .choices
-            .first()
-            .and_then(|choice| choice.message.content.as_ref())
-            .ok_or_else(|| {
-                anyhow::anyhow!(
-                    "LLM returned empty response for language '{}'. The model may not support structured outputs or encountered an error.",
-                    target_lang
-                )
-            })?;
+        request.stop = self
+            .stop
+            .clone()
+            .filter(|stop| !stop.is_empty())
+            .map(StopConfiguration::StringArray);
+        request.frequency_penalty = self.frequency_penalty;
+        request.presence_penalty = self.presence_penalty;
+
+        let content = if self.stream {
+            self.receive_stream(request, target_lang, expected).await?
+        } else {
+            let response = self
+                .with_retries(target_lang, || async {
+                    self.client.chat().create(request.clone()).await
+                })
+                .await
+                .map_err(|e| api_error(target_lang, e))?;
+
+            if let Some(usage) = &response.usage {
+                self.record_usage(usage);
+            }
+
+            response
+                .choices
+                .into_iter()
+                .next()
+                .and_then(|choice| choice.message.content)
+                .ok_or_else(|| empty_response_error(target_lang))?
+        };
+
+        Ok(content)
+    }
+
+    /// Translates each message into the languages listed with it, all in one
+    /// request. Returns per language the messages that came back translated;
+    /// missing or blank ones are left out for the language to retry alone.
+    pub async fn translate_languages(
+        &self,
+        messages: &[(TranslationUnit, Vec<String>)],
+        custom_prompt: &Option<String>,
+    ) -> Result<HashMap<String, Vec<TranslationUnit>>, PoLlmError> {
+        let mut langs: Vec<&str> = Vec::new();
+        for lang in messages.iter().flat_map(|(_, langs)| langs) {
+            if !langs.contains(&lang.as_str()) {
+                langs.push(lang);
+            }
+        }
+        let target_langs = langs.join(", ");
+
+        let mut prompt = String::new();
+        for (idx, (unit, langs)) in messages.iter().enumerate() {
+            prompt.push_str(&self.unit_prompt(idx, unit));
+            let langs: Vec<String> = if unit.is_plural() {
+                langs
+                    .iter()
+                    .map(|lang| {
+                        format!(
+                            "{} ({} plural forms)",
+                            lang,
+                            plural_forms_or_default(lang).nplurals
+                        )
+                    })
+                    .collect()
+            } else {
+                langs.clone()
+            };
+            prompt.push_str(&format!("Languages: {}\n", langs.join(", ")));
+            prompt.push_str("\n---\n");
+        }
+
+        let mut system_content = self.system_content(&target_langs, custom_prompt);
+        system_content.push_str(
+            "\n\nSeveral languages: Translate each text into every language listed under its \"Languages\". Instead of the output described above, return one object per index with the \"index\" and a \"translations\" array holding, for each listed language, an object with \"lang\" (the code as listed), \"msg_str\" and \"msg_str_plural\" (with that language's number of plural forms).",
+        );
+
+        #[derive(schemars::JsonSchema, serde::Deserialize)]
+        struct LlmLanguageTranslation {
+            lang: String,
+            msg_str: Option<String>,
+            msg_str_plural: Option<Vec<String>>,
+        }
 
-        let results: Vec<LlmResponseUnit> = serde_json::from_str(content).map_err(|e| {
-            anyhow::anyhow!(
+        #[derive(schemars::JsonSchema, serde::Deserialize)]
+        struct LlmMultiResponseUnit {
+            index: usize,
+            translations: Vec<LlmLanguageTranslation>,
+        }
+
+        let schema_value = adapt_schema(
+            schema_for!(Vec<LlmMultiResponseUnit>).to_value(),
+            self.schema_dialect,
+        );
+        let content = self
+            .complete(
+                system_content,
+                prompt,
+                schema_value,
+                &target_langs,
+                messages.len(),
+            )
+            .await?;
+
+        let results: Vec<LlmMultiResponseUnit> = serde_json::from_str(&content).map_err(|e| {
+            PoLlmError::Schema(format!(
+                "Failed to parse LLM JSON response for languages '{}':\n  Parse error: {}\n  Response preview: {}",
+                target_langs,
+                e,
+                content.chars().take(500).collect::<String>()
+            ))
+        })?;
+
+        let mut translated: HashMap<String, Vec<TranslationUnit>> = HashMap::new();
+        for result in results {
+            let Some((unit, langs)) = messages.get(result.index) else {
+                continue;
+            };
+            for translation in result.translations {
+                let Some(lang) = langs
+                    .iter()
+                    .find(|lang| lang.eq_ignore_ascii_case(&translation.lang))
+                else {
+                    continue;
+                };
+                if !has_translation(unit, &translation.msg_str, &translation.msg_str_plural) {
+                    continue;
+                }
+                let mut unit = unit.clone();
+                unit.msg_str = translation.msg_str;
+                unit.msg_str_plural = translation.msg_str_plural;
+                translated.entry(lang.clone()).or_default().push(unit);
+            }
+        }
+        Ok(translated)
+    }
+
+    fn record_usage(&self, usage: &CompletionUsage) {
+        if let Some(meter) = &self.usage {
+            meter.record(usage.prompt_tokens, usage.completion_tokens);
+        }
+    }
+}
+
+#[derive(Default)]
+struct CompletedObjects {
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    completed: usize,
+}
+
+impl CompletedObjects {
+    fn feed(&mut self, chunk: &str) {
+        for c in chunk.chars() {
+            if self.in_string {
+                match c {
+                    _ if self.escaped => self.escaped = false,
+                    '\\' => self.escaped = true,
+                    '"' => self.in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+
+            match c {
+                '"' => self.in_string = true,
+                '[' | '{' => self.depth += 1,
+                ']' | '}' => {
+                    self.depth = self.depth.saturating_sub(1);
+                    if c == '}' && self.depth == 1 {
+                        self.completed += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn is_transient(e: &OpenAIError) -> bool {
+    match e {
+        OpenAIError::ApiError(api_error) => ![&api_error.r#type, &api_error.code]
+            .into_iter()
+            .flatten()
+            .any(|kind| PERMANENT_ERRORS.contains(&kind.as_str())),
+        OpenAIError::Reqwest(e) => e.is_timeout() || e.is_connect() || e.is_request(),
+        // Proxies answer overload with HTML error pages.
+        OpenAIError::JSONDeserialize(..) => true,
+        _ => false,
+    }
+}
+
+fn api_error(target_lang: &str, e: OpenAIError) -> PoLlmError {
+    let status = match &e {
+        OpenAIError::Reqwest(e) => e.status().map(|status| status.as_u16()),
+        _ => None,
+    };
+    PoLlmError::Api {
+        status,
+        message: format!(
+            "LLM API call failed for language '{}': {}. Check your API key, base URL, and network connectivity.",
+            target_lang, e
+        ),
+    }
+}
+
+/// Whether the model's answer holds a non-blank translation of every form.
+fn has_translation(
+    unit: &TranslationUnit,
+    msg_str: &Option<String>,
+    msg_str_plural: &Option<Vec<String>>,
+) -> bool {
+    if unit.is_plural() {
+        msg_str_plural
+            .as_ref()
+            .is_some_and(|forms| !forms.is_empty() && forms.iter().all(|s| !s.trim().is_empty()))
+    } else {
+        msg_str.as_ref().is_some_and(|s| !s.trim().is_empty())
+    }
+}
+
+fn empty_response_error(target_lang: &str) -> PoLlmError {
+    PoLlmError::Schema(format!(
+        "LLM returned empty response for language '{}'. The model may not support structured outputs or encountered an error.",
+        target_lang
+    ))
+}
+
+#[async_trait]
+impl<M> Translator for LlmTranslator<M>
+where
+    M: Config,
+{
+    async fn translate(
+        &self,
+        target_lang: &str,
+        translation_units: &[TranslationUnit],
+        custom_prompt: &Option<String>,
+    ) -> Result<TranslationResult, PoLlmError> {
+        if translation_units.is_empty() {
+            return Ok(TranslationResult {
+                translated: vec![],
+                failed_translated: vec![],
+            });
+        }
+
+        let mut prompt = String::new();
+        for (idx, unit) in translation_units.iter().enumerate() {
+            prompt.push_str(&self.unit_prompt(idx, unit));
+            prompt.push_str("\n---\n");
+        }
+
+        let mut system_content = self.system_content(target_lang, custom_prompt);
+
+        if self.store_rationale {
+            system_content.push_str(
+                "\n\nAlso include a \"rationale\" key for each object: one short line explaining any non-obvious translation choice, written in English for human reviewers.",
+            );
+        }
+
+        #[derive(schemars::JsonSchema, serde::Deserialize)]
+        struct LlmResponseUnit {
+            index: usize,
+            msg_str: Option<String>,
+            msg_str_plural: Option<Vec<String>>,
+            #[serde(default)]
+            #[schemars(skip)]
+            rationale: Option<String>,
+        }
+
+        #[derive(schemars::JsonSchema)]
+        #[allow(dead_code)]
+        struct LlmResponseUnitWithRationale {
+            index: usize,
+            msg_str: Option<String>,
+            msg_str_plural: Option<Vec<String>>,
+            rationale: String,
+        }
+
+        let schema_value = adapt_schema(
+            if self.store_rationale {
+                schema_for!(Vec<LlmResponseUnitWithRationale>).to_value()
+            } else {
+                schema_for!(Vec<LlmResponseUnit>).to_value()
+            },
+            self.schema_dialect,
+        );
+
+        let content = self
+            .complete(
+                system_content,
+                prompt,
+                schema_value,
+                target_lang,
+                translation_units.len(),
+            )
+            .await?;
+
+        let results: Vec<LlmResponseUnit> = serde_json::from_str(&content).map_err(|e| {
+            PoLlmError::Schema(format!(
                 "Failed to parse LLM JSON response for language '{}':\n  Parse error: {}\n  Response preview: {}\n  This may indicate the model is not following the structured output format.",
                 target_lang,
                 e,
                 content.chars().take(500).collect::<String>()
-            )
+            ))
         })?;
 
         if results.is_empty() && !translation_units.is_empty() {
-            return Err(anyhow::anyhow!(
+            return Err(PoLlmError::Schema(format!(
                 "LLM returned empty translation array for {} messages in language '{}'. Expected {} translations.",
                 translation_units.len(),
                 target_lang,
                 translation_units.len()
-            ));
+            )));
         }
 
         let mut result_map: HashMap<usize, LlmResponseUnit> =
@@ -195,37 +742,33 @@ where
             if let Some(res_unit) = result_map.remove(&idx) {
                 let mut final_unit = original_unit.clone();
 
-                let is_valid = if original_unit.is_plural() {
-                    res_unit
-                        .msg_str_plural
-                        .as_ref()
-                        .map(|v| !v.is_empty() && v.iter().all(|s| !s.trim().is_empty()))
-                        .unwrap_or(false)
-                } else {
-                    res_unit
-                        .msg_str
-                        .as_ref()
-                        .map(|s| !s.trim().is_empty())
-                        .unwrap_or(false)
-                };
-
-                if is_valid {
+                if has_translation(original_unit, &res_unit.msg_str, &res_unit.msg_str_plural) {
                     final_unit.msg_str = res_unit.msg_str;
                     final_unit.msg_str_plural = res_unit.msg_str_plural;
+                    if self.store_rationale {
+                        final_unit.rationale = res_unit.rationale;
+                    }
                     translated.push(final_unit);
                 } else {
                     eprintln!(
                         "      ⚠️  Invalid translation for '{}' in {}: empty or whitespace-only | translated: {}",
-                        original_unit.msg_id, target_lang, content
+                        original_unit.label(),
+                        target_lang,
+                        content
+                    );
+                    failed.push(
+                        original_unit
+                            .clone()
+                            .failed("empty or whitespace-only translation"),
                     );
-                    failed.push(original_unit.clone());
                 }
             } else {
                 eprintln!(
                     "      ⚠️  Missing translation for '{}' in {}: not found in LLM response",
-                    original_unit.msg_id, target_lang
+                    original_unit.label(),
+                    target_lang
                 );
-                failed.push(original_unit.clone());
+                failed.push(original_unit.clone().failed("not found in LLM response"));
             }
         }
 
@@ -243,3 +786,456 @@ where
         })
     }
 }
+
+/// Translates each batch by running an external command (through the shell,
+/// like hooks): the batch is written to its stdin as JSON, and a
+/// `TranslationResult` is read back from its stdout.
+pub struct CommandTranslator {
+    pub command: String,
+    pub model: String,
+    pub project_context: String,
+}
+
+#[derive(serde::Serialize)]
+struct CommandRequest<'a> {
+    target_lang: &'a str,
+    model: &'a str,
+    project_context: &'a str,
+    custom_prompt: &'a Option<String>,
+    messages: &'a [TranslationUnit],
+}
+
+#[async_trait]
+impl Translator for CommandTranslator {
+    async fn translate(
+        &self,
+        target_lang: &str,
+        translation_units: &[TranslationUnit],
+        custom_prompt: &Option<String>,
+    ) -> Result<TranslationResult, PoLlmError> {
+        let request = serde_json::to_vec(&CommandRequest {
+            target_lang,
+            model: &self.model,
+            project_context: &self.project_context,
+            custom_prompt,
+            messages: translation_units,
+        })
+        .map_err(|e| PoLlmError::Schema(format!("Failed to encode the request: {}", e)))?;
+
+        let mut child = shell(&self.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| PoLlmError::io(format!("Failed to run `{}`", self.command), e))?;
+
+        // Written from a separate task so a command that answers while still
+        // reading cannot block on a full stdout pipe.
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let writer = tokio::spawn(async move { stdin.write_all(&request).await });
+        let output = child
+            .wait_with_output()
+            .await
+            .map_err(|e| PoLlmError::io(format!("Failed to run `{}`", self.command), e))?;
+        let written = writer.await.map_err(|e| {
+            PoLlmError::io(format!("Failed to write to `{}`", self.command), e.into())
+        })?;
+
+        if !output.status.success() {
+            let status = match output.status.code() {
+                Some(code) => format!("exit code {}", code),
+                None => "a signal".to_string(),
+            };
+            return Err(PoLlmError::Api {
+                status: None,
+                message: format!(
+                    "`{}` failed with {} for language '{}': {}",
+                    self.command,
+                    status,
+                    target_lang,
+                    String::from_utf8_lossy(&output.stderr).trim_end()
+                ),
+            });
+        }
+        written.map_err(|e| PoLlmError::io(format!("Failed to write to `{}`", self.command), e))?;
+
+        let mut result: TranslationResult =
+            serde_json::from_slice(&output.stdout).map_err(|e| {
+                PoLlmError::Schema(format!(
+                    "Failed to parse the output of `{}` for language '{}': {}\n  Output preview: {}",
+                    self.command,
+                    target_lang,
+                    e,
+                    String::from_utf8_lossy(&output.stdout)
+                        .chars()
+                        .take(500)
+                        .collect::<String>()
+                ))
+            })?;
+
+        // Fields the command does not see come from the units sent to it.
+        let format_flags: HashMap<String, &Vec<String>> = translation_units
+            .iter()
+            .map(|unit| (combined_key(unit), &unit.format_flags))
+            .collect();
+        for unit in &mut result.translated {
+            if let Some(flags) = format_flags.get(&combined_key(unit)) {
+                unit.format_flags = flags.to_vec();
+            }
+        }
+        result.failed_translated = result
+            .failed_translated
+            .into_iter()
+            .map(|unit| unit.failed("rejected by the translation command"))
+            .collect();
+
+        let returned: HashSet<String> = result
+            .translated
+            .iter()
+            .chain(&result.failed_translated)
+            .map(combined_key)
+            .collect();
+        for unit in translation_units {
+            if !returned.contains(&combined_key(unit)) {
+                eprintln!(
+                    "      ⚠️  Missing translation for '{}' in {}: not found in the command's output",
+                    unit.label(),
+                    target_lang
+                );
+                result
+                    .failed_translated
+                    .push(unit.clone().failed("not found in the command's output"));
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+/// Texts per DeepL request, the most its API accepts.
+const DEEPL_MAX_TEXTS: usize = 50;
+
+/// Translates each batch with DeepL. The source texts of the batch are sent
+/// as one array, singular and plural source of a plural message in turn, and
+/// the translations matched back by position.
+pub struct DeepLTranslator {
+    pub client: reqwest::Client,
+    pub api_base: String,
+    pub api_key: String,
+    pub source_lang: String,
+    pub formality: Option<String>,
+    pub glossary_id: Option<String>,
+    /// Sent as DeepL's `context`, which informs the translation without
+    /// being translated.
+    pub project_context: String,
+    pub retries: Option<Arc<RetryBudget>>,
+}
+
+#[derive(serde::Deserialize)]
+struct DeepLResponse {
+    translations: Vec<DeepLTranslation>,
+}
+
+#[derive(serde::Deserialize)]
+struct DeepLTranslation {
+    text: String,
+}
+
+impl DeepLTranslator {
+    async fn translate_texts(
+        &self,
+        target_lang: &str,
+        texts: &[&str],
+    ) -> Result<Vec<String>, PoLlmError> {
+        let mut body = serde_json::json!({
+            "text": texts,
+            "source_lang": deepl_language(&self.source_lang, false),
+            "target_lang": deepl_language(target_lang, true),
+        });
+        if let Some(formality) = &self.formality {
+            body["formality"] = formality.as_str().into();
+        }
+        if let Some(glossary_id) = &self.glossary_id {
+            body["glossary_id"] = glossary_id.as_str().into();
+        }
+        if !self.project_context.trim().is_empty() {
+            body["context"] = self.project_context.as_str().into();
+        }
+
+        let url = format!("{}/v2/translate", self.api_base.trim_end_matches('/'));
+        let mut delay = INITIAL_RETRY_DELAY;
+        let mut attempt = 0;
+        let response = loop {
+            let response = self
+                .client
+                .post(&url)
+                .header("Authorization", format!("DeepL-Auth-Key {}", self.api_key))
+                .json(&body)
+                .send()
+                .await;
+            let status = response.as_ref().ok().map(|response| response.status());
+            // 429 is a rate limit and 529 DeepL's "too many requests".
+            let transient = match (&response, status) {
+                (Err(e), _) => e.is_timeout() || e.is_connect() || e.is_request(),
+                (Ok(_), Some(status)) => status.as_u16() == 429 || status.is_server_error(),
+                (Ok(_), None) => false,
+            };
+            if !transient || attempt >= MAX_RETRIES_PER_REQUEST {
+                break response;
+            }
+            if let Some(budget) = &self.retries
+                && !budget.take()
+            {
+                eprintln!(
+                    "      ⚠️  Retry budget of {} exhausted, not retrying {}",
+                    budget.limit().unwrap_or_default(),
+                    target_lang
+                );
+                break response;
+            }
+
+            attempt += 1;
+            eprintln!(
+                "      🔁 Transient DeepL error for {} ({}), retrying in {}s ({}/{})",
+                target_lang,
+                match (&response, status) {
+                    (Err(e), _) => e.to_string(),
+                    (_, Some(status)) => status.to_string(),
+                    _ => String::new(),
+                },
+                delay.as_secs(),
+                attempt,
+                MAX_RETRIES_PER_REQUEST
+            );
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(MAX_RETRY_DELAY);
+        };
+
+        let response = response.map_err(|e| PoLlmError::Api {
+            status: e.status().map(|status| status.as_u16()),
+            message: format!(
+                "DeepL API call failed for language '{}': {}. Check your API key, base URL, and network connectivity.",
+                target_lang, e
+            ),
+        })?;
+        let status = response.status();
+        let text = response.text().await.map_err(|e| PoLlmError::Api {
+            status: Some(status.as_u16()),
+            message: format!(
+                "Failed to read the DeepL response for '{}': {}",
+                target_lang, e
+            ),
+        })?;
+        if !status.is_success() {
+            return Err(PoLlmError::Api {
+                status: Some(status.as_u16()),
+                message: format!(
+                    "DeepL API call failed for language '{}' with {}: {}",
+                    target_lang,
+                    status,
+                    text.trim()
+                ),
+            });
+        }
+
+        let response: DeepLResponse = serde_json::from_str(&text).map_err(|e| {
+            PoLlmError::Schema(format!(
+                "Failed to parse the DeepL response for language '{}': {}\n  Response preview: {}",
+                target_lang,
+                e,
+                text.chars().take(500).collect::<String>()
+            ))
+        })?;
+        if response.translations.len() != texts.len() {
+            return Err(PoLlmError::Schema(format!(
+                "DeepL returned {} translations for {} texts in language '{}'",
+                response.translations.len(),
+                texts.len(),
+                target_lang
+            )));
+        }
+        Ok(response
+            .translations
+            .into_iter()
+            .map(|translation| translation.text)
+            .collect())
+    }
+}
+
+#[async_trait]
+impl Translator for DeepLTranslator {
+    async fn translate(
+        &self,
+        target_lang: &str,
+        translation_units: &[TranslationUnit],
+        _custom_prompt: &Option<String>,
+    ) -> Result<TranslationResult, PoLlmError> {
+        let texts: Vec<&str> = translation_units
+            .iter()
+            .flat_map(|unit| {
+                std::iter::once(unit.msg_id.as_str()).chain(unit.msg_id_plural.as_deref())
+            })
+            .collect();
+        let mut translations = Vec::with_capacity(texts.len());
+        for chunk in texts.chunks(DEEPL_MAX_TEXTS) {
+            translations.extend(self.translate_texts(target_lang, chunk).await?);
+        }
+
+        let nplurals = plural_forms_or_default(target_lang).nplurals;
+        let mut translations = translations.into_iter();
+        let mut result = TranslationResult {
+            translated: Vec::new(),
+            failed_translated: Vec::new(),
+        };
+        for unit in translation_units {
+            let mut translated = unit.clone();
+            let singular = translations.next().unwrap_or_default();
+            if unit.is_plural() {
+                let plural = translations.next().unwrap_or_default();
+                // A language with a single form uses it for every count.
+                translated.msg_str_plural = Some(
+                    (0..nplurals)
+                        .map(|i| {
+                            if i == 0 && nplurals > 1 {
+                                singular.clone()
+                            } else {
+                                plural.clone()
+                            }
+                        })
+                        .collect(),
+                );
+            } else {
+                translated.msg_str = Some(singular);
+            }
+
+            if translated
+                .translation_pairs()
+                .iter()
+                .any(|(_, translation)| translation.trim().is_empty())
+            {
+                result
+                    .failed_translated
+                    .push(unit.clone().failed("empty translation from DeepL"));
+            } else {
+                result.translated.push(translated);
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// DeepL's code for a language tag: `de` is `DE`, `pt_BR` is `PT-BR` and
+/// `zh_TW` is `ZH-HANT`. Source languages take no region.
+fn deepl_language(lang: &str, target: bool) -> String {
+    let tag = lang.replace('_', "-").to_uppercase();
+    let primary = tag.split('-').next().unwrap_or_default().to_string();
+    if !target {
+        return primary;
+    }
+    match tag.as_str() {
+        "EN" => "EN-US".to_string(),
+        "PT" => "PT-PT".to_string(),
+        "NO" | "NB" | "NB-NO" => "NB".to_string(),
+        "ZH-TW" | "ZH-HK" | "ZH-MO" | "ZH-HANT" => "ZH-HANT".to_string(),
+        "ZH" | "ZH-CN" | "ZH-SG" | "ZH-HANS" => "ZH-HANS".to_string(),
+        "EN-US" | "EN-GB" | "PT-BR" | "PT-PT" | "ES-419" => tag,
+        _ => primary,
+    }
+}
+
+pub fn adapt_schema(schema: Value, dialect: SchemaDialect) -> Value {
+    if dialect == SchemaDialect::OpenAi {
+        return schema;
+    }
+
+    let defs = schema
+        .get("$defs")
+        .or_else(|| schema.get("definitions"))
+        .cloned()
+        .unwrap_or(Value::Null);
+
+    let mut schema = inline_refs(schema, &defs, 0);
+    if let Value::Object(map) = &mut schema {
+        map.remove("$defs");
+        map.remove("definitions");
+    }
+
+    if dialect == SchemaDialect::Minimal {
+        strip_unsupported_keywords(&mut schema);
+    }
+
+    schema
+}
+
+fn inline_refs(value: Value, defs: &Value, depth: usize) -> Value {
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::String(reference)) = map.get("$ref")
+                && depth < 32
+            {
+                let name = reference
+                    .strip_prefix("#/$defs/")
+                    .or_else(|| reference.strip_prefix("#/definitions/"));
+                if let Some(def) = name.and_then(|n| defs.get(n)) {
+                    return inline_refs(def.clone(), defs, depth + 1);
+                }
+            }
+
+            Value::Object(
+                map.into_iter()
+                    .map(|(k, v)| (k, inline_refs(v, defs, depth)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|v| inline_refs(v, defs, depth))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+fn strip_unsupported_keywords(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for key in [
+                "$schema",
+                "title",
+                "description",
+                "format",
+                "minimum",
+                "maximum",
+                "additionalProperties",
+            ] {
+                map.remove(key);
+            }
+
+            if let Some(Value::Array(types)) = map.get("type").cloned() {
+                let non_null: Vec<Value> = types
+                    .iter()
+                    .filter(|t| t.as_str() != Some("null"))
+                    .cloned()
+                    .collect();
+                if non_null.len() == 1 {
+                    map.insert("type".to_string(), non_null[0].clone());
+                    if non_null.len() < types.len() {
+                        map.insert("nullable".to_string(), Value::Bool(true));
+                    }
+                }
+            }
+
+            for (key, child) in map.iter_mut() {
+                match (key.as_str(), child) {
+                    ("properties", Value::Object(properties)) => {
+                        properties.values_mut().for_each(strip_unsupported_keywords)
+                    }
+                    (_, child) => strip_unsupported_keywords(child),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_unsupported_keywords),
+        _ => {}
+    }
+}
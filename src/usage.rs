@@ -0,0 +1,147 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A rough token count for text a model reads: about four characters of
+/// ASCII text per token, and one per character of other scripts.
+pub fn estimate_tokens(text: &str) -> usize {
+    let ascii = text.chars().filter(char::is_ascii).count();
+    ascii.div_ceil(4) + text.chars().count() - ascii
+}
+
+/// Output tokens a typical UI message takes in a response: the message
+/// echoed back with its translation, as JSON.
+const OUTPUT_TOKENS_PER_MESSAGE: usize = 100;
+/// Added per message when the model also explains its translation.
+const RATIONALE_TOKENS_PER_MESSAGE: usize = 60;
+
+/// The most output tokens per response of well-known models, by model name
+/// prefix, more specific prefixes first.
+const OUTPUT_LIMITS: &[(&str, usize)] = &[
+    ("gpt-3.5-turbo", 4096),
+    ("gpt-4o", 16384),
+    ("gpt-4.1", 32768),
+    ("gpt-4-turbo", 4096),
+    ("gpt-4", 8192),
+    ("gpt-5", 128000),
+    ("o1-mini", 65536),
+    ("o1", 100000),
+    ("o3", 100000),
+    ("o4-mini", 100000),
+    ("claude-3-haiku", 4096),
+    ("claude-3-opus", 4096),
+    ("claude-3-5", 8192),
+    ("claude-3-7-sonnet", 64000),
+    ("claude-sonnet-4", 64000),
+    ("claude-opus-4", 32000),
+    ("deepseek-chat", 8192),
+    ("gemini-1.5", 8192),
+    ("gemini-2.0", 8192),
+    ("gemini-2.5", 65536),
+];
+
+/// A rough count of the output tokens a response for `messages` messages
+/// takes.
+pub fn estimate_output_tokens(messages: usize, store_rationale: bool) -> usize {
+    let per_message = if store_rationale {
+        OUTPUT_TOKENS_PER_MESSAGE + RATIONALE_TOKENS_PER_MESSAGE
+    } else {
+        OUTPUT_TOKENS_PER_MESSAGE
+    };
+    messages * per_message
+}
+
+/// The output limit of a known model, ignoring a `provider/` prefix in its
+/// name.
+pub fn output_token_limit(model: &str) -> Option<usize> {
+    let name = model.rsplit('/').next().unwrap_or(model).to_lowercase();
+    OUTPUT_LIMITS
+        .iter()
+        .find(|(prefix, _)| name.starts_with(prefix))
+        .map(|(_, limit)| *limit)
+}
+
+#[derive(Default)]
+pub struct TokenUsage {
+    prompt_tokens: AtomicU64,
+    completion_tokens: AtomicU64,
+    requests: AtomicU64,
+}
+
+impl TokenUsage {
+    pub fn record(&self, prompt_tokens: u32, completion_tokens: u32) {
+        self.prompt_tokens
+            .fetch_add(prompt_tokens.into(), Ordering::Relaxed);
+        self.completion_tokens
+            .fetch_add(completion_tokens.into(), Ordering::Relaxed);
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn prompt_tokens(&self) -> u64 {
+        self.prompt_tokens.load(Ordering::Relaxed)
+    }
+
+    pub fn completion_tokens(&self) -> u64 {
+        self.completion_tokens.load(Ordering::Relaxed)
+    }
+
+    pub fn requests(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    pub fn cost(&self, pricing: &Pricing) -> f64 {
+        (self.prompt_tokens() as f64 * pricing.input_per_million
+            + self.completion_tokens() as f64 * pricing.output_per_million)
+            / 1_000_000.0
+    }
+
+    /// Spend so far plus one more request at the average cost seen so far.
+    pub fn projected_cost(&self, pricing: &Pricing) -> f64 {
+        let spent = self.cost(pricing);
+        match self.requests() {
+            0 => spent,
+            requests => spent + spent / requests as f64,
+        }
+    }
+}
+
+/// Retries of transient API failures across the whole run, optionally capped
+/// so a flaky endpoint cannot multiply the number of requests without bound.
+#[derive(Default)]
+pub struct RetryBudget {
+    limit: Option<u64>,
+    used: AtomicU64,
+}
+
+impl RetryBudget {
+    pub fn new(limit: Option<u64>) -> Self {
+        Self {
+            limit,
+            used: AtomicU64::new(0),
+        }
+    }
+
+    /// Takes one retry from the budget, or returns false once it is spent.
+    pub fn take(&self) -> bool {
+        self.used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                match self.limit {
+                    Some(limit) if used >= limit => None,
+                    _ => Some(used + 1),
+                }
+            })
+            .is_ok()
+    }
+
+    pub fn used(&self) -> u64 {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    pub fn limit(&self) -> Option<u64> {
+        self.limit
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Pricing {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
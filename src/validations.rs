@@ -0,0 +1,2854 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fmt,
+    sync::LazyLock,
+};
+
+use polib::{catalog::Catalog, message::MessageView};
+use regex::Regex;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    bidi::{check_directional_balance, is_rtl, normalize_bidi},
+    configs::{
+        BidiMode, CustomRule, DocumentFormat, EscapeMode, LengthMode, NormalizationForm,
+        ParagraphMode, PunctuationMode, RuleSeverity, SanitizeMode, TranslationConfig,
+        ValidationConfig,
+    },
+    icu::{self, is_icu_message},
+    languages::check_language,
+    plurals::plural_forms,
+    report::{Finding, Severity},
+    translations::{TranslationUnit, format_flags, is_locked},
+    translators::TranslationResult,
+};
+
+pub fn validate_translations(
+    result: TranslationResult,
+    target_lang: &str,
+    config: &TranslationConfig,
+    validation: &ValidationConfig,
+    format: DocumentFormat,
+) -> TranslationResult {
+    let mut translated = Vec::new();
+    let mut failed = result.failed_translated;
+
+    for mut unit in result.translated {
+        normalize_unicode(&mut unit, config.normalize_unicode);
+        restore_escapes(&mut unit, config.straighten_quotes);
+        let stripped = match config.stray_characters {
+            SanitizeMode::Strip => strip_stray_characters(&mut unit),
+            SanitizeMode::Fail => None,
+        };
+        if config.accelerator_autofix {
+            fix_accelerators(&mut unit, &config.accelerator_markers);
+        }
+        if config.terminal_punctuation == PunctuationMode::Fix {
+            fix_terminal_punctuation(&mut unit, &config.source_language, target_lang);
+        }
+        if config.preserve_escapes == EscapeMode::Repair {
+            repair_entities(&mut unit);
+        }
+        normalize_whitespace(&mut unit);
+        if is_rtl(target_lang, &config.rtl_languages) {
+            normalize_bidi_runs(&mut unit, config.bidi_isolation);
+        }
+
+        let mut pipeline = Pipeline::new(validation);
+        check_unit(&mut pipeline, &unit, target_lang, config, format);
+        let mut findings = if validation.autofix {
+            autofix(&mut unit, &mut pipeline, target_lang, config, format)
+        } else {
+            Vec::new()
+        };
+        if let Some(reason) = stripped {
+            pipeline.check(STRAY_CHARACTERS_RULE, Some(RuleSeverity::Warn), || {
+                Err(reason)
+            });
+        }
+        unit.fuzzy |= pipeline.fuzzy;
+        for fixed in &findings {
+            eprintln!(
+                "      🔧 Auto-fixed translation for '{}' in {}: {}",
+                unit.label(),
+                target_lang,
+                fixed.message
+            );
+        }
+        findings.extend(pipeline.findings);
+
+        for warning in findings
+            .iter()
+            .filter(|f| f.severity == Severity::Warning && !f.fixed)
+        {
+            eprintln!(
+                "      ⚠️  Translation for '{}' in {}: {}",
+                unit.label(),
+                target_lang,
+                warning.message
+            );
+        }
+        unit.warnings = findings
+            .iter()
+            .filter(|f| f.severity == Severity::Warning && !f.fixed)
+            .map(|f| f.message.clone())
+            .collect();
+        let error = findings
+            .iter()
+            .find(|f| f.severity == Severity::Error)
+            .map(|f| f.message.clone());
+        unit.findings = findings;
+
+        match error {
+            None => translated.push(unit),
+            Some(reason) => {
+                eprintln!(
+                    "      ⚠️  Invalid translation for '{}' in {}: {}",
+                    unit.label(),
+                    target_lang,
+                    reason
+                );
+                failed.push(unit.failed(reason));
+            }
+        }
+    }
+
+    TranslationResult {
+        translated,
+        failed_translated: failed,
+    }
+}
+
+const STRAY_CHARACTERS_RULE: &str = "stray-characters";
+const TOO_LONG_RULE: &str = "too-long";
+
+/// The built-in validation rules, by the names `[validation.rules]` and
+/// the validation report know them by.
+pub const RULES: &[&str] = &[
+    STRAY_CHARACTERS_RULE,
+    "literals",
+    "same-as-source",
+    "wrong-language",
+    "accelerator",
+    "delimiters",
+    "paragraph-structure",
+    "terminal-punctuation",
+    "format-flags",
+    "icu-message",
+    "placeholders",
+    "escapes",
+    "markdown",
+    "bidi-balance",
+    "inline-tags",
+    "format-variables",
+    "interpolations",
+    "format-items",
+    "android-markup",
+    TOO_LONG_RULE,
+];
+
+/// The findings of the rules run over one translation, each at the
+/// severity `[validation]` gives its rule.
+struct Pipeline<'a> {
+    validation: &'a ValidationConfig,
+    findings: Vec<Finding>,
+    /// Whether a rule set to `fuzzy` was broken.
+    fuzzy: bool,
+}
+
+impl<'a> Pipeline<'a> {
+    fn new(validation: &'a ValidationConfig) -> Self {
+        Self {
+            validation,
+            findings: Vec::new(),
+            fuzzy: false,
+        }
+    }
+
+    /// Runs a check unless its rule is off. `default` is the severity the
+    /// rest of the configuration gives the rule, `None` for not checked.
+    fn check(
+        &mut self,
+        rule: &str,
+        default: Option<RuleSeverity>,
+        check: impl FnOnce() -> Result<(), String>,
+    ) {
+        let Some(severity) = self.validation.severity(rule, default) else {
+            return;
+        };
+        let Err(reason) = check() else {
+            return;
+        };
+        self.findings.push(match severity {
+            RuleSeverity::Fail => Finding::error(rule, reason),
+            RuleSeverity::Fuzzy => {
+                self.fuzzy = true;
+                Finding::warning(rule, reason)
+            }
+            RuleSeverity::Warn | RuleSeverity::Off => Finding::warning(rule, reason),
+        });
+    }
+}
+
+fn enabled(on: bool, severity: RuleSeverity) -> Option<RuleSeverity> {
+    on.then_some(severity)
+}
+
+pub fn validate_unit(
+    unit: &TranslationUnit,
+    target_lang: &str,
+    config: &TranslationConfig,
+    validation: &ValidationConfig,
+    format: DocumentFormat,
+) -> Result<(), String> {
+    audit_unit(unit, target_lang, config, validation, format)
+        .into_iter()
+        .find(|finding| finding.severity == Severity::Error)
+        .map_or(Ok(()), |finding| Err(finding.message))
+}
+
+/// The findings of the validation pipeline over a translation as it
+/// stands, without the fixes applied to fresh translations first.
+pub fn audit_unit(
+    unit: &TranslationUnit,
+    target_lang: &str,
+    config: &TranslationConfig,
+    validation: &ValidationConfig,
+    format: DocumentFormat,
+) -> Vec<Finding> {
+    let mut pipeline = Pipeline::new(validation);
+    check_unit(&mut pipeline, unit, target_lang, config, format);
+    pipeline.findings
+}
+
+/// The rules, in the order they run, with the severity the rest of the
+/// configuration gives each; custom rules follow, and the length limit
+/// comes last.
+fn check_unit(
+    pipeline: &mut Pipeline,
+    unit: &TranslationUnit,
+    target_lang: &str,
+    config: &TranslationConfig,
+    format: DocumentFormat,
+) {
+    use RuleSeverity::{Fail, Warn};
+
+    let paragraphs = match config.paragraph_structure {
+        _ if paragraphs_exempt(config, target_lang) => None,
+        ParagraphMode::Off => None,
+        ParagraphMode::Warn => Some(Warn),
+        ParagraphMode::Fail => Some(Fail),
+    };
+    let punctuation = match config.terminal_punctuation {
+        PunctuationMode::Off => None,
+        PunctuationMode::Warn => Some(Warn),
+        PunctuationMode::Fail | PunctuationMode::Fix => Some(Fail),
+    };
+    let escapes = match config.preserve_escapes {
+        EscapeMode::Off => None,
+        EscapeMode::Warn => Some(Warn),
+        EscapeMode::Fail | EscapeMode::Repair => Some(Fail),
+    };
+    let placeholders = if config.check_placeholders {
+        Fail
+    } else {
+        Warn
+    };
+    let cross_language = !same_language(&config.source_language, target_lang);
+    let echo_allowed = config
+        .allow_same_as_source
+        .iter()
+        .any(|lang| same_language(lang, target_lang));
+    let validation = pipeline.validation;
+
+    for (source, translation) in unit.translation_pairs() {
+        pipeline.check(STRAY_CHARACTERS_RULE, Some(Fail), || {
+            check_control_characters(source, translation)
+        });
+        pipeline.check("literals", Some(Fail), || {
+            check_literals(source, translation, config.localized_urls.get(target_lang))
+        });
+        if cross_language && !config.same_as_source_pattern.is_match(source.trim()) {
+            if echo_allowed {
+                pipeline.check(
+                    "same-as-source",
+                    enabled(config.flag_untranslated_echo, Fail),
+                    || check_echo(source, translation),
+                );
+            } else {
+                pipeline.check("same-as-source", Some(Fail), || {
+                    check_same_as_source(source, translation)
+                });
+            }
+        }
+        pipeline.check(
+            "wrong-language",
+            enabled(config.detect_wrong_language, Fail),
+            || check_language(source, translation, &config.source_language, target_lang),
+        );
+        pipeline.check("accelerator", Some(Fail), || {
+            check_accelerator(source, translation, &config.accelerator_markers)
+        });
+        pipeline.check("delimiters", Some(Fail), || {
+            check_delimiters(source, translation, &config.preserve_delimiters)
+        });
+        pipeline.check("paragraph-structure", paragraphs, || {
+            check_paragraphs(source, translation)
+        });
+        pipeline.check("terminal-punctuation", punctuation, || {
+            check_terminal_punctuation(source, translation, &config.source_language, target_lang)
+        });
+        pipeline.check("format-flags", Some(Fail), || {
+            check_format_flags(&unit.format_flags, source, translation)
+        });
+        pipeline.check("icu-message", Some(Fail), || {
+            check_icu_message(source, translation, target_lang)
+        });
+        pipeline.check("placeholders", Some(placeholders), || {
+            check_placeholders(source, translation, config)
+        });
+        pipeline.check("escapes", escapes, || check_escapes(source, translation));
+        pipeline.check("markdown", enabled(config.markdown_aware, Fail), || {
+            check_markdown(source, translation)
+        });
+        pipeline.check(
+            "bidi-balance",
+            enabled(config.bidi_isolation != BidiMode::Off, Fail),
+            || check_directional_balance(translation),
+        );
+        pipeline.check(
+            "inline-tags",
+            enabled(format == DocumentFormat::Xliff, Fail),
+            || check_inline_tags(source, translation),
+        );
+        pipeline.check(
+            "format-variables",
+            enabled(format == DocumentFormat::Stringsdict, Fail),
+            || check_format_variables(source, translation),
+        );
+        if format == DocumentFormat::Yaml {
+            pipeline.check("interpolations", Some(Fail), || {
+                check_rails_interpolations(source, translation)
+            });
+        } else {
+            pipeline.check(
+                "interpolations",
+                enabled(format == DocumentFormat::Json, Fail),
+                || check_interpolations(source, translation),
+            );
+        }
+        pipeline.check(
+            "format-items",
+            enabled(format == DocumentFormat::Resx, Fail),
+            || check_format_items(source, translation),
+        );
+        pipeline.check(
+            "android-markup",
+            enabled(format == DocumentFormat::Android, Fail),
+            || check_android_markup(source, translation),
+        );
+        for custom in &validation.custom {
+            pipeline.check(&custom.name, Some(custom.severity), || {
+                check_custom_rule(custom, source, translation)
+            });
+        }
+    }
+
+    let too_long = match config.on_too_long {
+        LengthMode::Warn => Warn,
+        LengthMode::Fuzzy => RuleSeverity::Fuzzy,
+        LengthMode::Fail | LengthMode::Reprompt => Fail,
+    };
+    pipeline.check(TOO_LONG_RULE, Some(too_long), || {
+        check_length(unit, target_lang, config)
+    });
+}
+
+/// The rules `autofix` can correct, in the order it tries them.
+const FIXABLE_RULES: &[&str] = &[
+    STRAY_CHARACTERS_RULE,
+    "escapes",
+    "literals",
+    "accelerator",
+    "terminal-punctuation",
+];
+
+fn apply_fix(
+    rule: &str,
+    unit: &mut TranslationUnit,
+    target_lang: &str,
+    config: &TranslationConfig,
+) {
+    match rule {
+        STRAY_CHARACTERS_RULE => {
+            strip_stray_characters(unit);
+        }
+        "escapes" => {
+            map_forms(unit, decode_extra_entities);
+            repair_entities(unit);
+        }
+        "literals" => {
+            let localized = config.localized_urls.get(target_lang);
+            map_forms(unit, |source, translation| {
+                restore_literals(source, translation, localized)
+            });
+        }
+        "accelerator" => fix_accelerators(unit, &config.accelerator_markers),
+        "terminal-punctuation" => {
+            fix_terminal_punctuation(unit, &config.source_language, target_lang)
+        }
+        _ => {}
+    }
+}
+
+/// Tries the fix of each fixable rule the translation breaks, one rule at
+/// a time, keeping a fix only when the rule then passes, no rule fails
+/// that did not before, and the placeholders are unchanged and in the same
+/// order. Returns the findings fixed; the pipeline is left with the
+/// findings of the translation as fixed.
+fn autofix(
+    unit: &mut TranslationUnit,
+    pipeline: &mut Pipeline,
+    target_lang: &str,
+    config: &TranslationConfig,
+    format: DocumentFormat,
+) -> Vec<Finding> {
+    let mut fixed = Vec::new();
+    for rule in FIXABLE_RULES {
+        if !pipeline.findings.iter().any(|f| f.rule == *rule) {
+            continue;
+        }
+        let mut candidate = unit.clone();
+        apply_fix(rule, &mut candidate, target_lang, config);
+        if candidate.translation_pairs() == unit.translation_pairs()
+            || !keeps_placeholders(unit, &candidate, config)
+        {
+            continue;
+        }
+
+        let mut rerun = Pipeline::new(pipeline.validation);
+        check_unit(&mut rerun, &candidate, target_lang, config, format);
+        let converged = !rerun.findings.iter().any(|f| f.rule == *rule);
+        let regressed = rerun
+            .findings
+            .iter()
+            .any(|f| !pipeline.findings.iter().any(|old| old.rule == f.rule));
+        if converged && !regressed {
+            let (done, _): (Vec<_>, Vec<_>) = std::mem::take(&mut pipeline.findings)
+                .into_iter()
+                .partition(|f| f.rule == *rule);
+            fixed.extend(done.into_iter().map(Finding::fixed));
+            *unit = candidate;
+            *pipeline = rerun;
+        }
+    }
+    fixed
+}
+
+/// The placeholders of each form, in order: printf conversions, Python
+/// fields and the project's `placeholder_patterns`.
+fn placeholder_sequence<'a>(text: &'a str, config: &TranslationConfig) -> Vec<&'a str> {
+    let mut found: Vec<(usize, &str)> = [&*PRINTF_CONVERSION, &*PYTHON_NAMED, &*PYTHON_BRACE]
+        .into_iter()
+        .chain(config.placeholder_patterns.regex())
+        .flat_map(|pattern| pattern.find_iter(text))
+        .map(|m| (m.start(), m.as_str()))
+        .collect();
+    found.sort();
+    found
+        .into_iter()
+        .map(|(_, placeholder)| placeholder)
+        .collect()
+}
+
+fn keeps_placeholders(
+    unit: &TranslationUnit,
+    fixed: &TranslationUnit,
+    config: &TranslationConfig,
+) -> bool {
+    unit.translation_pairs()
+        .iter()
+        .zip(fixed.translation_pairs())
+        .all(|((_, before), (_, after))| {
+            placeholder_sequence(before, config) == placeholder_sequence(after, config)
+        })
+}
+
+/// Applies `fix` to each form of the translation with its source.
+fn map_forms(unit: &mut TranslationUnit, mut fix: impl FnMut(&str, &mut String)) {
+    let plural_source = unit
+        .msg_id_plural
+        .clone()
+        .unwrap_or_else(|| unit.msg_id.clone());
+
+    if let Some(plurals) = &mut unit.msg_str_plural {
+        for (i, translation) in plurals.iter_mut().enumerate() {
+            let source = if i == 0 { &unit.msg_id } else { &plural_source };
+            fix(source, translation);
+        }
+    } else if let Some(translation) = &mut unit.msg_str {
+        fix(&unit.msg_id, translation);
+    }
+}
+
+/// Decodes the entities the model added, such as `&lt;b&gt;` for a source's
+/// `<b>`, where the source has the character itself.
+fn decode_extra_entities(source: &str, translation: &mut String) {
+    let expected = escape_tokens(source);
+    let extra: Vec<(String, char, usize)> = escape_tokens(translation)
+        .into_iter()
+        .filter_map(|(entity, found)| {
+            let decoded = decode_entity(entity)?;
+            let want = expected.get(entity).copied().unwrap_or_default();
+            (found > want && source.contains(decoded))
+                .then(|| (entity.to_string(), decoded, found - want))
+        })
+        .collect();
+    for (entity, decoded, count) in extra {
+        *translation = translation.replacen(&entity, &decoded.to_string(), count);
+    }
+}
+
+/// Puts back the source's URLs, email addresses and paths the translation
+/// altered, each in place of the altered one of its kind; literals under a
+/// `localized_urls` replacement prefix are left alone.
+fn restore_literals(
+    source: &str,
+    translation: &mut String,
+    localized_urls: Option<&BTreeMap<String, String>>,
+) {
+    if check_literals(source, translation, localized_urls).is_ok() {
+        return;
+    }
+    let expected = literals(source);
+    let found: Vec<(&str, String)> = literals(translation)
+        .into_iter()
+        .map(|(kind, literal)| (kind, literal.to_string()))
+        .collect();
+    let localized = |literal: &str| {
+        localized_urls
+            .into_iter()
+            .flatten()
+            .any(|(_, replacement)| literal.starts_with(replacement.as_str()))
+    };
+    let kept = |literal: &str| {
+        found.iter().any(|(_, t)| t == literal)
+            || localized_urls
+                .into_iter()
+                .flatten()
+                .any(|(prefix, replacement)| {
+                    literal.strip_prefix(prefix.as_str()).is_some_and(|rest| {
+                        found
+                            .iter()
+                            .any(|(_, t)| t.strip_prefix(replacement.as_str()) == Some(rest))
+                    })
+                })
+    };
+    let mut altered: Vec<&(&str, String)> = found
+        .iter()
+        .filter(|(_, t)| !expected.iter().any(|(_, s)| s == t) && !localized(t))
+        .collect();
+    for (kind, literal) in &expected {
+        if kept(literal) {
+            continue;
+        }
+        if let Some(i) = altered.iter().position(|(k, _)| k == kind) {
+            let (_, wrong) = altered.remove(i);
+            *translation = translation.replacen(wrong.as_str(), literal, 1);
+        }
+    }
+}
+
+/// A custom rule's regex: with `must_match_source`, each of its matches in
+/// the source must be in the translation as often; otherwise the
+/// translation must not match it at all.
+fn check_custom_rule(rule: &CustomRule, source: &str, translation: &str) -> Result<(), String> {
+    let describe = |found: &str| match &rule.message {
+        Some(message) => format!("{} ({:?})", message, found),
+        None if rule.must_match_source => {
+            format!("{}: {:?} of the source is missing", rule.name, found)
+        }
+        None => format!("{}: {:?} is not allowed", rule.name, found),
+    };
+
+    if !rule.must_match_source {
+        return match rule.pattern.find(translation) {
+            Some(found) => Err(describe(found.as_str())),
+            None => Ok(()),
+        };
+    }
+    let mut expected: BTreeMap<&str, usize> = BTreeMap::new();
+    for found in rule.pattern.find_iter(source) {
+        *expected.entry(found.as_str()).or_default() += 1;
+    }
+    for (found, count) in expected {
+        if translation.matches(found).count() < count {
+            return Err(describe(found));
+        }
+    }
+    Ok(())
+}
+
+/// Checks a translation edited outside the tool, such as in a review
+/// spreadsheet: the configured checks, with placeholders always enforced.
+pub fn validate_import(
+    unit: &TranslationUnit,
+    target_lang: &str,
+    config: &TranslationConfig,
+    validation: &ValidationConfig,
+) -> Result<(), String> {
+    validate_unit(
+        unit,
+        target_lang,
+        config,
+        validation,
+        DocumentFormat::Gettext,
+    )?;
+    for (source, translation) in unit.translation_pairs() {
+        check_placeholders(source, translation, config)?;
+    }
+    Ok(())
+}
+
+pub fn restore_escapes(unit: &mut TranslationUnit, straighten_quotes: bool) {
+    let plural_source = unit
+        .msg_id_plural
+        .clone()
+        .unwrap_or_else(|| unit.msg_id.clone());
+
+    if let Some(plurals) = &mut unit.msg_str_plural {
+        for (i, translation) in plurals.iter_mut().enumerate() {
+            let source = if i == 0 { &unit.msg_id } else { &plural_source };
+            *translation = restore_form_escapes(source, translation, straighten_quotes);
+        }
+    } else if let Some(translation) = &mut unit.msg_str {
+        *translation = restore_form_escapes(&unit.msg_id, translation, straighten_quotes);
+    }
+}
+
+fn restore_form_escapes(source: &str, translation: &str, straighten_quotes: bool) -> String {
+    let mut result = translation.to_string();
+
+    for (escaped, real) in [("\\n", "\n"), ("\\t", "\t"), ("\\r", "\r")] {
+        let source_real = source.contains(real);
+        let source_escaped = source.contains(escaped);
+        if source_real && !source_escaped {
+            result = result.replace(escaped, real);
+        } else if source_escaped && !source_real {
+            result = result.replace(real, escaped);
+        }
+    }
+
+    if !source.contains("\\\"") {
+        result = result.replace("\\\"", "\"");
+    }
+
+    if straighten_quotes {
+        if source.contains('"') && !source.contains(['“', '”']) {
+            result = straighten_pairs(&result, '“', '”', '"');
+        }
+        if source.contains('\'') && !source.contains(['‘', '’']) {
+            result = straighten_pairs(&result, '‘', '’', '\'');
+        }
+    }
+
+    result
+}
+
+/// Replaces each `open` … `close` pair with `straight` quotes, leaving alone
+/// unpaired marks such as the closing `“` of German „…“ or an apostrophe.
+fn straighten_pairs(text: &str, open: char, close: char, straight: char) -> String {
+    let mut chars: Vec<char> = text.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == open
+            && let Some(end) = chars[i + 1..]
+                .iter()
+                .position(|&c| c == open || c == close)
+                .map(|offset| i + 1 + offset)
+                .filter(|&end| chars[end] == close)
+        {
+            chars[i] = straight;
+            chars[end] = straight;
+            i = end;
+        }
+        i += 1;
+    }
+    chars.into_iter().collect()
+}
+
+pub fn accelerator_marker(text: &str, markers: &[char]) -> Option<char> {
+    markers
+        .iter()
+        .copied()
+        .find(|&marker| accelerator_positions(text, marker).len() == 1)
+}
+
+fn accelerator_positions(text: &str, marker: char) -> Vec<usize> {
+    let mut positions = Vec::new();
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if c != marker {
+            continue;
+        }
+        match chars.peek() {
+            Some(&(_, next)) if next == marker => {
+                chars.next();
+            }
+            Some(&(_, next)) if next.is_alphanumeric() => positions.push(i),
+            _ => {}
+        }
+    }
+
+    positions
+}
+
+fn check_accelerator(source: &str, translation: &str, markers: &[char]) -> Result<(), String> {
+    let Some(marker) = accelerator_marker(source, markers) else {
+        return Ok(());
+    };
+
+    match accelerator_positions(translation, marker).len() {
+        1 => Ok(()),
+        found => Err(format!(
+            "expected exactly one '{}' accelerator, found {}",
+            marker, found
+        )),
+    }
+}
+
+/// Delimited values such as `Name,Email,Phone` must keep their field count,
+/// so each delimiter the source uses must appear as often in the translation.
+fn check_delimiters(source: &str, translation: &str, delimiters: &[char]) -> Result<(), String> {
+    for &delimiter in delimiters {
+        let expected = source.matches(delimiter).count();
+        let found = translation.matches(delimiter).count();
+        if expected > 0 && found != expected {
+            return Err(format!(
+                "expected {} '{}' delimiter(s) as in the source, found {}",
+                expected, delimiter, found
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// How a sentence or label ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Terminator {
+    Ellipsis,
+    Period,
+    Colon,
+    Question,
+    Exclamation,
+}
+
+/// The ways each terminator is written, longest first within a kind.
+const TERMINATORS: &[(Terminator, &[&str])] = &[
+    (Terminator::Ellipsis, &["……", "...", "…"]),
+    (Terminator::Period, &[".", "。", "．", "।", "۔", "։"]),
+    (Terminator::Colon, &[":", "："]),
+    (Terminator::Question, &["?", "？", "؟"]),
+    (Terminator::Exclamation, &["!", "！"]),
+];
+
+/// Closing quotes and brackets, which may follow the terminator.
+const CLOSERS: &[char] = &['"', '\'', '”', '’', '»', '›', '」', '』', ')', ']', '）'];
+
+impl Terminator {
+    fn name(self) -> &'static str {
+        match self {
+            Terminator::Ellipsis => "an ellipsis",
+            Terminator::Period => "a period",
+            Terminator::Colon => "a colon",
+            Terminator::Question => "a question mark",
+            Terminator::Exclamation => "an exclamation mark",
+        }
+    }
+
+    /// The form `lang` writes it in, when it differs from the source's.
+    fn written_in(self, lang: &str) -> Option<&'static str> {
+        let primary = lang.split(['-', '_']).next().unwrap_or_default();
+        match (self, primary.to_lowercase().as_str()) {
+            (Terminator::Ellipsis, "zh") => Some("……"),
+            (Terminator::Period, "zh" | "ja") => Some("。"),
+            (Terminator::Colon, "zh" | "ja") => Some("："),
+            (Terminator::Question, "zh" | "ja") => Some("？"),
+            (Terminator::Exclamation, "zh" | "ja") => Some("！"),
+            (Terminator::Question, "ar" | "fa" | "ur") => Some("؟"),
+            (Terminator::Period, "ur") => Some("۔"),
+            (Terminator::Period, "hi") => Some("।"),
+            (Terminator::Period, "hy") => Some("։"),
+            (Terminator::Question, "el") => Some(";"),
+            (Terminator::Colon, "fr") => Some("\u{a0}:"),
+            (Terminator::Question, "fr") => Some("\u{a0}?"),
+            (Terminator::Exclamation, "fr") => Some("\u{a0}!"),
+            _ => None,
+        }
+    }
+}
+
+/// Splits a text into what comes before its terminator, the terminator and
+/// its form, and the whitespace and closing quotes after it.
+fn split_terminator<'a>(
+    text: &'a str,
+    lang: &str,
+) -> (&'a str, Option<(Terminator, &'a str)>, &'a str) {
+    let core = text.trim_end_matches(|c: char| c.is_whitespace() || CLOSERS.contains(&c));
+    let tail = &text[core.len()..];
+    let greek = lang.split(['-', '_']).next() == Some("el");
+    let found = TERMINATORS
+        .iter()
+        .flat_map(|(terminator, forms)| forms.iter().map(move |form| (*terminator, *form)))
+        .chain(greek.then_some((Terminator::Question, ";")))
+        .chain(greek.then_some((Terminator::Question, "\u{37e}")))
+        .find(|(_, form)| core.ends_with(form));
+    match found {
+        Some((terminator, form)) => {
+            let start = core.len() - form.len();
+            (&core[..start], Some((terminator, &core[start..])), tail)
+        }
+        None => (core, None, tail),
+    }
+}
+
+/// The translation must end with the same kind of terminator as the source,
+/// in any of its forms: `...` and `…` are both an ellipsis, and `：` is a
+/// colon.
+fn check_terminal_punctuation(
+    source: &str,
+    translation: &str,
+    source_lang: &str,
+    target_lang: &str,
+) -> Result<(), String> {
+    let (_, expected, _) = split_terminator(source, source_lang);
+    let (_, found, _) = split_terminator(translation, target_lang);
+    match (expected.map(|(t, _)| t), found.map(|(t, _)| t)) {
+        (expected, found) if expected == found => Ok(()),
+        (Some(expected), None) => Err(format!(
+            "translation does not end with {} like the source",
+            expected.name()
+        )),
+        (None, Some(found)) => Err(format!(
+            "translation ends with {}, the source does not",
+            found.name()
+        )),
+        (Some(expected), Some(found)) => Err(format!(
+            "translation ends with {} instead of {}",
+            found.name(),
+            expected.name()
+        )),
+        (None, None) => Ok(()),
+    }
+}
+
+fn fix_terminal_punctuation(unit: &mut TranslationUnit, source_lang: &str, target_lang: &str) {
+    let plural_source = unit
+        .msg_id_plural
+        .clone()
+        .unwrap_or_else(|| unit.msg_id.clone());
+    let fix = |source: &str, translation: &mut String| {
+        let (_, expected, _) = split_terminator(source, source_lang);
+        let (body, found, tail) = split_terminator(translation, target_lang);
+        if translation.trim().is_empty() || expected.map(|(t, _)| t) == found.map(|(t, _)| t) {
+            return;
+        }
+        let terminator = expected.map_or("", |(terminator, form)| {
+            terminator.written_in(target_lang).unwrap_or(form)
+        });
+        *translation = format!("{}{}{}", body.trim_end(), terminator, tail);
+    };
+
+    if let Some(plurals) = &mut unit.msg_str_plural {
+        for (i, translation) in plurals.iter_mut().enumerate() {
+            let source = if i == 0 { &unit.msg_id } else { &plural_source };
+            fix(source, translation);
+        }
+    } else if let Some(translation) = &mut unit.msg_str {
+        fix(&unit.msg_id, translation);
+    }
+}
+
+fn fix_accelerators(unit: &mut TranslationUnit, markers: &[char]) {
+    let plural_source = unit
+        .msg_id_plural
+        .clone()
+        .unwrap_or_else(|| unit.msg_id.clone());
+
+    if let Some(plurals) = &mut unit.msg_str_plural {
+        for (i, translation) in plurals.iter_mut().enumerate() {
+            let source = if i == 0 { &unit.msg_id } else { &plural_source };
+            insert_accelerator(source, translation, markers);
+        }
+    } else if let Some(translation) = &mut unit.msg_str {
+        insert_accelerator(&unit.msg_id, translation, markers);
+    }
+}
+
+fn insert_accelerator(source: &str, translation: &mut String, markers: &[char]) {
+    let Some(marker) = accelerator_marker(source, markers) else {
+        return;
+    };
+    if !accelerator_positions(translation, marker).is_empty() {
+        return;
+    }
+
+    if let Some((position, _)) = translation.char_indices().find(|(_, c)| c.is_alphabetic()) {
+        translation.insert(position, marker);
+    }
+}
+
+static ESCAPE_TOKEN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"&(?:[a-zA-Z][a-zA-Z0-9]*|#[0-9]+|#[xX][0-9a-fA-F]+);|\\[ntr"\\]|[\n\t]"#).unwrap()
+});
+
+fn escape_tokens(text: &str) -> BTreeMap<&str, usize> {
+    let mut tokens = BTreeMap::new();
+    for token in ESCAPE_TOKEN.find_iter(text) {
+        *tokens.entry(token.as_str()).or_default() += 1;
+    }
+    tokens
+}
+
+fn check_escapes(source: &str, translation: &str) -> Result<(), String> {
+    let expected = escape_tokens(source);
+    let found = escape_tokens(translation);
+
+    let mismatches: Vec<String> = expected
+        .keys()
+        .chain(found.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter_map(|token| {
+            let want = expected.get(token).copied().unwrap_or_default();
+            let got = found.get(token).copied().unwrap_or_default();
+            (want != got).then(|| format!("{:?} expected {}, found {}", token, want, got))
+        })
+        .collect();
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "escape sequences or HTML entities changed: {}",
+            mismatches.join(", ")
+        ))
+    }
+}
+
+fn repair_entities(unit: &mut TranslationUnit) {
+    let plural_source = unit
+        .msg_id_plural
+        .clone()
+        .unwrap_or_else(|| unit.msg_id.clone());
+
+    if let Some(plurals) = &mut unit.msg_str_plural {
+        for (i, translation) in plurals.iter_mut().enumerate() {
+            let source = if i == 0 { &unit.msg_id } else { &plural_source };
+            *translation = reencode_entities(source, translation);
+        }
+    } else if let Some(translation) = &mut unit.msg_str {
+        *translation = reencode_entities(&unit.msg_id, translation);
+    }
+}
+
+/// Turns characters the model decoded back into the entities the source used,
+/// as many times as the entity is missing from the translation.
+fn reencode_entities(source: &str, translation: &str) -> String {
+    let expected = escape_tokens(source);
+    let found = escape_tokens(translation);
+    let mut result = translation.to_string();
+
+    for (entity, want) in expected {
+        let missing = want.saturating_sub(found.get(entity).copied().unwrap_or_default());
+        let Some(decoded) = decode_entity(entity) else {
+            continue;
+        };
+        if missing == 0 {
+            continue;
+        }
+
+        let mut repaired = String::with_capacity(result.len());
+        let mut remaining = missing;
+        let mut rest = result.as_str();
+        while remaining > 0 {
+            let Some(position) = rest
+                .char_indices()
+                .find_map(|(i, c)| (c == decoded && !starts_entity(&rest[i..])).then_some(i))
+            else {
+                break;
+            };
+            repaired.push_str(&rest[..position]);
+            repaired.push_str(entity);
+            rest = &rest[position + decoded.len_utf8()..];
+            remaining -= 1;
+        }
+        repaired.push_str(rest);
+        result = repaired;
+    }
+
+    result
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    let name = entity.strip_prefix('&')?.strip_suffix(';')?;
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some('\u{a0}'),
+        _ => {
+            let code = name.strip_prefix('#')?;
+            let code = match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+fn starts_entity(text: &str) -> bool {
+    ESCAPE_TOKEN
+        .find(text)
+        .is_some_and(|m| m.start() == 0 && text.starts_with('&'))
+}
+
+static POSITIONAL_PLACEHOLDER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"%(\d+)\$[-+ #0']*\d*(?:\.\d+)?(?:hh|h|ll|l|L|q|j|z|t)?[a-zA-Z]|\{(\d+)(?:[,:][^{}]*)?\}",
+    )
+    .unwrap()
+});
+
+fn positional_placeholders(text: &str) -> BTreeMap<String, usize> {
+    let text = text.replace("%%", "").replace("{{", "").replace("}}", "");
+    let mut placeholders = BTreeMap::new();
+
+    for captures in POSITIONAL_PLACEHOLDER.captures_iter(&text) {
+        let key = match (captures.get(1), captures.get(2)) {
+            (Some(index), _) => format!("%{}$", index.as_str()),
+            (_, Some(index)) => format!("{{{}}}", index.as_str()),
+            _ => continue,
+        };
+        *placeholders.entry(key).or_insert(0) += 1;
+    }
+
+    placeholders
+}
+
+fn check_positional_placeholders(source: &str, translation: &str) -> Result<(), String> {
+    let mut expected = positional_placeholders(source);
+    let mut actual = positional_placeholders(translation);
+    // A translation may number the unnumbered printf conversions of its
+    // source to reorder them, which the `c-format` check compares.
+    if printf_conversions(source)
+        .iter()
+        .any(|conversion| !conversion.contains('$'))
+    {
+        expected.retain(|placeholder, _| !placeholder.starts_with('%'));
+        actual.retain(|placeholder, _| !placeholder.starts_with('%'));
+    }
+    if expected == actual {
+        return Ok(());
+    }
+
+    let mut problems = Vec::new();
+    for (placeholder, count) in &expected {
+        match actual.get(placeholder).copied().unwrap_or(0) {
+            0 => problems.push(format!("{} dropped", placeholder)),
+            found if found > *count => problems.push(format!("{} duplicated", placeholder)),
+            found if found < *count => problems.push(format!(
+                "{} used {} time(s) instead of {}",
+                placeholder, found, count
+            )),
+            _ => {}
+        }
+    }
+    for placeholder in actual.keys() {
+        if !expected.contains_key(placeholder) {
+            problems.push(format!("{} not in source", placeholder));
+        }
+    }
+
+    Err(format!(
+        "positional placeholders changed: {}",
+        problems.join(", ")
+    ))
+}
+
+/// Positional placeholders, plus the project's own `placeholder_patterns`.
+fn check_placeholders(
+    source: &str,
+    translation: &str,
+    config: &TranslationConfig,
+) -> Result<(), String> {
+    check_positional_placeholders(source, translation)?;
+    match config.placeholder_patterns.regex() {
+        Some(pattern) => check_tokens(pattern, "placeholders", source, translation),
+        None => Ok(()),
+    }
+}
+
+/// The placeholder checks a message's format flags call for: printf
+/// conversions for `c-format`, and the Python styles for `python-format` and
+/// `python-brace-format`. Without any flag both Python styles are checked
+/// where they appear; printf is not, as a lone `%` is often a percent sign.
+pub fn check_format_flags(flags: &[String], source: &str, translation: &str) -> Result<(), String> {
+    let flagged = |flag: &str| flags.iter().any(|f| f == flag);
+    if flagged("c-format") {
+        check_printf_conversions(source, translation)?;
+    }
+    if flagged("python-format") || flags.is_empty() {
+        check_python_named(source, translation)?;
+    }
+    // ICU messages have their own check, and their branches look like fields.
+    if flagged("python-brace-format") || (flags.is_empty() && !is_icu_message(source)) {
+        check_python_brace(source, translation)?;
+    }
+    Ok(())
+}
+
+/// A source that is an ICU message with `plural` or `select` arguments needs
+/// a translation that parses as one, with the same arguments, and plurals
+/// with a branch for each of the target language's categories.
+fn check_icu_message(source: &str, translation: &str, target_lang: &str) -> Result<(), String> {
+    if !is_icu_message(source) {
+        return Ok(());
+    }
+    let expected = icu::arguments(source).map_err(|e| e.to_string())?;
+    let actual = icu::arguments(translation).map_err(|e| format!("invalid ICU message: {}", e))?;
+
+    let signatures = |arguments: &[icu::Argument]| -> BTreeSet<String> {
+        arguments.iter().map(ToString::to_string).collect()
+    };
+    let (expected_signatures, actual_signatures) = (signatures(&expected), signatures(&actual));
+    if expected_signatures != actual_signatures {
+        let list = |a: &BTreeSet<String>, b: &BTreeSet<String>| {
+            a.difference(b).cloned().collect::<Vec<_>>().join(" ")
+        };
+        let mut problems = Vec::new();
+        let missing = list(&expected_signatures, &actual_signatures);
+        if !missing.is_empty() {
+            problems.push(format!("missing {}", missing));
+        }
+        let unexpected = list(&actual_signatures, &expected_signatures);
+        if !unexpected.is_empty() {
+            problems.push(format!("unexpected {}", unexpected));
+        }
+        return Err(format!("ICU arguments changed: {}", problems.join(", ")));
+    }
+
+    let categories = plural_forms(target_lang).map_or(&["other"][..], |forms| forms.categories);
+    for argument in actual.iter().filter(|argument| argument.kind == "plural") {
+        let lacking: Vec<&str> = categories
+            .iter()
+            .copied()
+            .filter(|category| !argument.selectors.iter().any(|s| s == category))
+            .collect();
+        if !lacking.is_empty() {
+            return Err(format!(
+                "ICU plural {} lacks the {} branch(es) {} needs",
+                argument,
+                lacking.join(", "),
+                target_lang
+            ));
+        }
+    }
+    Ok(())
+}
+
+static PYTHON_NAMED: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"%\([^)]+\)[-+ #0]*(?:\d+|\*)?(?:\.(?:\d+|\*))?[hlL]?[diouxXeEfFgGcrsa]").unwrap()
+});
+
+/// Python `%(name)s` placeholders; `%%` is a literal percent sign.
+fn check_python_named(source: &str, translation: &str) -> Result<(), String> {
+    let literal = |text: &str| text.replace("%%", "");
+    check_token_set(
+        &PYTHON_NAMED,
+        "named placeholders",
+        &literal(source),
+        &literal(translation),
+    )
+}
+
+static PYTHON_BRACE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{(?:[A-Za-z_]\w*(?:\.\w+|\[\w+\])*|\d+)?(?:![rsa])?(?::[^{}]*)?\}").unwrap()
+});
+
+/// Python `str.format` fields such as `{count}`, `{user.name}` or `{0:>8}`;
+/// `{{` and `}}` are literal braces.
+fn check_python_brace(source: &str, translation: &str) -> Result<(), String> {
+    let literal = |text: &str| text.replace("{{", "").replace("}}", "");
+    check_token_set(
+        &PYTHON_BRACE,
+        "format fields",
+        &literal(source),
+        &literal(translation),
+    )
+}
+
+static PRINTF_CONVERSION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"%%|%(?:(\d+)\$)?[-+ #0']*(\d+|\*)?(?:\.(\d+|\*))?(hh|h|ll|l|L|q|j|z|t)?([diouxXeEfFgGaAcsp])",
+    )
+    .unwrap()
+});
+
+fn printf_conversions(text: &str) -> Vec<&str> {
+    PRINTF_CONVERSION
+        .find_iter(text)
+        .map(|m| m.as_str())
+        .filter(|m| *m != "%%")
+        .collect()
+}
+
+/// The argument types printf conversions take, by argument position:
+/// unnumbered conversions take the arguments in turn, and so does a `*`
+/// width or precision. Types are given as the conversion of their class,
+/// so `%x` and `%i` read as `%d`, while a length modifier makes another
+/// type, such as `%ld`.
+fn printf_arguments(text: &str) -> Result<BTreeMap<usize, String>, String> {
+    let mut arguments: BTreeMap<usize, String> = BTreeMap::new();
+    let mut next = 1;
+    let mut numbered = None;
+    for captures in PRINTF_CONVERSION.captures_iter(text) {
+        let Some(conversion) = captures.get(5) else {
+            continue;
+        };
+        let position = captures
+            .get(1)
+            .and_then(|n| n.as_str().parse::<usize>().ok());
+        if *numbered.get_or_insert(position.is_some()) != position.is_some() {
+            return Err(
+                "printf conversions mix numbered (%1$s) and unnumbered (%s) forms".to_string(),
+            );
+        }
+        let class = match conversion.as_str() {
+            "d" | "i" | "o" | "u" | "x" | "X" => "d",
+            "e" | "E" | "f" | "F" | "g" | "G" | "a" | "A" => "f",
+            other => other,
+        };
+        let modifier = captures.get(4).map_or("", |m| m.as_str());
+        let mut add = |position: usize, kind: String| match arguments.get(&position) {
+            Some(existing) if *existing != kind => Err(format!(
+                "printf argument {} is used as both {} and {}",
+                position, existing, kind
+            )),
+            _ => {
+                arguments.insert(position, kind);
+                Ok(())
+            }
+        };
+        if position.is_none() {
+            for star in [captures.get(2), captures.get(3)].into_iter().flatten() {
+                if star.as_str() == "*" {
+                    add(next, "%d".to_string())?;
+                    next += 1;
+                }
+            }
+        }
+        let position = position.unwrap_or_else(|| {
+            next += 1;
+            next - 1
+        });
+        add(position, format!("%{}{}", modifier, class))?;
+    }
+    Ok(arguments)
+}
+
+/// The printf conversions of a `c-format` message must take the same
+/// arguments in the translation, each as the same type, like `msgfmt -c`
+/// checks. Unnumbered conversions take the arguments in turn, so a
+/// translation may reorder them with numbered forms such as `%2$d`.
+fn check_printf_conversions(source: &str, translation: &str) -> Result<(), String> {
+    let expected = printf_arguments(source)?;
+    let actual = printf_arguments(translation).map_err(|e| format!("in the translation, {}", e))?;
+    if expected == actual {
+        return Ok(());
+    }
+
+    let missing: Vec<String> = expected
+        .iter()
+        .filter(|(position, _)| !actual.contains_key(position))
+        .map(|(position, kind)| format!("argument {} ({})", position, kind))
+        .collect();
+    let extra: Vec<String> = actual
+        .iter()
+        .filter(|(position, _)| !expected.contains_key(position))
+        .map(|(position, kind)| format!("argument {} ({})", position, kind))
+        .collect();
+    let changed: Vec<String> = expected
+        .iter()
+        .filter_map(|(position, kind)| {
+            let found = actual.get(position).filter(|found| *found != kind)?;
+            Some(format!(
+                "argument {} is {} in the source, {} in the translation",
+                position, kind, found
+            ))
+        })
+        .collect();
+
+    let mut problems = Vec::new();
+    if !missing.is_empty() {
+        problems.push(format!("missing {}", missing.join(", ")));
+    }
+    if !extra.is_empty() {
+        problems.push(format!("unexpected {}", extra.join(", ")));
+    }
+    problems.extend(changed);
+    let hint = if missing.is_empty()
+        && extra.is_empty()
+        && !printf_conversions(translation)
+            .iter()
+            .any(|conversion| conversion.contains('$'))
+    {
+        "; use numbered forms such as %1$s to reorder"
+    } else {
+        ""
+    };
+    Err(format!(
+        "printf conversions changed: {}{}",
+        problems.join(", "),
+        hint
+    ))
+}
+
+/// Why a translation identical to its source failed, for counting them.
+pub const SAME_AS_SOURCE: &str = "translation is the same as the source";
+
+/// A translation equal to its source once whitespace is normalized leaves
+/// the message untranslated while making it look done.
+fn check_same_as_source(source: &str, translation: &str) -> Result<(), String> {
+    if source.split_whitespace().eq(translation.split_whitespace()) {
+        Err(SAME_AS_SOURCE.to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// A translation identical to a source made of several ordinary words is
+/// most likely the model giving up. Sources that look like code, or whose
+/// words are all capitalized, such as product names, may stay as they are.
+fn check_echo(source: &str, translation: &str) -> Result<(), String> {
+    if source.trim() != translation.trim() {
+        return Ok(());
+    }
+    let tokens: Vec<&str> = source.split_whitespace().collect();
+    let words: Vec<&str> = tokens
+        .iter()
+        .map(|token| token.trim_end_matches(['.', ',', ';', ':', '!', '?']))
+        .filter(|word| word.chars().count() > 1 && word.chars().all(char::is_alphabetic))
+        .collect();
+    let prose = words.len() >= 2
+        && words.len() * 2 >= tokens.len()
+        && words
+            .iter()
+            .any(|word| word.chars().next().is_some_and(char::is_lowercase));
+    if prose {
+        Err(SAME_AS_SOURCE.to_string())
+    } else {
+        Ok(())
+    }
+}
+
+fn same_language(a: &str, b: &str) -> bool {
+    let primary = |lang: &str| {
+        lang.split(['-', '_'])
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase()
+    };
+    primary(a) == primary(b)
+}
+
+/// Brings every form of the translation to `form`, so that a decomposed
+/// `é` compares equal to the precomposed one in the checks that follow.
+fn normalize_unicode(unit: &mut TranslationUnit, form: NormalizationForm) {
+    let normalize = |text: &mut String| {
+        *text = match form {
+            NormalizationForm::None => return,
+            NormalizationForm::Nfc => text.nfc().collect(),
+            NormalizationForm::Nfkc => text.nfkc().collect(),
+        }
+    };
+    if let Some(plurals) = &mut unit.msg_str_plural {
+        plurals.iter_mut().for_each(normalize);
+    } else if let Some(translation) = &mut unit.msg_str {
+        normalize(translation);
+    }
+}
+
+fn normalize_bidi_runs(unit: &mut TranslationUnit, mode: BidiMode) {
+    let plural_source = unit
+        .msg_id_plural
+        .clone()
+        .unwrap_or_else(|| unit.msg_id.clone());
+
+    if let Some(plurals) = &mut unit.msg_str_plural {
+        for (i, translation) in plurals.iter_mut().enumerate() {
+            let source = if i == 0 { &unit.msg_id } else { &plural_source };
+            *translation = normalize_bidi(source, translation, mode);
+        }
+    } else if let Some(translation) = &mut unit.msg_str {
+        *translation = normalize_bidi(&unit.msg_id, translation, mode);
+    }
+}
+
+/// Why a translation had stray characters, stripped or failed, for counting
+/// them.
+pub const STRAY_CHARACTERS: &str = "stray characters";
+
+/// Invisible characters models leave in their output: a zero-width space, a
+/// word joiner and a BOM. Zero-width (non-)joiners are left alone, Persian
+/// and Indic scripts spell with them.
+const INVISIBLE: &[char] = &['\u{200b}', '\u{2060}', '\u{feff}'];
+
+fn is_stray(c: char) -> bool {
+    c.is_control() || INVISIBLE.contains(&c)
+}
+
+fn check_control_characters(source: &str, translation: &str) -> Result<(), String> {
+    if translation.contains('\u{fffd}') && !source.contains('\u{fffd}') {
+        return Err(format!(
+            "{}: U+FFFD, part of the response was not valid UTF-8",
+            STRAY_CHARACTERS
+        ));
+    }
+    match translation
+        .chars()
+        .find(|c| is_stray(*c) && !source.contains(*c))
+    {
+        Some(c) => Err(format!(
+            "{}: U+{:04X} not present in the source",
+            STRAY_CHARACTERS, c as u32
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Removes the stray characters a translation has and its source does not,
+/// returning a warning naming them. Added newlines and tabs are kept for
+/// the check to fail, removing them would run words together.
+fn strip_stray_characters(unit: &mut TranslationUnit) -> Option<String> {
+    let plural_source = unit
+        .msg_id_plural
+        .clone()
+        .unwrap_or_else(|| unit.msg_id.clone());
+    let mut removed = BTreeSet::new();
+    let mut strip = |source: &str, translation: &mut String| {
+        translation.retain(|c| {
+            let stray = is_stray(c) && !matches!(c, '\n' | '\r' | '\t') && !source.contains(c);
+            if stray {
+                removed.insert(c);
+            }
+            !stray
+        });
+    };
+
+    if let Some(plurals) = &mut unit.msg_str_plural {
+        for (i, translation) in plurals.iter_mut().enumerate() {
+            let source = if i == 0 { &unit.msg_id } else { &plural_source };
+            strip(source, translation);
+        }
+    } else if let Some(translation) = &mut unit.msg_str {
+        strip(&unit.msg_id, translation);
+    }
+
+    (!removed.is_empty()).then(|| {
+        let removed: Vec<String> = removed
+            .iter()
+            .map(|c| format!("U+{:04X}", *c as u32))
+            .collect();
+        format!("{}: removed {}", STRAY_CHARACTERS, removed.join(", "))
+    })
+}
+
+/// The length of a text as a reader sees it: an accented letter written
+/// with a combining mark, a flag or a family emoji counts once.
+pub fn graphemes(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+static MAX_LENGTH_COMMENT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"po-llm:max-length=(\d+)").unwrap());
+
+/// Checks each form of the translation against the entry's
+/// `po-llm:max-length=N` comment, else `max_chars`, and against
+/// `max_expansion_ratio` times the length of its source.
+pub fn check_length(
+    unit: &TranslationUnit,
+    target_lang: &str,
+    config: &TranslationConfig,
+) -> Result<(), String> {
+    let max_chars = unit
+        .comments
+        .as_deref()
+        .and_then(|comments| MAX_LENGTH_COMMENT.captures(comments))
+        .and_then(|caps| caps[1].parse().ok())
+        .or(config.max_chars);
+    let ratio = config
+        .max_expansion_ratio
+        .as_ref()
+        .and_then(|ratio| ratio.for_language(target_lang));
+
+    for (source, translation) in unit.translation_pairs() {
+        let length = graphemes(translation);
+        if let Some(max_chars) = max_chars
+            && length > max_chars
+        {
+            return Err(format!(
+                "translation is {} characters long, over the limit of {}",
+                length, max_chars
+            ));
+        }
+        let source_length = graphemes(source);
+        if let Some(ratio) = ratio
+            && length as f64 > source_length as f64 * ratio
+        {
+            return Err(format!(
+                "translation is {} characters long, {:.1}× the source's {}, over the limit of {}×",
+                length,
+                length as f64 / source_length.max(1) as f64,
+                source_length,
+                ratio
+            ));
+        }
+    }
+    Ok(())
+}
+
+static LITERAL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r#"(?P<url>\b(?:https?|ftp)://[^\s<>"'`]+)"#,
+        r"|(?P<email>\b[\w.+-]+@[\w-]+(?:\.[\w-]+)*\.[A-Za-z]{2,}\b)",
+        r#"|(?:^|[\s(\["'])(?P<path>(?:~|\.{1,2})?/[\w.-]+(?:/[\w.-]+)+/?|[A-Za-z]:\\[\w.-]+(?:\\[\w.-]+)*)"#,
+    ))
+    .unwrap()
+});
+
+/// The URLs, email addresses and paths such as `/usr/bin` or `~/.config`
+/// in a text, by kind, without the punctuation ending a sentence.
+fn literals(text: &str) -> Vec<(&'static str, &str)> {
+    LITERAL
+        .captures_iter(text)
+        .filter_map(|caps| {
+            let (kind, m) = [("URL", "url"), ("email address", "email"), ("path", "path")]
+                .into_iter()
+                .find_map(|(kind, name)| Some((kind, caps.name(name)?)))?;
+            let mut literal = m.as_str();
+            loop {
+                literal = literal.trim_end_matches(['.', ',', ';', ':', '!', '?']);
+                // A closing parenthesis belongs to the text around the
+                // literal unless the literal opened it.
+                match literal.strip_suffix(')') {
+                    Some(rest) if literal.matches(')').count() > literal.matches('(').count() => {
+                        literal = rest
+                    }
+                    _ => break,
+                }
+            }
+            Some((kind, literal))
+        })
+        .collect()
+}
+
+/// Each URL, email address and path of the source must appear unchanged in
+/// the translation, except URLs under a prefix `localized_urls` maps to the
+/// target language's own.
+fn check_literals(
+    source: &str,
+    translation: &str,
+    localized_urls: Option<&BTreeMap<String, String>>,
+) -> Result<(), String> {
+    let expected = literals(source);
+    let found = literals(translation);
+    let localized = |literal: &str| {
+        localized_urls
+            .into_iter()
+            .flatten()
+            .any(|(prefix, replacement)| {
+                literal.strip_prefix(prefix.as_str()).is_some_and(|rest| {
+                    found
+                        .iter()
+                        .any(|(_, t)| t.strip_prefix(replacement.as_str()) == Some(rest))
+                })
+            })
+    };
+
+    let Some((kind, literal)) = expected.iter().find(|(kind, literal)| {
+        let kept = found.iter().any(|(_, t)| t == literal);
+        !(kept || (*kind == "URL" && localized(literal)))
+    }) else {
+        return Ok(());
+    };
+    match found
+        .iter()
+        .find(|(k, t)| k == kind && !expected.iter().any(|(_, s)| s == t))
+    {
+        Some((_, altered)) => Err(format!("{} '{}' changed to '{}'", kind, literal, altered)),
+        None => Err(format!(
+            "{} '{}' missing from the translation",
+            kind, literal
+        )),
+    }
+}
+
+const XLIFF_INLINE_ELEMENTS: &[&str] = &[
+    "g", "x", "bx", "ex", "ph", "bpt", "ept", "it", "mrk", "pc", "sc", "ec", "sm", "em",
+];
+
+/// XLIFF inline elements in a segment, keyed by name and `id`.
+fn inline_tags(text: &str) -> Result<BTreeMap<String, usize>, String> {
+    let wrapped = format!("<segment>{}</segment>", text);
+    let document = roxmltree::Document::parse(&wrapped).map_err(|e| e.to_string())?;
+
+    let mut tags = BTreeMap::new();
+    for node in document.root_element().descendants().skip(1) {
+        let name = node.tag_name().name();
+        if node.is_element() && XLIFF_INLINE_ELEMENTS.contains(&name) {
+            let key = match node.attribute("id") {
+                Some(id) => format!("<{} id=\"{}\">", name, id),
+                None => format!("<{}>", name),
+            };
+            *tags.entry(key).or_insert(0) += 1;
+        }
+    }
+    Ok(tags)
+}
+
+fn check_inline_tags(source: &str, translation: &str) -> Result<(), String> {
+    let Ok(expected) = inline_tags(source) else {
+        return Ok(());
+    };
+    let actual = inline_tags(translation)
+        .map_err(|e| format!("translation is not well-formed XML: {}", e))?;
+    if expected == actual {
+        return Ok(());
+    }
+    Err(format!(
+        "inline tags changed: {}",
+        tag_changes(&expected, &actual).join(", ")
+    ))
+}
+
+fn tag_changes(
+    expected: &BTreeMap<String, usize>,
+    actual: &BTreeMap<String, usize>,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (tag, count) in expected {
+        match actual.get(tag).copied().unwrap_or(0) {
+            0 => problems.push(format!("{} dropped", tag)),
+            found if found != *count => problems.push(format!(
+                "{} used {} time(s) instead of {}",
+                tag, found, count
+            )),
+            _ => {}
+        }
+    }
+    for tag in actual.keys() {
+        if !expected.contains_key(tag) {
+            problems.push(format!("{} not in source", tag));
+        }
+    }
+    problems
+}
+
+const XLIFF_NAMESPACE: &str = "urn:oasis:names:tc:xliff:document:1.2";
+
+/// Markup in an Android string: styling tags such as `<b>` by name, and
+/// `<xliff:g>` spans by `id` and content, which is code to leave as is.
+fn android_markup(text: &str) -> Result<BTreeMap<String, usize>, String> {
+    let wrapped = format!(
+        "<string xmlns:xliff=\"{}\">{}</string>",
+        XLIFF_NAMESPACE, text
+    );
+    let document = roxmltree::Document::parse(&wrapped).map_err(|e| e.to_string())?;
+
+    let mut tags = BTreeMap::new();
+    for node in document.root_element().descendants().skip(1) {
+        if !node.is_element() {
+            continue;
+        }
+        let name = node.tag_name().name();
+        let key = if node.tag_name().namespace() == Some(XLIFF_NAMESPACE) {
+            let content: String = node.descendants().filter_map(|n| n.text()).collect();
+            match node.attribute("id") {
+                Some(id) => format!("<xliff:{} id=\"{}\">{}", name, id, content),
+                None => format!("<xliff:{}>{}", name, content),
+            }
+        } else {
+            format!("<{}>", name)
+        };
+        *tags.entry(key).or_insert(0) += 1;
+    }
+    Ok(tags)
+}
+
+fn check_android_markup(source: &str, translation: &str) -> Result<(), String> {
+    let Ok(expected) = android_markup(source) else {
+        return Ok(());
+    };
+    let actual = android_markup(translation)
+        .map_err(|e| format!("translation is not well-formed XML: {}", e))?;
+    if expected == actual {
+        return Ok(());
+    }
+    Err(format!(
+        "markup changed: {}",
+        tag_changes(&expected, &actual).join(", ")
+    ))
+}
+
+static FORMAT_VARIABLE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"%#@\w+@").unwrap());
+static INTERPOLATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{-?\s*[^{}]+?\s*\}\}|\$t\([^()]*\)").unwrap());
+
+/// `.stringsdict` format strings refer to their plural rules as `%#@name@`.
+fn check_format_variables(source: &str, translation: &str) -> Result<(), String> {
+    check_tokens(&FORMAT_VARIABLE, "format variables", source, translation)
+}
+
+/// i18next interpolates `{{name}}` and nests other keys with `$t(key)`.
+fn check_interpolations(source: &str, translation: &str) -> Result<(), String> {
+    check_tokens(&INTERPOLATION, "interpolations", source, translation)
+}
+
+static RAILS_INTERPOLATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"%\{\w+\}|%<\w+>[-+ 0#]*\d*(?:\.\d+)?[a-zA-Z]").unwrap());
+
+/// Rails interpolates `%{name}`, or `%<name>d` with a format.
+fn check_rails_interpolations(source: &str, translation: &str) -> Result<(), String> {
+    check_tokens(&RAILS_INTERPOLATION, "interpolations", source, translation)
+}
+
+static FORMAT_ITEM: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\d+(?:,\s*-?\d+)?(?::[^{}]*)?\}").unwrap());
+
+/// .NET composite format items such as `{0}`, `{1,-8}` or `{2:N2}`; `{{` and
+/// `}}` are literal braces.
+fn check_format_items(source: &str, translation: &str) -> Result<(), String> {
+    let literal = |text: &str| text.replace("{{", "").replace("}}", "");
+    check_tokens(
+        &FORMAT_ITEM,
+        "format items",
+        &literal(source),
+        &literal(translation),
+    )
+}
+
+fn check_tokens(
+    pattern: &Regex,
+    what: &str,
+    source: &str,
+    translation: &str,
+) -> Result<(), String> {
+    let tokens = |text: &str| {
+        let mut found: Vec<String> = pattern
+            .find_iter(text)
+            .map(|m| m.as_str().to_string())
+            .collect();
+        found.sort();
+        found
+    };
+    let expected = tokens(source);
+    if expected == tokens(translation) {
+        Ok(())
+    } else if expected.is_empty() {
+        Err(format!("{} changed, expected none", what))
+    } else {
+        Err(format!(
+            "{} changed, expected {}",
+            what,
+            expected.join(", ")
+        ))
+    }
+}
+
+/// Like `check_tokens`, for placeholders taking arguments by name: each one
+/// must still appear, but may move or be used a different number of times.
+fn check_token_set(
+    pattern: &Regex,
+    what: &str,
+    source: &str,
+    translation: &str,
+) -> Result<(), String> {
+    let tokens = |text: &str| -> BTreeSet<String> {
+        pattern
+            .find_iter(text)
+            .map(|m| m.as_str().to_string())
+            .collect()
+    };
+    let expected = tokens(source);
+    let actual = tokens(translation);
+    if expected == actual {
+        return Ok(());
+    }
+
+    let mut problems = Vec::new();
+    let missing: Vec<&str> = expected.difference(&actual).map(String::as_str).collect();
+    if !missing.is_empty() {
+        problems.push(format!("missing {}", missing.join(" ")));
+    }
+    let unexpected: Vec<&str> = actual.difference(&expected).map(String::as_str).collect();
+    if !unexpected.is_empty() {
+        problems.push(format!("unexpected {}", unexpected.join(" ")));
+    }
+    Err(format!("{} changed: {}", what, problems.join(", ")))
+}
+
+/// Gives the translation the leading and trailing newlines of its source,
+/// and its trailing spaces, so the checks that follow compare the interior.
+pub fn normalize_whitespace(unit: &mut TranslationUnit) {
+    let plural_source = unit
+        .msg_id_plural
+        .clone()
+        .unwrap_or_else(|| unit.msg_id.clone());
+
+    if let Some(plurals) = &mut unit.msg_str_plural {
+        for (i, translation) in plurals.iter_mut().enumerate() {
+            let source = if i == 0 { &unit.msg_id } else { &plural_source };
+            normalize_form(source, translation);
+        }
+    } else if let Some(translation) = &mut unit.msg_str {
+        normalize_form(&unit.msg_id, translation);
+    }
+}
+
+fn normalize_form(source: &str, translation: &mut String) {
+    if translation.is_empty() {
+        return;
+    }
+
+    let (leading, source_core, trailing) = split_newlines(source);
+    let (_, core, _) = split_newlines(translation);
+    let trailing_spaces = source_core.len() - source_core.trim_end_matches(' ').len();
+
+    *translation = format!(
+        "{}{}{}{}",
+        leading,
+        core.trim_end_matches(' '),
+        " ".repeat(trailing_spaces),
+        trailing
+    );
+}
+
+fn paragraphs_exempt(config: &TranslationConfig, target_lang: &str) -> bool {
+    config
+        .paragraph_structure_exempt
+        .iter()
+        .any(|lang| same_language(lang, target_lang))
+}
+
+static BLANK_LINE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\n[ \t]*\n\s*").unwrap());
+
+/// The translation must have as many blank-line separated paragraphs as the
+/// source, and as many newlines between its first and last line; leading
+/// and trailing newlines are already the source's.
+fn check_paragraphs(source: &str, translation: &str) -> Result<(), String> {
+    if translation.is_empty() {
+        return Ok(());
+    }
+    let (_, source_core, _) = split_newlines(source);
+    let (_, core, _) = split_newlines(translation);
+
+    let expected = BLANK_LINE.split(source_core).count();
+    let found = BLANK_LINE.split(core).count();
+    if expected != found {
+        return Err(format!(
+            "source has {} paragraph(s), translation has {}",
+            expected, found
+        ));
+    }
+    let expected = source_core.matches('\n').count();
+    let found = core.matches('\n').count();
+    if expected != found {
+        return Err(format!(
+            "source has {} embedded newline(s), translation has {}",
+            expected, found
+        ));
+    }
+    Ok(())
+}
+
+fn split_newlines(text: &str) -> (&str, &str, &str) {
+    let rest = text.trim_start_matches('\n');
+    let leading = &text[..text.len() - rest.len()];
+    let core = rest.trim_end_matches('\n');
+    let trailing = &rest[core.len()..];
+    (leading, core, trailing)
+}
+
+/// Checks that `po` has every message of `pot` exactly once, comparing the
+/// `(context, msgid)` keys as written in the files, since a PO parser
+/// merges duplicates.
+pub fn check_coverage(
+    pot: &[(Option<String>, String)],
+    po: &[(Option<String>, String)],
+) -> Result<(), String> {
+    let mut counts: BTreeMap<(Option<&str>, &str), isize> = BTreeMap::new();
+    for (ctxt, msgid) in pot {
+        *counts.entry((ctxt.as_deref(), msgid)).or_default() += 1;
+    }
+    for (ctxt, msgid) in po {
+        *counts.entry((ctxt.as_deref(), msgid)).or_default() -= 1;
+    }
+
+    let describe = |(ctxt, msgid): &(Option<&str>, &str)| match ctxt {
+        Some(ctxt) => format!("{:?} (context {:?})", msgid, ctxt),
+        None => format!("{:?}", msgid),
+    };
+    let missing: Vec<String> = counts
+        .iter()
+        .filter(|(_, count)| **count > 0)
+        .map(|(key, _)| describe(key))
+        .collect();
+    let extra: Vec<String> = counts
+        .iter()
+        .filter(|(_, count)| **count < 0)
+        .map(|(key, _)| describe(key))
+        .collect();
+
+    if missing.is_empty() && extra.is_empty() {
+        return Ok(());
+    }
+
+    let mut problems = Vec::new();
+    if !missing.is_empty() {
+        problems.push(format!("missing {}: {}", missing.len(), missing.join(", ")));
+    }
+    if !extra.is_empty() {
+        problems.push(format!(
+            "extra or duplicated {}: {}",
+            extra.len(),
+            extra.join(", ")
+        ));
+    }
+    Err(problems.join("; "))
+}
+
+/// Compares the Markdown constructs of source and translation in document
+/// order, ignoring their text: link and image destinations, code spans and
+/// fenced blocks must be identical, and headings and list items must keep
+/// their kind. Inline constructs may move within their line.
+fn check_markdown(source: &str, translation: &str) -> Result<(), String> {
+    let mut expected = MarkdownEvents::new(source);
+    let mut actual = MarkdownEvents::new(translation);
+    loop {
+        match (expected.next(), actual.next()) {
+            (None, None) => return Ok(()),
+            (Some(a), Some(b)) if a == b => {}
+            (Some(a), Some(b)) => {
+                return Err(format!("markdown changed: expected {}, found {}", a, b));
+            }
+            (Some(a), None) => return Err(format!("markdown {} missing", a)),
+            (None, Some(b)) => return Err(format!("markdown {} not in source", b)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum MarkdownEvent {
+    Heading(usize),
+    ListItem { ordered: bool },
+    CodeBlock(String),
+    CodeSpan(String),
+    Link(String),
+    Image(String),
+}
+
+impl fmt::Display for MarkdownEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarkdownEvent::Heading(level) => write!(f, "level {} heading", level),
+            MarkdownEvent::ListItem { ordered: true } => write!(f, "numbered list item"),
+            MarkdownEvent::ListItem { ordered: false } => write!(f, "bulleted list item"),
+            MarkdownEvent::CodeBlock(body) => {
+                write!(
+                    f,
+                    "code block starting {:?}",
+                    body.lines().next().unwrap_or("")
+                )
+            }
+            MarkdownEvent::CodeSpan(code) => write!(f, "code span `{}`", code),
+            MarkdownEvent::Link(url) => write!(f, "link to {}", url),
+            MarkdownEvent::Image(url) => write!(f, "image {}", url),
+        }
+    }
+}
+
+/// A line-based pull parser yielding the Markdown constructs of a text.
+/// Inline events are sorted within their line, so translations may reorder
+/// links and code spans in a sentence.
+struct MarkdownEvents<'a> {
+    lines: std::str::Lines<'a>,
+    pending: VecDeque<MarkdownEvent>,
+}
+
+impl<'a> MarkdownEvents<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            lines: text.lines(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// The body of a fenced block opened by `marker`, up to its closing
+    /// fence or the end of the text.
+    fn fenced_block(&mut self, marker: &str) -> String {
+        let mut body = String::new();
+        for line in self.lines.by_ref() {
+            if line.trim_start().starts_with(marker) {
+                break;
+            }
+            body.push_str(line);
+            body.push('\n');
+        }
+        body
+    }
+}
+
+impl Iterator for MarkdownEvents<'_> {
+    type Item = MarkdownEvent;
+
+    fn next(&mut self) -> Option<MarkdownEvent> {
+        while self.pending.is_empty() {
+            let line = self.lines.next()?;
+            let trimmed = line.trim_start();
+
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                let body = self.fenced_block(&trimmed[..3]);
+                self.pending.push_back(MarkdownEvent::CodeBlock(body));
+                continue;
+            }
+
+            let hashes = trimmed.chars().take_while(|c| *c == '#').count();
+            if (1..=6).contains(&hashes) && trimmed[hashes..].starts_with(' ') {
+                self.pending.push_back(MarkdownEvent::Heading(hashes));
+            }
+            if let Some(ordered) = list_item(trimmed) {
+                self.pending.push_back(MarkdownEvent::ListItem { ordered });
+            }
+
+            let mut inline = inline_events(line);
+            inline.sort();
+            self.pending.extend(inline);
+        }
+        self.pending.pop_front()
+    }
+}
+
+fn inline_events(line: &str) -> Vec<MarkdownEvent> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut events = Vec::new();
+    let mut brackets = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '`' => {
+                let ticks = chars[i..].iter().take_while(|c| **c == '`').count();
+                let start = i + ticks;
+                let closing = (start..chars.len()).find(|&j| {
+                    chars[j..].iter().take_while(|c| **c == '`').count() == ticks
+                        && (j == 0 || chars[j - 1] != '`')
+                });
+                match closing {
+                    Some(end) => {
+                        events.push(MarkdownEvent::CodeSpan(chars[start..end].iter().collect()));
+                        i = end + ticks;
+                    }
+                    None => i = start,
+                }
+            }
+            '[' => {
+                brackets.push(i);
+                i += 1;
+            }
+            ']' if chars.get(i + 1) == Some(&'(') => {
+                let image = brackets
+                    .pop()
+                    .is_some_and(|open| open > 0 && chars[open - 1] == '!');
+                let start = i + 2;
+                match chars[start..].iter().position(|c| *c == ')') {
+                    Some(len) => {
+                        let target: String = chars[start..start + len].iter().collect();
+                        let url = target.split_whitespace().next().unwrap_or("").to_string();
+                        events.push(if image {
+                            MarkdownEvent::Image(url)
+                        } else {
+                            MarkdownEvent::Link(url)
+                        });
+                        i = start + len + 1;
+                    }
+                    None => i += 1,
+                }
+            }
+            ']' => {
+                brackets.pop();
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    events
+}
+
+/// Whether the line starts a list item, and if so whether it is numbered.
+fn list_item(line: &str) -> Option<bool> {
+    if let Some(rest) = line
+        .strip_prefix("- ")
+        .or_else(|| line.strip_prefix("* "))
+        .or_else(|| line.strip_prefix("+ "))
+    {
+        return (!rest.trim().is_empty()).then_some(false);
+    }
+
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    (digits > 0 && (line[digits..].starts_with(". ") || line[digits..].starts_with(") ")))
+        .then_some(true)
+}
+
+pub fn check_roundtrip(written: &Catalog, reparsed: &Catalog) -> Result<(), String> {
+    let mut differences = Vec::new();
+
+    let expected = header_fields(&written.metadata.export_for_po());
+    let actual = header_fields(&reparsed.metadata.export_for_po());
+    for key in expected
+        .keys()
+        .chain(actual.keys())
+        .collect::<BTreeSet<_>>()
+    {
+        if expected.get(key) != actual.get(key) {
+            differences.push(format!(
+                "header {}: wrote {:?}, read back {:?}",
+                key,
+                expected.get(key),
+                actual.get(key)
+            ));
+        }
+    }
+
+    for message in written.messages() {
+        let msgid_plural = message.msgid_plural().ok();
+        let label = match message.msgctxt() {
+            Some(ctxt) => format!("{:?} (context {:?})", message.msgid(), ctxt),
+            None => format!("{:?}", message.msgid()),
+        };
+
+        let Some(reread) = reparsed.find_message(message.msgctxt(), message.msgid(), msgid_plural)
+        else {
+            differences.push(format!("{}: missing after re-parse", label));
+            continue;
+        };
+
+        for ((field, wrote), (_, read)) in message_fields(message)
+            .into_iter()
+            .zip(message_fields(reread))
+        {
+            if wrote != read {
+                differences.push(format!(
+                    "{} {}: wrote {}, read back {}",
+                    label, field, wrote, read
+                ));
+            }
+        }
+    }
+
+    if reparsed.count() != written.count() {
+        differences.push(format!(
+            "wrote {} message(s), read back {}",
+            written.count(),
+            reparsed.count()
+        ));
+    }
+
+    if differences.is_empty() {
+        return Ok(());
+    }
+
+    let shown = differences.len().min(10);
+    let mut report = differences[..shown].join("; ");
+    if differences.len() > shown {
+        report.push_str(&format!("; and {} more", differences.len() - shown));
+    }
+    Err(report)
+}
+
+fn header_fields(header: &str) -> BTreeMap<String, String> {
+    header
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+fn message_fields(message: &dyn MessageView) -> [(&'static str, String); 6] {
+    let translation = match message.msgstr_plural() {
+        Ok(forms) => format!("{:?}", forms),
+        Err(_) => format!("{:?}", message.msgstr().unwrap_or_default()),
+    };
+
+    [
+        ("msgstr", translation),
+        ("flags", format!("{:?}", message.flags().to_string())),
+        (
+            "translator comments",
+            format!("{:?}", message.translator_comments()),
+        ),
+        (
+            "extracted comments",
+            format!("{:?}", message.extracted_comments()),
+        ),
+        ("source", format!("{:?}", message.source())),
+        ("plural", format!("{:?}", message.is_plural())),
+    ]
+}
+
+#[derive(Debug, Default)]
+pub struct CatalogAudit {
+    pub messages: usize,
+    pub untranslated: usize,
+    pub fuzzy: usize,
+    /// Labels of the entries locked against retranslation.
+    pub locked: Vec<String>,
+    pub issues: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Audits a catalog's translations. Given a configuration, they go through
+/// its validation pipeline, as fresh translations would, rather than only
+/// the checks every catalog gets.
+pub fn audit_catalog(
+    catalog: &Catalog,
+    pipeline: Option<(&TranslationConfig, &ValidationConfig)>,
+) -> CatalogAudit {
+    let lang = &catalog.metadata.language;
+    let mut audit = CatalogAudit::default();
+
+    for message in catalog.messages() {
+        if message.msgid().is_empty() {
+            continue;
+        }
+        audit.messages += 1;
+
+        let label = match message.msgctxt() {
+            Some(ctxt) => format!("{:?} (context {:?})", message.msgid(), ctxt),
+            None => format!("{:?}", message.msgid()),
+        };
+        if is_locked(message) {
+            audit.locked.push(label.clone());
+        }
+
+        let pairs: Vec<(&str, &str)> = match message.msgstr_plural() {
+            Ok(forms) => {
+                let plural = message.msgid_plural().unwrap_or_default();
+                forms
+                    .iter()
+                    .enumerate()
+                    .map(|(i, form)| {
+                        let source = if i == 0 { message.msgid() } else { plural };
+                        (source, form.as_str())
+                    })
+                    .collect()
+            }
+            Err(_) => vec![(message.msgid(), message.msgstr().unwrap_or_default())],
+        };
+
+        if pairs.iter().all(|(_, translation)| translation.is_empty()) {
+            audit.untranslated += 1;
+            continue;
+        }
+        if message.is_fuzzy() {
+            audit.fuzzy += 1;
+            continue;
+        }
+
+        if pairs
+            .iter()
+            .any(|(_, translation)| translation.trim().is_empty())
+        {
+            audit
+                .issues
+                .push(format!("{}: empty or blank translation form", label));
+        }
+
+        for (source, translation) in &pairs {
+            if translation.trim().is_empty() {
+                continue;
+            }
+            let mut result = check_positional_placeholders(source, translation);
+            if pipeline.is_none() {
+                result = result
+                    .and_then(|()| check_control_characters(source, translation))
+                    // Flagged formats are checked with the rest of the file.
+                    .and_then(|()| {
+                        if format_flags(message.flags()).is_empty() {
+                            check_format_flags(&[], source, translation)
+                        } else {
+                            Ok(())
+                        }
+                    })
+                    .and_then(|()| check_icu_message(source, translation, lang));
+            }
+            if let Err(reason) = result {
+                audit.issues.push(format!("{}: {}", label, reason));
+            }
+        }
+
+        if let Some((config, validation)) = pipeline
+            && pairs
+                .iter()
+                .all(|(_, translation)| !translation.trim().is_empty())
+        {
+            let unit = TranslationUnit {
+                msg_id: message.msgid().to_string(),
+                msg_id_plural: message.msgid_plural().ok().map(String::from),
+                msg_str: message.msgstr().ok().map(String::from),
+                msg_str_plural: message.msgstr_plural().ok().cloned(),
+                context: message.msgctxt().map(String::from),
+                comments: None,
+                rationale: None,
+                failure: None,
+                warnings: Vec::new(),
+                findings: Vec::new(),
+                attribution: None,
+                format_flags: format_flags(message.flags()),
+                references: message.source().to_string(),
+                fuzzy: false,
+                previous: None,
+            };
+            for finding in audit_unit(&unit, lang, config, validation, DocumentFormat::Gettext) {
+                let line = format!("{}: [{}] {}", label, finding.rule, finding.message);
+                match finding.severity {
+                    Severity::Error => audit.issues.push(line),
+                    Severity::Warning => audit.warnings.push(line),
+                }
+            }
+        }
+    }
+
+    audit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn printf_numbered_forms_may_reorder_unnumbered_ones() {
+        assert_eq!(
+            check_printf_conversions("%s of %d files", "%1$s von %2$d Dateien"),
+            Ok(())
+        );
+        assert_eq!(
+            check_printf_conversions("%s of %d files", "%2$d Dateien: %1$s"),
+            Ok(())
+        );
+        assert_eq!(
+            check_positional_placeholders("%s of %d files", "%2$d Dateien: %1$s"),
+            Ok(())
+        );
+        assert_eq!(check_printf_conversions("%s: %d", "%s : %i"), Ok(()));
+    }
+
+    #[test]
+    fn printf_arguments_must_keep_their_types() {
+        assert_eq!(
+            check_printf_conversions("%s of %d files", "%d Dateien von %s"),
+            Err("printf conversions changed: argument 1 is %s in the source, %d in the translation, argument 2 is %d in the source, %s in the translation; use numbered forms such as %1$s to reorder".to_string())
+        );
+        assert_eq!(
+            check_printf_conversions("%s of %d files", "%1$d von %2$s"),
+            Err("printf conversions changed: argument 1 is %s in the source, %d in the translation, argument 2 is %d in the source, %s in the translation".to_string())
+        );
+        assert_eq!(
+            check_printf_conversions("%ld bytes", "%d octets"),
+            Err("printf conversions changed: argument 1 is %ld in the source, %d in the translation; use numbered forms such as %1$s to reorder".to_string())
+        );
+    }
+
+    #[test]
+    fn printf_arguments_must_all_be_used() {
+        assert_eq!(
+            check_printf_conversions("%s of %d files", "%s Dateien"),
+            Err("printf conversions changed: missing argument 2 (%d)".to_string())
+        );
+        assert_eq!(
+            check_printf_conversions("100%% of %s", "%s, %s zu 100%%"),
+            Err("printf conversions changed: unexpected argument 2 (%s)".to_string())
+        );
+        assert_eq!(
+            check_printf_conversions("%s of %d", "%1$s von %d"),
+            Err(
+                "in the translation, printf conversions mix numbered (%1$s) and unnumbered (%s) forms"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn printf_star_width_takes_an_argument() {
+        assert_eq!(
+            check_printf_conversions("%*d items", "%*d Elemente"),
+            Ok(())
+        );
+        assert!(check_printf_conversions("%*d items", "%d Elemente").is_err());
+    }
+
+    #[test]
+    fn coverage_counts_duplicates_and_contexts() {
+        let key = |ctxt: Option<&str>, msgid: &str| (ctxt.map(String::from), msgid.to_string());
+        let pot = vec![key(None, "Open"), key(Some("menu"), "Open")];
+        assert_eq!(check_coverage(&pot, &pot.clone()), Ok(()));
+        assert_eq!(
+            check_coverage(&pot, &[key(None, "Open"), key(None, "Open")]),
+            Err(
+                "missing 1: \"Open\" (context \"menu\"); extra or duplicated 1: \"Open\""
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn straighten_quotes_leaves_other_quote_styles() {
+        assert_eq!(
+            restore_form_escapes("Open \"%@\"", "„%@“ öffnen", true).as_bytes(),
+            "„%@“ öffnen".as_bytes()
+        );
+        assert_eq!(
+            restore_form_escapes("Open \"%@\"", "Ouvrir « %@ »", true),
+            "Ouvrir « %@ »"
+        );
+        assert_eq!(
+            restore_form_escapes("Open \"%@\"", "Open “%@”", true),
+            "Open \"%@\""
+        );
+        assert_eq!(
+            restore_form_escapes("Don't open '%@'", "N’ouvrez pas ‘%@’", true),
+            "N’ouvrez pas '%@'"
+        );
+        assert_eq!(
+            restore_form_escapes("Open \"%@\"", "Open “%@”", false),
+            "Open “%@”"
+        );
+    }
+
+    #[test]
+    fn graphemes_count_what_a_reader_sees() {
+        assert_eq!(graphemes("e\u{301}te\u{301}"), 3);
+        assert_eq!(graphemes("🇫🇷 👨‍👩‍👧"), 3);
+        assert_eq!(graphemes("नमस्ते"), 3);
+        assert_eq!(graphemes("a\r\nb"), 3);
+    }
+
+    #[test]
+    fn audit_lists_locked_entries() {
+        let catalog = polib::po_file::parse_from_reader(
+            "msgid \"\"\n\
+             msgstr \"Content-Type: text/plain; charset=UTF-8\\nLanguage: fr\\n\"\n\n\
+             #, x-po-llm-locked\nmsgid \"Open\"\nmsgstr \"Ouvrir\"\n\n\
+             # po-llm:lock\n#, fuzzy\nmsgctxt \"menu\"\nmsgid \"Save\"\nmsgstr \"Sauver\"\n\n\
+             msgid \"Close\"\nmsgstr \"Fermer\"\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        let audit = audit_catalog(&catalog, None);
+        assert_eq!(audit.locked, ["\"Open\"", "\"Save\" (context \"menu\")"]);
+        assert_eq!(audit.fuzzy, 1);
+    }
+
+    #[test]
+    fn escapes_follow_the_source_byte_for_byte() {
+        assert_eq!(
+            restore_form_escapes("Line\\nTab\\t", "Ligne\nTab\t", false).as_bytes(),
+            b"Ligne\\nTab\\t"
+        );
+        assert_eq!(
+            restore_form_escapes("Line\nTab\t", "Ligne\\nTab\\t", false).as_bytes(),
+            b"Ligne\nTab\t"
+        );
+        assert_eq!(
+            restore_form_escapes("Say \"hi\"", "Dis \\\"salut\\\"", false).as_bytes(),
+            b"Dis \"salut\""
+        );
+        assert_eq!(
+            restore_form_escapes("Say \\\"hi\\\"", "Dis \\\"salut\\\"", false).as_bytes(),
+            b"Dis \\\"salut\\\""
+        );
+    }
+
+    #[test]
+    fn control_characters_must_come_from_the_source() {
+        assert!(check_control_characters("Ring", "Sonner\u{7}").is_err());
+        assert!(check_control_characters("Ring\u{7}", "Sonner\u{7}").is_ok());
+        assert!(check_control_characters("Name", "Nom\tcomplet").is_err());
+        assert!(check_control_characters("A\nB", "A\nB").is_ok());
+        assert!(check_control_characters("Save", "Enregistrer\u{fffd}").is_err());
+    }
+
+    #[test]
+    fn roundtrip_finds_what_changed_on_disk() {
+        let parse = |text: &str| polib::po_file::parse_from_reader(text.as_bytes()).unwrap();
+        let header = "msgid \"\"\nmsgstr \"Content-Type: text/plain; charset=UTF-8\\n\"\n\n";
+        let written = parse(&format!(
+            "{}#. Toolbar\n#, c-format\nmsgctxt \"menu\"\nmsgid \"Open %s\"\nmsgstr \"Ouvrir %s\"\n\n\
+             msgid \"A long message that the writer wraps over several lines when it is written out to disk.\"\n\
+             msgstr \"Un long message que l'écrivain coupe sur plusieurs lignes quand il l'écrit sur le disque.\"\n",
+            header
+        ));
+        let mut bytes = Vec::new();
+        crate::po_writer::write_catalog(&written, &mut bytes, crate::configs::WrapMode::Gettext)
+            .unwrap();
+        let reparsed = parse(std::str::from_utf8(&bytes).unwrap());
+        assert_eq!(check_roundtrip(&written, &reparsed), Ok(()));
+
+        let changed = parse(&format!(
+            "{}#. Toolbar\nmsgctxt \"menu\"\nmsgid \"Open %s\"\nmsgstr \"Ouvrir\"\n",
+            header
+        ));
+        let differences = check_roundtrip(&written, &changed).unwrap_err();
+        assert!(
+            differences.contains(
+                "\"Open %s\" (context \"menu\") msgstr: wrote \"Ouvrir %s\", read back \"Ouvrir\""
+            ),
+            "{}",
+            differences
+        );
+        assert!(
+            differences.contains(
+                "\"Open %s\" (context \"menu\") flags: wrote \"c-format\", read back \"\""
+            ),
+            "{}",
+            differences
+        );
+        assert!(
+            differences.contains("missing after re-parse"),
+            "{}",
+            differences
+        );
+    }
+
+    #[test]
+    fn python_placeholders_may_reorder() {
+        let named = vec!["python-format".to_string()];
+        assert_eq!(
+            check_format_flags(
+                &named,
+                "%(count)d files in %(folder)s",
+                "Dans %(folder)s : %(count)d fichiers"
+            ),
+            Ok(())
+        );
+        let brace = vec!["python-brace-format".to_string()];
+        assert_eq!(
+            check_format_flags(
+                &brace,
+                "{user.name} has {count:>3} items",
+                "{count:>3} éléments pour {user.name}"
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            check_format_flags(&brace, "{0} of {1}", "{1} sur {0}"),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn python_placeholders_are_compared_as_sets() {
+        let brace = vec!["python-brace-format".to_string()];
+        assert!(check_format_flags(&brace, "{count} files", "{nombre} fichiers").is_err());
+        assert!(check_format_flags(&brace, "{count} files", "fichiers").is_err());
+        // Doubled braces are literal, on either side.
+        assert_eq!(
+            check_format_flags(&brace, "{{count}} is {count}", "{{count}} vaut {count}"),
+            Ok(())
+        );
+        assert!(check_format_flags(&brace, "{{literal}}", "{literal}").is_err());
+
+        let named = vec!["python-format".to_string()];
+        assert!(check_format_flags(&named, "%(n)d%% done", "%(n)s%% fait").is_err());
+        assert_eq!(
+            check_format_flags(&named, "%(n)d%% done", "%(n)d %% fait"),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn the_flag_picks_the_placeholder_style() {
+        let mixed = "%(name)s sent {count} files";
+        // Without a flag every style is checked.
+        assert!(check_format_flags(&[], mixed, "%(name)s a envoyé des fichiers").is_err());
+        assert!(check_format_flags(&[], mixed, "{count} fichiers").is_err());
+        assert_eq!(
+            check_format_flags(&[], mixed, "{count} fichiers envoyés par %(name)s"),
+            Ok(())
+        );
+        // A flag checks its own style only.
+        let named = vec!["python-format".to_string()];
+        assert_eq!(
+            check_format_flags(&named, mixed, "%(name)s a envoyé des fichiers"),
+            Ok(())
+        );
+        let brace = vec!["python-brace-format".to_string()];
+        assert_eq!(
+            check_format_flags(&brace, mixed, "{count} fichiers"),
+            Ok(())
+        );
+    }
+
+    fn translation_config(extra: &str) -> TranslationConfig {
+        toml::from_str(&format!(
+            "target_languages = [\"fr\"]\nbatch_size = 10\n{}",
+            extra
+        ))
+        .unwrap()
+    }
+
+    fn validated(
+        unit: serde_json::Value,
+        lang: &str,
+        config: &TranslationConfig,
+        validation: &ValidationConfig,
+    ) -> TranslationResult {
+        let result = TranslationResult {
+            translated: vec![serde_json::from_value(unit).unwrap()],
+            failed_translated: Vec::new(),
+        };
+        validate_translations(result, lang, config, validation, DocumentFormat::Gettext)
+    }
+
+    #[test]
+    fn normalization_comes_before_term_checks() {
+        let validation: ValidationConfig = toml::from_str(
+            "[[custom]]\nname = \"product\"\npattern = \"Café Noir\"\nmust_match_source = true\n",
+        )
+        .unwrap();
+        let unit = serde_json::json!({
+            "msg_id": "Open Café Noir",
+            "msg_str": "Ouvrir Cafe\u{301} Noir",
+        });
+
+        let kept = validated(unit.clone(), "fr", &translation_config(""), &validation);
+        assert_eq!(kept.translated.len(), 0);
+        assert!(
+            kept.failed_translated[0]
+                .failure
+                .as_deref()
+                .unwrap()
+                .contains("product")
+        );
+
+        let config = translation_config("normalize_unicode = \"nfc\"");
+        let normalized = validated(unit, "fr", &config, &validation);
+        assert_eq!(normalized.failed_translated.len(), 0);
+        assert_eq!(
+            normalized.translated[0]
+                .msg_str
+                .as_deref()
+                .unwrap()
+                .as_bytes(),
+            "Ouvrir Caf\u{e9} Noir".as_bytes()
+        );
+    }
+
+    #[test]
+    fn nfkc_folds_compatibility_forms_in_every_plural_form() {
+        let config = translation_config("normalize_unicode = \"nfkc\"");
+        let result = validated(
+            serde_json::json!({
+                "msg_id": "One file",
+                "msg_id_plural": "%d files",
+                "msg_str_plural": ["Un ﬁchier", "%d ﬁchiers"],
+            }),
+            "fr",
+            &config,
+            &ValidationConfig::default(),
+        );
+        assert_eq!(
+            result.translated[0].msg_str_plural.as_deref().unwrap(),
+            ["Un fichier", "%d fichiers"]
+        );
+    }
+
+    #[test]
+    fn bidi_isolation_touches_only_rtl_translations() {
+        let config = translation_config("bidi_isolation = \"isolate\"");
+        let unit = serde_json::json!({ "msg_id": "Open %s", "msg_str": "افتح %s" });
+        let arabic = validated(unit, "ar", &config, &ValidationConfig::default());
+        assert_eq!(arabic.translated[0].msg_id, "Open %s");
+        assert_eq!(
+            arabic.translated[0].msg_str.as_deref(),
+            Some("افتح \u{2068}%s\u{2069}")
+        );
+
+        let unit = serde_json::json!({ "msg_id": "Open %s", "msg_str": "Ouvrir %s" });
+        let french = validated(unit, "fr", &config, &ValidationConfig::default());
+        assert_eq!(french.translated[0].msg_str.as_deref(), Some("Ouvrir %s"));
+
+        let config = translation_config("bidi_isolation = \"check\"");
+        let unit = serde_json::json!({ "msg_id": "Open %s", "msg_str": "افتح \u{2067}%s" });
+        let unbalanced = validated(unit, "ar", &config, &ValidationConfig::default());
+        assert_eq!(unbalanced.translated.len(), 0);
+    }
+
+    fn autofixed(unit: serde_json::Value, config: &TranslationConfig) -> TranslationResult {
+        let validation = ValidationConfig {
+            autofix: true,
+            ..ValidationConfig::default()
+        };
+        validated(unit, "fr", config, &validation)
+    }
+
+    fn fixed_rules(unit: &TranslationUnit) -> Vec<&str> {
+        unit.findings
+            .iter()
+            .filter(|f| f.fixed)
+            .map(|f| f.rule.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn autofix_strips_stray_characters() {
+        let unit = serde_json::json!({ "msg_id": "Save", "msg_str": "Enregis\u{200b}trer" });
+        let config = translation_config("");
+        let plain = validated(unit.clone(), "fr", &config, &ValidationConfig::default());
+        assert_eq!(plain.translated.len(), 0);
+
+        let result = autofixed(unit, &config);
+        let unit = &result.translated[0];
+        assert_eq!(unit.msg_str.as_deref(), Some("Enregistrer"));
+        assert_eq!(fixed_rules(unit), [STRAY_CHARACTERS_RULE]);
+        assert!(unit.warnings.is_empty());
+    }
+
+    #[test]
+    fn autofix_decodes_added_entities_and_restores_dropped_ones() {
+        let config = translation_config("");
+        let result = autofixed(
+            serde_json::json!({
+                "msg_id": "Use <b>bold</b>",
+                "msg_str": "Utiliser &lt;b&gt;gras&lt;/b&gt;",
+            }),
+            &config,
+        );
+        assert_eq!(
+            result.translated[0].msg_str.as_deref(),
+            Some("Utiliser <b>gras</b>")
+        );
+        assert_eq!(fixed_rules(&result.translated[0]), ["escapes"]);
+
+        let result = autofixed(
+            serde_json::json!({
+                "msg_id": "Tom &amp; Jerry on holiday",
+                "msg_str": "Tom & Jerry en vacances",
+            }),
+            &config,
+        );
+        assert_eq!(
+            result.translated[0].msg_str.as_deref(),
+            Some("Tom &amp; Jerry en vacances")
+        );
+        assert_eq!(fixed_rules(&result.translated[0]), ["escapes"]);
+    }
+
+    #[test]
+    fn autofix_puts_back_altered_literals() {
+        let result = autofixed(
+            serde_json::json!({
+                "msg_id": "See https://example.com/docs for help.",
+                "msg_str": "Voir https://exemple.com/docs pour de l'aide.",
+            }),
+            &translation_config(""),
+        );
+        assert_eq!(result.failed_translated.len(), 0);
+        assert_eq!(
+            result.translated[0].msg_str.as_deref(),
+            Some("Voir https://example.com/docs pour de l'aide.")
+        );
+        assert_eq!(fixed_rules(&result.translated[0]), ["literals"]);
+    }
+
+    #[test]
+    fn autofix_inserts_a_missing_accelerator() {
+        let config = translation_config("accelerator_markers = [\"&\"]");
+        let result = autofixed(
+            serde_json::json!({ "msg_id": "&Open", "msg_str": "Ouvrir" }),
+            &config,
+        );
+        assert_eq!(result.translated[0].msg_str.as_deref(), Some("&Ouvrir"));
+        assert_eq!(fixed_rules(&result.translated[0]), ["accelerator"]);
+    }
+
+    #[test]
+    fn autofix_restores_terminal_punctuation_in_the_target_style() {
+        let config = translation_config("terminal_punctuation = \"warn\"");
+        let result = autofixed(
+            serde_json::json!({ "msg_id": "Loading...", "msg_str": "Chargement" }),
+            &config,
+        );
+        assert_eq!(
+            result.translated[0].msg_str.as_deref(),
+            Some("Chargement...")
+        );
+        assert_eq!(fixed_rules(&result.translated[0]), ["terminal-punctuation"]);
+        assert!(result.translated[0].warnings.is_empty());
+
+        let result = autofixed(
+            serde_json::json!({ "msg_id": "Quit?", "msg_str": "Quitter" }),
+            &config,
+        );
+        assert_eq!(
+            result.translated[0].msg_str.as_deref(),
+            Some("Quitter\u{a0}?")
+        );
+    }
+
+    #[test]
+    fn autofix_fixes_every_plural_form() {
+        let config = translation_config("terminal_punctuation = \"warn\"");
+        let result = autofixed(
+            serde_json::json!({
+                "msg_id": "Deleting %d file...",
+                "msg_id_plural": "Deleting %d files...",
+                "msg_str_plural": ["Suppression de %d fichier", "Suppression de %d fichiers"],
+            }),
+            &config,
+        );
+        assert_eq!(
+            result.translated[0].msg_str_plural.as_deref().unwrap(),
+            [
+                "Suppression de %d fichier...",
+                "Suppression de %d fichiers..."
+            ]
+        );
+        assert_eq!(
+            fixed_rules(&result.translated[0]),
+            ["terminal-punctuation", "terminal-punctuation"]
+        );
+    }
+
+    #[test]
+    fn autofix_refuses_fixes_that_change_placeholders() {
+        // The accelerator goes before the first letter, which here is the
+        // conversion of `%s`.
+        let config = translation_config("accelerator_markers = [\"&\"]");
+        let result = autofixed(
+            serde_json::json!({ "msg_id": "&Open %s", "msg_str": "%s ouvrir" }),
+            &config,
+        );
+        assert_eq!(result.translated.len(), 0);
+        let failed = &result.failed_translated[0];
+        assert_eq!(failed.msg_str.as_deref(), Some("%s ouvrir"));
+        assert!(fixed_rules(failed).is_empty());
+        assert!(failed.failure.as_deref().unwrap().contains("accelerator"));
+    }
+}
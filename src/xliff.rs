@@ -0,0 +1,603 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::Write,
+    ops::Range,
+    path::Path,
+};
+
+use roxmltree::{Document, Node};
+
+use crate::{
+    configs::DocumentFormat,
+    errors::PoLlmError,
+    files::{FileGuard, write_atomically},
+    translations::{
+        self, FormatAdapter, TranslationUnit, WriteOptions, document_as, existing_or_template,
+    },
+};
+
+const XLIFF_12: &str = "urn:oasis:names:tc:xliff:document:1.2";
+const XLIFF_20: &str = "urn:oasis:names:tc:xliff:document:2.0";
+
+const TRANSLATED_STATES_12: &[&str] = &["translated", "signed-off", "final"];
+const TRANSLATED_STATES_20: &[&str] = &["translated", "reviewed", "final"];
+
+type SegmentKey = (Option<String>, String);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Version {
+    V12,
+    V20,
+}
+
+impl Version {
+    fn namespace(self) -> &'static str {
+        match self {
+            Version::V12 => XLIFF_12,
+            Version::V20 => XLIFF_20,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct XliffDocument {
+    text: String,
+}
+
+/// One `<trans-unit>` (1.2) or `<segment>` (2.0) with the nodes needed to
+/// read and rewrite it.
+struct Segment<'a, 'input> {
+    key: SegmentKey,
+    source: Node<'a, 'input>,
+    target: Option<Node<'a, 'input>>,
+    /// Element carrying the `state` attribute: `<target>` in 1.2, `<segment>` in 2.0.
+    state_holder: Option<Node<'a, 'input>>,
+    translatable: bool,
+    translated: bool,
+}
+
+impl XliffDocument {
+    pub fn read(path: &Path) -> Result<Self, PoLlmError> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| PoLlmError::io(format!("Failed to read XLIFF file {:?}", path), e))?;
+        Self::parse(text).map_err(|e| e.context(format!("Failed to parse XLIFF file {:?}", path)))
+    }
+
+    pub fn parse(text: String) -> Result<Self, PoLlmError> {
+        let document = Document::parse(&text).map_err(|e| PoLlmError::Parse(e.to_string()))?;
+        version(&document).map_err(PoLlmError::Parse)?;
+        Ok(Self { text })
+    }
+
+    fn units(&self) -> Vec<(SegmentKey, bool)> {
+        let Ok(document) = Document::parse(&self.text) else {
+            return Vec::new();
+        };
+        let Ok(version) = version(&document) else {
+            return Vec::new();
+        };
+
+        segments(&document, version)
+            .into_iter()
+            .filter(|segment| segment.translatable)
+            .map(|segment| (segment.key, segment.translated))
+            .collect()
+    }
+
+    fn translated_keys(&self) -> HashSet<SegmentKey> {
+        self.units()
+            .into_iter()
+            .filter(|(_, translated)| *translated)
+            .map(|(key, _)| key)
+            .collect()
+    }
+}
+
+pub struct XliffAdapter;
+
+impl FormatAdapter for XliffAdapter {
+    fn format(&self) -> DocumentFormat {
+        DocumentFormat::Xliff
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["xlf", "xliff"]
+    }
+
+    fn load_template(&self, path: &Path) -> Result<translations::Document, PoLlmError> {
+        Ok(Box::new(XliffDocument::read(path)?))
+    }
+
+    fn load_existing(
+        &self,
+        output_path: &Path,
+        template: &translations::Document,
+    ) -> Result<translations::Document, PoLlmError> {
+        existing_or_template(output_path, template, XliffDocument::read)
+    }
+
+    fn extract_units(
+        &self,
+        existing: &translations::Document,
+        template: &translations::Document,
+        skip_translated: bool,
+    ) -> Vec<TranslationUnit> {
+        let document: &XliffDocument = document_as(existing);
+        let template: &XliffDocument = document_as(template);
+        let translated = if skip_translated {
+            document.translated_keys()
+        } else {
+            HashSet::new()
+        };
+
+        let mut seen = HashSet::new();
+        template
+            .units()
+            .into_iter()
+            .map(|(key, _)| key)
+            .filter(|key| !key.1.trim().is_empty() && !translated.contains(key))
+            .filter(|key| seen.insert(key.clone()))
+            .map(|(context, msg_id)| TranslationUnit {
+                msg_id,
+                msg_id_plural: None,
+                msg_str: Some(String::new()),
+                msg_str_plural: None,
+                context,
+                comments: None,
+                rationale: None,
+                failure: None,
+                warnings: Vec::new(),
+                findings: Vec::new(),
+                attribution: None,
+                format_flags: Vec::new(),
+                references: String::new(),
+                fuzzy: false,
+                previous: None,
+            })
+            .collect()
+    }
+
+    fn write_translations(
+        &self,
+        translations: Vec<TranslationUnit>,
+        _target_lang: &str,
+        output: &FileGuard,
+        options: &WriteOptions,
+    ) -> Result<(), PoLlmError> {
+        let output_path = output.path();
+        let document = XliffDocument::read(output_path)?;
+
+        // XLIFF has no way to mark a single failed entry without losing its
+        // existing target, so failed entries are left untouched.
+        let targets: HashMap<SegmentKey, String> = translations
+            .into_iter()
+            .filter(|unit| unit.failure.is_none())
+            .filter_map(|unit| Some(((unit.context, unit.msg_id), unit.msg_str?)))
+            .filter(|(_, target)| !target.is_empty())
+            .collect();
+
+        let text = write_targets(&document.text, &targets).map_err(PoLlmError::Parse)?;
+
+        write_atomically(output_path, |writer| writer.write_all(text.as_bytes()))
+            .map_err(|e| PoLlmError::io("Failed to write XLIFF file", e))?;
+
+        if options.verify_roundtrip {
+            let reparsed = XliffDocument::read(output_path).map_err(|e| {
+                PoLlmError::Validation(format!("Round-trip check failed, cannot re-parse: {}", e))
+            })?;
+            let translated = reparsed.translated_keys();
+            let missing = targets
+                .keys()
+                .filter(|key| !translated.contains(*key))
+                .count();
+            if missing > 0 {
+                return Err(PoLlmError::Validation(format!(
+                    "Round-trip check failed: {} written target(s) not read back as translated",
+                    missing
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn version(document: &Document) -> Result<Version, String> {
+    let root = document.root_element();
+    match root.tag_name().namespace() {
+        _ if root.tag_name().name() != "xliff" => Err(format!(
+            "root element is <{}>, not <xliff>",
+            root.tag_name().name()
+        )),
+        Some(XLIFF_12) => Ok(Version::V12),
+        Some(XLIFF_20) => Ok(Version::V20),
+        Some(namespace) => Err(format!("unsupported XLIFF namespace {:?}", namespace)),
+        None => Err("missing XLIFF namespace".to_string()),
+    }
+}
+
+fn segments<'a, 'input>(
+    document: &'a Document<'input>,
+    version: Version,
+) -> Vec<Segment<'a, 'input>> {
+    let namespace = version.namespace();
+    let child = |node: Node<'a, 'input>, name: &str| {
+        node.children()
+            .find(|n| n.is_element() && n.has_tag_name((namespace, name)))
+    };
+    let notes = |parent: Option<Node<'a, 'input>>| {
+        let notes: Vec<String> = parent
+            .into_iter()
+            .flat_map(|p| p.children())
+            .filter(|n| n.is_element() && n.has_tag_name((namespace, "note")))
+            .map(|note| {
+                note.descendants()
+                    .filter_map(|n| n.is_text().then(|| n.text()).flatten())
+                    .collect::<String>()
+                    .trim()
+                    .to_string()
+            })
+            .filter(|note| !note.is_empty())
+            .collect();
+        (!notes.is_empty()).then(|| notes.join("\n"))
+    };
+
+    let mut segments = Vec::new();
+    match version {
+        Version::V12 => {
+            for unit in document
+                .descendants()
+                .filter(|n| n.has_tag_name((namespace, "trans-unit")))
+            {
+                let Some(source) = child(unit, "source") else {
+                    continue;
+                };
+                let target = child(unit, "target");
+                let translated = target.is_some_and(|target| {
+                    !inner_xml(document.input_text(), target).trim().is_empty()
+                        && target
+                            .attribute("state")
+                            .is_none_or(|state| TRANSLATED_STATES_12.contains(&state))
+                });
+
+                segments.push(Segment {
+                    key: (notes(Some(unit)), inner_xml(document.input_text(), source)),
+                    source,
+                    target,
+                    state_holder: target,
+                    translatable: unit.attribute("translate") != Some("no"),
+                    translated,
+                });
+            }
+        }
+        Version::V20 => {
+            for unit in document
+                .descendants()
+                .filter(|n| n.has_tag_name((namespace, "unit")))
+            {
+                let context = notes(child(unit, "notes"));
+                for segment in unit
+                    .children()
+                    .filter(|n| n.has_tag_name((namespace, "segment")))
+                {
+                    let Some(source) = child(segment, "source") else {
+                        continue;
+                    };
+                    let target = child(segment, "target");
+                    let translated = target.is_some_and(|target| {
+                        !inner_xml(document.input_text(), target).trim().is_empty()
+                            && segment
+                                .attribute("state")
+                                .is_none_or(|state| TRANSLATED_STATES_20.contains(&state))
+                    });
+
+                    segments.push(Segment {
+                        key: (context.clone(), inner_xml(document.input_text(), source)),
+                        source,
+                        target,
+                        state_holder: Some(segment),
+                        translatable: unit.attribute("translate") != Some("no"),
+                        translated,
+                    });
+                }
+            }
+        }
+    }
+
+    segments
+}
+
+/// Fills in `<target>` for every segment with a translation, leaving the rest
+/// of the document byte-for-byte as it was.
+fn write_targets(text: &str, targets: &HashMap<SegmentKey, String>) -> Result<String, String> {
+    let document = Document::parse(text).map_err(|e| e.to_string())?;
+    let version = version(&document)?;
+
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+    for segment in segments(&document, version) {
+        if !segment.translatable {
+            continue;
+        }
+        let Some(target) = targets.get(&segment.key) else {
+            continue;
+        };
+        check_fragment(target, version)
+            .map_err(|e| format!("Translation of {:?} is not valid XML: {}", segment.key.1, e))?;
+
+        match segment.target {
+            Some(node) => {
+                let tag_end = start_tag_end(text, node.range().start);
+                if text[..tag_end].ends_with("/>") {
+                    edits.push((
+                        tag_end - 2..tag_end,
+                        format!(">{}</{}>", target, qualified_name(text, node)),
+                    ));
+                } else {
+                    edits.push((inner_range(text, node), target.clone()));
+                }
+            }
+            None => {
+                let source_end = segment.source.range().end;
+                let indent = segment
+                    .source
+                    .prev_sibling()
+                    .filter(|n| n.is_text())
+                    .and_then(|n| n.text())
+                    .filter(|t| t.trim().is_empty())
+                    .unwrap_or("");
+                let state = match version {
+                    Version::V12 => " state=\"translated\"",
+                    Version::V20 => "",
+                };
+                let name = qualified_name(text, segment.source).replacen("source", "target", 1);
+                edits.push((
+                    source_end..source_end,
+                    format!("{}<{}{}>{}</{}>", indent, name, state, target, name),
+                ));
+            }
+        }
+
+        if let Some(holder) = segment.state_holder {
+            edits.push(set_state(text, holder));
+        }
+    }
+
+    // Apply from the end so earlier offsets stay valid; at the same offset the
+    // wider edit goes first so an insertion lands in front of it.
+    edits.sort_by(|(a, _), (b, _)| b.start.cmp(&a.start).then(b.end.cmp(&a.end)));
+    let mut result = text.to_string();
+    for (range, replacement) in edits {
+        result.replace_range(range, &replacement);
+    }
+
+    Document::parse(&result).map_err(|e| format!("Rewritten document is not valid XML: {}", e))?;
+    Ok(result)
+}
+
+fn check_fragment(fragment: &str, version: Version) -> Result<(), String> {
+    Document::parse(&format!(
+        "<target xmlns=\"{}\">{}</target>",
+        version.namespace(),
+        fragment
+    ))
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+/// Sets `state="translated"` on `node`, replacing any existing state.
+fn set_state(text: &str, node: Node) -> (Range<usize>, String) {
+    let value = "state=\"translated\"";
+    match node.attribute_node("state") {
+        Some(attribute) => (attribute.range(), value.to_string()),
+        None => {
+            let tag_end = start_tag_end(text, node.range().start);
+            let insert_at = if text[..tag_end].ends_with("/>") {
+                tag_end - 2
+            } else {
+                tag_end - 1
+            };
+            (insert_at..insert_at, format!(" {}", value))
+        }
+    }
+}
+
+/// Raw markup between an element's start and end tags, inline tags and
+/// entities included.
+fn inner_xml(text: &str, node: Node) -> String {
+    let range = inner_range(text, node);
+    text[range].to_string()
+}
+
+pub(crate) fn inner_range(text: &str, node: Node) -> Range<usize> {
+    let range = node.range();
+    let tag_end = start_tag_end(text, range.start);
+    if text[..tag_end].ends_with("/>") {
+        return tag_end..tag_end;
+    }
+    let close_start = text[..range.end].rfind("</").unwrap_or(range.end);
+    tag_end..close_start.max(tag_end)
+}
+
+/// Byte offset just past the `>` closing the start tag that begins at `start`,
+/// ignoring any `>` inside quoted attribute values.
+pub(crate) fn start_tag_end(text: &str, start: usize) -> usize {
+    let mut quote = None;
+    for (offset, c) in text[start..].char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return start + offset + 1,
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+fn qualified_name<'t>(text: &'t str, node: Node) -> &'t str {
+    let start = node.range().start + 1;
+    let rest = &text[start..];
+    let end = rest
+        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .unwrap_or(rest.len());
+    &rest[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE_12: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff version="1.2" xmlns="urn:oasis:names:tc:xliff:document:1.2">
+  <file source-language="en" target-language="fr" datatype="plaintext" original="app">
+    <body>
+      <trans-unit id="open">
+        <source>Open <g id="1">file</g></source>
+      </trans-unit>
+      <trans-unit id="save">
+        <source>Save</source>
+        <target state="needs-translation"/>
+        <note>toolbar</note>
+      </trans-unit>
+      <trans-unit id="name" translate="no">
+        <source>Po-LLM</source>
+      </trans-unit>
+      <trans-unit id="quit">
+        <source>Quit</source>
+        <target state="final">Quitter</target>
+      </trans-unit>
+    </body>
+  </file>
+</xliff>
+"#;
+
+    const TRANSLATED_12: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff version="1.2" xmlns="urn:oasis:names:tc:xliff:document:1.2">
+  <file source-language="en" target-language="fr" datatype="plaintext" original="app">
+    <body>
+      <trans-unit id="open">
+        <source>Open <g id="1">file</g></source>
+        <target state="translated">Ouvrir le <g id="1">fichier</g></target>
+      </trans-unit>
+      <trans-unit id="save">
+        <source>Save</source>
+        <target state="translated">Enregistrer</target>
+        <note>toolbar</note>
+      </trans-unit>
+      <trans-unit id="name" translate="no">
+        <source>Po-LLM</source>
+      </trans-unit>
+      <trans-unit id="quit">
+        <source>Quit</source>
+        <target state="final">Quitter</target>
+      </trans-unit>
+    </body>
+  </file>
+</xliff>
+"#;
+
+    const SOURCE_20: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff version="2.0" xmlns="urn:oasis:names:tc:xliff:document:2.0" srcLang="en" trgLang="fr">
+  <file id="f1">
+    <unit id="u1">
+      <notes><note>menu</note></notes>
+      <segment>
+        <source>Open</source>
+      </segment>
+      <segment state="initial">
+        <source>Close</source>
+        <target></target>
+      </segment>
+    </unit>
+  </file>
+</xliff>
+"#;
+
+    const TRANSLATED_20: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff version="2.0" xmlns="urn:oasis:names:tc:xliff:document:2.0" srcLang="en" trgLang="fr">
+  <file id="f1">
+    <unit id="u1">
+      <notes><note>menu</note></notes>
+      <segment state="translated">
+        <source>Open</source>
+        <target>Ouvrir</target>
+      </segment>
+      <segment state="translated">
+        <source>Close</source>
+        <target>Fermer</target>
+      </segment>
+    </unit>
+  </file>
+</xliff>
+"#;
+
+    fn key(context: Option<&str>, msg_id: &str) -> SegmentKey {
+        (context.map(String::from), msg_id.to_string())
+    }
+
+    fn msg_ids(text: &str) -> Vec<(Option<String>, String)> {
+        let document: translations::Document =
+            Box::new(XliffDocument::parse(text.to_string()).unwrap());
+        XliffAdapter
+            .extract_units(&document, &document, true)
+            .into_iter()
+            .map(|unit| (unit.context, unit.msg_id))
+            .collect()
+    }
+
+    #[test]
+    fn units_leave_out_untranslatable_and_translated_segments() {
+        assert_eq!(
+            msg_ids(SOURCE_12),
+            [
+                key(None, "Open <g id=\"1\">file</g>"),
+                key(Some("toolbar"), "Save"),
+            ]
+        );
+        assert_eq!(
+            msg_ids(SOURCE_20),
+            [key(Some("menu"), "Open"), key(Some("menu"), "Close")]
+        );
+    }
+
+    #[test]
+    fn xliff_12_targets_match_the_golden_file() {
+        let targets = HashMap::from([
+            (
+                key(None, "Open <g id=\"1\">file</g>"),
+                "Ouvrir le <g id=\"1\">fichier</g>".to_string(),
+            ),
+            (key(Some("toolbar"), "Save"), "Enregistrer".to_string()),
+            (key(None, "Po-LLM"), "Po-LLM".to_string()),
+        ]);
+        assert_eq!(write_targets(SOURCE_12, &targets).unwrap(), TRANSLATED_12);
+        assert!(msg_ids(TRANSLATED_12).is_empty());
+    }
+
+    #[test]
+    fn xliff_20_targets_match_the_golden_file() {
+        let targets = HashMap::from([
+            (key(Some("menu"), "Open"), "Ouvrir".to_string()),
+            (key(Some("menu"), "Close"), "Fermer".to_string()),
+        ]);
+        assert_eq!(write_targets(SOURCE_20, &targets).unwrap(), TRANSLATED_20);
+        assert!(msg_ids(TRANSLATED_20).is_empty());
+    }
+
+    #[test]
+    fn targets_that_are_not_xml_are_refused() {
+        let targets =
+            HashMap::from([(key(Some("toolbar"), "Save"), "Enregistrer <b>".to_string())]);
+        let error = write_targets(SOURCE_12, &targets).unwrap_err();
+        assert!(error.contains("\"Save\" is not valid XML"), "{}", error);
+    }
+
+    #[test]
+    fn documents_without_the_xliff_namespace_are_refused() {
+        let error = XliffDocument::parse("<xliff version=\"1.2\"/>".to_string()).unwrap_err();
+        assert!(
+            matches!(error, PoLlmError::Parse(message) if message == "missing XLIFF namespace")
+        );
+    }
+}
@@ -0,0 +1,975 @@
+use std::{cmp::Reverse, fs, io::Write, ops::Range, path::Path};
+
+use yaml_rust2::{Event, parser::Parser, scanner::TScalarStyle};
+
+use crate::{
+    configs::DocumentFormat,
+    errors::PoLlmError,
+    files::{FileGuard, write_atomically},
+    plurals::{cldr_forms, plural_forms_or_default},
+    translations::{
+        Document, FormatAdapter, TranslationUnit, WriteOptions, document_as, existing_or_template,
+    },
+};
+
+/// Sub-keys Rails pluralization looks up, one per CLDR category.
+const PLURAL_CATEGORIES: &[&str] = &["zero", "one", "two", "few", "many", "other"];
+
+/// A Rails-style locale file: a single top-level language key holding a
+/// tree of messages. Edits are spliced into the original text, so comments,
+/// anchors, aliases and quoting survive untouched.
+#[derive(Debug, Clone)]
+pub struct YamlDocument {
+    text: String,
+    language: Scalar,
+    root: Mapping,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Scalar(Scalar),
+    Mapping(Mapping),
+    Sequence(Vec<Node>, Range<usize>),
+    /// Aliases and anything else that is kept as written.
+    Other(Range<usize>),
+}
+
+#[derive(Debug, Clone)]
+struct Scalar {
+    value: String,
+    style: TScalarStyle,
+    flow: bool,
+    /// Byte range of the scalar as written; for block scalars only the
+    /// content, after the `|` or `>` header.
+    range: Range<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct Mapping {
+    flow: bool,
+    entries: Vec<(Scalar, Node)>,
+    /// For block mappings the range runs from the first key to the end of the
+    /// last value; for flow mappings it covers the braces.
+    range: Range<usize>,
+}
+
+impl Node {
+    fn range(&self) -> Range<usize> {
+        match self {
+            Node::Scalar(scalar) => scalar.range.clone(),
+            Node::Mapping(mapping) => mapping.range.clone(),
+            Node::Sequence(_, range) | Node::Other(range) => range.clone(),
+        }
+    }
+
+    fn get(&self, segment: &str) -> Option<&Node> {
+        match self {
+            Node::Mapping(mapping) => mapping.get(segment),
+            Node::Sequence(items, _) => items.get(segment.parse::<usize>().ok()?),
+            _ => None,
+        }
+    }
+}
+
+impl Mapping {
+    fn get(&self, key: &str) -> Option<&Node> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k.value == key)
+            .map(|(_, node)| node)
+    }
+
+    /// `(category, scalar)` pairs when every key is a plural category and
+    /// `other` is among them.
+    fn plural_forms(&self) -> Option<Vec<(&str, &Scalar)>> {
+        let forms = self
+            .entries
+            .iter()
+            .map(|(key, node)| match node {
+                Node::Scalar(scalar) if PLURAL_CATEGORIES.contains(&key.value.as_str()) => {
+                    Some((key.value.as_str(), scalar))
+                }
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        forms
+            .iter()
+            .any(|(category, _)| *category == "other")
+            .then_some(forms)
+    }
+}
+
+/// A translatable leaf or plural group, addressed by its dot path below the
+/// language key.
+enum Message<'a> {
+    Single(String, &'a Scalar),
+    Plural(String, Vec<(&'a str, &'a Scalar)>),
+}
+
+impl YamlDocument {
+    pub fn read(path: &Path) -> Result<Self, PoLlmError> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| PoLlmError::io(format!("Failed to read YAML file {:?}", path), e))?;
+        Self::parse(text)
+            .map_err(|e| PoLlmError::Parse(format!("Failed to parse YAML file {:?}: {}", path, e)))
+    }
+
+    fn parse(text: String) -> Result<Self, String> {
+        let top = TreeBuilder::new(&text)?.document()?;
+        let Node::Mapping(top) = top else {
+            return Err("expected a mapping with the language as its only key".to_string());
+        };
+        let [(language, root)] = <[_; 1]>::try_from(top.entries)
+            .map_err(|_| "expected the language as the only top-level key".to_string())?;
+        let Node::Mapping(root) = root else {
+            return Err(format!("'{}' does not hold a mapping", language.value));
+        };
+        if root.flow {
+            return Err(format!("'{}' holds a flow mapping", language.value));
+        }
+
+        Ok(Self {
+            text,
+            language,
+            root,
+        })
+    }
+
+    fn get(&self, path: &str) -> Option<&Node> {
+        let mut segments = path.split('.');
+        let mut node = self.root.get(segments.next()?)?;
+        for segment in segments {
+            node = node.get(segment)?;
+        }
+        Some(node)
+    }
+
+    fn messages(&self) -> Vec<Message<'_>> {
+        let mut messages = Vec::new();
+        collect_messages(&self.root, "", &mut messages);
+        messages
+    }
+
+    fn is_translated(&self, path: &str, source: &str) -> bool {
+        match self.get(path) {
+            Some(Node::Scalar(scalar)) => {
+                !scalar.value.trim().is_empty() && scalar.value.trim() != source.trim()
+            }
+            _ => false,
+        }
+    }
+
+    /// Applies byte-range edits and re-parses the result.
+    fn edited(&self, mut edits: Vec<(Range<usize>, String)>) -> Result<Self, String> {
+        edits.sort_by_key(|(range, _)| Reverse(range.start));
+        let mut text = self.text.clone();
+        for (range, replacement) in edits {
+            text.replace_range(range, &replacement);
+        }
+        Self::parse(text).map_err(|e| format!("Rewritten YAML does not parse: {}", e))
+    }
+
+    fn plural_text(&self, mapping: &Mapping, forms: &[(&str, String)]) -> String {
+        let style = mapping
+            .plural_forms()
+            .and_then(|existing| existing.last().map(|(_, scalar)| scalar.style))
+            .unwrap_or(TScalarStyle::Plain);
+        let pairs = forms
+            .iter()
+            .map(|(category, text)| {
+                format!(
+                    "{}: {}",
+                    category,
+                    render_scalar(text, style, mapping.flow, "")
+                )
+            })
+            .collect::<Vec<_>>();
+        if mapping.flow {
+            format!("{{{}}}", pairs.join(", "))
+        } else {
+            pairs.join(&format!("\n{}", self.indent_at(mapping.range.start)))
+        }
+    }
+
+    /// An insertion adding a key the output lacks below its deepest existing
+    /// parent, creating the mappings in between.
+    fn insertion(
+        &self,
+        path: &str,
+        translation: &Translation,
+    ) -> Result<(Range<usize>, String), String> {
+        let segments: Vec<&str> = path.split('.').collect();
+        let mut parent = &self.root;
+        let mut depth = 0;
+        while depth + 1 < segments.len() {
+            match parent.get(segments[depth]) {
+                Some(Node::Mapping(child)) if !child.flow => parent = child,
+                None => break,
+                Some(_) => {
+                    return Err(format!(
+                        "cannot add '{}' below '{}'",
+                        path,
+                        segments[..=depth].join(".")
+                    ));
+                }
+            }
+            depth += 1;
+        }
+
+        let base = self.indent_at(parent.range.start).to_string();
+        let step = self.indent_step();
+        let indent = |level: usize| format!("{}{}", base, step.repeat(level));
+        let mut text = String::new();
+        for (level, segment) in segments[depth..].iter().enumerate() {
+            text.push_str(&format!(
+                "\n{}{}:",
+                indent(level),
+                render_scalar(segment, TScalarStyle::Plain, false, "")
+            ));
+        }
+        match translation {
+            Translation::Single(value) => {
+                text.push(' ');
+                text.push_str(&render_scalar(value, TScalarStyle::Plain, false, ""));
+            }
+            Translation::Plural(forms) => {
+                for (category, value) in forms {
+                    text.push_str(&format!(
+                        "\n{}{}: {}",
+                        indent(segments.len() - depth),
+                        category,
+                        render_scalar(value, TScalarStyle::DoubleQuoted, false, "")
+                    ));
+                }
+            }
+        }
+
+        let at = parent.range.end;
+        Ok((at..at, text))
+    }
+
+    /// The document with `targets` written in. Keys already there are
+    /// rewritten in one pass; missing ones are added one at a time so shared
+    /// new parents are created once. Keys whose shape differs from the
+    /// translation are left alone and returned.
+    fn with_translations<'p>(
+        &self,
+        targets: &'p [(String, Translation)],
+    ) -> Result<(Self, Vec<&'p str>), String> {
+        let mut edits = Vec::new();
+        let mut missing = Vec::new();
+        let mut skipped = Vec::new();
+        for (path, translation) in targets {
+            match (self.get(path), translation) {
+                (Some(Node::Scalar(scalar)), Translation::Single(text)) => edits.push((
+                    scalar.range.clone(),
+                    render_scalar(
+                        text,
+                        scalar.style,
+                        scalar.flow,
+                        self.indent_at(scalar.range.start),
+                    ),
+                )),
+                (Some(Node::Mapping(mapping)), Translation::Plural(forms))
+                    if mapping.plural_forms().is_some() =>
+                {
+                    edits.push((mapping.range.clone(), self.plural_text(mapping, forms)));
+                }
+                (None, _) => missing.push((path, translation)),
+                _ => skipped.push(path.as_str()),
+            }
+        }
+        let mut document = self.edited(edits)?;
+        for (path, translation) in missing {
+            let insertion = document.insertion(path, translation)?;
+            document = document.edited(vec![insertion])?;
+        }
+        Ok((document, skipped))
+    }
+
+    /// Indentation of a line's content up to `pos`.
+    fn indent_at(&self, pos: usize) -> &str {
+        let line_start = self.text[..pos].rfind('\n').map_or(0, |i| i + 1);
+        &self.text[line_start..pos]
+    }
+
+    /// One level of indentation, as used below the language key.
+    fn indent_step(&self) -> String {
+        self.indent_at(self.root.range.start).to_string()
+    }
+}
+
+fn collect_messages<'a>(mapping: &'a Mapping, prefix: &str, messages: &mut Vec<Message<'a>>) {
+    for (key, node) in &mapping.entries {
+        // Merge keys pull in an anchored mapping translated where it is defined.
+        if key.value == "<<" {
+            continue;
+        }
+        let path = if prefix.is_empty() {
+            key.value.clone()
+        } else {
+            format!("{}.{}", prefix, key.value)
+        };
+        collect_node(node, path, messages);
+    }
+}
+
+fn collect_node<'a>(node: &'a Node, path: String, messages: &mut Vec<Message<'a>>) {
+    match node {
+        Node::Scalar(scalar) if is_text(scalar) => messages.push(Message::Single(path, scalar)),
+        Node::Mapping(mapping) => match mapping.plural_forms() {
+            Some(forms) => messages.push(Message::Plural(path, forms)),
+            None => collect_messages(mapping, &path, messages),
+        },
+        Node::Sequence(items, _) => {
+            for (i, item) in items.iter().enumerate() {
+                collect_node(item, format!("{}.{}", path, i), messages);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Leaves worth translating: not empty, and not a plain number, boolean or null.
+fn is_text(scalar: &Scalar) -> bool {
+    let literal = scalar.style == TScalarStyle::Plain && is_plain_literal(&scalar.value);
+    !scalar.value.trim().is_empty() && !literal
+}
+
+fn is_plain_literal(value: &str) -> bool {
+    matches!(
+        value.to_lowercase().as_str(),
+        "true" | "false" | "yes" | "no" | "on" | "off" | "y" | "n" | "null" | "~"
+    ) || value.parse::<f64>().is_ok()
+        || value.starts_with("0x")
+        || value.starts_with("0o")
+}
+
+/// Builds the node tree from parser events, locating each scalar's end
+/// from the position of the event after it.
+struct TreeBuilder<'t> {
+    text: &'t str,
+    events: Vec<(Event, usize)>,
+    pos: usize,
+    flow_depth: usize,
+}
+
+impl<'t> TreeBuilder<'t> {
+    fn new(text: &'t str) -> Result<Self, String> {
+        // Parser marks count characters, and their running index drifts
+        // after block scalars holding non-ASCII text; line and column do not.
+        let line_starts: Vec<usize> = [0]
+            .into_iter()
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        let offset = |line: usize, col: usize| {
+            let start = line_starts[line.saturating_sub(1).min(line_starts.len() - 1)];
+            text[start..]
+                .char_indices()
+                .nth(col)
+                .map_or(text.len(), |(i, _)| start + i)
+        };
+        let mut parser = Parser::new_from_str(text);
+        let mut events = Vec::new();
+        loop {
+            let (event, mark) = parser.next_token().map_err(|e| e.to_string())?;
+            let done = event == Event::StreamEnd;
+            events.push((event, offset(mark.line(), mark.col())));
+            if done {
+                break;
+            }
+        }
+        Ok(Self {
+            text,
+            events,
+            pos: 0,
+            flow_depth: 0,
+        })
+    }
+
+    fn document(mut self) -> Result<Node, String> {
+        while let Some((event, _)) = self.events.get(self.pos) {
+            match event {
+                Event::StreamStart | Event::DocumentStart => self.pos += 1,
+                Event::StreamEnd => return Err("the file is empty".to_string()),
+                _ => return self.node(),
+            }
+        }
+        Err("the file is empty".to_string())
+    }
+
+    fn next(&mut self) -> Result<(Event, usize), String> {
+        let event = self
+            .events
+            .get(self.pos)
+            .cloned()
+            .ok_or("unexpected end of document")?;
+        self.pos += 1;
+        Ok(event)
+    }
+
+    fn peek(&self) -> Option<&Event> {
+        self.events.get(self.pos).map(|(event, _)| event)
+    }
+
+    fn node(&mut self) -> Result<Node, String> {
+        let (event, start) = self.next()?;
+        match event {
+            Event::Scalar(value, style, _, _) => Ok(Node::Scalar(self.scalar(value, style, start))),
+            Event::MappingStart(..) => {
+                let flow = self.text[start..].starts_with('{');
+                self.flow_depth += usize::from(flow);
+                let mut entries = Vec::new();
+                while self.peek() != Some(&Event::MappingEnd) {
+                    let key = match self.node()? {
+                        Node::Scalar(key) => key,
+                        _ => return Err("only scalar keys are supported".to_string()),
+                    };
+                    let value = self.node()?;
+                    entries.push((key, value));
+                }
+                let (_, end) = self.next()?;
+                self.flow_depth -= usize::from(flow);
+
+                let range = if flow {
+                    start..end + 1
+                } else {
+                    let first = entries.first().map_or(start, |(key, _)| key.range.start);
+                    let last = entries.last().map_or(start, |(_, value)| value.range().end);
+                    first..last
+                };
+                Ok(Node::Mapping(Mapping {
+                    flow,
+                    entries,
+                    range,
+                }))
+            }
+            Event::SequenceStart(..) => {
+                let flow = self.text[start..].starts_with('[');
+                self.flow_depth += usize::from(flow);
+                let mut items = Vec::new();
+                while self.peek() != Some(&Event::SequenceEnd) {
+                    items.push(self.node()?);
+                }
+                let (_, end) = self.next()?;
+                self.flow_depth -= usize::from(flow);
+
+                let end = if flow {
+                    end + 1
+                } else {
+                    items.last().map_or(start, |item| item.range().end)
+                };
+                Ok(Node::Sequence(items, start..end))
+            }
+            Event::Alias(_) => {
+                let length = self.text[start..]
+                    .find(|c: char| c.is_whitespace() || ",]}".contains(c))
+                    .unwrap_or(self.text.len() - start);
+                Ok(Node::Other(start..start + length))
+            }
+            other => Err(format!("unexpected {:?}", other)),
+        }
+    }
+
+    fn scalar(&self, value: String, style: TScalarStyle, start: usize) -> Scalar {
+        let limit = self
+            .events
+            .get(self.pos)
+            .map_or(self.text.len(), |(_, at)| *at)
+            .max(start);
+        let flow = self.flow_depth > 0;
+        let line_start = self.text[..start].rfind('\n').map_or(0, |i| i + 1);
+        let indent = start - line_start;
+        let line = &self.text[line_start..start];
+        let line_indent = line.len() - line.trim_start_matches(' ').len();
+        let end = scalar_end(&self.text[start..limit], style, flow, indent, line_indent) + start;
+        Scalar {
+            value,
+            style,
+            flow,
+            range: start..end,
+        }
+    }
+}
+
+/// Length of the scalar at the start of `region`, which runs up to the next
+/// token and so may carry trailing whitespace, comments, separators and the
+/// `-` of the next sequence item. `indent` is the column the scalar starts
+/// at, `line_indent` the indentation of its first line.
+fn scalar_end(
+    region: &str,
+    style: TScalarStyle,
+    flow: bool,
+    indent: usize,
+    line_indent: usize,
+) -> usize {
+    let bytes = region.as_bytes();
+    match style {
+        TScalarStyle::SingleQuoted => {
+            let mut i = 1;
+            while i < bytes.len() {
+                if bytes[i] == b'\'' {
+                    if bytes.get(i + 1) == Some(&b'\'') {
+                        i += 2;
+                        continue;
+                    }
+                    return i + 1;
+                }
+                i += 1;
+            }
+            region.len()
+        }
+        TScalarStyle::DoubleQuoted => {
+            let mut i = 1;
+            while i < bytes.len() {
+                match bytes[i] {
+                    b'\\' => i += 2,
+                    b'"' => return i + 1,
+                    _ => i += 1,
+                }
+            }
+            region.len()
+        }
+        TScalarStyle::Plain => {
+            let mut end = if flow {
+                region.len()
+            } else {
+                plain_block_end(region, indent, line_indent)
+            };
+            if flow && let Some(i) = region.find([',', '[', ']', '{', '}']) {
+                end = i;
+            }
+            // A `#` after whitespace starts a comment, ending the scalar.
+            if let Some(i) = region[..end]
+                .char_indices()
+                .find(|&(i, c)| c == '#' && i > 0 && region[..i].ends_with(char::is_whitespace))
+                .map(|(i, _)| i)
+            {
+                end = i;
+            }
+            // A `:` followed by whitespace ends a key.
+            if let Some((i, _)) = region[..end].match_indices(':').find(|(i, _)| {
+                let rest = &region[i + 1..end];
+                rest.is_empty() || rest.starts_with(char::is_whitespace)
+            }) {
+                end = i;
+            }
+            region[..end].trim_end().len()
+        }
+        // Block scalar content ends with its last non-blank line at least as
+        // indented as the first; anything after it is blank lines, comments or
+        // the next, less indented key.
+        TScalarStyle::Literal | TScalarStyle::Folded => {
+            let first_line = region.find('\n').unwrap_or(region.len());
+            let mut end = region[..first_line].trim_end().len();
+            let mut line_start = first_line + 1;
+            for line in region.get(line_start..).unwrap_or_default().split('\n') {
+                let content = line.trim_end_matches('\r');
+                if !content.trim().is_empty() {
+                    if content.len() - content.trim_start_matches(' ').len() < indent {
+                        break;
+                    }
+                    end = line_start + content.len();
+                }
+                line_start += line.len() + 1;
+            }
+            end
+        }
+    }
+}
+
+/// End of a plain block scalar: its first line, and the lines after it
+/// indented deeper than that line, or when the scalar starts its line, at
+/// least as deep as the scalar. A comment line ends it.
+fn plain_block_end(region: &str, indent: usize, line_indent: usize) -> usize {
+    let min_indent = if indent == line_indent {
+        indent
+    } else {
+        line_indent + 1
+    };
+    let mut end = region.find('\n').unwrap_or(region.len());
+    let mut line_start = end + 1;
+    for line in region.get(line_start..).unwrap_or_default().split('\n') {
+        let content = line.trim_end_matches('\r');
+        let trimmed = content.trim_start_matches(' ');
+        if !trimmed.trim().is_empty() {
+            if trimmed.starts_with('#') || content.len() - trimmed.len() < min_indent {
+                break;
+            }
+            end = line_start + content.len();
+        }
+        line_start += line.len() + 1;
+    }
+    end
+}
+
+/// Writes the value back in the style it had where that can hold it, and
+/// double-quoted otherwise. `indent` is the content indentation of a block
+/// scalar.
+fn render_scalar(value: &str, style: TScalarStyle, flow: bool, indent: &str) -> String {
+    match style {
+        TScalarStyle::Plain if is_plain_safe(value, flow) => value.to_string(),
+        TScalarStyle::SingleQuoted if !value.contains('\n') => {
+            format!("'{}'", value.replace('\'', "''"))
+        }
+        TScalarStyle::Literal | TScalarStyle::Folded if !flow && !indent.is_empty() => {
+            // A folded scalar reads a single line break as a space.
+            let separator = if style == TScalarStyle::Folded {
+                "\n\n"
+            } else {
+                "\n"
+            };
+            value
+                .strip_suffix('\n')
+                .unwrap_or(value)
+                .split('\n')
+                .enumerate()
+                .map(|(i, line)| match (i, line.is_empty()) {
+                    (0, _) => line.to_string(),
+                    (_, true) => separator.to_string(),
+                    (_, false) => format!("{}{}{}", separator, indent, line),
+                })
+                .collect()
+        }
+        _ => double_quoted(value),
+    }
+}
+
+fn is_plain_safe(value: &str, flow: bool) -> bool {
+    let Some(first) = value.chars().next() else {
+        return false;
+    };
+    !"-?:,[]{}#&*!|>'\"%@`".contains(first)
+        && value.trim() == value
+        && !value.contains(['\n', '\t'])
+        && !value.contains(": ")
+        && !value.contains(" #")
+        && !value.ends_with(':')
+        && !(flow && value.contains([',', '[', ']', '{', '}']))
+        && !is_plain_literal(value)
+}
+
+fn double_quoted(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Writes a copy of a locale file for another language, renaming its
+/// top-level key to the language.
+pub fn copy_with_language(
+    template_path: &Path,
+    output_path: &Path,
+    target_lang: &str,
+) -> Result<(), PoLlmError> {
+    let template = YamlDocument::read(template_path)?;
+    let mut text = template.text.clone();
+    text.replace_range(
+        template.language.range.clone(),
+        &render_scalar(target_lang, template.language.style, false, ""),
+    );
+    write_atomically(output_path, |writer| writer.write_all(text.as_bytes()))
+        .map_err(|e| PoLlmError::io("Failed to write YAML file", e))
+}
+
+enum Translation {
+    Single(String),
+    /// `(category, text)` pairs for the target language.
+    Plural(Vec<(&'static str, String)>),
+}
+
+/// Outputs start as a copy of their input under the target language's key,
+/// so an entry counts as translated once its value no longer matches the
+/// source. Plural entries are checked by their `other` form.
+pub struct YamlAdapter;
+
+impl FormatAdapter for YamlAdapter {
+    fn format(&self) -> DocumentFormat {
+        DocumentFormat::Yaml
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["yml", "yaml"]
+    }
+
+    fn create_output(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+        target_lang: &str,
+    ) -> Result<(), PoLlmError> {
+        copy_with_language(input_path, output_path, target_lang)
+    }
+
+    fn load_template(&self, path: &Path) -> Result<Document, PoLlmError> {
+        Ok(Box::new(YamlDocument::read(path)?))
+    }
+
+    fn load_existing(
+        &self,
+        output_path: &Path,
+        template: &Document,
+    ) -> Result<Document, PoLlmError> {
+        existing_or_template(output_path, template, YamlDocument::read)
+    }
+
+    fn extract_units(
+        &self,
+        existing: &Document,
+        template: &Document,
+        skip_translated: bool,
+    ) -> Vec<TranslationUnit> {
+        let document: &YamlDocument = document_as(existing);
+        let template: &YamlDocument = document_as(template);
+        template
+            .messages()
+            .into_iter()
+            .filter_map(|message| {
+                let (context, msg_id, msg_id_plural) = match message {
+                    Message::Single(path, scalar) => (path, scalar.value.clone(), None),
+                    Message::Plural(path, forms) => {
+                        let form = |category: &str| {
+                            forms
+                                .iter()
+                                .find(|(c, _)| *c == category)
+                                .map(|(_, scalar)| scalar.value.clone())
+                        };
+                        let singular = form("one").unwrap_or_else(|| forms[0].1.value.clone());
+                        (path, singular, form("other"))
+                    }
+                };
+                let is_plural = msg_id_plural.is_some();
+
+                if skip_translated {
+                    let (probe, source) = match &msg_id_plural {
+                        Some(plural) => (format!("{}.other", context), plural),
+                        None => (context.clone(), &msg_id),
+                    };
+                    if document.is_translated(&probe, source) {
+                        return None;
+                    }
+                }
+
+                Some(TranslationUnit {
+                    msg_id,
+                    msg_id_plural,
+                    msg_str: (!is_plural).then(String::new),
+                    msg_str_plural: is_plural.then(Vec::new),
+                    context: Some(context),
+                    comments: None,
+                    rationale: None,
+                    failure: None,
+                    warnings: Vec::new(),
+                    findings: Vec::new(),
+                    attribution: None,
+                    format_flags: Vec::new(),
+                    references: String::new(),
+                    fuzzy: false,
+                    previous: None,
+                })
+            })
+            .collect()
+    }
+
+    fn write_translations(
+        &self,
+        translations: Vec<TranslationUnit>,
+        target_lang: &str,
+        output: &FileGuard,
+        options: &WriteOptions,
+    ) -> Result<(), PoLlmError> {
+        let output_path = output.path();
+        let document = YamlDocument::read(output_path)?;
+        let categories = plural_forms_or_default(target_lang).categories;
+
+        let mut targets: Vec<(String, Translation)> = Vec::new();
+        for unit in translations {
+            let Some(path) = unit.context.clone().filter(|_| unit.failure.is_none()) else {
+                continue;
+            };
+            if unit.msg_id_plural.is_some() {
+                let forms = unit.msg_str_plural.unwrap_or_default();
+                if forms.iter().all(String::is_empty) {
+                    continue;
+                }
+                let pairs = cldr_forms(categories, &forms)
+                    .into_iter()
+                    .map(|(category, form)| (category, form.to_string()))
+                    .collect();
+                targets.push((path, Translation::Plural(pairs)));
+            } else {
+                let text = unit.msg_str.unwrap_or_default();
+                if !text.is_empty() {
+                    targets.push((path, Translation::Single(text)));
+                }
+            }
+        }
+
+        let (document, skipped) = document
+            .with_translations(&targets)
+            .map_err(PoLlmError::Validation)?;
+        for path in skipped {
+            eprintln!(
+                "         ⚠️  Skipping '{}': its shape in {:?} differs from the input",
+                path, output_path
+            );
+        }
+
+        write_atomically(output_path, |writer| {
+            writer.write_all(document.text.as_bytes())
+        })
+        .map_err(|e| PoLlmError::io("Failed to write YAML file", e))?;
+
+        if options.verify_roundtrip {
+            let reparsed = YamlDocument::read(output_path).map_err(|e| {
+                PoLlmError::Validation(format!("Round-trip check failed, cannot re-parse: {}", e))
+            })?;
+            let reads_back = |path: &str, expected: &str| match reparsed.get(path) {
+                Some(Node::Scalar(scalar)) => scalar.value.trim_end() == expected.trim_end(),
+                _ => false,
+            };
+            let differing = targets
+                .iter()
+                .filter(|(path, translation)| match translation {
+                    Translation::Single(text) => !reads_back(path, text),
+                    Translation::Plural(forms) => forms.iter().any(|(category, text)| {
+                        !reads_back(&format!("{}.{}", path, category), text)
+                    }),
+                })
+                .count();
+            if differing > 0 {
+                return Err(PoLlmError::Validation(format!(
+                    "Round-trip check failed: {} value(s) read back differently",
+                    differing
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translate(text: &str, targets: &[(&str, &str)]) -> String {
+        let document = YamlDocument::parse(text.to_string()).unwrap();
+        let targets: Vec<(String, Translation)> = targets
+            .iter()
+            .map(|(path, text)| (path.to_string(), Translation::Single(text.to_string())))
+            .collect();
+        let (document, skipped) = document.with_translations(&targets).unwrap();
+        assert!(skipped.is_empty());
+        document.text
+    }
+
+    fn paths(text: &str) -> Vec<String> {
+        YamlDocument::parse(text.to_string())
+            .unwrap()
+            .messages()
+            .into_iter()
+            .map(|message| match message {
+                Message::Single(path, _) | Message::Plural(path, _) => path,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn block_sequence_items_stay_apart() {
+        let text = "en:\n  list:\n    - First\n    - Second\n  after: Done\n";
+        assert_eq!(paths(text), ["list.0", "list.1", "after"]);
+        assert_eq!(
+            translate(text, &[("list.0", "Erste"), ("list.1", "Zweite")]),
+            "en:\n  list:\n    - Erste\n    - Zweite\n  after: Done\n"
+        );
+    }
+
+    #[test]
+    fn top_level_sequence_items_stay_apart() {
+        let text = "en:\n  list:\n  - First\n  - Second\n";
+        assert_eq!(
+            translate(text, &[("list.0", "Erste"), ("list.1", "Zweite")]),
+            "en:\n  list:\n  - Erste\n  - Zweite\n"
+        );
+    }
+
+    #[test]
+    fn plain_scalar_continuation_lines_are_replaced() {
+        let text = "en:\n  intro: A long\n    sentence # note\n  next:\n    - item\n";
+        assert_eq!(
+            translate(text, &[("intro", "Ein langer Satz"), ("next.0", "Punkt")]),
+            "en:\n  intro: Ein langer Satz # note\n  next:\n    - Punkt\n"
+        );
+    }
+
+    #[test]
+    fn anchors_aliases_and_comments_survive() {
+        let text = "\
+en:
+  defaults: &defaults
+    title: Hello # greeting
+    body: 'World'
+  page:
+    <<: *defaults
+    footer: Bye
+  names:
+    - First
+    - *defaults
+";
+        assert_eq!(
+            paths(text),
+            ["defaults.title", "defaults.body", "page.footer", "names.0"]
+        );
+        assert_eq!(
+            translate(
+                text,
+                &[
+                    ("defaults.title", "Hallo"),
+                    ("defaults.body", "Welt"),
+                    ("page.footer", "Tschüss"),
+                    ("names.0", "Erste"),
+                ],
+            ),
+            "\
+en:
+  defaults: &defaults
+    title: Hallo # greeting
+    body: 'Welt'
+  page:
+    <<: *defaults
+    footer: Tschüss
+  names:
+    - Erste
+    - *defaults
+"
+        );
+    }
+
+    #[test]
+    fn missing_keys_are_added_below_their_parent() {
+        let text = "en:\n  menu:\n    open: Open\n";
+        let document = YamlDocument::parse(text.to_string()).unwrap();
+        let targets = vec![(
+            "menu.save".to_string(),
+            Translation::Single("Speichern".to_string()),
+        )];
+        let (document, _) = document.with_translations(&targets).unwrap();
+        assert_eq!(
+            document.text,
+            "en:\n  menu:\n    open: Open\n    save: Speichern\n"
+        );
+    }
+}